@@ -8,11 +8,14 @@
 use bencher::{benchmark_group, benchmark_main, Bencher};
 use hecs::*;
 
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 struct Position(f32);
-#[derive(Clone)]
+#[derive(Clone, Copy)]
 struct Velocity(f32);
 
+#[derive(Clone, Copy)]
+struct Transform([f32; 16]);
+
 fn spawn_tuple(b: &mut Bencher) {
     let mut world = World::new();
     b.iter(|| {
@@ -55,6 +58,29 @@ fn spawn_batch(b: &mut Bencher) {
     });
 }
 
+fn spawn_column_batch(b: &mut Bencher) {
+    let positions = vec![Position(0.0); 1_000];
+    let velocities = vec![Velocity(0.0); 1_000];
+    let mut world = World::new();
+    b.iter(|| {
+        let mut ty = ColumnBatchType::new();
+        ty.add::<Position>().add::<Velocity>();
+        let mut batch = ty.into_batch(1_000);
+        batch
+            .writer::<Position>()
+            .unwrap()
+            .extend_from_slice(&positions);
+        batch
+            .writer::<Velocity>()
+            .unwrap()
+            .extend_from_slice(&velocities);
+        world
+            .spawn_column_batch(batch.build().unwrap())
+            .for_each(|_| {});
+        world.clear();
+    });
+}
+
 fn remove(b: &mut Bencher) {
     let mut world = World::new();
     b.iter(|| {
@@ -85,13 +111,55 @@ fn insert(b: &mut Bencher) {
     });
 }
 
+fn insert_batch(b: &mut Bencher) {
+    let mut world = World::new();
+    b.iter(|| {
+        let entities = world
+            .spawn_batch((0..1_000).map(|_| (Position(0.0),)))
+            .collect::<Vec<_>>();
+        world.insert_batch(&entities, Velocity(0.0));
+        world.clear();
+    });
+}
+
+fn toggle_tag_12_components(b: &mut Bencher) {
+    struct Tag;
+    let mut world = World::new();
+    let entities = (0..1_000)
+        .map(|i| {
+            world.spawn((
+                Position(0.0),
+                Velocity(0.0),
+                Transform([0.0; 16]),
+                i as u8,
+                i as u16,
+                i as u32,
+                i as u64,
+                i as i8,
+                i as i16,
+                i,
+                i as i64,
+                i as f64,
+            ))
+        })
+        .collect::<Vec<_>>();
+    b.iter(|| {
+        for &e in &entities {
+            world.insert_one(e, Tag).unwrap();
+        }
+        for &e in &entities {
+            world.remove_one::<Tag>(e).unwrap();
+        }
+    });
+}
+
 fn iterate_100k(b: &mut Bencher) {
     let mut world = World::new();
     for i in 0..100_000 {
         world.spawn((Position(-(i as f32)), Velocity(i as f32)));
     }
     b.iter(|| {
-        for (_, (pos, vel)) in &mut world.query::<(&mut Position, &Velocity)>() {
+        for (_, (mut pos, vel)) in &mut world.query::<(&mut Position, &Velocity)>() {
             pos.0 += vel.0;
         }
     })
@@ -103,12 +171,40 @@ fn iterate_mut_100k(b: &mut Bencher) {
         world.spawn((Position(-(i as f32)), Velocity(i as f32)));
     }
     b.iter(|| {
-        for (_, (pos, vel)) in world.query_mut::<(&mut Position, &Velocity)>() {
+        for (_, (mut pos, vel)) in world.query_mut::<(&mut Position, &Velocity)>() {
             pos.0 += vel.0;
         }
     })
 }
 
+fn iterate_derived_100k(b: &mut Bencher) {
+    #[derive(Query)]
+    struct Physics<'a> {
+        pos: Mut<'a, Position>,
+        vel: &'a Velocity,
+    }
+
+    let mut world = World::new();
+    for i in 0..100_000 {
+        world.spawn((Position(-(i as f32)), Velocity(i as f32)));
+    }
+    b.iter(|| {
+        for (_, mut item) in world.query_mut::<Physics>() {
+            item.pos.0 += item.vel.0;
+        }
+    })
+}
+
+fn iterate_transforms_1m(b: &mut Bencher) {
+    let mut world = World::new();
+    world.spawn_batch((0..1_000_000).map(|i| (Transform([i as f32; 16]), Velocity(1.0))));
+    b.iter(|| {
+        for (_, (mut transform, vel)) in world.query_mut::<(&mut Transform, &Velocity)>() {
+            transform.0[12] += vel.0;
+        }
+    })
+}
+
 fn spawn_100_by_50(world: &mut World) {
     fn spawn_two<const N: usize>(world: &mut World, i: i32) {
         world.spawn((Position(-(i as f32)), Velocity(i as f32), [(); N]));
@@ -148,7 +244,7 @@ fn iterate_uncached_100_by_50(b: &mut Bencher) {
     let mut world = World::new();
     spawn_100_by_50(&mut world);
     b.iter(|| {
-        for (_, (pos, vel)) in world.query::<(&mut Position, &Velocity)>().iter() {
+        for (_, (mut pos, vel)) in world.query::<(&mut Position, &Velocity)>().iter() {
             pos.0 += vel.0;
         }
     })
@@ -160,7 +256,7 @@ fn iterate_cached_100_by_50(b: &mut Bencher) {
     let mut query = PreparedQuery::<(&mut Position, &Velocity)>::default();
     let _ = query.query(&world).iter();
     b.iter(|| {
-        for (_, (pos, vel)) in query.query(&world).iter() {
+        for (_, (mut pos, vel)) in query.query(&world).iter() {
             pos.0 += vel.0;
         }
     })
@@ -170,7 +266,7 @@ fn iterate_mut_uncached_100_by_50(b: &mut Bencher) {
     let mut world = World::new();
     spawn_100_by_50(&mut world);
     b.iter(|| {
-        for (_, (pos, vel)) in world.query_mut::<(&mut Position, &Velocity)>() {
+        for (_, (mut pos, vel)) in world.query_mut::<(&mut Position, &Velocity)>() {
             pos.0 += vel.0;
         }
     })
@@ -182,12 +278,44 @@ fn iterate_mut_cached_100_by_50(b: &mut Bencher) {
     let mut query = PreparedQuery::<(&mut Position, &Velocity)>::default();
     let _ = query.query_mut(&mut world);
     b.iter(|| {
-        for (_, (pos, vel)) in query.query_mut(&mut world) {
+        for (_, (mut pos, vel)) in query.query_mut(&mut world) {
             pos.0 += vel.0;
         }
     })
 }
 
+/// Spawn one entity into each of 1024 archetypes, none of which have `Velocity`
+fn spawn_1k_archetypes(world: &mut World) {
+    fn add<const N: usize>(builder: &mut EntityBuilder, mask: u32) {
+        if mask & (1 << N) != 0 {
+            builder.add([(); N]);
+        }
+    }
+
+    let mut builder = EntityBuilder::new();
+    for mask in 0..1024 {
+        builder.add(Position(0.0));
+        add::<0>(&mut builder, mask);
+        add::<1>(&mut builder, mask);
+        add::<2>(&mut builder, mask);
+        add::<3>(&mut builder, mask);
+        add::<4>(&mut builder, mask);
+        add::<5>(&mut builder, mask);
+        add::<6>(&mut builder, mask);
+        add::<7>(&mut builder, mask);
+        add::<8>(&mut builder, mask);
+        add::<9>(&mut builder, mask);
+        world.spawn(builder.build());
+    }
+}
+
+fn query_setup_1k_archetypes(b: &mut Bencher) {
+    let mut world = World::new();
+    spawn_1k_archetypes(&mut world);
+    world.spawn((Position(0.0), Velocity(0.0)));
+    b.iter(|| world.query::<(&Position, &Velocity)>().iter().count())
+}
+
 fn build(b: &mut Bencher) {
     let mut world = World::new();
     let mut builder = EntityBuilder::new();
@@ -207,20 +335,69 @@ fn build_cloneable(b: &mut Bencher) {
     });
 }
 
+fn spawn_identical_1m(b: &mut Bencher) {
+    let mut builder = EntityBuilder::new_cloneable();
+    builder.add(Position(0.0)).add(Velocity(0.0));
+    let bundle = builder.build();
+    b.iter(|| {
+        let mut world = World::new();
+        for _ in 0..1_000_000 {
+            world.spawn(&bundle);
+        }
+        world
+    });
+}
+
+fn get_100k(b: &mut Bencher) {
+    let mut world = World::new();
+    let entities = (0..100_000)
+        .map(|_| world.spawn((Position(0.0), Velocity(0.0))))
+        .collect::<Vec<_>>();
+    b.iter(|| {
+        for &e in &entities {
+            world.get_mut::<Position>(e).unwrap().0 += world.get::<Velocity>(e).unwrap().0;
+        }
+    });
+}
+
+fn get_unchecked_100k(b: &mut Bencher) {
+    let mut world = World::new();
+    let entities = (0..100_000)
+        .map(|_| world.spawn((Position(0.0), Velocity(0.0))))
+        .collect::<Vec<_>>();
+    b.iter(|| {
+        for &e in &entities {
+            // Safety: every entity is live and holds both components, which are distinct columns
+            unsafe {
+                world.get_unchecked_mut::<Position>(e).0 += world.get_unchecked::<Velocity>(e).0;
+            }
+        }
+    });
+}
+
 benchmark_group!(
     benches,
     spawn_tuple,
     spawn_static,
     spawn_batch,
+    spawn_column_batch,
     remove,
     insert,
+    insert_batch,
+    toggle_tag_12_components,
     iterate_100k,
     iterate_mut_100k,
+    iterate_derived_100k,
+    iterate_transforms_1m,
     iterate_uncached_100_by_50,
     iterate_cached_100_by_50,
     iterate_mut_uncached_100_by_50,
     iterate_mut_cached_100_by_50,
+    query_setup_1k_archetypes,
     build,
     build_cloneable,
+    spawn_identical_1m,
+    get_100k,
+    get_unchecked_100k,
 );
 benchmark_main!(benches);
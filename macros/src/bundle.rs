@@ -16,15 +16,28 @@ pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
             ))
         }
     };
-    let (tys, field_members) = struct_fields(&data.fields);
-    let field_idents = member_as_idents(&field_members);
-    let generics = add_additional_bounds_to_generic_params(input.generics);
+    let fields = struct_fields(&data.fields)?;
+    let mut generics = add_additional_bounds_to_generic_params(input.generics);
+    for field in &fields {
+        let ty = field.ty;
+        match field.kind {
+            FieldKind::Component => {}
+            FieldKind::Nested => generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(#ty: ::hecs::Bundle)),
+            FieldKind::Skip => generics
+                .make_where_clause()
+                .predicates
+                .push(syn::parse_quote!(#ty: ::core::default::Default)),
+        }
+    }
 
-    let dyn_bundle_code = gen_dynamic_bundle_impl(&ident, &generics, &field_members, &tys);
-    let bundle_code = if tys.is_empty() {
-        gen_unit_struct_bundle_impl(ident, &generics)
+    let dyn_bundle_code = gen_dynamic_bundle_impl(&ident, &generics, &fields);
+    let bundle_code = if fields.iter().all(|x| x.kind == FieldKind::Skip) {
+        gen_unit_struct_bundle_impl(ident, &generics, &fields)
     } else {
-        gen_bundle_impl(&ident, &generics, &field_members, &field_idents, &tys)
+        gen_bundle_impl(&ident, &generics, &fields)
     };
     let mut ts = dyn_bundle_code;
     ts.extend(bundle_code);
@@ -34,29 +47,39 @@ pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
 fn gen_dynamic_bundle_impl(
     ident: &syn::Ident,
     generics: &syn::Generics,
-    field_members: &[syn::Member],
-    tys: &[&syn::Type],
+    fields: &[Field<'_>],
 ) -> TokenStream2 {
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let (tys, field_members) = fields_of_kind(fields, FieldKind::Component);
+    let (nested_tys, nested_members) = fields_of_kind(fields, FieldKind::Nested);
+    let (_, skipped_members) = fields_of_kind(fields, FieldKind::Skip);
     quote! {
         unsafe impl #impl_generics ::hecs::DynamicBundle for #ident #ty_generics #where_clause {
             fn key(&self) -> ::core::option::Option<::core::any::TypeId> {
                 ::core::option::Option::Some(::core::any::TypeId::of::<Self>())
             }
 
-            fn with_ids<__hecs__T>(&self, f: impl ::std::ops::FnOnce(&[::std::any::TypeId]) -> __hecs__T) -> __hecs__T {
+            fn with_ids<__hecs__T>(&self, f: impl ::core::ops::FnOnce(&[::core::any::TypeId]) -> __hecs__T) -> __hecs__T {
                 <Self as ::hecs::Bundle>::with_static_ids(f)
             }
 
-            fn type_info(&self) -> ::std::vec::Vec<::hecs::TypeInfo> {
+            fn type_info(&self) -> ::hecs::alloc::vec::Vec<::hecs::TypeInfo> {
                 <Self as ::hecs::Bundle>::static_type_info()
             }
 
-            #[allow(clippy::forget_copy)]
-            unsafe fn put(mut self, mut f: impl ::std::ops::FnMut(*mut u8, ::hecs::TypeInfo)) {
+            #[allow(unused_mut, unused_variables)]
+            unsafe fn put(self, mut f: impl ::core::ops::FnMut(*mut u8, ::hecs::TypeInfo)) {
+                // Ownership of each field passes to `f`, except for skipped fields, which are
+                // dropped here
+                let mut this = ::core::mem::ManuallyDrop::new(self);
+                #(
+                    f((&mut this.#field_members as *mut #tys).cast::<u8>(), ::hecs::TypeInfo::of::<#tys>());
+                )*
                 #(
-                    f((&mut self.#field_members as *mut #tys).cast::<u8>(), ::hecs::TypeInfo::of::<#tys>());
-                    ::std::mem::forget(self.#field_members);
+                    <#nested_tys as ::hecs::DynamicBundle>::put(::core::ptr::read(&this.#nested_members), &mut f);
+                )*
+                #(
+                    ::core::ptr::drop_in_place(&mut this.#skipped_members);
                 )*
             }
         }
@@ -66,82 +89,126 @@ fn gen_dynamic_bundle_impl(
 fn gen_bundle_impl(
     ident: &syn::Ident,
     generics: &syn::Generics,
-    field_members: &[syn::Member],
-    field_idents: &[Cow<syn::Ident>],
-    tys: &[&syn::Type],
+    fields: &[Field<'_>],
 ) -> TokenStream2 {
-    let num_tys = tys.len();
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-    let with_static_ids_inner = quote! {
-        {
-            let mut tys = [#((::std::mem::align_of::<#tys>(), ::std::any::TypeId::of::<#tys>())),*];
-            tys.sort_unstable_by(|x, y| {
-                ::std::cmp::Ord::cmp(&x.0, &y.0)
-                    .reverse()
-                    .then(::std::cmp::Ord::cmp(&x.1, &y.1))
-            });
-            let mut ids = [::std::any::TypeId::of::<()>(); #num_tys];
-            for (id, info) in ::std::iter::Iterator::zip(ids.iter_mut(), tys.iter()) {
-                *id = info.1;
+    let (tys, field_members) = fields_of_kind(fields, FieldKind::Component);
+    let field_idents = member_as_idents(&field_members);
+    let (nested_tys, nested_members) = fields_of_kind(fields, FieldKind::Nested);
+    let nested_idents = member_as_idents(&nested_members);
+    let (_, skipped_members) = fields_of_kind(fields, FieldKind::Skip);
+    let num_tys = tys.len();
+    let with_static_ids_inner = if nested_tys.is_empty() {
+        quote! {
+            {
+                let mut tys = [#((::core::mem::align_of::<#tys>(), ::core::any::TypeId::of::<#tys>())),*];
+                tys.sort_unstable_by(|x, y| {
+                    ::core::cmp::Ord::cmp(&x.0, &y.0)
+                        .reverse()
+                        .then(::core::cmp::Ord::cmp(&x.1, &y.1))
+                });
+                let mut ids = [::core::any::TypeId::of::<()>(); #num_tys];
+                for (id, info) in ::core::iter::Iterator::zip(ids.iter_mut(), tys.iter()) {
+                    *id = info.1;
+                }
+                ids
             }
-            ids
+        }
+    } else {
+        // The number of components contributed by nested bundles isn't known here. `Self` can't
+        // be named from within the `lazy_static`.
+        quote! {
+            ::core::iter::Iterator::collect::<::hecs::alloc::vec::Vec<::core::any::TypeId>>(
+                ::core::iter::Iterator::map(
+                    <#ident #ty_generics as ::hecs::Bundle>::static_type_info().iter(),
+                    ::hecs::TypeInfo::id,
+                ),
+            )
         }
     };
     let with_static_ids_body = if generics.params.is_empty() {
+        let elements_ty = if nested_tys.is_empty() {
+            quote! { [::core::any::TypeId; #num_tys] }
+        } else {
+            quote! { ::hecs::alloc::vec::Vec<::core::any::TypeId> }
+        };
         quote! {
             ::hecs::lazy_static::lazy_static! {
-                static ref ELEMENTS: [::std::any::TypeId; #num_tys] = {
+                static ref ELEMENTS: #elements_ty = {
                     #with_static_ids_inner
                 };
             }
-            f(&*ELEMENTS)
+            f(&ELEMENTS[..])
         }
     } else {
         quote! {
-            f(&#with_static_ids_inner)
+            f(&#with_static_ids_inner[..])
         }
     };
     quote! {
         unsafe impl #impl_generics ::hecs::Bundle for #ident #ty_generics #where_clause {
             #[allow(non_camel_case_types)]
-            fn with_static_ids<__hecs__T>(f: impl ::std::ops::FnOnce(&[::std::any::TypeId]) -> __hecs__T) -> __hecs__T {
+            fn with_static_ids<__hecs__T>(f: impl ::core::ops::FnOnce(&[::core::any::TypeId]) -> __hecs__T) -> __hecs__T {
                 #with_static_ids_body
             }
 
-            fn static_type_info() -> ::std::vec::Vec<::hecs::TypeInfo> {
-                let mut info = ::std::vec![#(::hecs::TypeInfo::of::<#tys>()),*];
+            #[allow(unused_mut)]
+            fn static_type_info() -> ::hecs::alloc::vec::Vec<::hecs::TypeInfo> {
+                let mut info = ::hecs::alloc::vec![#(::hecs::TypeInfo::of::<#tys>()),*];
+                #(
+                    info.extend(<#nested_tys as ::hecs::Bundle>::static_type_info());
+                )*
                 info.sort_unstable();
                 info
             }
 
             unsafe fn get(
-                mut f: impl ::std::ops::FnMut(::hecs::TypeInfo) -> ::std::option::Option<::std::ptr::NonNull<u8>>,
-            ) -> ::std::result::Result<Self, ::hecs::MissingComponent> {
+                mut f: impl ::core::ops::FnMut(::hecs::TypeInfo) -> ::core::option::Option<::core::ptr::NonNull<u8>>,
+            ) -> ::core::result::Result<Self, ::hecs::MissingComponent> {
                 #(
                     let #field_idents = f(::hecs::TypeInfo::of::<#tys>())
                             .ok_or_else(::hecs::MissingComponent::new::<#tys>)?
                             .cast::<#tys>()
                             .as_ptr();
                 )*
-                ::std::result::Result::Ok(Self { #( #field_members: #field_idents.read(), )* })
+                // Nested bundles are forgotten rather than dropped if a later one is incomplete,
+                // since the source still owns their components
+                #(
+                    let #nested_idents = ::core::mem::ManuallyDrop::new(
+                        <#nested_tys as ::hecs::Bundle>::get(&mut f)?
+                    );
+                )*
+                ::core::result::Result::Ok(Self {
+                    #( #field_members: #field_idents.read(), )*
+                    #( #nested_members: ::core::mem::ManuallyDrop::into_inner(#nested_idents), )*
+                    #( #skipped_members: ::core::default::Default::default(), )*
+                })
             }
         }
     }
 }
 
 // no reason to generate a static for unit structs
-fn gen_unit_struct_bundle_impl(ident: syn::Ident, generics: &syn::Generics) -> TokenStream2 {
+fn gen_unit_struct_bundle_impl(
+    ident: syn::Ident,
+    generics: &syn::Generics,
+    fields: &[Field<'_>],
+) -> TokenStream2 {
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let (_, skipped_members) = fields_of_kind(fields, FieldKind::Skip);
     quote! {
         unsafe impl #impl_generics ::hecs::Bundle for #ident #ty_generics #where_clause {
             #[allow(non_camel_case_types)]
-            fn with_static_ids<__hecs__T>(f: impl ::std::ops::FnOnce(&[::std::any::TypeId]) -> __hecs__T) -> __hecs__T { f(&[]) }
-            fn static_type_info() -> ::std::vec::Vec<::hecs::TypeInfo> { ::std::vec::Vec::new() }
+            fn with_static_ids<__hecs__T>(f: impl ::core::ops::FnOnce(&[::core::any::TypeId]) -> __hecs__T) -> __hecs__T { f(&[]) }
+            fn static_type_info() -> ::hecs::alloc::vec::Vec<::hecs::TypeInfo> { ::hecs::alloc::vec::Vec::new() }
 
             unsafe fn get(
-                mut f: impl ::std::ops::FnMut(::hecs::TypeInfo) -> ::std::option::Option<::std::ptr::NonNull<u8>>,
-            ) -> ::std::result::Result<Self, ::hecs::MissingComponent> {
-                ::std::result::Result::Ok(Self {/* for some reason this works for all unit struct variations */})
+                mut f: impl ::core::ops::FnMut(::hecs::TypeInfo) -> ::core::option::Option<::core::ptr::NonNull<u8>>,
+            ) -> ::core::result::Result<Self, ::hecs::MissingComponent> {
+                ::core::result::Result::Ok(Self {
+                    // for some reason this works for all unit struct variations
+                    #( #skipped_members: ::core::default::Default::default(), )*
+                })
             }
         }
     }
@@ -164,29 +231,80 @@ fn add_additional_bounds_to_generic_params(mut generics: syn::Generics) -> syn::
     generics
 }
 
-fn struct_fields(fields: &syn::Fields) -> (Vec<&syn::Type>, Vec<syn::Member>) {
-    match fields {
-        syn::Fields::Named(ref fields) => fields
-            .named
-            .iter()
-            .map(|f| (&f.ty, syn::Member::Named(f.ident.clone().unwrap())))
-            .unzip(),
-        syn::Fields::Unnamed(ref fields) => fields
-            .unnamed
-            .iter()
-            .enumerate()
-            .map(|(i, f)| {
-                (
-                    &f.ty,
-                    syn::Member::Unnamed(syn::Index {
-                        index: i as u32,
-                        span: Span::call_site(),
-                    }),
-                )
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum FieldKind {
+    /// A single component
+    Component,
+    /// A `#[bundle]` field whose components are included
+    Nested,
+    /// A `#[bundle(skip)]` field, default-initialized when the bundle is removed
+    Skip,
+}
+
+struct Field<'a> {
+    ty: &'a syn::Type,
+    member: syn::Member,
+    kind: FieldKind,
+}
+
+fn struct_fields(fields: &syn::Fields) -> Result<Vec<Field<'_>>> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let member = match f.ident {
+                Some(ref ident) => syn::Member::Named(ident.clone()),
+                None => syn::Member::Unnamed(syn::Index {
+                    index: i as u32,
+                    span: Span::call_site(),
+                }),
+            };
+            Ok(Field {
+                ty: &f.ty,
+                member,
+                kind: field_kind(&f.attrs)?,
             })
-            .unzip(),
-        syn::Fields::Unit => (Vec::new(), Vec::new()),
+        })
+        .collect()
+}
+
+fn field_kind(attrs: &[syn::Attribute]) -> Result<FieldKind> {
+    let mut kind = FieldKind::Component;
+    for attr in attrs.iter().filter(|x| x.path.is_ident("bundle")) {
+        if kind != FieldKind::Component {
+            return Err(Error::new_spanned(attr, "duplicate bundle attribute"));
+        }
+        kind = match attr.parse_meta()? {
+            syn::Meta::Path(_) => FieldKind::Nested,
+            syn::Meta::List(ref list)
+                if list.nested.len() == 1
+                    && matches!(
+                        list.nested[0],
+                        syn::NestedMeta::Meta(syn::Meta::Path(ref path)) if path.is_ident("skip")
+                    ) =>
+            {
+                FieldKind::Skip
+            }
+            meta => {
+                return Err(Error::new_spanned(
+                    meta,
+                    "expected `#[bundle]` or `#[bundle(skip)]`",
+                ))
+            }
+        };
     }
+    Ok(kind)
+}
+
+fn fields_of_kind<'a>(
+    fields: &[Field<'a>],
+    kind: FieldKind,
+) -> (Vec<&'a syn::Type>, Vec<syn::Member>) {
+    fields
+        .iter()
+        .filter(|x| x.kind == kind)
+        .map(|x| (x.ty, x.member.clone()))
+        .unzip()
 }
 
 fn member_as_idents(members: &[syn::Member]) -> Vec<Cow<'_, syn::Ident>> {
@@ -0,0 +1,67 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{DeriveInput, Error, Lit, Meta, NestedMeta, Result};
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
+    let ident = &input.ident;
+    let mut name = None;
+    let mut key = None;
+    for attr in input.attrs.iter().filter(|x| x.path.is_ident("component")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => {
+                return Err(Error::new_spanned(
+                    meta,
+                    "expected `#[component(name = \"...\", key = \"...\")]`",
+                ))
+            }
+        };
+        for nested in list.nested {
+            let (slot, value) = match nested {
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("name") => {
+                    (&mut name, &nv.lit)
+                }
+                NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("key") => {
+                    (&mut key, &nv.lit)
+                }
+                _ => {
+                    return Err(Error::new_spanned(
+                        nested,
+                        "expected `name = \"...\"` or `key = \"...\"`",
+                    ))
+                }
+            };
+            let value = match value {
+                Lit::Str(s) => s.clone(),
+                _ => return Err(Error::new_spanned(value, "expected a string literal")),
+            };
+            if slot.replace(value).is_some() {
+                return Err(Error::new_spanned(nested, "duplicate component attribute"));
+            }
+        }
+    }
+
+    let name = match name {
+        Some(name) => quote!(#name),
+        None => {
+            let name = ident.to_string();
+            quote!(#name)
+        }
+    };
+    let key = match key {
+        Some(key) => quote!(::core::option::Option::Some(#key)),
+        None => quote!(::core::option::Option::None),
+    };
+    let mut generics = input.generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(syn::parse_quote!(Self: ::core::marker::Send + ::core::marker::Sync + 'static));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::hecs::ComponentMeta for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #name;
+            const KEY: ::core::option::Option<&'static str> = #key;
+        }
+    })
+}
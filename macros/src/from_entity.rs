@@ -0,0 +1,96 @@
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{DeriveInput, Error, Result};
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
+    let ident = input.ident;
+    let data = match input.data {
+        syn::Data::Struct(s) => s,
+        _ => {
+            return Err(Error::new_spanned(
+                ident,
+                "derive(FromEntity) does not support enums or unions",
+            ))
+        }
+    };
+
+    let mut generics = input.generics;
+    let mut inits = Vec::with_capacity(data.fields.len());
+    for (i, field) in data.fields.iter().enumerate() {
+        let member = match field.ident {
+            Some(ref ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(syn::Index {
+                index: i as u32,
+                span: Span::call_site(),
+            }),
+        };
+        let ty = &field.ty;
+        let predicates = &mut generics.make_where_clause().predicates;
+        let init = if is_skipped(&field.attrs)? {
+            predicates.push(syn::parse_quote!(#ty: ::core::default::Default));
+            quote!(::core::default::Default::default())
+        } else if let Some(inner) = option_inner(ty) {
+            predicates.push(syn::parse_quote!(#inner: ::hecs::Component + ::core::clone::Clone));
+            quote! {
+                ::core::option::Option::map(entity.get::<#inner>(), |x| {
+                    ::core::clone::Clone::clone(&*x)
+                })
+            }
+        } else {
+            predicates.push(syn::parse_quote!(#ty: ::hecs::Component + ::core::clone::Clone));
+            quote!(::core::clone::Clone::clone(&*entity.get::<#ty>()?))
+        };
+        inits.push(quote!(#member: #init));
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::hecs::FromEntity for #ident #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn from_entity(entity: ::hecs::EntityRef<'_>) -> ::core::option::Option<Self> {
+                ::core::option::Option::Some(Self { #(#inits,)* })
+            }
+        }
+    })
+}
+
+fn is_skipped(attrs: &[syn::Attribute]) -> Result<bool> {
+    let mut skip = false;
+    for attr in attrs.iter().filter(|x| x.path.is_ident("from_entity")) {
+        if skip {
+            return Err(Error::new_spanned(attr, "duplicate from_entity attribute"));
+        }
+        match attr.parse_meta()? {
+            syn::Meta::List(ref list)
+                if list.nested.len() == 1
+                    && matches!(
+                        list.nested[0],
+                        syn::NestedMeta::Meta(syn::Meta::Path(ref path)) if path.is_ident("skip")
+                    ) => {}
+            meta => return Err(Error::new_spanned(meta, "expected `#[from_entity(skip)]`")),
+        }
+        skip = true;
+    }
+    Ok(skip)
+}
+
+/// The `T` in a field type spelled `Option<T>`, which is read from an optional `T` component
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let path = match ty {
+        syn::Type::Path(syn::TypePath { qself: None, path }) => path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match segment.arguments {
+        syn::PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => {
+            match args.args[0] {
+                syn::GenericArgument::Type(ref inner) => Some(inner),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
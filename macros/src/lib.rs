@@ -8,6 +8,8 @@
 extern crate proc_macro;
 
 mod bundle;
+mod component;
+mod from_entity;
 mod query;
 
 use proc_macro::TokenStream;
@@ -30,7 +32,32 @@ use syn::{parse_macro_input, DeriveInput};
 /// let e = world.spawn(Foo { x: 42, y: 'a' });
 /// assert_eq!(*world.get::<i32>(e).unwrap(), 42);
 /// ```
-#[proc_macro_derive(Bundle)]
+///
+/// # Field attributes
+///
+/// - `#[bundle(skip)]` excludes a field from the bundle's components. The field is dropped when
+///   the bundle is spawned or inserted, and initialized with `Default::default()` when the bundle
+///   is removed.
+/// - `#[bundle]` marks a field whose type is itself a `Bundle`, contributing all of its components
+///   rather than being stored as a single component.
+///
+/// ```ignore
+/// #[derive(Bundle)]
+/// struct Body {
+///     position: [f32; 2],
+///     velocity: [f32; 2],
+/// }
+///
+/// #[derive(Bundle)]
+/// struct Player {
+///     #[bundle]
+///     body: Body,
+///     health: u32,
+///     #[bundle(skip)]
+///     debug_name: Option<String>,
+/// }
+/// ```
+#[proc_macro_derive(Bundle, attributes(bundle))]
 pub fn derive_bundle(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     match bundle::derive(input) {
@@ -40,11 +67,77 @@ pub fn derive_bundle(input: TokenStream) -> TokenStream {
     .into()
 }
 
+/// Implement `ComponentMeta` for a type
+///
+/// Declares the type as a component when hecs is compiled with `--cfg hecs_explicit_components`.
+/// `ComponentMeta::NAME` defaults to the name of the type.
+///
+/// # Example
+/// ```ignore
+/// #[derive(Component)]
+/// struct Position([f32; 2]);
+///
+/// #[derive(Component)]
+/// #[component(name = "velocity", key = "vel")]
+/// struct Velocity([f32; 2]);
+///
+/// assert_eq!(<Position as ComponentMeta>::NAME, "Position");
+/// assert_eq!(<Velocity as ComponentMeta>::NAME, "velocity");
+/// assert_eq!(<Velocity as ComponentMeta>::KEY, Some("vel"));
+/// ```
+#[proc_macro_derive(Component, attributes(component))]
+pub fn derive_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match component::derive(input) {
+        Ok(ts) => ts,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
+
+/// Implement `FromEntity` for a struct
+///
+/// Each field is cloned from the entity's component of the same type, so every field type must be
+/// `Clone`. `from_entity` returns `None` if the entity lacks any of them, except for fields of
+/// type `Option<T>`, which are `None` when the entity has no `T` component. The inverse of
+/// `#[derive(Bundle)]`.
+///
+/// # Example
+/// ```ignore
+/// #[derive(FromEntity)]
+/// struct Snapshot {
+///     position: [f32; 2],
+///     health: u32,
+///     name: Option<String>,
+/// }
+///
+/// let mut world = World::new();
+/// let e = world.spawn(([1.0f32, 2.0], 100u32));
+/// let snapshot = Snapshot::from_entity(world.entity(e).unwrap()).unwrap();
+/// assert_eq!(snapshot.health, 100);
+/// assert_eq!(snapshot.name, None);
+/// ```
+///
+/// # Field attributes
+///
+/// - `#[from_entity(skip)]` excludes a field, initializing it with `Default::default()` instead.
+#[proc_macro_derive(FromEntity, attributes(from_entity))]
+pub fn derive_from_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match from_entity::derive(input) {
+        Ok(ts) => ts,
+        Err(e) => e.to_compile_error(),
+    }
+    .into()
+}
+
 /// Implement `Query` for a struct
 ///
 /// Queries structs can be passed to the type parameter of `World::query`. They must have exactly
 /// one lifetime parameter, and all of their fields must be queries (e.g. references) using that
-/// lifetime.
+/// lifetime. Each field holds the item its type yields, so unique access is written `Mut<'a, T>`
+/// rather than `&'a mut T`, and optional components `Option<&'a T>`. Derived queries are as
+/// efficient as the equivalent tuple.
 ///
 /// # Example
 /// ```ignore
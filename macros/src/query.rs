@@ -1,6 +1,7 @@
 use proc_macro2::Span;
 use proc_macro2::TokenStream as TokenStream2;
-use quote::quote;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
 use syn::{DeriveInput, Error, Ident, Lifetime, Result, Type};
 
 pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
@@ -36,6 +37,24 @@ pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
         ));
     }
 
+    for field in data.fields.iter() {
+        if let Type::Reference(syn::TypeReference {
+            mutability: Some(_),
+            ref elem,
+            ..
+        }) = field.ty
+        {
+            return Err(Error::new_spanned(
+                &field.ty,
+                format!(
+                    "mutable references yield `Mut` when queried; use `Mut<{}, {}>` instead",
+                    lifetime,
+                    quote!(#elem)
+                ),
+            ));
+        }
+    }
+
     let (fields, fetches) = match data.fields {
         syn::Fields::Named(ref fields) => fields
             .named
@@ -125,12 +144,12 @@ pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
             }
 
             #[allow(unused_variables, unused_mut)]
-            fn access(archetype: &::hecs::Archetype) -> ::std::option::Option<::hecs::Access> {
+            fn access(archetype: &::hecs::Archetype) -> ::core::option::Option<::hecs::Access> {
                 let mut access = ::hecs::Access::Iterate;
                 #(
                     access = ::core::cmp::max(access, #fetches::access(archetype)?);
                 )*
-                ::std::option::Option::Some(access)
+                ::core::option::Option::Some(access)
             }
 
             #[allow(unused_variables)]
@@ -139,8 +158,8 @@ pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
             }
 
             #[allow(unused_variables)]
-            fn prepare(archetype: &::hecs::Archetype) -> ::std::option::Option<Self::State> {
-                ::std::option::Option::Some(#state_ident {
+            fn prepare(archetype: &::hecs::Archetype) -> ::core::option::Option<Self::State> {
+                ::core::option::Option::Some(#state_ident {
                     #(
                         #fields: #fetches::prepare(archetype)?,
                     )*
@@ -156,18 +175,57 @@ pub fn derive(input: DeriveInput) -> Result<TokenStream2> {
                 }
             }
 
+            #[allow(unused_variables)]
+            fn execute_since(archetype: &'a ::hecs::Archetype, state: Self::State, last_run: u32) -> Self {
+                Self {
+                    #(
+                        #fields: #fetches::execute_since(archetype, state.#fields, last_run),
+                    )*
+                }
+            }
+
             #[allow(unused_variables)]
             fn release(archetype: &::hecs::Archetype, state: Self::State) {
                 #(#fetches::release(archetype, state.#fields);)*
             }
 
             #[allow(unused_variables, unused_mut)]
-            fn for_each_borrow(mut f: impl ::core::ops::FnMut(::core::any::TypeId, bool)) {
+            fn for_each_borrow(mut f: impl ::core::ops::FnMut(::core::any::TypeId, &'static str, bool)) {
                 #(
                     <#fetches as ::hecs::Fetch<'static>>::for_each_borrow(&mut f);
                 )*
             }
 
+            #[allow(unused_variables, unused_mut)]
+            fn for_each_requirement(mut f: impl ::core::ops::FnMut(::core::any::TypeId, bool)) {
+                #(
+                    <#fetches as ::hecs::Fetch<'static>>::for_each_requirement(&mut f);
+                )*
+            }
+
+            #[allow(unused_variables)]
+            unsafe fn filter(&self, n: usize) -> bool {
+                true #(&& <#fetches as ::hecs::Fetch<'a>>::filter(&self.#fields, n))*
+            }
+
+            #[allow(unused_variables)]
+            unsafe fn matches(archetype: &::hecs::Archetype, state: Self::State, n: usize) -> bool {
+                true #(&& <#fetches as ::hecs::Fetch<'a>>::matches(archetype, state.#fields, n))*
+            }
+
+            fn is_dense() -> bool {
+                true #(&& <#fetches as ::hecs::Fetch<'a>>::is_dense())*
+            }
+
+            fn may_match(&self) -> bool {
+                true #(&& <#fetches as ::hecs::Fetch<'a>>::may_match(&self.#fields))*
+            }
+
+            #[allow(unused_variables)]
+            unsafe fn prefetch(&self, n: usize) {
+                #(<#fetches as ::hecs::Fetch<'a>>::prefetch(&self.#fields, n);)*
+            }
+
             #[allow(unused_variables)]
             unsafe fn get(&self, n: usize) -> Self::Item {
                 #ident {
@@ -194,7 +252,8 @@ fn query_fetch_ty(lifetime: &Lifetime, ty: &Type) -> TokenStream2 {
 
     let mut ty = ty.clone();
     syn::visit_mut::visit_type_mut(&mut Visitor { replace: lifetime }, &mut ty);
-    quote! {
+    // Spanned so that fields which aren't queries are reported at their type
+    quote_spanned! {ty.span()=>
         <#ty as ::hecs::Query>::Fetch
     }
 }
@@ -7,18 +7,21 @@
 
 use crate::alloc::alloc::{alloc, dealloc, Layout};
 use crate::alloc::boxed::Box;
+use crate::alloc::sync::Arc;
 use crate::alloc::{vec, vec::Vec};
 use core::any::{type_name, TypeId};
 use core::hash::{BuildHasher, BuildHasherDefault, Hasher};
-use core::ops::Deref;
+use core::mem;
+use core::ops::{Deref, DerefMut};
 use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use core::{fmt, slice};
 
 use hashbrown::{hash_map::DefaultHashBuilder, HashMap};
 
 use crate::borrow::AtomicBorrow;
 use crate::query::Fetch;
-use crate::{Access, Component, Query};
+use crate::{Access, Children, Component, Parent, Query};
 
 /// A collection of entities having the same component types
 ///
@@ -34,6 +37,12 @@ pub struct Archetype {
     /// Maps static bundle types to the archetype that an entity from this archetype is moved to
     /// after removing the components from that bundle.
     pub(crate) remove_edges: TypeIdMap<u32>,
+    /// The component types in `types`, as indexed by the world's [`ComponentRegistry`]
+    pub(crate) component_bits: ComponentSet,
+    /// Whether `types` includes [`Parent`] or [`Children`]
+    hierarchy: bool,
+    /// Tick state shared by every archetype in a world
+    change_tick: Arc<ChangeTicks>,
 }
 
 impl Archetype {
@@ -44,7 +53,7 @@ impl Archetype {
             core::cmp::Ordering::Equal => panic!(
                 "attempted to allocate entity with duplicate {} components; \
                  each type must occur at most once!",
-                x[0].type_name
+                x[0].type_name.unwrap_or("<unknown>")
             ),
             #[cfg(not(debug_assertions))]
             core::cmp::Ordering::Equal => panic!(
@@ -55,10 +64,13 @@ impl Archetype {
         });
     }
 
-    pub(crate) fn new(types: Vec<TypeInfo>) -> Self {
+    pub(crate) fn new(types: Vec<TypeInfo>, change_tick: Arc<ChangeTicks>) -> Self {
         let max_align = types.first().map_or(1, |ty| ty.layout.align());
         Self::assert_type_info(&types);
         let component_count = types.len();
+        let hierarchy = types
+            .iter()
+            .any(|ty| ty.id == TypeId::of::<Parent>() || ty.id == TypeId::of::<Children>());
         Self {
             index: OrderedTypeIdMap::new(types.iter().enumerate().map(|(i, ty)| (ty.id, i))),
             types,
@@ -70,9 +82,15 @@ impl Archetype {
                     storage: NonNull::new(max_align as *mut u8).unwrap(),
                     mutated_entities: Vec::new(),
                     added_entities: Vec::new(),
+                    changed: AtomicBool::new(false),
+                    change_ticks: Vec::new(),
+                    added_ticks: Vec::new(),
                 })
                 .collect(),
             remove_edges: HashMap::default(),
+            component_bits: ComponentSet::default(),
+            hierarchy,
+            change_tick,
         }
     }
 
@@ -98,6 +116,11 @@ impl Archetype {
         self.index.contains_key(&id)
     }
 
+    /// Whether entities in this archetype may be linked to a hierarchy
+    pub(crate) fn has_hierarchy(&self) -> bool {
+        self.hierarchy
+    }
+
     /// Find the state index associated with `T`, if present
     pub(crate) fn get_state<T: Component>(&self) -> Option<usize> {
         self.index.get(&TypeId::of::<T>()).copied()
@@ -128,6 +151,58 @@ impl Archetype {
         }
     }
 
+    #[inline]
+    pub(crate) fn get_change_ticks(&self, state: usize) -> NonNull<u32> {
+        unsafe {
+            NonNull::new_unchecked(self.data.get_unchecked(state).change_ticks.as_ptr() as *mut u32)
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_added_ticks(&self, state: usize) -> NonNull<u32> {
+        unsafe {
+            NonNull::new_unchecked(self.data.get_unchecked(state).added_ticks.as_ptr() as *mut u32)
+        }
+    }
+
+    /// The counter new change ticks should be drawn from
+    #[inline]
+    pub(crate) fn change_tick(&self) -> &AtomicU32 {
+        &self.change_tick.current
+    }
+
+    /// The tick that [`ChangedSince`](crate::ChangedSince) compares against
+    #[inline]
+    pub(crate) fn last_change_tick(&self) -> u32 {
+        self.change_tick.last_run.load(Ordering::Relaxed)
+    }
+
+    /// Share `change_tick` with another archetype, stamping every entity as freshly added
+    pub(crate) fn set_change_tick(&mut self, change_tick: Arc<ChangeTicks>) {
+        self.change_tick = change_tick;
+        let tick = next_tick(&self.change_tick.current);
+        for data in self.data.iter_mut() {
+            data.change_ticks[..self.len as usize].fill(tick);
+            data.added_ticks[..self.len as usize].fill(tick);
+        }
+    }
+
+    /// Clamp change ticks older than `max_age` relative to `current`, so they don't appear new
+    /// once the tick counter wraps around
+    pub(crate) fn check_change_ticks(&mut self, current: u32, max_age: u32) {
+        for data in self.data.iter_mut() {
+            let len = self.len as usize;
+            for tick in data.change_ticks[..len]
+                .iter_mut()
+                .chain(&mut data.added_ticks[..len])
+            {
+                if current.wrapping_sub(*tick) > max_age {
+                    *tick = current.wrapping_sub(max_age);
+                }
+            }
+        }
+    }
+
     #[allow(missing_docs)]
     pub(crate) fn clear_trackers(&mut self) {
         for data in self.data.iter_mut() {
@@ -137,7 +212,50 @@ impl Archetype {
             for added in data.added_entities.iter_mut() {
                 *added = false;
             }
+            *data.changed.get_mut() = false;
+        }
+    }
+
+    /// Reset the tracker state of the component identified by `state`
+    pub(crate) fn clear_trackers_for(&mut self, state: usize) {
+        let data = &mut self.data[state];
+        for mutated in data.mutated_entities.iter_mut() {
+            *mutated = false;
+        }
+        for added in data.added_entities.iter_mut() {
+            *added = false;
         }
+        *data.changed.get_mut() = false;
+    }
+
+    /// Whether any component identified by `state` may have been added or mutated since trackers
+    /// were last cleared
+    ///
+    /// Never `false` while any entity's added or mutated flag is set, letting filters skip the
+    /// whole archetype.
+    #[inline]
+    pub(crate) fn may_have_changed(&self, state: usize) -> bool {
+        unsafe {
+            self.data
+                .get_unchecked(state)
+                .changed
+                .load(Ordering::Relaxed)
+        }
+    }
+
+    /// Record that a component identified by `state` is being flagged as mutated
+    ///
+    /// Must be called by anything that sets a mutated flag through [`get_mutated`](Self::get_mutated).
+    #[inline]
+    pub(crate) fn mark_changed(&self, state: usize) {
+        set_flag(unsafe { &self.data.get_unchecked(state).changed });
+    }
+
+    /// The flag set by [`mark_changed`](Self::mark_changed), for borrows that record mutations
+    /// lazily
+    #[inline]
+    pub(crate) fn changed_flag(&self, state: usize) -> NonNull<AtomicBool> {
+        unsafe { NonNull::from(&self.data.get_unchecked(state).changed) }
     }
 
     /// Get the address of the first `T` component using an index from `get_state::<T>`
@@ -145,26 +263,104 @@ impl Archetype {
         assert_eq!(self.types[state].id, TypeId::of::<T>());
 
         unsafe {
-            NonNull::new_unchecked(
-                self.data.get_unchecked(state).storage.as_ptr().cast::<T>() as *mut T
-            )
+            NonNull::new_unchecked(self.data.get_unchecked(state).storage.as_ptr().cast::<T>())
         }
     }
 
     /// Get the `T` components of these entities, if present
     ///
-    /// Useful for efficient serialization.
+    /// The column is index-aligned with [`ids`](Self::ids). Useful for efficient serialization.
+    /// Panics if the column is uniquely borrowed, e.g. by a query.
     pub fn get<T: Component>(&self) -> Option<ColumnRef<'_, T>> {
         let state = self.get_state::<T>()?;
         let ptr = self.get_base::<T>(state);
-        let column = unsafe { slice::from_raw_parts_mut(ptr.as_ptr(), self.len as usize) };
         self.borrow::<T>(state);
+        let column = unsafe { slice::from_raw_parts(ptr.as_ptr(), self.len as usize) };
         Some(ColumnRef {
             archetype: self,
             column,
         })
     }
 
+    /// Uniquely borrow the `T` components of these entities, if present
+    ///
+    /// The column is index-aligned with [`ids`](Self::ids). Panics if the column is already
+    /// borrowed, e.g. by a query. Mutably dereferencing the result marks every component in the
+    /// column as mutated.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.spawn((1, true));
+    /// world.spawn((2, true));
+    /// for archetype in world.archetypes() {
+    ///     if let Some(mut column) = archetype.get_mut::<i32>() {
+    ///         column.copy_from_slice(&[10, 20]);
+    ///     }
+    /// }
+    /// assert_eq!(world.query::<&i32>().iter().map(|(_, &x)| x).sum::<i32>(), 30);
+    /// ```
+    pub fn get_mut<T: Component>(&self) -> Option<ColumnRefMut<'_, T>> {
+        let state = self.get_state::<T>()?;
+        let ptr = self.get_base::<T>(state);
+        self.borrow_mut::<T>(state);
+        let column = unsafe { slice::from_raw_parts_mut(ptr.as_ptr(), self.len as usize) };
+        Some(ColumnRefMut {
+            archetype: self,
+            state,
+            column,
+            recorded: false,
+        })
+    }
+
+    /// Get the [`Added`](crate::Added) flags of these entities' `T` components, if present
+    ///
+    /// The flags are index-aligned with [`ids`](Self::ids), and reflect the state at the time of
+    /// the call. [`World::clear_trackers`](crate::World::clear_trackers) resets them all to
+    /// `false`. Holds a shared borrow of the `T` column, so panics if the column is uniquely
+    /// borrowed, e.g. by a query.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.spawn((1,));
+    /// world.clear_trackers();
+    /// let b = world.spawn((2,));
+    /// for archetype in world.archetypes() {
+    ///     if let Some(added) = archetype.added_flags::<i32>() {
+    ///         assert_eq!(*added, [false, true]);
+    ///         assert_eq!(archetype.ids()[1], b.id());
+    ///     }
+    /// }
+    /// ```
+    pub fn added_flags<T: Component>(&self) -> Option<FlagsRef<'_>> {
+        let state = self.get_state::<T>()?;
+        Some(self.borrow_flags::<T>(state, self.get_added(state)))
+    }
+
+    /// Get the [`Mutated`](crate::Mutated) flags of these entities' `T` components, if present
+    ///
+    /// The flags are index-aligned with [`ids`](Self::ids), and reflect the state at the time of
+    /// the call. [`World::clear_trackers`](crate::World::clear_trackers) resets them all to
+    /// `false`. Holds a shared borrow of the `T` column, so panics if the column is uniquely
+    /// borrowed, e.g. by a query.
+    pub fn mutated_flags<T: Component>(&self) -> Option<FlagsRef<'_>> {
+        let state = self.get_state::<T>()?;
+        Some(self.borrow_flags::<T>(state, self.get_mutated(state)))
+    }
+
+    fn borrow_flags<T: Component>(&self, state: usize, flags: NonNull<bool>) -> FlagsRef<'_> {
+        // Flags are only written under a unique borrow of their column
+        self.borrow::<T>(state);
+        FlagsRef {
+            archetype: self,
+            state,
+            flags: unsafe { slice::from_raw_parts(flags.as_ptr(), self.len as usize) },
+        }
+    }
+
     pub(crate) fn borrow<T: Component>(&self, state: usize) {
         assert_eq!(self.types[state].id, TypeId::of::<T>());
 
@@ -181,6 +377,31 @@ impl Archetype {
         }
     }
 
+    /// Borrow the column identified by `state` without static type information, returning `false`
+    /// if that would conflict with an outstanding borrow
+    pub(crate) fn borrow_dynamic(&self, state: usize, unique: bool) -> bool {
+        let borrow = &self.data[state].state;
+        if unique {
+            borrow.borrow_mut()
+        } else {
+            borrow.borrow()
+        }
+    }
+
+    pub(crate) fn release_dynamic(&self, state: usize, unique: bool) {
+        let borrow = &self.data[state].state;
+        if unique {
+            borrow.release_mut();
+        } else {
+            borrow.release();
+        }
+    }
+
+    /// Get the address of the first component in the column identified by `state`
+    pub(crate) fn get_base_dynamic(&self, state: usize) -> NonNull<u8> {
+        unsafe { NonNull::new_unchecked(self.data[state].storage.as_ptr()) }
+    }
+
     pub(crate) fn release<T: Component>(&self, state: usize) {
         assert_eq!(self.types[state].id, TypeId::of::<T>());
         self.data[state].state.release();
@@ -271,6 +492,10 @@ impl Archetype {
     pub(crate) unsafe fn set_len(&mut self, len: u32) {
         debug_assert!(len <= self.capacity());
         self.len = len;
+        // Vacant slots are flagged as added
+        for data in self.data.iter_mut() {
+            *data.changed.get_mut() |= len != 0;
+        }
     }
 
     pub(crate) fn reserve(&mut self, additional: u32) {
@@ -280,10 +505,57 @@ impl Archetype {
         }
     }
 
-    pub(crate) fn capacity(&self) -> u32 {
+    pub(crate) fn reserve_exact(&mut self, additional: u32) {
+        if additional > (self.capacity() - self.len()) {
+            self.grow_exact(additional - (self.capacity() - self.len()));
+        }
+    }
+
+    /// Number of entities this archetype can hold without reallocating
+    ///
+    /// See [`World::reserve`](crate::World::reserve).
+    pub fn capacity(&self) -> u32 {
         self.entities.len() as u32
     }
 
+    /// Estimate of the heap memory in bytes owned by this archetype
+    ///
+    /// Counts storage for [`capacity`](Self::capacity) components of each type, their change
+    /// tracking state, the entity IDs, and internal indices, but not heap memory owned by the
+    /// components themselves, e.g. the contents of a `Vec` component.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((1u64, true));
+    /// let (archetype, _) = world.location(e).unwrap();
+    /// let archetype = world.archetype(archetype).unwrap();
+    /// assert!(archetype.memory_usage() >= archetype.capacity() as usize * 9);
+    /// ```
+    pub fn memory_usage(&self) -> usize {
+        let capacity = self.capacity() as usize;
+        let columns = self
+            .types
+            .iter()
+            .zip(&*self.data)
+            .map(|(ty, data)| {
+                ty.layout.size() * capacity
+                    + data.mutated_entities.capacity()
+                    + data.added_entities.capacity()
+                    + (data.change_ticks.capacity() + data.added_ticks.capacity())
+                        * mem::size_of::<u32>()
+            })
+            .sum::<usize>();
+        columns
+            + self.types.capacity() * mem::size_of::<TypeInfo>()
+            + self.index.0.len() * mem::size_of::<(TypeId, usize)>()
+            + self.entities.len() * mem::size_of::<u32>()
+            + self.data.len() * mem::size_of::<Data>()
+            + hash_map_memory(&self.remove_edges)
+            + self.component_bits.memory_usage()
+    }
+
     /// Increase capacity by at least `min_increment`
     fn grow(&mut self, min_increment: u32) {
         // Double capacity or increase it by `min_increment`, whichever is larger.
@@ -292,10 +564,23 @@ impl Archetype {
 
     /// Increase capacity by exactly `increment`
     fn grow_exact(&mut self, increment: u32) {
+        self.set_capacity(self.entities.len() + increment as usize);
+    }
+
+    /// Release storage beyond what's needed for the current entities
+    pub(crate) fn shrink_to_fit(&mut self) {
+        if self.capacity() > self.len {
+            self.set_capacity(self.len as usize);
+        }
+        self.remove_edges.shrink_to_fit();
+    }
+
+    /// Reallocate storage to hold exactly `new_cap` entities, which must be at least `len`
+    fn set_capacity(&mut self, new_cap: usize) {
+        debug_assert!(new_cap >= self.len as usize);
         unsafe {
             let old_count = self.len as usize;
             let old_cap = self.entities.len();
-            let new_cap = self.entities.len() + increment as usize;
             let mut new_entities = vec![!0; new_cap].into_boxed_slice();
             new_entities[0..old_count].copy_from_slice(&self.entities[0..old_count]);
             self.entities = new_entities;
@@ -305,7 +590,7 @@ impl Archetype {
                 .iter()
                 .zip(&mut *self.data)
                 .map(|(info, old)| {
-                    let storage = if info.layout.size() == 0 {
+                    let storage = if info.layout.size() == 0 || new_cap == 0 {
                         NonNull::new(info.layout.align() as *mut u8).unwrap()
                     } else {
                         let mem = alloc(
@@ -320,27 +605,38 @@ impl Archetype {
                             mem,
                             info.layout.size() * old_count,
                         );
-                        if old_cap > 0 {
-                            dealloc(
-                                old.storage.as_ptr(),
-                                Layout::from_size_align(
-                                    info.layout.size() * old_cap,
-                                    info.layout.align(),
-                                )
-                                .unwrap(),
-                            );
-                        }
                         NonNull::new(mem).unwrap()
                     };
+                    if info.layout.size() != 0 && old_cap > 0 {
+                        dealloc(
+                            old.storage.as_ptr(),
+                            Layout::from_size_align(
+                                info.layout.size() * old_cap,
+                                info.layout.align(),
+                            )
+                            .unwrap(),
+                        );
+                    }
                     let mut mutated_entities = old.mutated_entities.split_off(0);
                     mutated_entities.resize_with(new_cap, || false);
+                    mutated_entities.shrink_to_fit();
                     let mut added_entities = old.added_entities.split_off(0);
                     added_entities.resize_with(new_cap, || true);
+                    added_entities.shrink_to_fit();
+                    let mut change_ticks = old.change_ticks.split_off(0);
+                    change_ticks.resize(new_cap, 0);
+                    change_ticks.shrink_to_fit();
+                    let mut added_ticks = old.added_ticks.split_off(0);
+                    added_ticks.resize(new_cap, 0);
+                    added_ticks.shrink_to_fit();
                     Data {
                         state: AtomicBorrow::new(), // &mut self guarantees no outstanding borrows
                         storage,
                         mutated_entities,
                         added_entities,
+                        changed: AtomicBool::new(*old.changed.get_mut()),
+                        change_ticks,
+                        added_ticks,
                     }
                 })
                 .collect::<Box<[_]>>();
@@ -362,6 +658,8 @@ impl Archetype {
                 ptr::copy_nonoverlapping(moved, removed, ty.layout.size());
                 data.mutated_entities[index as usize] = data.mutated_entities[last as usize];
                 data.added_entities[index as usize] = data.added_entities[last as usize];
+                data.change_ticks[index as usize] = data.change_ticks[last as usize];
+                data.added_ticks[index as usize] = data.added_ticks[last as usize];
             }
         }
         self.len = last;
@@ -377,19 +675,29 @@ impl Archetype {
     pub(crate) unsafe fn move_to(
         &mut self,
         index: u32,
-        mut f: impl FnMut(*mut u8, TypeId, usize, bool, bool),
+        mut f: impl FnMut(*mut u8, TypeId, usize, bool, bool, u32),
     ) -> Option<u32> {
         let last = self.len - 1;
         for (ty, data) in self.types.iter().zip(&mut *self.data) {
             let moved_out = data.storage.as_ptr().add(index as usize * ty.layout.size());
             let is_added = data.added_entities[index as usize];
             let is_mutated = data.mutated_entities[index as usize];
-            f(moved_out, ty.id(), ty.layout().size(), is_added, is_mutated);
+            let change_tick = data.change_ticks[index as usize];
+            f(
+                moved_out,
+                ty.id(),
+                ty.layout().size(),
+                is_added,
+                is_mutated,
+                change_tick,
+            );
             if index != last {
                 let moved = data.storage.as_ptr().add(last as usize * ty.layout.size());
                 ptr::copy_nonoverlapping(moved, moved_out, ty.layout.size());
                 data.added_entities[index as usize] = data.added_entities[last as usize];
                 data.mutated_entities[index as usize] = data.mutated_entities[last as usize];
+                data.change_ticks[index as usize] = data.change_ticks[last as usize];
+                data.added_ticks[index as usize] = data.added_ticks[last as usize];
             }
         }
         self.len -= 1;
@@ -401,22 +709,82 @@ impl Archetype {
         }
     }
 
+    /// Move the entity at `index` into the allocated but unwritten slot `target_index` of
+    /// `target`, returning the ID of the entity moved into `index`, if any
+    ///
+    /// Components `target` shares are moved along with their tracking state. Both archetypes'
+    /// columns are walked together in their common sorted order, so no types need to be looked up.
+    /// `f` is given the type and address of each other component, which it must move out or drop.
+    pub(crate) unsafe fn migrate(
+        &mut self,
+        index: u32,
+        target: &mut Archetype,
+        target_index: u32,
+        mut f: impl FnMut(&TypeInfo, *mut u8),
+    ) -> Option<u32> {
+        let (index, target_index) = (index as usize, target_index as usize);
+        let last = self.len as usize - 1;
+        let mut dst = target.types.iter().zip(&mut *target.data).peekable();
+        for (ty, data) in self.types.iter().zip(&mut *self.data) {
+            let size = ty.layout.size();
+            let src = data.storage.as_ptr().add(index * size);
+            while dst.next_if(|(x, _)| *x < ty).is_some() {}
+            match dst.peek_mut() {
+                Some((x, dst)) if x.id == ty.id => {
+                    ptr::copy_nonoverlapping(
+                        src,
+                        dst.storage.as_ptr().add(target_index * size),
+                        size,
+                    );
+                    dst.added_entities[target_index] = data.added_entities[index];
+                    dst.mutated_entities[target_index] = data.mutated_entities[index];
+                    *dst.changed.get_mut() |=
+                        data.added_entities[index] || data.mutated_entities[index];
+                    dst.change_ticks[target_index] = data.change_ticks[index];
+                    dst.added_ticks[target_index] = data.added_ticks[index];
+                }
+                _ => f(ty, src),
+            }
+            if index != last {
+                ptr::copy_nonoverlapping(data.storage.as_ptr().add(last * size), src, size);
+                data.added_entities[index] = data.added_entities[last];
+                data.mutated_entities[index] = data.mutated_entities[last];
+                data.change_ticks[index] = data.change_ticks[last];
+                data.added_ticks[index] = data.added_ticks[last];
+            }
+        }
+        self.len -= 1;
+        if index != last {
+            self.entities[index] = self.entities[last];
+            Some(self.entities[last])
+        } else {
+            None
+        }
+    }
+
     pub(crate) unsafe fn put_dynamic(
         &mut self,
         component: *mut u8,
         ty: TypeId,
-        size: usize,
         index: u32,
         added: bool,
         mutated: bool,
+        change_tick: u32,
     ) {
-        let data = self.data.get_unchecked_mut(*self.index.get(&ty).unwrap());
+        let state = *self.index.get(&ty).unwrap();
+        let size = self.types.get_unchecked(state).layout.size();
+        let data = self.data.get_unchecked_mut(state);
         if added {
             data.added_entities[index as usize] = true;
+            data.added_ticks[index as usize] = change_tick;
+            // The slot may have been vacated by an entity that was mutated
+            data.mutated_entities[index as usize] = false;
         }
         if mutated {
             data.mutated_entities[index as usize] = true;
         }
+        *data.changed.get_mut() |= added || mutated;
+        data.change_ticks[index as usize] = change_tick;
         let ptr = self
             .get_dynamic(ty, size, index)
             .unwrap()
@@ -425,6 +793,40 @@ impl Archetype {
         ptr::copy_nonoverlapping(component, ptr, size);
     }
 
+    /// Clone the `ty` component of the entity at `src_index` in `src` into the slot at `dst_index`,
+    /// along with its tracking state
+    ///
+    /// # Safety
+    ///
+    /// Both archetypes must have `ty` components, `src_index` must be in bounds for `src`,
+    /// `dst_index` must be within this archetype's capacity and vacant, and `clone` must read and
+    /// write values of type `ty`.
+    pub(crate) unsafe fn clone_component(
+        &mut self,
+        src: &Archetype,
+        src_index: u32,
+        dst_index: u32,
+        ty: &TypeInfo,
+        clone: unsafe fn(*const u8, *mut u8),
+    ) {
+        let size = ty.layout.size();
+        let src_state = src.index.get(&ty.id).copied().unwrap();
+        let src_data = &src.data[src_state];
+        let state = *self.index.get(&ty.id).unwrap();
+        let data = &mut self.data[state];
+        clone(
+            src_data.storage.as_ptr().add(size * src_index as usize),
+            data.storage.as_ptr().add(size * dst_index as usize),
+        );
+        let (src_index, dst_index) = (src_index as usize, dst_index as usize);
+        data.added_entities[dst_index] = src_data.added_entities[src_index];
+        data.mutated_entities[dst_index] = src_data.mutated_entities[src_index];
+        *data.changed.get_mut() |=
+            src_data.added_entities[src_index] || src_data.mutated_entities[src_index];
+        data.change_ticks[dst_index] = src_data.change_ticks[src_index];
+        data.added_ticks[dst_index] = src_data.added_ticks[src_index];
+    }
+
     /// How, if at all, `Q` will access entities in this archetype
     pub fn access<Q: Query>(&self) -> Option<Access> {
         Q::Fetch::access(self)
@@ -437,19 +839,76 @@ impl Archetype {
     /// Component types must match exactly.
     pub(crate) unsafe fn merge(&mut self, mut other: Archetype) {
         self.reserve(other.len);
-        for ((info, dst), src) in self.types.iter().zip(&*self.data).zip(&*other.data) {
+        let tick = next_tick(&self.change_tick.current);
+        for ((info, dst), src) in self.types.iter().zip(&mut *self.data).zip(&*other.data) {
             dst.storage
                 .as_ptr()
                 .add(self.len as usize * info.layout.size())
                 .copy_from_nonoverlapping(
                     src.storage.as_ptr(),
                     other.len as usize * info.layout.size(),
-                )
+                );
+            let range = self.len as usize..(self.len + other.len) as usize;
+            dst.change_ticks[range.clone()].fill(tick);
+            dst.added_ticks[range.clone()].fill(tick);
+            dst.added_entities[range.clone()].fill(true);
+            dst.mutated_entities[range].fill(false);
+            *dst.changed.get_mut() |= other.len != 0;
         }
         self.len += other.len;
         other.len = 0;
     }
 
+    /// Move every entity into `target`, returning the index in `target` of the first one
+    ///
+    /// Columns `target` shares are copied wholesale along with their tracking state. `f` is given
+    /// the type and base pointer of each other column, and is responsible for moving out or
+    /// dropping all `len` of its components.
+    ///
+    /// # Safety
+    ///
+    /// Every component type of `target` must be present in this archetype.
+    pub(crate) unsafe fn move_all_to(
+        &mut self,
+        target: &mut Archetype,
+        mut f: impl FnMut(&TypeInfo, *mut u8),
+    ) -> u32 {
+        target.reserve(self.len);
+        let (start, len) = (target.len as usize, self.len as usize);
+        for (info, src) in self.types.iter().zip(&*self.data) {
+            let state = match target.index.get(&info.id) {
+                Some(&x) => x,
+                None => {
+                    f(info, src.storage.as_ptr());
+                    continue;
+                }
+            };
+            let dst = &mut target.data[state];
+            dst.storage
+                .as_ptr()
+                .add(start * info.layout.size())
+                .copy_from_nonoverlapping(src.storage.as_ptr(), len * info.layout.size());
+            dst.added_entities[start..start + len].copy_from_slice(&src.added_entities[..len]);
+            dst.mutated_entities[start..start + len].copy_from_slice(&src.mutated_entities[..len]);
+            *dst.changed.get_mut() |= src.changed.load(Ordering::Relaxed);
+            dst.change_ticks[start..start + len].copy_from_slice(&src.change_ticks[..len]);
+            dst.added_ticks[start..start + len].copy_from_slice(&src.added_ticks[..len]);
+        }
+        target.entities[start..start + len].copy_from_slice(&self.entities[..len]);
+        target.len += self.len;
+        self.len = 0;
+        start as u32
+    }
+
+    /// Report every component as newly added and unmutated, e.g. when moving into another world
+    pub(crate) fn mark_added(&mut self) {
+        for data in self.data.iter_mut() {
+            data.added_entities[..self.len as usize].fill(true);
+            data.mutated_entities[..self.len as usize].fill(false);
+            *data.changed.get_mut() |= self.len != 0;
+        }
+    }
+
     /// Raw IDs of the entities in this archetype
     ///
     /// Convertible into [`Entity`](crate::Entity)s with
@@ -464,7 +923,7 @@ impl Archetype {
 impl Drop for Archetype {
     fn drop(&mut self) {
         self.clear();
-        if self.entities.len() == 0 {
+        if self.entities.is_empty() {
             return;
         }
         for (info, data) in self.types.iter().zip(&*self.data) {
@@ -488,6 +947,43 @@ struct Data {
     storage: NonNull<u8>,
     mutated_entities: Vec<bool>,
     added_entities: Vec<bool>,
+    /// Whether any of `mutated_entities` or `added_entities` may be set
+    changed: AtomicBool,
+    /// Tick at which each component was last added or mutated
+    change_ticks: Vec<u32>,
+    /// Tick at which each component was added
+    added_ticks: Vec<u32>,
+}
+
+/// Draw a fresh tick from a world's change tick counter
+#[inline]
+pub(crate) fn next_tick(change_tick: &AtomicU32) -> u32 {
+    change_tick.fetch_add(1, Ordering::Relaxed).wrapping_add(1)
+}
+
+/// Set `flag` without writing to it if it's already set, avoiding contention over its cache line
+#[inline]
+pub(crate) fn set_flag(flag: &AtomicBool) {
+    if !flag.load(Ordering::Relaxed) {
+        flag.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Whether `tick` is more recent than `last_run`, given that the counter currently reads `current`
+///
+/// Correct across wraparound so long as neither tick is more than `u32::MAX` ticks old.
+#[inline]
+pub(crate) fn is_tick_newer(tick: u32, last_run: u32, current: u32) -> bool {
+    current.wrapping_sub(tick) < current.wrapping_sub(last_run)
+}
+
+/// Change tick state shared by every archetype in a world
+#[derive(Default)]
+pub(crate) struct ChangeTicks {
+    /// Counter from which change ticks are drawn
+    pub current: AtomicU32,
+    /// Set by [`World::set_last_change_tick`](crate::World::set_last_change_tick)
+    pub last_run: AtomicU32,
 }
 
 /// A hasher optimized for hashing a single TypeId.
@@ -534,6 +1030,12 @@ impl Hasher for TypeIdHasher {
 /// faster no-op hash.
 pub(crate) type TypeIdMap<V> = HashMap<TypeId, V, BuildHasherDefault<TypeIdHasher>>;
 
+/// Estimate of the heap memory in bytes used by the table of `map`, assuming a control byte per
+/// entry, excluding memory owned by the keys and values
+pub(crate) fn hash_map_memory<K, V, S>(map: &HashMap<K, V, S>) -> usize {
+    map.capacity() * (mem::size_of::<(K, V)>() + 1)
+}
+
 struct OrderedTypeIdMap<V>(Box<[(TypeId, V)]>);
 
 impl<V> OrderedTypeIdMap<V> {
@@ -556,6 +1058,108 @@ impl<V> OrderedTypeIdMap<V> {
     }
 }
 
+/// A set of component types, as bits indexed by a [`ComponentRegistry`]
+///
+/// Words past the end are implicitly zero, so sets built before a component type was registered
+/// remain valid without being extended.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ComponentSet(Vec<u64>);
+
+impl ComponentSet {
+    pub fn insert(&mut self, index: u32) {
+        let word = index as usize / 64;
+        if word >= self.0.len() {
+            self.0.resize(word + 1, 0);
+        }
+        self.0[word] |= 1 << (index % 64);
+    }
+
+    /// Whether every element of `other` is also in `self`
+    #[inline]
+    pub fn contains_all(&self, other: &ComponentSet) -> bool {
+        other
+            .0
+            .iter()
+            .enumerate()
+            .all(|(i, &x)| self.0.get(i).map_or(0, |&y| y) & x == x)
+    }
+
+    /// Whether any element of `other` is also in `self`
+    #[inline]
+    pub fn intersects(&self, other: &ComponentSet) -> bool {
+        self.0.iter().zip(&other.0).any(|(&x, &y)| x & y != 0)
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        self.0.capacity() * mem::size_of::<u64>()
+    }
+}
+
+/// Dense indices for a world's component types, assigned in the order they're first seen
+#[derive(Default)]
+pub(crate) struct ComponentRegistry {
+    indices: TypeIdMap<u32>,
+}
+
+impl ComponentRegistry {
+    pub fn get(&self, id: TypeId) -> Option<u32> {
+        self.indices.get(&id).copied()
+    }
+
+    /// Compute the set of `types`, assigning fresh indices to any not seen before
+    pub fn register(&mut self, types: &[TypeInfo]) -> ComponentSet {
+        let mut set = ComponentSet::default();
+        for ty in types {
+            let next = self.indices.len() as u32;
+            set.insert(*self.indices.entry(ty.id).or_insert(next));
+        }
+        set
+    }
+
+    pub fn memory_usage(&self) -> usize {
+        hash_map_memory(&self.indices)
+    }
+}
+
+/// Component types an archetype must and must not have to match a query, as reported by
+/// [`Fetch::for_each_requirement`]
+///
+/// Lets queries reject most archetypes that can't match with a few bitwise operations, rather
+/// than looking up each component.
+#[derive(Clone)]
+pub(crate) struct ArchetypeFilter {
+    include: ComponentSet,
+    exclude: ComponentSet,
+    /// Whether a required component type has never been registered, so no archetype can match
+    unsatisfiable: bool,
+}
+
+impl ArchetypeFilter {
+    pub fn new<Q: Query>(registry: &ComponentRegistry) -> Self {
+        let mut filter = Self {
+            include: ComponentSet::default(),
+            exclude: ComponentSet::default(),
+            unsatisfiable: false,
+        };
+        Q::Fetch::for_each_requirement(|id, required| match (registry.get(id), required) {
+            (Some(index), true) => filter.include.insert(index),
+            (Some(index), false) => filter.exclude.insert(index),
+            (None, true) => filter.unsatisfiable = true,
+            (None, false) => {}
+        });
+        filter
+    }
+
+    /// Whether the query might match `archetype`, leaving `Fetch::access` or `Fetch::prepare` to
+    /// decide
+    #[inline]
+    pub fn admits(&self, archetype: &Archetype) -> bool {
+        !self.unsatisfiable
+            && archetype.component_bits.contains_all(&self.include)
+            && !archetype.component_bits.intersects(&self.exclude)
+    }
+}
+
 /// Metadata required to store a component
 #[derive(Debug, Copy, Clone)]
 pub struct TypeInfo {
@@ -563,7 +1167,7 @@ pub struct TypeInfo {
     layout: Layout,
     drop: unsafe fn(*mut u8),
     #[cfg(debug_assertions)]
-    type_name: &'static str,
+    type_name: Option<&'static str>,
 }
 
 impl TypeInfo {
@@ -578,19 +1182,62 @@ impl TypeInfo {
             layout: Layout::new::<T>(),
             drop: drop_ptr::<T>,
             #[cfg(debug_assertions)]
-            type_name: core::any::type_name::<T>(),
+            type_name: Some(core::any::type_name::<T>()),
+        }
+    }
+
+    /// Construct metadata for a type that isn't known statically
+    ///
+    /// Useful for storing components whose types are only known at runtime, e.g. when
+    /// deserializing or interfacing with a scripting language.
+    ///
+    /// # Safety
+    ///
+    /// `layout` must be the layout of the type identified by `id`, and `drop` must be sound to call
+    /// on a valid, suitably aligned value of that type. Components stored under `id` are read back
+    /// as that type, e.g. by [`World::get`](crate::World::get).
+    pub unsafe fn from_parts(id: TypeId, layout: Layout, drop: unsafe fn(*mut u8)) -> Self {
+        Self {
+            id,
+            layout,
+            drop,
+            #[cfg(debug_assertions)]
+            type_name: None,
         }
     }
 
-    pub(crate) fn id(&self) -> TypeId {
+    /// Unique identifier of the type
+    pub fn id(&self) -> TypeId {
         self.id
     }
 
-    pub(crate) fn layout(&self) -> Layout {
+    /// Size and alignment of the type
+    pub fn layout(&self) -> Layout {
         self.layout
     }
 
-    pub(crate) unsafe fn drop(&self, data: *mut u8) {
+    /// Name of the type, for diagnostics
+    ///
+    /// Only recorded in builds with debug assertions enabled, and never for types described with
+    /// [`from_parts`](Self::from_parts).
+    pub fn type_name(&self) -> Option<&'static str> {
+        #[cfg(debug_assertions)]
+        {
+            self.type_name
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            None
+        }
+    }
+
+    /// Drop the value at `data` in place
+    ///
+    /// # Safety
+    ///
+    /// `data` must point to a valid, suitably aligned value of this type, which must not be used
+    /// afterwards.
+    pub unsafe fn drop(&self, data: *mut u8) {
         (self.drop)(data)
     }
 }
@@ -656,3 +1303,81 @@ impl<T: Component + fmt::Debug> fmt::Debug for ColumnRef<'_, T> {
         self.column.fmt(f)
     }
 }
+
+/// Unique reference to a single column of component data in an [`Archetype`]
+pub struct ColumnRefMut<'a, T: Component> {
+    archetype: &'a Archetype,
+    /// State index for `T` in `archetype`
+    state: usize,
+    column: &'a mut [T],
+    /// Whether the change has already been recorded
+    recorded: bool,
+}
+
+impl<T: Component> Deref for ColumnRefMut<'_, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        self.column
+    }
+}
+
+impl<T: Component> DerefMut for ColumnRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        if !self.recorded {
+            let tick = next_tick(self.archetype.change_tick());
+            self.archetype.mark_changed(self.state);
+            let mutated = self.archetype.get_mutated(self.state).as_ptr();
+            let change_ticks = self.archetype.get_change_ticks(self.state).as_ptr();
+            unsafe {
+                for i in 0..self.column.len() {
+                    *mutated.add(i) = true;
+                    *change_ticks.add(i) = tick;
+                }
+            }
+            self.recorded = true;
+        }
+        self.column
+    }
+}
+
+impl<T: Component> Drop for ColumnRefMut<'_, T> {
+    fn drop(&mut self) {
+        self.archetype.release_mut::<T>(self.state);
+    }
+}
+
+/// Shared reference to the change tracking flags of a single column in an [`Archetype`]
+///
+/// Obtained from [`Archetype::added_flags`] or [`Archetype::mutated_flags`]. Holds a shared borrow
+/// of the column, preventing the flags from changing while it's live.
+pub struct FlagsRef<'a> {
+    archetype: &'a Archetype,
+    /// State index of the column the flags belong to
+    state: usize,
+    flags: &'a [bool],
+}
+
+impl Deref for FlagsRef<'_> {
+    type Target = [bool];
+    fn deref(&self) -> &[bool] {
+        self.flags
+    }
+}
+
+impl Drop for FlagsRef<'_> {
+    fn drop(&mut self) {
+        self.archetype.release_dynamic(self.state, false);
+    }
+}
+
+impl fmt::Debug for FlagsRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.flags.fmt(f)
+    }
+}
+
+impl<T: Component + fmt::Debug> fmt::Debug for ColumnRefMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.column.fmt(f)
+    }
+}
@@ -0,0 +1,147 @@
+use core::any::{type_name, TypeId};
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::NonNull;
+
+use crate::archetype::next_tick;
+use crate::{Access, Archetype, Component, Fetch, Query};
+
+/// Components that [`AtomicRef`] can view as their atomic counterparts
+///
+/// Implemented for primitive integers and `bool`, unless primitives aren't components because
+/// hecs was compiled with `--cfg hecs_explicit_components`.
+///
+/// # Safety
+///
+/// `Atomic` must have the same size and bit validity as `Self`, and accessing a `Self` through a
+/// shared reference to `Atomic` must be sound whenever a unique reference to `Self` would be.
+pub unsafe trait AtomicComponent: Component {
+    /// The atomic type with the same in-memory representation
+    type Atomic: Send + Sync + 'static;
+}
+
+macro_rules! atomic_component {
+    ($($width:literal: $($ty:ty => $atomic:ident),*;)*) => {
+        $($(
+            #[cfg(all(target_has_atomic = $width, not(hecs_explicit_components)))]
+            unsafe impl AtomicComponent for $ty {
+                type Atomic = core::sync::atomic::$atomic;
+            }
+        )*)*
+    };
+}
+
+atomic_component! {
+    "8": bool => AtomicBool, u8 => AtomicU8, i8 => AtomicI8;
+    "16": u16 => AtomicU16, i16 => AtomicI16;
+    "32": u32 => AtomicU32, i32 => AtomicI32;
+    "64": u64 => AtomicU64, i64 => AtomicI64;
+    "ptr": usize => AtomicUsize, isize => AtomicIsize;
+}
+
+/// Query that yields a shared reference to the atomic view of a `T` component
+///
+/// Lets many threads update the same components concurrently, e.g. to accumulate forces between
+/// pairs of entities, without a unique borrow per entity. The column is borrowed uniquely, like
+/// `&mut T`, so no non-atomic access can race with the atomic updates; unlike `&mut T`, the items
+/// are shared references that can be freely copied, collected, and sent to other threads for as
+/// long as the query is borrowed.
+///
+/// Every yielded component is recorded as [mutated](crate::Mutated) with a fresh
+/// [`ChangeTick`](crate::ChangeTick), whether or not it's actually written.
+///
+/// Panics when accessing an archetype if `T::Atomic` requires stricter alignment than `T`, as
+/// `u64` and `i64` do on some 32-bit targets.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// # use std::sync::atomic::Ordering;
+/// let mut world = World::new();
+/// let a = world.spawn((1u32,));
+/// let b = world.spawn((2u32,));
+/// let mut query = world.query::<AtomicRef<u32>>();
+/// let counters = query.iter().map(|(_, x)| x).collect::<Vec<_>>();
+/// std::thread::scope(|s| {
+///     for _ in 0..4 {
+///         s.spawn(|| {
+///             for counter in &counters {
+///                 counter.fetch_add(10, Ordering::Relaxed);
+///             }
+///         });
+///     }
+/// });
+/// drop(query);
+/// assert_eq!(*world.get::<u32>(a).unwrap(), 41);
+/// assert_eq!(*world.get::<u32>(b).unwrap(), 42);
+/// ```
+pub struct AtomicRef<T>(PhantomData<fn(T)>);
+
+impl<T: AtomicComponent> Query for AtomicRef<T> {
+    type Fetch = FetchAtomicRef<T>;
+}
+
+#[doc(hidden)]
+pub struct FetchAtomicRef<T: AtomicComponent>(NonNull<T::Atomic>, NonNull<bool>, NonNull<u32>, u32);
+
+unsafe impl<'a, T: AtomicComponent> Fetch<'a> for FetchAtomicRef<T> {
+    type Item = &'a T::Atomic;
+
+    type State = usize;
+
+    fn dangling() -> Self {
+        Self(
+            NonNull::dangling(),
+            NonNull::dangling(),
+            NonNull::dangling(),
+            0,
+        )
+    }
+
+    fn access(archetype: &Archetype) -> Option<Access> {
+        if archetype.has::<T>() {
+            Some(Access::Write)
+        } else {
+            None
+        }
+    }
+
+    fn borrow(archetype: &Archetype, state: Self::State) {
+        archetype.borrow_mut::<T>(state);
+    }
+    fn prepare(archetype: &Archetype) -> Option<Self::State> {
+        assert!(
+            mem::align_of::<T::Atomic>() <= mem::align_of::<T>(),
+            "{} can't be accessed atomically on this target",
+            type_name::<T>()
+        );
+        archetype.get_state::<T>()
+    }
+    fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
+        // Every component fetched is recorded as mutated
+        archetype.mark_changed(state);
+        Self(
+            archetype.get_base::<T>(state).cast(),
+            archetype.get_mutated(state),
+            archetype.get_change_ticks(state),
+            next_tick(archetype.change_tick()),
+        )
+    }
+    fn release(archetype: &Archetype, state: Self::State) {
+        archetype.release_mut::<T>(state);
+    }
+
+    fn for_each_borrow(mut f: impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<T>(), type_name::<T>(), true);
+    }
+
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), true);
+    }
+
+    unsafe fn get(&self, n: usize) -> Self::Item {
+        *self.1.as_ptr().add(n) = true;
+        *self.2.as_ptr().add(n) = self.3;
+        &*self.0.as_ptr().add(n)
+    }
+}
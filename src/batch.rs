@@ -1,5 +1,5 @@
 use crate::alloc::collections::BinaryHeap;
-use core::{any::TypeId, fmt, mem::MaybeUninit, slice};
+use core::{any::TypeId, fmt, mem, mem::MaybeUninit, ptr, ptr::NonNull, slice};
 
 use crate::{
     archetype::{TypeIdMap, TypeInfo},
@@ -24,12 +24,20 @@ impl ColumnBatchType {
         self
     }
 
+    /// Update to include components of the type described by `ty`
+    ///
+    /// Like [`add`](Self::add), for types only known at runtime.
+    pub fn add_dynamic(&mut self, ty: TypeInfo) -> &mut Self {
+        self.types.push(ty);
+        self
+    }
+
     /// Construct a [`ColumnBatchBuilder`] for *exactly* `size` entities with these components
     pub fn into_batch(self, size: u32) -> ColumnBatchBuilder {
         let mut types = self.types.into_sorted_vec();
         types.dedup();
         let fill = TypeIdMap::with_capacity_and_hasher(types.len(), Default::default());
-        let mut arch = Archetype::new(types);
+        let mut arch = Archetype::new(types, Default::default());
         arch.reserve(size);
         ColumnBatchBuilder {
             fill,
@@ -61,12 +69,33 @@ impl ColumnBatchBuilder {
         let archetype = self.archetype.as_mut().unwrap();
         let state = archetype.get_state::<T>()?;
         let base = archetype.get_base::<T>(state);
+        let fill = self.fill.entry(TypeId::of::<T>()).or_insert(0);
         Some(BatchWriter {
-            fill: self.fill.entry(TypeId::of::<T>()).or_insert(0),
             storage: unsafe {
-                slice::from_raw_parts_mut(base.as_ptr().cast(), self.target_fill as usize)
-                    .iter_mut()
+                // Resume after any components written by previous writers
+                slice::from_raw_parts_mut(
+                    base.as_ptr().add(*fill as usize).cast(),
+                    (self.target_fill - *fill) as usize,
+                )
+                .iter_mut()
             },
+            fill,
+        })
+    }
+
+    /// Get a handle for inserting components of the type identified by `id`, if it was in the
+    /// [`ColumnBatchType`]
+    pub fn writer_dynamic(&mut self, id: TypeId) -> Option<DynamicBatchWriter<'_>> {
+        let archetype = self.archetype.as_mut().unwrap();
+        let state = archetype.get_state_by_id(&id)?;
+        let ty = archetype.types()[state];
+        let base = unsafe { archetype.get_dynamic(id, 0, 0).unwrap() };
+        let fill = self.fill.entry(id).or_insert(0);
+        Some(DynamicBatchWriter {
+            ty,
+            base,
+            fill,
+            target_fill: self.target_fill,
         })
     }
 
@@ -95,7 +124,7 @@ impl Drop for ColumnBatchBuilder {
                 unsafe {
                     let base = archetype.get_dynamic(ty.id(), 0, 0).unwrap();
                     for i in 0..fill {
-                        base.as_ptr().add(i as usize).drop_in_place()
+                        ty.drop(base.as_ptr().add(i as usize * ty.layout().size()));
                     }
                 }
             }
@@ -125,6 +154,59 @@ impl<T> BatchWriter<'_, T> {
         }
     }
 
+    /// Add as many components from `xs` as there's space for, returning how many were added
+    ///
+    /// Copies all of them at once, which is much faster than repeated [`push`](Self::push)es.
+    pub fn extend_from_slice(&mut self, xs: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        let storage = mem::take(&mut self.storage).into_slice();
+        let n = xs.len().min(storage.len());
+        let (dst, rest) = storage.split_at_mut(n);
+        unsafe {
+            ptr::copy_nonoverlapping(xs.as_ptr(), dst.as_mut_ptr().cast::<T>(), n);
+        }
+        self.storage = rest.iter_mut();
+        *self.fill += n as u32;
+        n
+    }
+
+    /// How many components have been added so far
+    pub fn fill(&self) -> u32 {
+        *self.fill
+    }
+}
+
+/// Handle for appending components whose type is only known at runtime
+pub struct DynamicBatchWriter<'a> {
+    ty: TypeInfo,
+    base: NonNull<u8>,
+    fill: &'a mut u32,
+    target_fill: u32,
+}
+
+impl DynamicBatchWriter<'_> {
+    /// Add a component if there's space remaining, returning `false` otherwise
+    ///
+    /// On success, the component is moved into the batch and `component` must not be used or
+    /// dropped afterwards.
+    ///
+    /// # Safety
+    ///
+    /// `component` must point to a valid, suitably aligned value of the type this writer was
+    /// obtained for.
+    pub unsafe fn push(&mut self, component: *mut u8) -> bool {
+        if *self.fill == self.target_fill {
+            return false;
+        }
+        let size = self.ty.layout().size();
+        let dst = self.base.as_ptr().add(*self.fill as usize * size);
+        ptr::copy_nonoverlapping(component, dst, size);
+        *self.fill += 1;
+        true
+    }
+
     /// How many components have been added so far
     pub fn fill(&self) -> u32 {
         *self.fill
@@ -158,4 +240,20 @@ mod tests {
         let mut writer = builder.writer::<usize>().unwrap();
         assert!(writer.push(42).is_err());
     }
+
+    #[test]
+    fn extend_from_slice() {
+        let mut types = ColumnBatchType::new();
+        types.add::<u32>();
+        let mut builder = types.into_batch(5);
+        let mut writer = builder.writer::<u32>().unwrap();
+        writer.push(0).unwrap();
+        assert_eq!(writer.extend_from_slice(&[1, 2]), 2);
+        assert_eq!(writer.extend_from_slice(&[3, 4, 5]), 2);
+        assert_eq!(writer.extend_from_slice(&[6]), 0);
+        assert_eq!(writer.fill(), 5);
+        assert!(writer.push(7).is_err());
+        let batch = builder.build().unwrap();
+        assert_eq!(*batch.0.get::<u32>().unwrap(), [0, 1, 2, 3, 4]);
+    }
 }
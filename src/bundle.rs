@@ -14,6 +14,11 @@ use crate::archetype::TypeInfo;
 use crate::Component;
 
 /// A dynamically typed collection of components
+///
+/// # Safety
+///
+/// Implementers must report the same set of types from `key`, `with_ids`, and `type_info`, and
+/// `put` must pass exactly one valid, owned value of each of those types.
 pub unsafe trait DynamicBundle {
     /// Returns a `TypeId` uniquely identifying the set of components, if known
     #[doc(hidden)]
@@ -25,9 +30,28 @@ pub unsafe trait DynamicBundle {
     #[doc(hidden)]
     fn with_ids<T>(&self, f: impl FnOnce(&[TypeId]) -> T) -> T;
 
-    /// Obtain the fields' TypeInfos, sorted by descending alignment then id
-    #[doc(hidden)]
+    /// Describe the components this bundle would add to an entity, without consuming it
+    ///
+    /// Reports exactly the components a subsequent `spawn` or `insert` would install, in the
+    /// canonical order used by archetypes: descending alignment, then [`TypeId`]. Useful for
+    /// validating bundles up front, e.g. rejecting component types unknown to an editor.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// # use std::any::TypeId;
+    /// let bundle = (true, 123u64, "abc");
+    /// let info = bundle.type_info();
+    /// assert_eq!(info.len(), 3);
+    /// assert!(info.iter().any(|x| x.id() == TypeId::of::<u64>()));
+    /// assert!(info.windows(2).all(|x| x[0].layout().align() >= x[1].layout().align()));
+    /// let mut world = World::new();
+    /// let e = world.spawn(bundle);
+    /// let archetype = world.entity(e).unwrap().component_types().collect::<Vec<_>>();
+    /// assert_eq!(archetype, info.iter().map(|x| x.id()).collect::<Vec<_>>());
+    /// ```
     fn type_info(&self) -> Vec<TypeInfo>;
+
     /// Allow a callback to move all components out of the bundle
     ///
     /// Must invoke `f` only with a valid pointer and the pointee's type and size. `put` may only be
@@ -37,6 +61,11 @@ pub unsafe trait DynamicBundle {
 }
 
 /// A statically typed collection of components
+///
+/// # Safety
+///
+/// `with_static_ids` and `static_type_info` must agree with the [`DynamicBundle`] impl, and `get`
+/// must take ownership of exactly one value of each reported type.
 pub unsafe trait Bundle: DynamicBundle {
     #[doc(hidden)]
     fn with_static_ids<T>(f: impl FnOnce(&[TypeId]) -> T) -> T;
@@ -143,4 +172,4 @@ macro_rules! count {
     ($x: ident $(, $rest: ident)*) => { 1 + count!($($rest),*) };
 }
 
-smaller_tuples_too!(tuple_impl, O, N, M, L, K, J, I, H, G, F, E, D, C, B, A);
+smaller_tuples_too!(tuple_impl, P, O, N, M, L, K, J, I, H, G, F, E, D, C, B, A);
@@ -0,0 +1,82 @@
+use core::any::TypeId;
+use core::fmt;
+use core::ptr;
+
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::archetype::TypeIdMap;
+use crate::Component;
+
+/// Functions for cloning components whose types are only known at runtime
+///
+/// Used by [`World::clone_with`](crate::World::clone_with), e.g. to snapshot a world for
+/// rollback.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut registry = CloneRegistry::new();
+/// registry.register::<i32>().register::<String>();
+/// let mut world = World::new();
+/// let e = world.spawn((123, "abc".to_string()));
+/// let snapshot = world.clone_with(&registry).unwrap();
+/// *world.get_mut::<i32>(e).unwrap() = 456;
+/// assert_eq!(*snapshot.get::<i32>(e).unwrap(), 123);
+/// ```
+#[derive(Default)]
+pub struct CloneRegistry {
+    cloners: TypeIdMap<unsafe fn(*const u8, *mut u8)>,
+    skip_unregistered: bool,
+}
+
+impl CloneRegistry {
+    /// Create a registry that can't clone any components
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow `T` components to be cloned
+    pub fn register<T: Component + Clone>(&mut self) -> &mut Self {
+        unsafe fn clone<T: Clone>(src: *const u8, dst: *mut u8) {
+            ptr::write(dst.cast::<T>(), (*src.cast::<T>()).clone());
+        }
+        self.cloners.insert(TypeId::of::<T>(), clone::<T>);
+        self
+    }
+
+    /// Whether components that weren't registered are left out of clones, rather than causing an
+    /// error
+    ///
+    /// Defaults to `false`.
+    pub fn skip_unregistered(&mut self, skip: bool) -> &mut Self {
+        self.skip_unregistered = skip;
+        self
+    }
+
+    /// Whether `T` components can be cloned
+    pub fn contains<T: Component>(&self) -> bool {
+        self.cloners.contains_key(&TypeId::of::<T>())
+    }
+
+    pub(crate) fn get(&self, id: TypeId) -> Option<unsafe fn(*const u8, *mut u8)> {
+        self.cloners.get(&id).copied()
+    }
+
+    pub(crate) fn skips_unregistered(&self) -> bool {
+        self.skip_unregistered
+    }
+}
+
+/// Error indicating that a world contains components that a [`CloneRegistry`] can't clone
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MissingCloner(pub TypeId);
+
+impl fmt::Display for MissingCloner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no cloner registered for component {:?}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for MissingCloner {}
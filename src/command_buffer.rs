@@ -0,0 +1,497 @@
+use crate::alloc::alloc::{alloc, dealloc, Layout};
+use crate::alloc::vec::Vec;
+use core::any::TypeId;
+use core::num::NonZeroU32;
+use core::ops::Range;
+use core::ptr::{self, NonNull};
+
+use crate::archetype::TypeInfo;
+use crate::{align, Bundle, Component, DynamicBundle, Entity, World};
+
+/// Records operations for future application to a [`World`]
+///
+/// Useful when operations cannot be applied directly due to ordering concerns or borrow checking.
+/// Commands are applied in the order they were recorded.
+///
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let e = world.spawn((123,));
+/// let mut cmd = CommandBuffer::new();
+/// for (entity, &n) in world.query::<&i32>().iter() {
+///     cmd.insert_one(entity, n > 100);
+/// }
+/// cmd.run_on(&mut world);
+/// assert_eq!(*world.get::<bool>(e).unwrap(), true);
+/// ```
+///
+/// The placeholder handle returned by [`spawn`](Self::spawn) refers to the new entity in later
+/// commands of the same buffer. Where the handle must remain valid after the buffer runs, e.g. to
+/// be stored in a component, use [`spawn_reserved`](Self::spawn_reserved), which reserves its ID
+/// from the world up front:
+///
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let mut cmd = CommandBuffer::new();
+/// let e = cmd.spawn_reserved(&world, (123, true));
+/// cmd.remove_one::<bool>(e);
+/// cmd.run_on(&mut world);
+/// assert_eq!(*world.get::<i32>(e).unwrap(), 123);
+/// assert!(world.get::<bool>(e).is_err());
+/// ```
+pub struct CommandBuffer {
+    cmds: Vec<Cmd>,
+    storage: NonNull<u8>,
+    layout: Layout,
+    cursor: usize,
+    components: Vec<ComponentInfo>,
+    ids: Vec<TypeId>,
+    /// Number of placeholder handles handed out by `spawn`
+    spawned: u32,
+}
+
+impl CommandBuffer {
+    /// Create an empty command buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    unsafe fn grow(
+        min_size: usize,
+        cursor: usize,
+        align: usize,
+        storage: NonNull<u8>,
+    ) -> (NonNull<u8>, Layout) {
+        let layout = Layout::from_size_align(min_size.next_power_of_two().max(64), align).unwrap();
+        let new_storage = NonNull::new_unchecked(alloc(layout));
+        ptr::copy_nonoverlapping(storage.as_ptr(), new_storage.as_ptr(), cursor);
+        (new_storage, layout)
+    }
+
+    unsafe fn add_inner(&mut self, ptr: *mut u8, ty: TypeInfo) {
+        let offset = align(self.cursor, ty.layout().align());
+        let end = offset + ty.layout().size();
+        if end > self.layout.size() || ty.layout().align() > self.layout.align() {
+            let new_align = self.layout.align().max(ty.layout().align());
+            let (new_storage, new_layout) = Self::grow(end, self.cursor, new_align, self.storage);
+            if self.layout.size() != 0 {
+                dealloc(self.storage.as_ptr(), self.layout);
+            }
+            self.storage = new_storage;
+            self.layout = new_layout;
+        }
+
+        let addr = self.storage.as_ptr().add(offset);
+        ptr::copy_nonoverlapping(ptr, addr, ty.layout().size());
+        self.components.push(ComponentInfo { ty, offset });
+        self.cursor = end;
+    }
+
+    /// Record the components in `components` and return the range they occupy
+    fn record(&mut self, components: impl DynamicBundle) -> Range<usize> {
+        let first_component = self.components.len();
+        unsafe {
+            components.put(|ptr, ty| self.add_inner(ptr, ty));
+        }
+        self.components[first_component..].sort_unstable_by_key(|c| c.ty);
+        first_component..self.components.len()
+    }
+
+    /// Add components from `components` to `entity`, if it exists
+    ///
+    /// If `entity` already has components of the same types, they are replaced. See
+    /// [`World::insert`].
+    pub fn insert(&mut self, entity: Entity, components: impl DynamicBundle) {
+        let components = self.record(components);
+        self.cmds.push(Cmd::SpawnOrInsert(EntityIndex {
+            entity: Some(entity),
+            components,
+        }));
+    }
+
+    /// Add `component` to `entity`, if it exists
+    ///
+    /// See [`insert`](Self::insert).
+    pub fn insert_one(&mut self, entity: Entity, component: impl Component) {
+        self.insert(entity, (component,));
+    }
+
+    /// Remove components from `entity` if they exist
+    ///
+    /// If any of the components in `T` are missing, none are removed. See [`World::remove`].
+    pub fn remove<T: Bundle + 'static>(&mut self, entity: Entity) {
+        fn remove_bundle_and_ignore_result<T: Bundle + 'static>(world: &mut World, entity: Entity) {
+            let _ = world.remove::<T>(entity);
+        }
+        self.cmds.push(Cmd::Remove(RemovedComps {
+            remove: remove_bundle_and_ignore_result::<T>,
+            entity,
+        }));
+    }
+
+    /// Remove a component from `entity` if it exists
+    ///
+    /// See [`remove`](Self::remove).
+    pub fn remove_one<T: Component>(&mut self, entity: Entity) {
+        self.remove::<(T,)>(entity);
+    }
+
+    /// Despawn `entity` from the world, if it exists
+    pub fn despawn(&mut self, entity: Entity) {
+        self.cmds.push(Cmd::Despawn(entity));
+    }
+
+    /// Spawn a new entity with `components`, returning a placeholder handle for it
+    ///
+    /// The placeholder may be passed to later commands in this buffer, which are applied to the
+    /// spawned entity when the buffer is run. It isn't a valid handle for any [`World`], and is
+    /// invalidated by [`run_on`](Self::run_on) and [`clear`](Self::clear); in particular, copies
+    /// stored inside components aren't updated. To obtain a handle that remains valid, use
+    /// [`spawn_reserved`](Self::spawn_reserved).
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let mut cmd = CommandBuffer::new();
+    /// let e = cmd.spawn((123,));
+    /// cmd.insert_one(e, true);
+    /// cmd.run_on(&mut world);
+    /// assert_eq!(world.query::<(&i32, &bool)>().iter().count(), 1);
+    /// ```
+    pub fn spawn(&mut self, components: impl DynamicBundle) -> Entity {
+        let components = self.record(components);
+        self.cmds.push(Cmd::SpawnOrInsert(EntityIndex {
+            entity: None,
+            components,
+        }));
+        let placeholder = Entity {
+            id: u32::MAX - self.spawned,
+            generation: PLACEHOLDER_GENERATION,
+        };
+        self.spawned += 1;
+        placeholder
+    }
+
+    /// Spawn a new entity with `components`, returning its handle immediately
+    ///
+    /// The handle is reserved from `world` with [`World::reserve_entity`], so this can be called
+    /// while `world` is borrowed, e.g. during a query, and the handle may be passed to later
+    /// commands in this buffer. The buffer must be run on the same `world`.
+    pub fn spawn_reserved(&mut self, world: &World, components: impl DynamicBundle) -> Entity {
+        let entity = world.reserve_entity();
+        self.insert(entity, components);
+        entity
+    }
+
+    /// Run recorded commands on `world`, clearing the command buffer
+    ///
+    /// All of `world`'s reserved entities are first [flushed](World::flush) in a single pass, so
+    /// every entity from [`spawn_reserved`](Self::spawn_reserved) exists, without components, before
+    /// any command is applied. Commands are then applied in the order they were recorded; in
+    /// particular, a reserved entity that's despawned before components are inserted into it stays
+    /// despawned, and the insertion is ignored.
+    ///
+    /// Commands referring to entities that don't exist when they're applied are ignored.
+    pub fn run_on(&mut self, world: &mut World) {
+        world.flush();
+        let mut cmds = core::mem::take(&mut self.cmds);
+        // Entities spawned so far, indexed by their placeholder
+        let mut spawned = Vec::with_capacity(self.spawned as usize);
+        for cmd in cmds.drain(..) {
+            match cmd {
+                Cmd::SpawnOrInsert(cmd) => match cmd.entity {
+                    Some(entity) => {
+                        // If the entity doesn't exist, its components are dropped by `clear`
+                        if let Some(entity) = self.resolve(&spawned, entity) {
+                            let components = self.build(cmd.components);
+                            let _ = world.insert(entity, components);
+                        }
+                    }
+                    None => {
+                        let components = self.build(cmd.components);
+                        spawned.push(world.spawn(components));
+                    }
+                },
+                Cmd::Remove(remove) => {
+                    if let Some(entity) = self.resolve(&spawned, remove.entity) {
+                        (remove.remove)(world, entity);
+                    }
+                }
+                Cmd::Despawn(entity) => {
+                    if let Some(entity) = self.resolve(&spawned, entity) {
+                        let _ = world.despawn(entity);
+                    }
+                }
+            }
+        }
+        // Keep the allocation for reuse
+        self.cmds = cmds;
+        self.clear();
+    }
+
+    /// Map a placeholder from `spawn` to the entity spawned for it, if any
+    fn resolve(&self, spawned: &[Entity], entity: Entity) -> Option<Entity> {
+        let index = u32::MAX - entity.id;
+        if entity.generation != PLACEHOLDER_GENERATION || index >= self.spawned {
+            return Some(entity);
+        }
+        spawned.get(index as usize).copied()
+    }
+
+    fn build(&mut self, components: Range<usize>) -> RecordedEntity<'_> {
+        self.ids.clear();
+        self.ids.extend(
+            self.components[components.clone()]
+                .iter()
+                .map(|x| x.ty.id()),
+        );
+        RecordedEntity {
+            cmd: self,
+            components,
+        }
+    }
+
+    /// Drop all recorded commands
+    pub fn clear(&mut self) {
+        self.ids.clear();
+        self.cursor = 0;
+        for info in self.components.drain(..) {
+            // Components consumed by `run_on` have already been replaced by an empty type
+            unsafe {
+                info.ty.drop(self.storage.as_ptr().add(info.offset));
+            }
+        }
+        self.cmds.clear();
+        self.spawned = 0;
+    }
+}
+
+/// Generation of placeholder handles returned by [`CommandBuffer::spawn`], whose IDs count down
+/// from `u32::MAX`, far beyond any live entity's
+const PLACEHOLDER_GENERATION: NonZeroU32 = match NonZeroU32::new(u32::MAX) {
+    Some(x) => x,
+    None => unreachable!(),
+};
+
+unsafe impl Send for CommandBuffer {}
+unsafe impl Sync for CommandBuffer {}
+
+impl Drop for CommandBuffer {
+    fn drop(&mut self) {
+        self.clear();
+        if self.layout.size() != 0 {
+            unsafe {
+                dealloc(self.storage.as_ptr(), self.layout);
+            }
+        }
+    }
+}
+
+impl Default for CommandBuffer {
+    /// Create an empty command buffer
+    fn default() -> Self {
+        Self {
+            cmds: Vec::new(),
+            storage: NonNull::dangling(),
+            layout: Layout::from_size_align(0, 8).unwrap(),
+            cursor: 0,
+            components: Vec::new(),
+            ids: Vec::new(),
+            spawned: 0,
+        }
+    }
+}
+
+/// The output of a [`CommandBuffer`] suitable for passing to [`World::spawn`] or
+/// [`World::insert`]
+struct RecordedEntity<'a> {
+    cmd: &'a mut CommandBuffer,
+    components: Range<usize>,
+}
+
+unsafe impl DynamicBundle for RecordedEntity<'_> {
+    fn with_ids<T>(&self, f: impl FnOnce(&[TypeId]) -> T) -> T {
+        f(&self.cmd.ids)
+    }
+
+    fn type_info(&self) -> Vec<TypeInfo> {
+        self.cmd.components[self.components.clone()]
+            .iter()
+            .map(|x| x.ty)
+            .collect()
+    }
+
+    unsafe fn put(self, mut f: impl FnMut(*mut u8, TypeInfo)) {
+        // Mark the components as moved out so `clear` won't drop them
+        for info in &mut self.cmd.components[self.components.clone()] {
+            let ptr = self.cmd.storage.as_ptr().add(info.offset);
+            f(ptr, info.ty);
+            info.ty = TypeInfo::of::<()>();
+        }
+    }
+}
+
+/// Data required to store components and their offset
+struct ComponentInfo {
+    ty: TypeInfo,
+    // Position in 'storage'
+    offset: usize,
+}
+
+/// Data of the entity to be spawned or modified
+struct EntityIndex {
+    // Entity to insert components into, or `None` to spawn a new one
+    entity: Option<Entity>,
+    // Range of indices in `components` holding this entity's components
+    components: Range<usize>,
+}
+
+/// Data required to remove components from `entity`
+struct RemovedComps {
+    remove: fn(&mut World, Entity),
+    entity: Entity,
+}
+
+/// A buffered command
+enum Cmd {
+    SpawnOrInsert(EntityIndex),
+    Remove(RemovedComps),
+    Despawn(Entity),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn populate_archetypes() {
+        let mut world = World::new();
+        let mut buffer = CommandBuffer::new();
+        let ent = world.reserve_entity();
+        let enta = world.reserve_entity();
+        let entb = world.reserve_entity();
+        let entc = world.reserve_entity();
+        buffer.insert(ent, (true, "a"));
+        buffer.insert(entc, (true, "a"));
+        buffer.insert(enta, (1, 1.0));
+        buffer.insert(entb, (1.0, "a"));
+        buffer.run_on(&mut world);
+        assert_eq!(world.archetypes().len(), 4);
+    }
+
+    #[test]
+    fn failed_insert_regression() {
+        // Verify that failing to insert components doesn't leak them
+        let mut world = World::new();
+        let mut buffer = CommandBuffer::new();
+        let ent = world.reserve_entity();
+        world.despawn(ent).unwrap();
+        buffer.insert(ent, (crate::alloc::boxed::Box::new(123),));
+        buffer.run_on(&mut world);
+    }
+
+    #[test]
+    fn insert_then_remove() {
+        let mut world = World::new();
+        let mut buffer = CommandBuffer::new();
+        let ent = world.spawn((1,));
+        buffer.insert_one(ent, true);
+        buffer.remove_one::<bool>(ent);
+        buffer.insert_one(ent, "abc");
+        buffer.remove::<(i32, &str)>(ent);
+        buffer.spawn((2, false));
+        buffer.run_on(&mut world);
+        assert_eq!(world.entity(ent).unwrap().len(), 0);
+        assert_eq!(world.len(), 2);
+        assert_eq!(world.query_mut::<(&i32, &bool)>().into_iter().count(), 1);
+    }
+
+    #[test]
+    fn despawn_drops() {
+        let mut world = World::new();
+        let mut buffer = CommandBuffer::new();
+        let ent = world.spawn((1,));
+        buffer.despawn(ent);
+        buffer.insert_one(ent, true);
+        buffer.run_on(&mut world);
+        assert!(!world.contains(ent));
+        assert!(world.is_empty());
+
+        // Unapplied commands are dropped with the buffer
+        let value = crate::alloc::sync::Arc::new(());
+        buffer.spawn((value.clone(),));
+        drop(buffer);
+        assert_eq!(crate::alloc::sync::Arc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn placeholder_spawns() {
+        let mut world = World::new();
+        let existing = world.spawn((0,));
+        let mut buffer = CommandBuffer::new();
+        let a = buffer.spawn((1,));
+        let b = buffer.spawn((2, "b"));
+        assert_ne!(a, b);
+        buffer.insert_one(a, true);
+        buffer.remove_one::<&str>(b);
+        buffer.insert_one(existing, "existing");
+        let doomed = buffer.spawn((3,));
+        buffer.despawn(doomed);
+        buffer.run_on(&mut world);
+        assert_eq!(world.len(), 3);
+        let mut spawned = world
+            .query::<(&i32, Option<&bool>, Option<&&str>)>()
+            .iter()
+            .map(|(_, (&x, flag, name))| (x, flag.copied(), name.copied()))
+            .collect::<Vec<_>>();
+        spawned.sort();
+        assert_eq!(
+            spawned,
+            [
+                (0, None, Some("existing")),
+                (1, Some(true), None),
+                (2, None, None)
+            ]
+        );
+
+        // Placeholders from a buffer that's already run don't refer to anything
+        buffer.insert_one(a, false);
+        buffer.despawn(b);
+        buffer.run_on(&mut world);
+        assert_eq!(world.len(), 3);
+        assert_eq!(world.query::<&bool>().iter().count(), 1);
+    }
+
+    #[test]
+    fn interleaved_reserved_spawns() {
+        let mut world = World::new();
+        let existing = world.spawn((0,));
+        let mut buffer = CommandBuffer::new();
+        let a = buffer.spawn_reserved(&world, (1,));
+        let b = buffer.spawn_reserved(&world, (2, "b"));
+        buffer.insert_one(a, true);
+        buffer.despawn(b);
+        buffer.insert_one(b, false);
+        let c = world.reserve_entity();
+        buffer.despawn(c);
+        buffer.insert_one(c, 3);
+        buffer.remove_one::<i32>(a);
+        buffer.insert_one(existing, a);
+        assert_eq!(world.len(), 1);
+
+        buffer.run_on(&mut world);
+        assert_eq!(world.len(), 2);
+        assert!(world.get::<i32>(a).is_err());
+        assert!(*world.get::<bool>(a).unwrap());
+        assert!(!world.contains(b));
+        assert!(!world.contains(c));
+        assert_eq!(*world.get::<Entity>(existing).unwrap(), a);
+
+        // Despawned handles are invalidated and their slots reused with a new generation
+        let d = world.spawn(());
+        assert!(d.id() == b.id() || d.id() == c.id());
+        assert!(!world.contains(b) && !world.contains(c));
+    }
+}
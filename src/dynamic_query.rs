@@ -0,0 +1,189 @@
+use crate::alloc::vec::Vec;
+use core::any::TypeId;
+use core::ptr;
+
+use crate::archetype::next_tick;
+use crate::entities::EntityMeta;
+use crate::{Access, Archetype, Entity, World};
+
+/// A query whose component types are only known at runtime
+///
+/// Each component is identified by its [`TypeId`] along with the [`Access`] required:
+/// - [`Access::Read`] and [`Access::Write`] borrow the component shared or uniquely, with the same
+///   dynamic borrow checking as statically typed queries, so conflicts with concurrent queries of
+///   either kind are detected.
+/// - [`Access::Iterate`] only requires the component to be present, like [`With`](crate::With),
+///   and yields a null pointer in its place.
+///
+/// Useful for scripting layers and other code that can't name component types statically.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// # use std::any::TypeId;
+/// let mut world = World::new();
+/// let a = world.spawn((1, true));
+/// world.spawn((2,));
+/// let query = DynamicQuery::new(&[
+///     (TypeId::of::<i32>(), Access::Write),
+///     (TypeId::of::<bool>(), Access::Read),
+/// ]);
+/// query.query(&world).for_each(|entity, components| unsafe {
+///     if *components[1].cast::<bool>() {
+///         *components[0].cast::<i32>() += 10;
+///     }
+///     assert_eq!(entity, a);
+/// });
+/// assert_eq!(*world.get::<i32>(a).unwrap(), 11);
+/// ```
+pub struct DynamicQuery {
+    components: Vec<(TypeId, Access)>,
+}
+
+impl DynamicQuery {
+    /// Construct a query for entities having every component in `components`
+    ///
+    /// Panics if any type appears more than once.
+    pub fn new(components: &[(TypeId, Access)]) -> Self {
+        for (i, &(a, _)) in components.iter().enumerate() {
+            assert!(
+                components[..i].iter().all(|&(b, _)| a != b),
+                "dynamic query names component {:?} more than once",
+                a
+            );
+        }
+        Self {
+            components: components.to_vec(),
+        }
+    }
+
+    /// The components accessed by this query, in the order their pointers are yielded
+    pub fn components(&self) -> &[(TypeId, Access)] {
+        &self.components
+    }
+
+    /// Whether `archetype` has every component this query requires
+    pub fn matches(&self, archetype: &Archetype) -> bool {
+        self.components
+            .iter()
+            .all(|&(id, _)| archetype.has_dynamic(id))
+    }
+
+    /// Borrow the components of every matching archetype in `world`
+    ///
+    /// Panics if any declared access conflicts with an outstanding borrow.
+    pub fn query<'q>(&'q self, world: &'q World) -> DynamicQueryBorrow<'q> {
+        DynamicQueryBorrow::new(self, world.entities_meta(), world.archetypes_inner())
+    }
+}
+
+/// A borrow of a [`World`] sufficient to execute a [`DynamicQuery`]
+///
+/// Borrows are released when this is dropped.
+pub struct DynamicQueryBorrow<'q> {
+    query: &'q DynamicQuery,
+    meta: &'q [EntityMeta],
+    archetypes: &'q [Archetype],
+    /// Column indices of each matching archetype's components, flattened
+    states: Vec<usize>,
+}
+
+impl<'q> DynamicQueryBorrow<'q> {
+    fn new(query: &'q DynamicQuery, meta: &'q [EntityMeta], archetypes: &'q [Archetype]) -> Self {
+        let mut this = Self {
+            query,
+            meta,
+            archetypes,
+            states: Vec::new(),
+        };
+        for archetype in archetypes.iter().filter(|x| query.matches(x)) {
+            for &(id, access) in &query.components {
+                let state = archetype.get_state_by_id(&id).unwrap();
+                if access != Access::Iterate
+                    && !archetype.borrow_dynamic(state, access == Access::Write)
+                {
+                    // Dropping `this` releases everything borrowed so far
+                    drop(this);
+                    panic!("dynamic query component {:?} already borrowed", id);
+                }
+                this.states.push(state);
+            }
+        }
+        this
+    }
+
+    /// Invoke `f` for every matching entity with pointers to its components
+    ///
+    /// Pointers are given in the order the components were passed to [`DynamicQuery::new`], and
+    /// are only valid for the duration of the call. Components with [`Access::Write`] are
+    /// reported as mutated whether or not `f` actually writes to them.
+    pub fn for_each(&mut self, mut f: impl FnMut(Entity, &[*mut u8])) {
+        let n = self.query.components.len();
+        let mut ptrs = Vec::with_capacity(n);
+        let mut states = self.states.chunks(n.max(1));
+        let writes = self
+            .query
+            .components
+            .iter()
+            .any(|&(_, access)| access == Access::Write);
+        for archetype in self.archetypes.iter().filter(|x| self.query.matches(x)) {
+            let states = if n == 0 {
+                &[][..]
+            } else {
+                states.next().unwrap()
+            };
+            let tick = if writes {
+                next_tick(archetype.change_tick())
+            } else {
+                0
+            };
+            for (index, &id) in archetype.ids().iter().enumerate() {
+                ptrs.clear();
+                for (&(_, access), &state) in self.query.components.iter().zip(states) {
+                    if access == Access::Iterate {
+                        ptrs.push(ptr::null_mut());
+                        continue;
+                    }
+                    let size = archetype.types()[state].layout().size();
+                    unsafe {
+                        ptrs.push(archetype.get_base_dynamic(state).as_ptr().add(size * index));
+                        if access == Access::Write {
+                            archetype.mark_changed(state);
+                            *archetype.get_mutated(state).as_ptr().add(index) = true;
+                            *archetype.get_change_ticks(state).as_ptr().add(index) = tick;
+                        }
+                    }
+                }
+                let entity = Entity {
+                    id,
+                    generation: self.meta[id as usize].generation,
+                };
+                f(entity, &ptrs);
+            }
+        }
+    }
+}
+
+impl Drop for DynamicQueryBorrow<'_> {
+    fn drop(&mut self) {
+        let n = self.query.components.len();
+        if n == 0 {
+            return;
+        }
+        for (archetype, states) in self
+            .archetypes
+            .iter()
+            .filter(|x| self.query.matches(x))
+            .zip(self.states.chunks(n))
+        {
+            for (&(_, access), &state) in self.query.components.iter().zip(states) {
+                if access != Access::Iterate {
+                    archetype.release_dynamic(state, access == Access::Write);
+                }
+            }
+        }
+    }
+}
+
+unsafe impl Send for DynamicQueryBorrow<'_> {}
+unsafe impl Sync for DynamicQueryBorrow<'_> {}
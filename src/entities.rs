@@ -4,7 +4,7 @@ use core::convert::TryFrom;
 use core::iter::ExactSizeIterator;
 use core::num::{NonZeroU32, NonZeroU64};
 use core::ops::Range;
-use core::sync::atomic::{AtomicI64, Ordering};
+use core::sync::atomic::Ordering;
 use core::{fmt, mem};
 #[cfg(feature = "std")]
 use std::error::Error;
@@ -15,6 +15,12 @@ use std::error::Error;
 ///
 /// Enable the `serde` feature on the crate to make this `Serialize`able. Some applications may be
 /// able to save space by only serializing the output of `Entity::id`.
+///
+/// Entities are ordered by generation, then by ID, consistent with the numeric order of
+/// [`to_bits`](Self::to_bits), and hashing depends only on those two values. Both are guaranteed
+/// stable, so ordered collections and hashes with a fixed seed are reproducible across runs and
+/// hecs versions. For attaching data to entities, [`EntityMap`](crate::EntityMap) is usually faster
+/// than either.
 #[derive(Clone, Copy, Hash, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Entity {
     pub(crate) generation: NonZeroU32,
@@ -29,6 +35,19 @@ impl Entity {
     /// Useful for storing entity IDs externally, or in conjunction with `Entity::from_bits` and
     /// `World::spawn_at` for easy serialization. Alternatively, consider `id` for more compact
     /// representation.
+    ///
+    /// The result depends only on the entity, so worlds populated in the same order produce the
+    /// same bits in every run.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((123,));
+    /// let bits = e.to_bits().get();
+    /// assert_eq!(Entity::from_bits(bits), Some(e));
+    /// assert_eq!(Entity::from_bits(0), None);
+    /// ```
     pub fn to_bits(self) -> NonZeroU64 {
         unsafe {
             NonZeroU64::new_unchecked(u64::from(self.generation.get()) << 32 | u64::from(self.id))
@@ -57,6 +76,14 @@ impl Entity {
     pub fn id(self) -> u32 {
         self.id
     }
+
+    /// Extract the generation that distinguishes this entity from others that share its `id`
+    ///
+    /// Each time an ID is reused after its entity is despawned, the generation changes. Two
+    /// handles with the same `id` but different generations never refer to the same entity.
+    pub fn generation(self) -> NonZeroU32 {
+        self.generation
+    }
 }
 
 impl fmt::Debug for Entity {
@@ -131,6 +158,16 @@ impl<'a> Iterator for ReserveEntitiesIterator<'a> {
 
 impl<'a> ExactSizeIterator for ReserveEntitiesIterator<'a> {}
 
+// 64-bit atomics are unavailable on some 32-bit targets
+#[cfg(target_has_atomic = "64")]
+type AtomicIdCursor = core::sync::atomic::AtomicI64;
+#[cfg(target_has_atomic = "64")]
+type IdCursor = i64;
+#[cfg(not(target_has_atomic = "64"))]
+type AtomicIdCursor = core::sync::atomic::AtomicIsize;
+#[cfg(not(target_has_atomic = "64"))]
+type IdCursor = isize;
+
 #[derive(Default)]
 pub(crate) struct Entities {
     pub meta: Vec<EntityMeta>,
@@ -171,20 +208,33 @@ pub(crate) struct Entities {
     //
     // Once `flush()` is done, `free_cursor` will equal `pending.len()`.
     pending: Vec<u32>,
-    free_cursor: AtomicI64,
+    free_cursor: AtomicIdCursor,
     len: u32,
 }
 
+impl Clone for Entities {
+    fn clone(&self) -> Self {
+        Self {
+            meta: self.meta.clone(),
+            pending: self.pending.clone(),
+            free_cursor: AtomicIdCursor::new(self.free_cursor.load(Ordering::Relaxed)),
+            len: self.len,
+        }
+    }
+}
+
 impl Entities {
     /// Reserve entity IDs concurrently
     ///
     /// Storage for entity generation and location is lazily allocated by calling `flush`.
-    pub fn reserve_entities(&self, count: u32) -> ReserveEntitiesIterator {
+    pub fn reserve_entities(&self, count: u32) -> ReserveEntitiesIterator<'_> {
         // Use one atomic subtract to grab a range of new IDs. The range might be
         // entirely nonnegative, meaning all IDs come from the freelist, or entirely
         // negative, meaning they are all new IDs to allocate, or a mix of both.
-        let range_end = self.free_cursor.fetch_sub(count as i64, Ordering::Relaxed);
-        let range_start = range_end - count as i64;
+        let range_end = self
+            .free_cursor
+            .fetch_sub(count as IdCursor, Ordering::Relaxed);
+        let range_start = range_end - count as IdCursor;
 
         let freelist_range = range_start.max(0) as usize..range_end.max(0) as usize;
 
@@ -201,7 +251,7 @@ impl Entities {
             // In this example, we truncate the end to 0, leaving us with `-3..0`.
             // Then we negate these values to indicate how far beyond the end of `meta.end()`
             // to go, yielding `meta.len()+0 .. meta.len()+3`.
-            let base = self.meta.len() as i64;
+            let base = self.meta.len() as IdCursor;
 
             let new_id_end = u32::try_from(base - range_start).expect("too many entities");
 
@@ -238,7 +288,7 @@ impl Entities {
             // and farther beyond `meta.len()`.
             Entity {
                 generation: NonZeroU32::new(1).unwrap(),
-                id: u32::try_from(self.meta.len() as i64 - n).expect("too many entities"),
+                id: u32::try_from(self.meta.len() as IdCursor - n).expect("too many entities"),
             }
         }
     }
@@ -259,7 +309,7 @@ impl Entities {
 
         self.len += 1;
         if let Some(id) = self.pending.pop() {
-            let new_free_cursor = self.pending.len() as i64;
+            let new_free_cursor = self.pending.len() as IdCursor;
             self.free_cursor.store(new_free_cursor, Ordering::Relaxed); // Not racey due to &mut self
             Entity {
                 generation: self.meta[id as usize].generation,
@@ -326,14 +376,14 @@ impl Entities {
 
         let loc = if entity.id as usize >= self.meta.len() {
             self.pending.extend((self.meta.len() as u32)..entity.id);
-            let new_free_cursor = self.pending.len() as i64;
+            let new_free_cursor = self.pending.len() as IdCursor;
             self.free_cursor.store(new_free_cursor, Ordering::Relaxed); // Not racey due to &mut self
             self.meta.resize(entity.id as usize + 1, EntityMeta::EMPTY);
             self.len += 1;
             None
         } else if let Some(index) = self.pending.iter().position(|item| *item == entity.id) {
             self.pending.swap_remove(index);
-            let new_free_cursor = self.pending.len() as i64;
+            let new_free_cursor = self.pending.len() as IdCursor;
             self.free_cursor.store(new_free_cursor, Ordering::Relaxed); // Not racey due to &mut self
             self.len += 1;
             None
@@ -367,7 +417,7 @@ impl Entities {
 
         self.pending.push(entity.id);
 
-        let new_free_cursor = self.pending.len() as i64;
+        let new_free_cursor = self.pending.len() as IdCursor;
         self.free_cursor.store(new_free_cursor, Ordering::Relaxed); // Not racey due to &mut self
         self.len -= 1;
 
@@ -379,7 +429,7 @@ impl Entities {
         self.verify_flushed();
 
         let freelist_size = self.free_cursor.load(Ordering::Relaxed);
-        let shortfall = additional as i64 - freelist_size;
+        let shortfall = additional as IdCursor - freelist_size;
         if shortfall > 0 {
             self.meta.reserve(shortfall as usize);
         }
@@ -388,15 +438,91 @@ impl Entities {
     pub fn contains(&self, entity: Entity) -> bool {
         // Note that out-of-range IDs are considered to be "contained" because
         // they must be reserved IDs that we haven't flushed yet.
+        match self.meta.get(entity.id as usize) {
+            Some(meta) => meta.generation == entity.generation,
+            None => true,
+        }
+    }
+
+    /// Whether `id` belongs to a live entity of any generation
+    pub fn is_live(&self, id: u32) -> bool {
         self.meta
-            .get(entity.id as usize)
-            .map_or(true, |meta| meta.generation == entity.generation)
+            .get(id as usize)
+            .is_some_and(|meta| meta.location.index != u32::MAX)
+    }
+
+    /// The live entity with ID `id`, if any
+    pub fn resolve(&self, id: u32) -> Option<Entity> {
+        let meta = self.meta.get(id as usize)?;
+        if meta.location.index == u32::MAX {
+            return None;
+        }
+        Some(Entity {
+            generation: meta.generation,
+            id,
+        })
     }
 
     pub fn clear(&mut self) {
-        self.meta.clear();
         self.pending.clear();
-        self.free_cursor.store(0, Ordering::Relaxed); // Not racey due to &mut self
+        // Retire every ID so that handles from before the clear can't alias new entities
+        for (id, meta) in self.meta.iter_mut().enumerate().rev() {
+            meta.generation = NonZeroU32::new(u32::from(meta.generation).wrapping_add(1))
+                .unwrap_or_else(|| NonZeroU32::new(1).unwrap());
+            meta.location = EntityMeta::EMPTY.location;
+            self.pending.push(id as u32);
+        }
+        let new_free_cursor = self.pending.len() as IdCursor;
+        self.free_cursor.store(new_free_cursor, Ordering::Relaxed); // Not racey due to &mut self
+        self.len = 0;
+    }
+
+    /// Move every live entity with an ID of at least `len` into the lowest free ID, returning the
+    /// old and new handle of each moved entity
+    ///
+    /// Vacated IDs advance their generation and are queued to be reused lowest first.
+    pub fn compact(&mut self) -> Vec<(Entity, Entity)> {
+        self.verify_flushed();
+
+        let len = self.len as usize;
+        let mut vacant = (0..len).filter(|&id| self.meta[id].location.index == u32::MAX);
+        let mut moved = Vec::new();
+        for id in len..self.meta.len() {
+            if self.meta[id].location.index == u32::MAX {
+                continue;
+            }
+            let new_id = vacant.next().expect("more live entities than `len`");
+            moved.push((
+                Entity {
+                    generation: self.meta[id].generation,
+                    id: id as u32,
+                },
+                Entity {
+                    generation: self.meta[new_id].generation,
+                    id: new_id as u32,
+                },
+            ));
+        }
+        for &(old, new) in &moved {
+            let meta = &mut self.meta[old.id as usize];
+            meta.generation = NonZeroU32::new(u32::from(meta.generation).wrapping_add(1))
+                .unwrap_or_else(|| NonZeroU32::new(1).unwrap());
+            self.meta[new.id as usize].location =
+                mem::replace(&mut meta.location, EntityMeta::EMPTY.location);
+        }
+
+        self.pending.clear();
+        self.pending
+            .extend((len as u32..self.meta.len() as u32).rev());
+        let new_free_cursor = self.pending.len() as IdCursor;
+        self.free_cursor.store(new_free_cursor, Ordering::Relaxed); // Not racey due to &mut self
+        moved
+    }
+
+    /// Heap memory in bytes used by entity metadata and the free list
+    pub fn memory_usage(&self) -> usize {
+        self.meta.capacity() * mem::size_of::<EntityMeta>()
+            + self.pending.capacity() * mem::size_of::<u32>()
     }
 
     /// Access the location storage of an entity
@@ -416,7 +542,7 @@ impl Entities {
         if self.meta.len() <= entity.id as usize {
             return Ok(Location {
                 archetype: 0,
-                index: u32::max_value(),
+                index: u32::MAX,
             });
         }
         let meta = &self.meta[entity.id as usize];
@@ -458,7 +584,7 @@ impl Entities {
 
     fn needs_flush(&mut self) -> bool {
         // Not racey due to &mut self
-        self.free_cursor.load(Ordering::Relaxed) != self.pending.len() as i64
+        self.free_cursor.load(Ordering::Relaxed) != self.pending.len() as IdCursor
     }
 
     /// Allocates space for entities previously reserved with `reserve_entity` or
@@ -503,10 +629,13 @@ pub(crate) struct EntityMeta {
 
 impl EntityMeta {
     const EMPTY: EntityMeta = EntityMeta {
-        generation: unsafe { NonZeroU32::new_unchecked(1) },
+        generation: match NonZeroU32::new(1) {
+            Some(x) => x,
+            None => unreachable!(),
+        },
         location: Location {
             archetype: 0,
-            index: u32::max_value(), // dummy value, to be filled in
+            index: u32::MAX, // dummy value, to be filled in
         },
     };
 }
@@ -530,6 +659,19 @@ impl fmt::Display for NoSuchEntity {
 #[cfg(feature = "std")]
 impl Error for NoSuchEntity {}
 
+/// Error indicating that an entity ID is already in use by a live entity
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EntityOccupied;
+
+impl fmt::Display for EntityOccupied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("entity ID already in use")
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for EntityOccupied {}
+
 #[derive(Clone)]
 pub(crate) struct AllocManyState {
     pub pending_end: usize,
@@ -567,6 +709,20 @@ mod tests {
         assert_eq!(Entity::from_bits(e.to_bits().into()).unwrap(), e);
     }
 
+    #[test]
+    fn entity_bits_invalid() {
+        // A zero generation is never produced
+        assert_eq!(Entity::from_bits(0), None);
+        assert_eq!(Entity::from_bits(0xFFFF_FFFF), None);
+        assert_eq!(
+            Entity::from_bits(1 << 32),
+            Some(Entity {
+                generation: NonZeroU32::new(1).unwrap(),
+                id: 0,
+            })
+        );
+    }
+
     #[test]
     fn alloc_and_free() {
         let mut rng = StdRng::seed_from_u64(0xFEEDFACEDEADF00D);
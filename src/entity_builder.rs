@@ -13,7 +13,7 @@ use core::ptr::{self, NonNull};
 use hashbrown::hash_map::Entry;
 
 use crate::archetype::{TypeIdMap, TypeInfo};
-use crate::{align, Component, DynamicBundle};
+use crate::{align, Component, DynamicBundle, Entity};
 
 /// Helper for incrementally constructing a bundle of components with dynamic component types
 ///
@@ -86,6 +86,15 @@ impl<M> EntityBuilder<M> {
         self.info.iter().map(|(info, _, _)| info.id())
     }
 
+    /// Pointers to each component, along with its type
+    pub(crate) fn components(&self) -> impl Iterator<Item = (TypeId, *const u8)> + '_ {
+        self.info.iter().map(move |(info, offset, _)| {
+            (info.id(), unsafe {
+                self.storage.as_ptr().add(*offset) as *const u8
+            })
+        })
+    }
+
     unsafe fn grow(
         min_size: usize,
         cursor: usize,
@@ -113,6 +122,27 @@ impl<M> EntityBuilder<M> {
         }
     }
 
+    /// Move the component of type `T` out, if it exists
+    fn take<T: Component>(&mut self) -> Option<T> {
+        let index = self.indices.remove(&TypeId::of::<T>())?;
+        let (_, offset, _) = self.info.remove(index);
+        let value = unsafe { self.storage.as_ptr().add(offset).cast::<T>().read() };
+        // Later components have shifted down
+        self.sort();
+        Some(value)
+    }
+
+    /// Put components in canonical order and record their IDs, as required by `DynamicBundle`
+    fn sort(&mut self) {
+        self.info.sort_unstable_by_key(|x| x.0);
+        self.ids.clear();
+        self.ids.extend(self.info.iter().map(|x| x.0.id()));
+        // Keep lookups by type pointing at the right components
+        for (index, (ty, _, _)) in self.info.iter().enumerate() {
+            self.indices.insert(ty.id(), index);
+        }
+    }
+
     unsafe fn add_inner(&mut self, ptr: *mut u8, ty: TypeInfo, meta: M) {
         match self.indices.entry(ty.id()) {
             Entry::Occupied(occupied) => {
@@ -177,10 +207,23 @@ impl EntityBuilder<()> {
         self
     }
 
+    /// Add the component at `component`, whose type is described by `ty`, to the entity
+    ///
+    /// Like [`add`](Self::add), for types only known at runtime. The component is moved into the
+    /// builder, so it must not be used or dropped afterwards. If the builder already contains a
+    /// component of the same type, it will be dropped and replaced.
+    ///
+    /// # Safety
+    ///
+    /// `component` must point to a valid, suitably aligned value of the type described by `ty`.
+    pub unsafe fn add_dynamic(&mut self, component: *mut u8, ty: TypeInfo) -> &mut Self {
+        self.add_inner(component, ty, ());
+        self
+    }
+
     /// Construct a `Bundle` suitable for spawning
     pub fn build(&mut self) -> BuiltEntity<'_> {
-        self.info.sort_unstable_by_key(|x| x.0);
-        self.ids.extend(self.info.iter().map(|x| x.0.id()));
+        self.sort();
         BuiltEntity { builder: self }
     }
 }
@@ -218,12 +261,17 @@ impl EntityBuilder<Cloneable> {
 impl Clone for EntityBuilder<Cloneable> {
     fn clone(&self) -> Self {
         unsafe {
+            let storage = if self.layout.size() == 0 {
+                NonNull::dangling()
+            } else {
+                NonNull::new_unchecked(alloc(self.layout))
+            };
             let result = Self {
-                storage: NonNull::new_unchecked(alloc(self.layout)),
+                storage,
                 layout: self.layout,
                 cursor: self.cursor,
                 info: self.info.clone(),
-                ids: Vec::new(),
+                ids: self.ids.clone(),
                 indices: self.indices.clone(),
             };
             for &(_, offset, ref clone) in &self.info {
@@ -294,7 +342,6 @@ unsafe impl DynamicBundle for BuiltEntity<'_> {
         f(&self.builder.ids)
     }
 
-    #[doc(hidden)]
     fn type_info(&self) -> Vec<TypeInfo> {
         self.builder.info.iter().map(|x| x.0).collect()
     }
@@ -315,6 +362,92 @@ impl Drop for BuiltEntity<'_> {
     }
 }
 
+/// An entity's components, removed from a [`World`](crate::World) by
+/// [`World::take`](crate::World::take)
+///
+/// Pass to [`World::spawn`](crate::World::spawn) to move the entity into another world. Its
+/// components are dropped normally if it's never spawned.
+pub struct TakenEntity {
+    entity: Entity,
+    builder: EntityBuilder,
+}
+
+impl TakenEntity {
+    pub(crate) fn new(entity: Entity, mut builder: EntityBuilder) -> Self {
+        builder.sort();
+        Self { entity, builder }
+    }
+
+    /// The handle the entity had in the world it was taken from
+    ///
+    /// Useful for remapping references after the entity is spawned elsewhere.
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Checks to see if the component of type `T` exists
+    pub fn has<T: Component>(&self) -> bool {
+        self.builder.has::<T>()
+    }
+
+    /// Borrow the component of type `T`, if it exists
+    pub fn get<T: Component>(&self) -> Option<&T> {
+        self.builder.get()
+    }
+
+    /// Uniquely borrow the component of type `T`, if it exists
+    pub fn get_mut<T: Component>(&mut self) -> Option<&mut T> {
+        self.builder.get_mut()
+    }
+
+    /// Remove and return the component of type `T`, if it exists
+    ///
+    /// Useful for salvaging specific components from a despawned entity, leaving the rest to be
+    /// dropped with the `TakenEntity`.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Inventory(Vec<&'static str>);
+    /// let mut world = World::new();
+    /// let killer = world.spawn((Inventory(vec![]),));
+    /// let victim = world.spawn((Inventory(vec!["sword"]), 0));
+    /// let mut loot = world.take(victim).unwrap().take::<Inventory>().unwrap();
+    /// world.get_mut::<Inventory>(killer).unwrap().0.append(&mut loot.0);
+    /// assert_eq!(world.get::<Inventory>(killer).unwrap().0, ["sword"]);
+    /// ```
+    pub fn take<T: Component>(&mut self) -> Option<T> {
+        self.builder.take()
+    }
+
+    /// Enumerate the types of the entity's components
+    pub fn component_types(&self) -> impl Iterator<Item = TypeId> + '_ {
+        self.builder.component_types()
+    }
+
+    /// Pointers to each component, along with its type
+    pub(crate) fn components(&self) -> impl Iterator<Item = (TypeId, *const u8)> + '_ {
+        self.builder.components()
+    }
+}
+
+unsafe impl DynamicBundle for TakenEntity {
+    fn with_ids<T>(&self, f: impl FnOnce(&[TypeId]) -> T) -> T {
+        f(&self.builder.ids)
+    }
+
+    fn type_info(&self) -> Vec<TypeInfo> {
+        self.builder.info.iter().map(|x| x.0).collect()
+    }
+
+    unsafe fn put(mut self, mut f: impl FnMut(*mut u8, TypeInfo)) {
+        for (ty, offset, ()) in self.builder.info.drain(..) {
+            let ptr = self.builder.storage.as_ptr().add(offset);
+            f(ptr, ty);
+        }
+    }
+}
+
 /// A collection of components that implement [`Clone`]
 ///
 /// Built from, and convertible back into, [`EntityBuilder<Cloneable>`]
@@ -340,8 +473,7 @@ unsafe impl DynamicBundle for &'_ ReusableBuiltEntity {
 
 impl From<EntityBuilder<Cloneable>> for ReusableBuiltEntity {
     fn from(mut x: EntityBuilder<Cloneable>) -> Self {
-        x.info.sort_unstable_by_key(|y| y.0);
-        x.ids.extend(x.info.iter().map(|y| y.0.id()));
+        x.sort();
         Self(x)
     }
 }
@@ -0,0 +1,170 @@
+use crate::alloc::vec::Vec;
+use core::fmt;
+use core::iter::FromIterator;
+use core::num::NonZeroU32;
+
+use crate::Entity;
+
+/// A map from [`Entity`] handles to values, stored densely by entity ID
+///
+/// Much faster than hashing for attaching external data to entities, at the cost of memory
+/// proportional to the largest ID stored. Each value remembers the generation of the entity it was
+/// inserted for, so a stale handle never observes a value associated with a different entity that
+/// later reused its ID.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let a = world.spawn(());
+/// let mut names = EntityMap::new();
+/// names.insert(a, "a");
+/// assert_eq!(names.get(a), Some(&"a"));
+/// world.despawn(a).unwrap();
+/// let b = world.spawn(());
+/// assert_eq!(a.id(), b.id());
+/// assert_eq!(names.get(b), None);
+/// ```
+#[derive(Clone)]
+pub struct EntityMap<V> {
+    slots: Vec<Option<(NonZeroU32, V)>>,
+    len: usize,
+}
+
+impl<V> EntityMap<V> {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Associate `value` with `entity`, returning the value previously associated with it
+    ///
+    /// Replaces any value associated with a different generation of the same ID, without
+    /// returning it.
+    pub fn insert(&mut self, entity: Entity, value: V) -> Option<V> {
+        let index = entity.id() as usize;
+        if index >= self.slots.len() {
+            self.slots.resize_with(index + 1, || None);
+        }
+        let old = self.slots[index].replace((entity.generation(), value));
+        match old {
+            Some((generation, value)) if generation == entity.generation() => Some(value),
+            Some(_) => None,
+            None => {
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    /// Get the value associated with `entity`, if any
+    pub fn get(&self, entity: Entity) -> Option<&V> {
+        match self.slots.get(entity.id() as usize)? {
+            Some((generation, value)) if *generation == entity.generation() => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Uniquely borrow the value associated with `entity`, if any
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut V> {
+        match self.slots.get_mut(entity.id() as usize)? {
+            Some((generation, value)) if *generation == entity.generation() => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Whether a value is associated with `entity`
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    /// Remove and return the value associated with `entity`, if any
+    pub fn remove(&mut self, entity: Entity) -> Option<V> {
+        let slot = self.slots.get_mut(entity.id() as usize)?;
+        match *slot {
+            Some((generation, _)) if generation == entity.generation() => {}
+            _ => return None,
+        }
+        self.len -= 1;
+        slot.take().map(|(_, value)| value)
+    }
+
+    /// Number of entities with associated values
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no entities have associated values
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove all values
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.len = 0;
+    }
+
+    /// Iterate over entities and their associated values, in order of ID
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &V)> + '_ {
+        self.slots.iter().enumerate().filter_map(|(id, slot)| {
+            let (generation, value) = slot.as_ref()?;
+            let entity = Entity {
+                id: id as u32,
+                generation: *generation,
+            };
+            Some((entity, value))
+        })
+    }
+
+    /// Iterate over entities and unique borrows of their associated values, in order of ID
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut V)> + '_ {
+        self.slots.iter_mut().enumerate().filter_map(|(id, slot)| {
+            let (generation, value) = slot.as_mut()?;
+            let entity = Entity {
+                id: id as u32,
+                generation: *generation,
+            };
+            Some((entity, value))
+        })
+    }
+}
+
+impl<V> Default for EntityMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: PartialEq> PartialEq for EntityMap<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().all(|(e, x)| other.get(e) == Some(x))
+    }
+}
+
+impl<V: Eq> Eq for EntityMap<V> {}
+
+impl<V: fmt::Debug> fmt::Debug for EntityMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V> Extend<(Entity, V)> for EntityMap<V> {
+    fn extend<I: IntoIterator<Item = (Entity, V)>>(&mut self, iter: I) {
+        for (entity, value) in iter {
+            self.insert(entity, value);
+        }
+    }
+}
+
+impl<V> FromIterator<(Entity, V)> for EntityMap<V> {
+    fn from_iter<I: IntoIterator<Item = (Entity, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
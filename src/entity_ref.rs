@@ -1,9 +1,9 @@
-use core::any::TypeId;
+use core::any::{type_name, TypeId};
 use core::ops::{Deref, DerefMut};
 use core::ptr::NonNull;
 
-use crate::archetype::Archetype;
-use crate::{Component, Entity, MissingComponent, Query, QueryOne};
+use crate::archetype::{next_tick, Archetype};
+use crate::{Component, ComponentError, Entity, MissingComponent, Mut, Query, QueryOne};
 
 /// Handle to an entity with any component types
 #[derive(Copy, Clone)]
@@ -38,14 +38,24 @@ impl<'a> EntityRef<'a> {
     /// Panics if the component is already uniquely borrowed from another entity with the same
     /// components.
     pub fn get<T: Component>(&self) -> Option<Ref<'a, T>> {
-        Some(unsafe { Ref::new(self.archetype, self.index).ok()? })
+        match unsafe { Ref::new(self.archetype, self.index) } {
+            Ok(x) => Some(x),
+            Err(ComponentError::AlreadyBorrowed(name)) => {
+                panic!("{} already borrowed uniquely", name)
+            }
+            Err(_) => None,
+        }
     }
 
     /// Uniquely borrow the component of type `T`, if it exists
     ///
     /// Panics if the component is already borrowed from another entity with the same components.
     pub fn get_mut<T: Component>(&self) -> Option<RefMut<'a, T>> {
-        Some(unsafe { RefMut::new(self.archetype, self.index).ok()? })
+        match unsafe { RefMut::new(self.archetype, self.index) } {
+            Ok(x) => Some(x),
+            Err(ComponentError::AlreadyBorrowed(name)) => panic!("{} already borrowed", name),
+            Err(_) => None,
+        }
     }
 
     /// Run a query against this entity
@@ -92,6 +102,143 @@ impl<'a> EntityRef<'a> {
 unsafe impl<'a> Send for EntityRef<'a> {}
 unsafe impl<'a> Sync for EntityRef<'a> {}
 
+/// Types that can be reconstructed by cloning components of an entity
+///
+/// The inverse of [`Bundle`](crate::Bundle), convenient for snapshotting entities into plain data
+/// for serialization or scripting. Implemented for tuples of up to 16 cloneable components, and
+/// for structs with `#[derive(FromEntity)]` when the `macros` feature is enabled. To borrow rather
+/// than clone components, use a [`Query`] with [`EntityRef::query`] instead.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let e = world.spawn((123, "abc", true));
+/// let entity = world.entity(e).unwrap();
+/// assert_eq!(<(i32, &str)>::from_entity(entity), Some((123, "abc")));
+/// assert_eq!(<(i32, char)>::from_entity(entity), None);
+/// ```
+pub trait FromEntity: Sized {
+    /// Clone the components of `entity` that make up `Self`, or return `None` if any required
+    /// component is missing
+    ///
+    /// Panics if a required component is already uniquely borrowed.
+    fn from_entity(entity: EntityRef<'_>) -> Option<Self>;
+}
+
+macro_rules! from_entity_tuple_impl {
+    ($($name: ident),*) => {
+        impl<$($name: Component + Clone),*> FromEntity for ($($name,)*) {
+            #[allow(unused_variables)]
+            fn from_entity(entity: EntityRef<'_>) -> Option<Self> {
+                Some(($((*entity.get::<$name>()?).clone(),)*))
+            }
+        }
+    };
+}
+
+smaller_tuples_too!(
+    from_entity_tuple_impl,
+    P,
+    O,
+    N,
+    M,
+    L,
+    K,
+    J,
+    I,
+    H,
+    G,
+    F,
+    E,
+    D,
+    C,
+    B,
+    A
+);
+
+/// Handle to an entity with any component types, with exclusive access to its components
+///
+/// Obtained by iterating over a `&mut World`. Because the world is borrowed uniquely, components
+/// can be accessed without runtime borrow checking.
+pub struct EntityMut<'a> {
+    archetype: &'a Archetype,
+    entity: Entity,
+    index: u32,
+}
+
+impl<'a> EntityMut<'a> {
+    /// Safety: no other handle to the same entity may exist, and nothing else may borrow
+    /// `archetype`'s components during `'a`
+    pub(crate) unsafe fn from_ref(x: EntityRef<'a>) -> Self {
+        Self {
+            archetype: x.archetype,
+            entity: x.entity,
+            index: x.index,
+        }
+    }
+
+    /// Get the [`Entity`] handle associated with this entity
+    #[inline]
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// Determine whether this entity has a `T` component
+    pub fn has<T: Component>(&self) -> bool {
+        self.archetype.has::<T>()
+    }
+
+    /// Borrow the component of type `T`, if it exists
+    pub fn get<T: Component>(&self) -> Option<&T> {
+        let state = self.archetype.get_state::<T>()?;
+        unsafe {
+            Some(
+                &*self
+                    .archetype
+                    .get_base::<T>(state)
+                    .as_ptr()
+                    .add(self.index as usize),
+            )
+        }
+    }
+
+    /// Uniquely borrow the component of type `T`, if it exists
+    ///
+    /// As with `&mut T` queries, the component is only reported as mutated once it's accessed
+    /// through `DerefMut`.
+    pub fn get_mut<T: Component>(&mut self) -> Option<Mut<'_, T>> {
+        let state = self.archetype.get_state::<T>()?;
+        let index = self.index as usize;
+        unsafe {
+            Some(Mut::from_archetype(
+                self.archetype,
+                state,
+                index,
+                next_tick(self.archetype.change_tick()),
+            ))
+        }
+    }
+
+    /// Enumerate the types of the entity's components
+    pub fn component_types(&self) -> impl Iterator<Item = TypeId> + 'a {
+        self.archetype.types().iter().map(|ty| ty.id())
+    }
+
+    /// Number of components in this entity
+    pub fn len(&self) -> usize {
+        self.archetype.types().len()
+    }
+
+    /// Shorthand for `self.len() == 0`
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+unsafe impl<'a> Send for EntityMut<'a> {}
+unsafe impl<'a> Sync for EntityMut<'a> {}
+
 /// Shared borrow of an entity's component
 #[derive(Clone)]
 pub struct Ref<'a, T: Component> {
@@ -102,16 +249,15 @@ pub struct Ref<'a, T: Component> {
 }
 
 impl<'a, T: Component> Ref<'a, T> {
-    pub(crate) unsafe fn new(
-        archetype: &'a Archetype,
-        index: u32,
-    ) -> Result<Self, MissingComponent> {
+    pub(crate) unsafe fn new(archetype: &'a Archetype, index: u32) -> Result<Self, ComponentError> {
         let state = archetype
             .get_state::<T>()
             .ok_or_else(MissingComponent::new::<T>)?;
         let target =
             NonNull::new_unchecked(archetype.get_base::<T>(state).as_ptr().add(index as usize));
-        archetype.borrow::<T>(state);
+        if !archetype.borrow_dynamic(state, false) {
+            return Err(ComponentError::AlreadyBorrowed(type_name::<T>()));
+        }
         Ok(Self {
             archetype,
             state,
@@ -143,25 +289,33 @@ pub struct RefMut<'a, T: Component> {
     state: usize,
     target: NonNull<T>,
     mutated: &'a mut bool,
+    change_tick: &'a mut u32,
+    /// Whether the change has already been recorded
+    recorded: bool,
 }
 
 impl<'a, T: Component> RefMut<'a, T> {
-    pub(crate) unsafe fn new(
-        archetype: &'a Archetype,
-        index: u32,
-    ) -> Result<Self, MissingComponent> {
+    pub(crate) unsafe fn new(archetype: &'a Archetype, index: u32) -> Result<Self, ComponentError> {
         let state = archetype
             .get_state::<T>()
             .ok_or_else(MissingComponent::new::<T>)?;
         let target =
             NonNull::new_unchecked(archetype.get_base::<T>(state).as_ptr().add(index as usize));
-        archetype.borrow_mut::<T>(state);
+        if !archetype.borrow_dynamic(state, true) {
+            return Err(ComponentError::AlreadyBorrowed(type_name::<T>()));
+        }
         let mutated = &mut *archetype.get_mutated(state).as_ptr().add(index as usize);
+        let change_tick = &mut *archetype
+            .get_change_ticks(state)
+            .as_ptr()
+            .add(index as usize);
         Ok(Self {
             archetype,
             state,
             target,
             mutated,
+            change_tick,
+            recorded: false,
         })
     }
 }
@@ -184,7 +338,12 @@ impl<'a, T: Component> Deref for RefMut<'a, T> {
 
 impl<'a, T: Component> DerefMut for RefMut<'a, T> {
     fn deref_mut(&mut self) -> &mut T {
-        *self.mutated = true;
+        if !self.recorded {
+            *self.mutated = true;
+            *self.change_tick = next_tick(self.archetype.change_tick());
+            self.archetype.mark_changed(self.state);
+            self.recorded = true;
+        }
         unsafe { self.target.as_mut() }
     }
 }
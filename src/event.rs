@@ -0,0 +1,75 @@
+use crate::alloc::boxed::Box;
+use crate::alloc::vec::Vec;
+use core::any::{Any, TypeId};
+use core::mem;
+
+use crate::archetype::TypeIdMap;
+
+/// Double-buffered queues of events stored alongside a [`World`](crate::World), keyed by type
+#[derive(Default)]
+pub(crate) struct Events {
+    map: TypeIdMap<Box<dyn AnyQueue>>,
+}
+
+impl Events {
+    pub fn send<E: Send + Sync + 'static>(&mut self, event: E) {
+        self.map
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(Queue::<E>::default()))
+            .as_any_mut()
+            .downcast_mut::<Queue<E>>()
+            .unwrap()
+            .current
+            .push(event);
+    }
+
+    /// Remove every queued `E`, oldest first
+    pub fn drain<E: Send + Sync + 'static>(&mut self) -> impl Iterator<Item = E> + '_ {
+        self.map
+            .get_mut(&TypeId::of::<E>())
+            .into_iter()
+            .flat_map(|queue| {
+                let queue = queue.as_any_mut().downcast_mut::<Queue<E>>().unwrap();
+                queue.previous.drain(..).chain(queue.current.drain(..))
+            })
+    }
+
+    /// Drop events that have survived a whole update, and start a new one
+    pub fn update(&mut self) {
+        for queue in self.map.values_mut() {
+            queue.update();
+        }
+    }
+}
+
+trait AnyQueue: Send + Sync {
+    fn update(&mut self);
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct Queue<E> {
+    /// Events sent before the most recent update
+    previous: Vec<E>,
+    /// Events sent since the most recent update
+    current: Vec<E>,
+}
+
+impl<E> Default for Queue<E> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+}
+
+impl<E: Send + Sync + 'static> AnyQueue for Queue<E> {
+    fn update(&mut self) {
+        self.previous.clear();
+        mem::swap(&mut self.previous, &mut self.current);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
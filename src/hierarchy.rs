@@ -0,0 +1,72 @@
+use crate::alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use crate::{ComponentMeta, Entity, NoSuchEntity};
+
+/// Component linking an entity to its parent
+///
+/// Maintained by [`World::set_parent`](crate::World::set_parent) and
+/// [`World::remove_parent`](crate::World::remove_parent) together with the parent's [`Children`].
+/// Inserting or removing it directly will leave the hierarchy inconsistent.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Parent(pub Entity);
+
+/// Component listing the entities whose [`Parent`] is this entity, in the order they were added
+///
+/// Maintained by [`World::set_parent`](crate::World::set_parent) and
+/// [`World::remove_parent`](crate::World::remove_parent).
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct Children(pub(crate) Vec<Entity>);
+
+impl Children {
+    pub(crate) fn remove(&mut self, entity: Entity) {
+        self.0.retain(|&x| x != entity);
+    }
+}
+
+impl Deref for Children {
+    type Target = [Entity];
+    fn deref(&self) -> &[Entity] {
+        &self.0
+    }
+}
+
+impl ComponentMeta for Parent {
+    const NAME: &'static str = "Parent";
+}
+
+impl ComponentMeta for Children {
+    const NAME: &'static str = "Children";
+}
+
+/// Errors that arise when modifying an entity hierarchy
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum HierarchyError {
+    /// The entity was already despawned
+    NoSuchEntity,
+    /// The new parent is the child itself or one of its descendants
+    Cycle,
+}
+
+#[cfg(feature = "std")]
+impl Error for HierarchyError {}
+
+impl fmt::Display for HierarchyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use HierarchyError::*;
+        match *self {
+            NoSuchEntity => f.write_str("no such entity"),
+            Cycle => f.write_str("parent is a descendant of the child"),
+        }
+    }
+}
+
+impl From<NoSuchEntity> for HierarchyError {
+    fn from(NoSuchEntity: NoSuchEntity) -> Self {
+        HierarchyError::NoSuchEntity
+    }
+}
@@ -39,7 +39,8 @@
 #[cfg(feature = "std")]
 extern crate std;
 
-extern crate alloc;
+#[doc(hidden)]
+pub extern crate alloc;
 
 /// Imagine macro parameters, but more like those Russian dolls.
 ///
@@ -57,39 +58,60 @@ macro_rules! smaller_tuples_too {
 }
 
 mod archetype;
+mod atomic;
 mod batch;
 mod borrow;
 mod bundle;
+mod cloning;
+mod command_buffer;
+mod dynamic_query;
 mod entities;
 mod entity_builder;
+mod entity_map;
 mod entity_ref;
+mod event;
+mod hierarchy;
+mod observer;
 mod query;
 mod query_one;
+mod resource;
 #[cfg(any(feature = "row-serialize", feature = "column-serialize"))]
 pub mod serialize;
 mod tracked;
 mod world;
 
-pub use archetype::Archetype;
-pub use batch::{BatchIncomplete, BatchWriter, ColumnBatch, ColumnBatchBuilder, ColumnBatchType};
+pub use archetype::{Archetype, ColumnRef, ColumnRefMut, FlagsRef, TypeInfo};
+pub use atomic::{AtomicComponent, AtomicRef};
+pub use batch::{
+    BatchIncomplete, BatchWriter, ColumnBatch, ColumnBatchBuilder, ColumnBatchType,
+    DynamicBatchWriter,
+};
 pub use bundle::{Bundle, DynamicBundle, MissingComponent};
-pub use entities::{Entity, NoSuchEntity};
-pub use entity_builder::{BuiltEntity, Cloneable, EntityBuilder, ReusableBuiltEntity};
-pub use entity_ref::{EntityRef, Ref, RefMut};
+pub use cloning::{CloneRegistry, MissingCloner};
+pub use command_buffer::CommandBuffer;
+pub use dynamic_query::{DynamicQuery, DynamicQueryBorrow};
+pub use entities::{Entity, EntityOccupied, NoSuchEntity};
+pub use entity_builder::{BuiltEntity, Cloneable, EntityBuilder, ReusableBuiltEntity, TakenEntity};
+pub use entity_map::EntityMap;
+pub use entity_ref::{EntityMut, EntityRef, FromEntity, Ref, RefMut};
+pub use hierarchy::{Children, HierarchyError, Parent};
 pub use query::{
-    Access, BatchedIter, Or, PreparedQuery, PreparedQueryBorrow, PreparedQueryIter, Query,
-    QueryBorrow, QueryItem, QueryIter, QueryMut, With, Without,
+    Access, BatchedIter, Copied, Mut, Or, PreparedQuery, PreparedQueryBorrow, PreparedQueryIter,
+    Query, QueryBorrow, QueryEntityRefIter, QueryFilterIter, QueryIntoIter, QueryItem, QueryIter,
+    QueryMany, QueryMut, Satisfies, View, With, Without,
 };
 pub use query_one::QueryOne;
-pub use tracked::{Added, Changed, Mutated};
+pub use resource::{ResourceRef, ResourceRefMut};
+pub use tracked::{
+    Added, AddedFilter, AnyChanged, ChangeTick, Changed, ChangedFilter, ChangedSince, Mutated,
+    Tracked, TrackedMut,
+};
 pub use world::{
-    ArchetypesGeneration, Component, ComponentError, Iter, QueryOneError, SpawnBatchIter,
-    SpawnColumnBatchIter, World,
+    ArchetypesGeneration, Component, ComponentError, ComponentMeta, Insertion, Iter, IterMut,
+    QueryOneError, Removal, SpawnBatchIter, SpawnColumnBatchIter, World,
 };
 
 // Unstable implementation details needed by the macros
-#[doc(hidden)]
-pub use archetype::TypeInfo;
 #[cfg(feature = "macros")]
 #[doc(hidden)]
 pub use lazy_static;
@@ -97,7 +119,7 @@ pub use lazy_static;
 pub use query::Fetch;
 
 #[cfg(feature = "macros")]
-pub use hecs_macros::{Bundle, Query};
+pub use hecs_macros::{Bundle, Component, FromEntity, Query};
 
 fn align(x: usize, alignment: usize) -> usize {
     debug_assert!(alignment.is_power_of_two());
@@ -0,0 +1,216 @@
+use crate::alloc::boxed::Box;
+use crate::alloc::vec::Vec;
+use core::any::{Any, TypeId};
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use crate::archetype::TypeIdMap;
+use crate::{Archetype, Component, Entity, EntityMap};
+
+type Callback = Box<dyn FnMut(Entity, *const u8) + Send + Sync>;
+
+/// Callbacks registered with [`World::on_add`](crate::World::on_add) and
+/// [`World::on_remove`](crate::World::on_remove), and the key indices maintained by
+/// [`World::upsert`](crate::World::upsert)
+#[derive(Default)]
+pub(crate) struct Observers {
+    on_add: TypeIdMap<Vec<Callback>>,
+    on_remove: TypeIdMap<Vec<Callback>>,
+    indices: TypeIdMap<Box<dyn AnyKeyIndex>>,
+}
+
+impl Observers {
+    pub fn on_add<T: Component>(&mut self, f: impl FnMut(Entity, &T) + Send + Sync + 'static) {
+        register(&mut self.on_add, f);
+    }
+
+    pub fn on_remove<T: Component>(&mut self, f: impl FnMut(Entity, &T) + Send + Sync + 'static) {
+        register(&mut self.on_remove, f);
+    }
+
+    /// Whether any `on_add` callbacks exist, allowing callers to skip collecting added components
+    pub fn observes_addition(&self) -> bool {
+        !self.on_add.is_empty()
+    }
+
+    /// Whether any `on_remove` callbacks or key indices exist, allowing callers to skip looking up
+    /// entities
+    pub fn observes_removal(&self) -> bool {
+        !self.on_remove.is_empty() || !self.indices.is_empty()
+    }
+
+    /// The index of entities by their `K` component, created on first use
+    pub fn index<K: Component + Eq + Hash>(&mut self) -> &mut HashMap<K, Entity> {
+        &mut self
+            .indices
+            .entry(TypeId::of::<K>())
+            .or_insert_with(|| Box::new(KeyIndex::<K>(HashMap::new())))
+            .as_any_mut()
+            .downcast_mut::<KeyIndex<K>>()
+            .unwrap()
+            .0
+    }
+
+    /// The index of entities by their `K` component, if one exists
+    pub fn get_index<K: Component + Eq + Hash>(&self) -> Option<&HashMap<K, Entity>> {
+        let index = self.indices.get(&TypeId::of::<K>())?;
+        Some(&index.as_any().downcast_ref::<KeyIndex<K>>().unwrap().0)
+    }
+
+    /// Report that all components of the entity at `index` in `archetype` were added
+    pub fn added(&mut self, archetype: &Archetype, index: u32, entity: Entity) {
+        notify_all(&mut self.on_add, archetype, index, entity);
+    }
+
+    /// Report that the `ty` component of the entity at `index` in `archetype` was added
+    pub fn added_one(&mut self, archetype: &Archetype, index: u32, entity: Entity, ty: TypeId) {
+        notify(&mut self.on_add, archetype, index, entity, ty);
+    }
+
+    /// Report that all components of the entity at `index` in `archetype` are about to be removed
+    pub fn removed(&mut self, archetype: &Archetype, index: u32, entity: Entity) {
+        notify_all(&mut self.on_remove, archetype, index, entity);
+        if self.indices.is_empty() {
+            return;
+        }
+        for ty in archetype.types() {
+            unindex(&mut self.indices, archetype, index, entity, ty.id());
+        }
+    }
+
+    /// Report that the `ty` component of the entity at `index` in `archetype` is about to be
+    /// removed
+    pub fn removed_one(&mut self, archetype: &Archetype, index: u32, entity: Entity, ty: TypeId) {
+        notify(&mut self.on_remove, archetype, index, entity, ty);
+        unindex(&mut self.indices, archetype, index, entity, ty);
+    }
+
+    /// Report that the `ty` component of `entity`, now stored at `ptr` outside of the world, was
+    /// removed
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a valid value of type `ty`.
+    pub unsafe fn removed_detached(&mut self, entity: Entity, ty: TypeId, ptr: *const u8) {
+        if let Some(callbacks) = self.on_remove.get_mut(&ty) {
+            for f in callbacks {
+                f(entity, ptr);
+            }
+        }
+        if let Some(key_index) = self.indices.get_mut(&ty) {
+            key_index.removed(entity, ptr);
+        }
+    }
+
+    /// Update indexed entities after [`World::compact_entities`](crate::World::compact_entities)
+    pub fn remap(&mut self, map: &EntityMap<Entity>) {
+        for key_index in self.indices.values_mut() {
+            key_index.remap(map);
+        }
+    }
+}
+
+/// Type-erased [`KeyIndex`]
+trait AnyKeyIndex: Send + Sync {
+    /// Forget `entity` if it's indexed under the key at `key`
+    unsafe fn removed(&mut self, entity: Entity, key: *const u8);
+    /// Replace each indexed entity that appears in `map`
+    fn remap(&mut self, map: &EntityMap<Entity>);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct KeyIndex<K>(HashMap<K, Entity>);
+
+impl<K: Component + Eq + Hash> AnyKeyIndex for KeyIndex<K> {
+    unsafe fn removed(&mut self, entity: Entity, key: *const u8) {
+        let key = &*key.cast::<K>();
+        if self.0.get(key) == Some(&entity) {
+            self.0.remove(key);
+        }
+    }
+
+    fn remap(&mut self, map: &EntityMap<Entity>) {
+        for entity in self.0.values_mut() {
+            if let Some(&new) = map.get(*entity) {
+                *entity = new;
+            }
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+fn unindex(
+    indices: &mut TypeIdMap<Box<dyn AnyKeyIndex>>,
+    archetype: &Archetype,
+    index: u32,
+    entity: Entity,
+    ty: TypeId,
+) {
+    let key_index = match indices.get_mut(&ty) {
+        Some(x) => x,
+        None => return,
+    };
+    let info = archetype.types()[archetype.get_state_by_id(&ty).unwrap()];
+    unsafe {
+        let ptr = archetype
+            .get_dynamic(ty, info.layout().size(), index)
+            .unwrap();
+        key_index.removed(entity, ptr.as_ptr());
+    }
+}
+
+fn register<T: Component>(
+    map: &mut TypeIdMap<Vec<Callback>>,
+    mut f: impl FnMut(Entity, &T) + Send + Sync + 'static,
+) {
+    map.entry(TypeId::of::<T>())
+        .or_default()
+        .push(Box::new(move |entity, ptr| {
+            f(entity, unsafe { &*ptr.cast::<T>() })
+        }));
+}
+
+fn notify_all(
+    map: &mut TypeIdMap<Vec<Callback>>,
+    archetype: &Archetype,
+    index: u32,
+    entity: Entity,
+) {
+    if map.is_empty() {
+        return;
+    }
+    for ty in archetype.types() {
+        notify(map, archetype, index, entity, ty.id());
+    }
+}
+
+fn notify(
+    map: &mut TypeIdMap<Vec<Callback>>,
+    archetype: &Archetype,
+    index: u32,
+    entity: Entity,
+    ty: TypeId,
+) {
+    let callbacks = match map.get_mut(&ty) {
+        Some(x) => x,
+        None => return,
+    };
+    let info = archetype.types()[archetype.get_state_by_id(&ty).unwrap()];
+    let ptr = unsafe {
+        archetype
+            .get_dynamic(ty, info.layout().size(), index)
+            .unwrap()
+    };
+    for f in callbacks {
+        f(entity, ptr.as_ptr());
+    }
+}
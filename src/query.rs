@@ -5,22 +5,59 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use core::any::TypeId;
+use core::any::{type_name, TypeId};
 use core::marker::PhantomData;
 use core::mem;
 use core::ops::{Deref, DerefMut};
 use core::ptr::NonNull;
-use core::slice::Iter as SliceIter;
+use core::slice::{self, Iter as SliceIter};
+use core::sync::atomic::AtomicBool;
 
 use crate::alloc::boxed::Box;
-use crate::archetype::Archetype;
+use crate::alloc::vec::Vec;
+use crate::archetype::{next_tick, set_flag, Archetype, ArchetypeFilter, ComponentRegistry};
 use crate::entities::EntityMeta;
-use crate::{Component, Entity, World};
+use crate::{Component, Entity, EntityRef, World};
 
 /// A collection of component types to fetch from a [`World`](crate::World)
 pub trait Query {
     #[doc(hidden)]
     type Fetch: for<'a> Fetch<'a>;
+
+    /// The components this query accesses, and how
+    ///
+    /// Reports exactly the borrows that are checked when the query is run, including through
+    /// nested tuples and combinators like [`Or`], with each component listed once at the strongest
+    /// access required. Components that are only tested for presence, e.g. by [`With`], are
+    /// omitted. Two queries can safely run in parallel if neither writes to a component the other
+    /// accesses.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// # use std::any::TypeId;
+    /// assert_eq!(
+    ///     <(&i32, Or<&mut bool, &i32>, With<u8, ()>)>::component_access(),
+    ///     [
+    ///         (TypeId::of::<i32>(), Access::Read),
+    ///         (TypeId::of::<bool>(), Access::Write),
+    ///     ]
+    /// );
+    /// ```
+    fn component_access() -> Vec<(TypeId, Access)>
+    where
+        Self: Sized,
+    {
+        let mut result = Vec::<(TypeId, Access)>::new();
+        Self::Fetch::for_each_borrow(|id, _, unique| {
+            let access = if unique { Access::Write } else { Access::Read };
+            match result.iter_mut().find(|x| x.0 == id) {
+                Some(existing) => existing.1 = existing.1.max(access),
+                None => result.push((id, access)),
+            }
+        });
+        result
+    }
 }
 
 /// Type of values yielded by a query
@@ -29,6 +66,11 @@ pub trait Query {
 pub type QueryItem<'a, Q> = <<Q as Query>::Fetch as Fetch<'a>>::Item;
 
 /// Streaming iterators over contiguous homogeneous ranges of components
+///
+/// # Safety
+///
+/// `borrow`, `release`, and `for_each_borrow` must account for every component `get` accesses,
+/// with the same uniqueness.
 pub unsafe trait Fetch<'a>: Sized {
     /// Type of value to be fetched
     type Item;
@@ -49,11 +91,30 @@ pub unsafe trait Fetch<'a>: Sized {
     fn prepare(archetype: &Archetype) -> Option<Self::State>;
     /// Construct a `Fetch` for `archetype` based on the associated state
     fn execute(archetype: &'a Archetype, state: Self::State) -> Self;
+    /// Like `execute`, but with change ticks compared against `last_run` rather than
+    /// [`World::last_change_tick()`](crate::World::last_change_tick())
+    ///
+    /// Used by [`QueryBorrow::changed_since`]. Queries that compare change ticks, like
+    /// [`ChangedSince`](crate::ChangedSince), and queries that wrap other queries override this.
+    /// The default ignores `last_run`.
+    #[inline(always)]
+    fn execute_since(archetype: &'a Archetype, state: Self::State, _last_run: u32) -> Self {
+        Self::execute(archetype, state)
+    }
     /// Release dynamic borrows acquired by `borrow`
     fn release(archetype: &Archetype, state: Self::State);
 
-    /// Invoke `f` for every component type that may be borrowed and whether the borrow is unique
-    fn for_each_borrow(f: impl FnMut(TypeId, bool));
+    /// Invoke `f` for every component type that may be borrowed, with its name and whether the
+    /// borrow is unique
+    fn for_each_borrow(f: impl FnMut(TypeId, &'static str, bool));
+
+    /// Invoke `f` for every component type that `access` requires an archetype to have, with
+    /// `true`, or to lack, with `false`
+    ///
+    /// Lets queries skip most archetypes that can't match without calling `access` or `prepare`.
+    /// Reporting nothing is always correct, and is the default.
+    #[inline(always)]
+    fn for_each_requirement(_f: impl FnMut(TypeId, bool)) {}
 
     /// Access the `n`th item in this archetype without bounds checking
     ///
@@ -63,6 +124,62 @@ pub unsafe trait Fetch<'a>: Sized {
     /// - Bounds-checking must be performed externally
     /// - Any resulting borrows must be legal (e.g. no &mut to something another iterator might access)
     unsafe fn get(&self, n: usize) -> Self::Item;
+
+    /// Whether the `n`th item in this archetype should be yielded
+    ///
+    /// Queries that select individual entities rather than whole archetypes, like
+    /// [`ChangedFilter`](crate::ChangedFilter), override this. Entities for which this returns
+    /// `false` are skipped without calling `get`.
+    ///
+    /// # Safety
+    /// - Must only be called after `borrow`
+    /// - Bounds-checking must be performed externally
+    #[inline(always)]
+    unsafe fn filter(&self, _n: usize) -> bool {
+        true
+    }
+
+    /// Whether `filter` would accept the `n`th item in `archetype`, without constructing a fetch
+    ///
+    /// Lets queries be counted without the side effects of `execute`, such as drawing a change
+    /// tick. Must agree with `filter`, and so must be overridden alongside it.
+    ///
+    /// # Safety
+    /// - `state` must have been obtained from `prepare` on `archetype`
+    /// - Bounds-checking must be performed externally
+    #[inline(always)]
+    unsafe fn matches(_archetype: &Archetype, _state: Self::State, _n: usize) -> bool {
+        true
+    }
+
+    /// Hint that the `n`th item in this archetype will be accessed soon
+    ///
+    /// Called by query iterators some distance ahead of the current item when the `prefetch`
+    /// feature is enabled, so that component data can be loaded into cache in advance.
+    ///
+    /// # Safety
+    /// - Must only be called after `borrow`
+    /// - `n` may be out of bounds, so implementations must not dereference anything based on it
+    #[inline(always)]
+    unsafe fn prefetch(&self, _n: usize) {}
+
+    /// Whether `filter` accepts every entity, so that matching is decided per archetype
+    ///
+    /// Must return `false` if `filter` is overridden to reject any entities.
+    #[inline(always)]
+    fn is_dense() -> bool {
+        true
+    }
+
+    /// Whether `filter` may accept any entity in this archetype
+    ///
+    /// Lets queries skip whole archetypes without visiting their entities, e.g. when no component
+    /// a [`ChangedFilter`](crate::ChangedFilter) looks at has changed. Returning `true` is always
+    /// correct, and is the default.
+    #[inline(always)]
+    fn may_match(&self) -> bool {
+        true
+    }
 }
 
 /// Type of access a [`Query`] may have to an [`Archetype`]
@@ -76,7 +193,7 @@ pub enum Access {
     Write,
 }
 
-impl<'a, T: Component> Query for &'a T {
+impl<T: Component> Query for &'_ T {
     type Fetch = FetchRead<T>;
 }
 
@@ -113,28 +230,84 @@ unsafe impl<'a, T: Component> Fetch<'a> for FetchRead<T> {
         archetype.release::<T>(state);
     }
 
-    fn for_each_borrow(mut f: impl FnMut(TypeId, bool)) {
-        f(TypeId::of::<T>(), false);
+    fn for_each_borrow(mut f: impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<T>(), type_name::<T>(), false);
+    }
+
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), true);
     }
 
     unsafe fn get(&self, n: usize) -> Self::Item {
         &*self.0.as_ptr().add(n)
     }
+
+    #[inline(always)]
+    unsafe fn prefetch(&self, n: usize) {
+        prefetch(self.0.as_ptr().wrapping_add(n));
+    }
 }
 
 /// Unique borrow of an entity's component
+///
+/// The component is only reported as [`Mutated`](crate::Mutated) and given a new
+/// [`ChangeTick`](crate::ChangeTick) once it's actually accessed mutably through `DerefMut`. Reads
+/// don't count as changes. The tick recorded is drawn once when the borrow is made, e.g. once per
+/// archetype by a query, so every component changed through the same query shares a tick.
 pub struct Mut<'a, T: Component> {
     pub(crate) value: &'a mut T,
     pub(crate) mutated: &'a mut bool,
+    pub(crate) change_tick: &'a mut u32,
+    /// Tick recorded in `change_tick` upon mutable access
+    pub(crate) tick: u32,
+    /// Set upon mutable access to let filters know the component's archetype has changes
+    pub(crate) column_changed: Option<&'a AtomicBool>,
+    /// Whether the change has already been recorded
+    pub(crate) recorded: bool,
 }
 
 impl<'a, T: Component> Mut<'a, T> {
     /// Creates a new mutable reference to a component. This is unsafe because the index bounds are not checked.
     ///
+    /// `tick` is stored in `change_tick` upon the first mutable access.
+    ///
     /// # Safety
     /// This doesn't check the bounds of index in archetype
-    pub unsafe fn new(value: &'a mut T, mutated: &'a mut bool) -> Self {
-        Mut { value, mutated }
+    pub unsafe fn new(
+        value: &'a mut T,
+        mutated: &'a mut bool,
+        change_tick: &'a mut u32,
+        tick: u32,
+    ) -> Self {
+        Mut {
+            value,
+            mutated,
+            change_tick,
+            tick,
+            column_changed: None,
+            recorded: false,
+        }
+    }
+
+    /// Borrow the component at `index` in the column identified by `state`
+    ///
+    /// # Safety
+    ///
+    /// `index` must be in bounds, and the component must not be otherwise borrowed for `'a`.
+    pub(crate) unsafe fn from_archetype(
+        archetype: &'a Archetype,
+        state: usize,
+        index: usize,
+        tick: u32,
+    ) -> Self {
+        Mut {
+            value: &mut *archetype.get_base::<T>(state).as_ptr().add(index),
+            mutated: &mut *archetype.get_mutated(state).as_ptr().add(index),
+            change_tick: &mut *archetype.get_change_ticks(state).as_ptr().add(index),
+            tick,
+            column_changed: Some(archetype.changed_flag(state).as_ref()),
+            recorded: false,
+        }
     }
 }
 
@@ -153,7 +326,14 @@ impl<'a, T: Component> Deref for Mut<'a, T> {
 impl<'a, T: Component> DerefMut for Mut<'a, T> {
     #[inline]
     fn deref_mut(&mut self) -> &mut T {
-        *self.mutated = true;
+        if !self.recorded {
+            *self.mutated = true;
+            *self.change_tick = self.tick;
+            if let Some(flag) = self.column_changed {
+                set_flag(flag);
+            }
+            self.recorded = true;
+        }
         self.value
     }
 }
@@ -164,12 +344,23 @@ impl<'a, T: Component + core::fmt::Debug> core::fmt::Debug for Mut<'a, T> {
     }
 }
 
-impl<'a, T: Component> Query for &'a mut T {
+impl<T: Component> Query for &'_ mut T {
+    type Fetch = FetchWrite<T>;
+}
+
+/// Equivalent to `&mut T`, allowing the item type to be named, e.g. in `#[derive(Query)]`
+impl<T: Component> Query for Mut<'_, T> {
     type Fetch = FetchWrite<T>;
 }
 
 #[doc(hidden)]
-pub struct FetchWrite<T>(NonNull<T>, NonNull<bool>);
+pub struct FetchWrite<T>(
+    NonNull<T>,
+    NonNull<bool>,
+    NonNull<u32>,
+    u32,
+    NonNull<AtomicBool>,
+);
 
 unsafe impl<'a, T: Component> Fetch<'a> for FetchWrite<T> {
     type Item = Mut<'a, T>;
@@ -177,7 +368,13 @@ unsafe impl<'a, T: Component> Fetch<'a> for FetchWrite<T> {
     type State = usize;
 
     fn dangling() -> Self {
-        Self(NonNull::dangling(), NonNull::dangling())
+        Self(
+            NonNull::dangling(),
+            NonNull::dangling(),
+            NonNull::dangling(),
+            0,
+            NonNull::dangling(),
+        )
     }
 
     fn access(archetype: &Archetype) -> Option<Access> {
@@ -196,21 +393,72 @@ unsafe impl<'a, T: Component> Fetch<'a> for FetchWrite<T> {
         Some(archetype.get_state::<T>()?)
     }
     fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
-        Self(archetype.get_base::<T>(state), archetype.get_mutated(state))
+        Self(
+            archetype.get_base::<T>(state),
+            archetype.get_mutated(state),
+            archetype.get_change_ticks(state),
+            // Drawn once per archetype rather than per access, avoiding contention on the shared
+            // counter
+            next_tick(archetype.change_tick()),
+            archetype.changed_flag(state),
+        )
     }
     fn release(archetype: &Archetype, state: Self::State) {
         archetype.release_mut::<T>(state);
     }
 
-    fn for_each_borrow(mut f: impl FnMut(TypeId, bool)) {
+    fn for_each_borrow(mut f: impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<T>(), type_name::<T>(), true);
+    }
+
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
         f(TypeId::of::<T>(), true);
     }
 
     unsafe fn get(&self, n: usize) -> Self::Item {
-        Mut::new(&mut *self.0.as_ptr().add(n), &mut *self.1.as_ptr().add(n))
+        Mut {
+            value: &mut *self.0.as_ptr().add(n),
+            mutated: &mut *self.1.as_ptr().add(n),
+            change_tick: &mut *self.2.as_ptr().add(n),
+            tick: self.3,
+            column_changed: Some(self.4.as_ref()),
+            recorded: false,
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn prefetch(&self, n: usize) {
+        prefetch(self.0.as_ptr().wrapping_add(n));
     }
 }
 
+/// Matches every entity, yielding `Some` for entities that `T` matches and `None` otherwise
+///
+/// Trackers nest as expected: `Option<Mutated<T>>` yields `None` for entities lacking `T`, so
+/// they can be told apart from entities whose `T` is unchanged. Filters like
+/// [`ChangedFilter`](crate::ChangedFilter) yield `None` for the entities they reject.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// struct Position(f32);
+/// struct Velocity(f32);
+///
+/// let mut world = World::new();
+/// let still = world.spawn((Position(0.0),));
+/// let moving = world.spawn((Position(0.0), Velocity(1.0)));
+/// let mut query = world.query::<(&Position, Option<(&Velocity, Mutated<Velocity>)>)>();
+/// for (entity, (_, velocity)) in query.iter() {
+///     match velocity {
+///         None => assert_eq!(entity, still),
+///         Some((velocity, mutated)) => {
+///             assert_eq!(entity, moving);
+///             assert_eq!(velocity.0, 1.0);
+///             assert!(!mutated);
+///         }
+///     }
+/// }
+/// ```
 impl<T: Query> Query for Option<T> {
     type Fetch = TryFetch<T::Fetch>;
 }
@@ -242,22 +490,40 @@ unsafe impl<'a, T: Fetch<'a>> Fetch<'a> for TryFetch<T> {
     fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
         Self(state.map(|state| T::execute(archetype, state)))
     }
+    fn execute_since(archetype: &'a Archetype, state: Self::State, last_run: u32) -> Self {
+        Self(state.map(|state| T::execute_since(archetype, state, last_run)))
+    }
     fn release(archetype: &Archetype, state: Self::State) {
         if let Some(state) = state {
             T::release(archetype, state);
         }
     }
 
-    fn for_each_borrow(f: impl FnMut(TypeId, bool)) {
+    fn for_each_borrow(f: impl FnMut(TypeId, &'static str, bool)) {
         T::for_each_borrow(f);
     }
 
     unsafe fn get(&self, n: usize) -> Option<T::Item> {
-        Some(self.0.as_ref()?.get(n))
+        let fetch = self.0.as_ref()?;
+        if !fetch.filter(n) {
+            return None;
+        }
+        Some(fetch.get(n))
+    }
+
+    #[inline(always)]
+    unsafe fn prefetch(&self, n: usize) {
+        if let Some(ref x) = self.0 {
+            x.prefetch(n);
+        }
     }
 }
 
 /// Holds an `L`, or an `R`, or both
+///
+/// As a query, matches entities satisfying at least one of `L` and `R`, and borrows everything
+/// either of them would. More than two alternatives can be expressed by nesting, e.g.
+/// `Or<&A, Or<&B, &C>>`.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Or<L, R> {
     /// Just an `L`
@@ -381,17 +647,77 @@ unsafe impl<'a, L: Fetch<'a>, R: Fetch<'a>> Fetch<'a> for FetchOr<L, R> {
         Self(state.map(|l| L::execute(archetype, l), |r| R::execute(archetype, r)))
     }
 
+    fn execute_since(archetype: &'a Archetype, state: Self::State, last_run: u32) -> Self {
+        Self(state.map(
+            |l| L::execute_since(archetype, l, last_run),
+            |r| R::execute_since(archetype, r, last_run),
+        ))
+    }
+
     fn release(archetype: &Archetype, state: Self::State) {
         state.map(|l| L::release(archetype, l), |r| R::release(archetype, r));
     }
 
-    fn for_each_borrow(mut f: impl FnMut(TypeId, bool)) {
+    fn for_each_borrow(mut f: impl FnMut(TypeId, &'static str, bool)) {
         L::for_each_borrow(&mut f);
         R::for_each_borrow(&mut f);
     }
 
     unsafe fn get(&self, n: usize) -> Self::Item {
-        self.0.as_ref().map(|l| l.get(n), |r| r.get(n))
+        match self.0 {
+            Or::Left(ref l) => Or::Left(l.get(n)),
+            Or::Right(ref r) => Or::Right(r.get(n)),
+            // Only yield the sides whose filters accept this entity
+            Or::Both(ref l, ref r) => match (l.filter(n), r.filter(n)) {
+                (true, false) => Or::Left(l.get(n)),
+                (false, true) => Or::Right(r.get(n)),
+                _ => Or::Both(l.get(n), r.get(n)),
+            },
+        }
+    }
+
+    #[inline]
+    unsafe fn filter(&self, n: usize) -> bool {
+        match self.0 {
+            Or::Left(ref l) => l.filter(n),
+            Or::Right(ref r) => r.filter(n),
+            Or::Both(ref l, ref r) => l.filter(n) || r.filter(n),
+        }
+    }
+
+    #[inline]
+    unsafe fn matches(archetype: &Archetype, state: Self::State, n: usize) -> bool {
+        match state {
+            Or::Left(l) => L::matches(archetype, l, n),
+            Or::Right(r) => R::matches(archetype, r, n),
+            Or::Both(l, r) => L::matches(archetype, l, n) || R::matches(archetype, r, n),
+        }
+    }
+
+    #[inline]
+    fn is_dense() -> bool {
+        L::is_dense() && R::is_dense()
+    }
+
+    #[inline]
+    fn may_match(&self) -> bool {
+        match self.0 {
+            Or::Left(ref l) => l.may_match(),
+            Or::Right(ref r) => r.may_match(),
+            Or::Both(ref l, ref r) => l.may_match() || r.may_match(),
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn prefetch(&self, n: usize) {
+        match self.0 {
+            Or::Left(ref l) => l.prefetch(n),
+            Or::Right(ref r) => r.prefetch(n),
+            Or::Both(ref l, ref r) => {
+                l.prefetch(n);
+                r.prefetch(n);
+            }
+        }
     }
 }
 
@@ -450,17 +776,50 @@ unsafe impl<'a, T: Component, F: Fetch<'a>> Fetch<'a> for FetchWithout<T, F> {
     fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
         Self(F::execute(archetype, state), PhantomData)
     }
+    fn execute_since(archetype: &'a Archetype, state: Self::State, last_run: u32) -> Self {
+        Self(F::execute_since(archetype, state, last_run), PhantomData)
+    }
     fn release(archetype: &Archetype, state: Self::State) {
         F::release(archetype, state)
     }
 
-    fn for_each_borrow(f: impl FnMut(TypeId, bool)) {
+    fn for_each_borrow(f: impl FnMut(TypeId, &'static str, bool)) {
         F::for_each_borrow(f);
     }
 
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), false);
+        F::for_each_requirement(f);
+    }
+
     unsafe fn get(&self, n: usize) -> F::Item {
         self.0.get(n)
     }
+
+    #[inline]
+    unsafe fn filter(&self, n: usize) -> bool {
+        self.0.filter(n)
+    }
+
+    #[inline]
+    unsafe fn matches(archetype: &Archetype, state: Self::State, n: usize) -> bool {
+        F::matches(archetype, state, n)
+    }
+
+    #[inline]
+    fn is_dense() -> bool {
+        F::is_dense()
+    }
+
+    #[inline]
+    fn may_match(&self) -> bool {
+        self.0.may_match()
+    }
+
+    #[inline(always)]
+    unsafe fn prefetch(&self, n: usize) {
+        self.0.prefetch(n);
+    }
 }
 
 /// Query transformer skipping entities that do not have a `T` component
@@ -482,6 +841,23 @@ unsafe impl<'a, T: Component, F: Fetch<'a>> Fetch<'a> for FetchWithout<T, F> {
 /// assert!(entities.contains(&(a, 123)));
 /// assert!(entities.contains(&(b, 456)));
 /// ```
+///
+/// `With<T, ()>` selects entities having a `T` without borrowing or reading any component data at
+/// all, making it the cheapest way to collect matching entities, e.g. for deferred despawning:
+/// ```
+/// # use hecs::*;
+/// struct Dead;
+/// let mut world = World::new();
+/// let a = world.spawn((123, Dead));
+/// let b = world.spawn((456,));
+/// let dead = world.query::<With<Dead, ()>>()
+///     .iter()
+///     .map(|(e, ())| e)
+///     .collect::<Vec<_>>();
+/// assert_eq!(dead, [a]);
+/// world.despawn_batch(dead);
+/// assert!(!world.contains(a) && world.contains(b));
+/// ```
 pub struct With<T, Q>(PhantomData<(Q, fn(T))>);
 
 impl<T: Component, Q: Query> Query for With<T, Q> {
@@ -520,17 +896,174 @@ unsafe impl<'a, T: Component, F: Fetch<'a>> Fetch<'a> for FetchWith<T, F> {
     fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
         Self(F::execute(archetype, state), PhantomData)
     }
+    fn execute_since(archetype: &'a Archetype, state: Self::State, last_run: u32) -> Self {
+        Self(F::execute_since(archetype, state, last_run), PhantomData)
+    }
     fn release(archetype: &Archetype, state: Self::State) {
         F::release(archetype, state)
     }
 
-    fn for_each_borrow(f: impl FnMut(TypeId, bool)) {
+    fn for_each_borrow(f: impl FnMut(TypeId, &'static str, bool)) {
         F::for_each_borrow(f);
     }
 
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), true);
+        F::for_each_requirement(f);
+    }
+
     unsafe fn get(&self, n: usize) -> F::Item {
         self.0.get(n)
     }
+
+    #[inline]
+    unsafe fn filter(&self, n: usize) -> bool {
+        self.0.filter(n)
+    }
+
+    #[inline]
+    unsafe fn matches(archetype: &Archetype, state: Self::State, n: usize) -> bool {
+        F::matches(archetype, state, n)
+    }
+
+    #[inline]
+    fn is_dense() -> bool {
+        F::is_dense()
+    }
+
+    #[inline]
+    fn may_match(&self) -> bool {
+        self.0.may_match()
+    }
+
+    #[inline(always)]
+    unsafe fn prefetch(&self, n: usize) {
+        self.0.prefetch(n);
+    }
+}
+
+/// A query that yields `true` iff an entity has a `T` component, without borrowing it
+///
+/// Matches every entity. Unlike `Option<&T>`, no borrow of `T` is made, so this never conflicts
+/// with another query accessing `T`.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let a = world.spawn((123, true));
+/// let b = world.spawn((456,));
+/// let mut entities = world.query::<(&i32, Satisfies<bool>)>()
+///     .iter()
+///     .map(|(e, (&i, has_bool))| (e, i, has_bool))
+///     .collect::<Vec<_>>();
+/// entities.sort();
+/// assert_eq!(entities, &[(a, 123, true), (b, 456, false)]);
+/// ```
+pub struct Satisfies<T>(PhantomData<fn(T)>);
+
+impl<T: Component> Query for Satisfies<T> {
+    type Fetch = FetchSatisfies<T>;
+}
+
+#[doc(hidden)]
+pub struct FetchSatisfies<T>(bool, PhantomData<fn(T)>);
+
+unsafe impl<'a, T: Component> Fetch<'a> for FetchSatisfies<T> {
+    type Item = bool;
+
+    type State = bool;
+
+    fn dangling() -> Self {
+        Self(false, PhantomData)
+    }
+
+    fn access(_archetype: &Archetype) -> Option<Access> {
+        Some(Access::Iterate)
+    }
+
+    fn borrow(_archetype: &Archetype, _state: Self::State) {}
+    fn prepare(archetype: &Archetype) -> Option<Self::State> {
+        Some(archetype.has::<T>())
+    }
+    fn execute(_archetype: &'a Archetype, state: Self::State) -> Self {
+        Self(state, PhantomData)
+    }
+    fn release(_archetype: &Archetype, _state: Self::State) {}
+
+    fn for_each_borrow(_: impl FnMut(TypeId, &'static str, bool)) {}
+
+    unsafe fn get(&self, _: usize) -> bool {
+        self.0
+    }
+}
+
+/// Query that yields a copy of an entity's `T` component
+///
+/// Borrows `T` like `&T`, but yields it by value, sparing dereferences in code working with small
+/// components.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let a = world.spawn((1.0f32, 2u32));
+/// for (_, (scale, mut value)) in world.query::<(Copied<f32>, &mut u32)>().iter() {
+///     *value = (*value as f32 * scale * 3.0) as u32;
+/// }
+/// assert_eq!(*world.get::<u32>(a).unwrap(), 6);
+/// ```
+pub struct Copied<T>(PhantomData<fn(T)>);
+
+impl<T: Component + Copy> Query for Copied<T> {
+    type Fetch = FetchCopied<T>;
+}
+
+#[doc(hidden)]
+pub struct FetchCopied<T>(FetchRead<T>);
+
+unsafe impl<'a, T: Component + Copy> Fetch<'a> for FetchCopied<T> {
+    type Item = T;
+
+    type State = usize;
+
+    fn dangling() -> Self {
+        Self(FetchRead::dangling())
+    }
+
+    fn access(archetype: &Archetype) -> Option<Access> {
+        FetchRead::<T>::access(archetype)
+    }
+
+    fn borrow(archetype: &Archetype, state: Self::State) {
+        FetchRead::<T>::borrow(archetype, state);
+    }
+    fn prepare(archetype: &Archetype) -> Option<Self::State> {
+        FetchRead::<T>::prepare(archetype)
+    }
+    fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
+        Self(FetchRead::execute(archetype, state))
+    }
+    fn release(archetype: &Archetype, state: Self::State) {
+        FetchRead::<T>::release(archetype, state);
+    }
+
+    fn for_each_borrow(f: impl FnMut(TypeId, &'static str, bool)) {
+        FetchRead::<T>::for_each_borrow(f);
+    }
+
+    fn for_each_requirement(f: impl FnMut(TypeId, bool)) {
+        FetchRead::<T>::for_each_requirement(f);
+    }
+
+    unsafe fn get(&self, n: usize) -> T {
+        *self.0.get(n)
+    }
+
+    #[inline(always)]
+    unsafe fn prefetch(&self, n: usize) {
+        self.0.prefetch(n);
+    }
 }
 
 /// A borrow of a [`World`](crate::World) sufficient to execute the query `Q`
@@ -539,43 +1072,285 @@ unsafe impl<'a, T: Component, F: Fetch<'a>> Fetch<'a> for FetchWith<T, F> {
 pub struct QueryBorrow<'w, Q: Query> {
     meta: &'w [EntityMeta],
     archetypes: &'w [Archetype],
+    components: &'w ComponentRegistry,
+    filter: ArchetypeFilter,
+    last_run: Option<u32>,
     borrowed: bool,
     _marker: PhantomData<Q>,
 }
 
 impl<'w, Q: Query> QueryBorrow<'w, Q> {
-    pub(crate) fn new(meta: &'w [EntityMeta], archetypes: &'w [Archetype]) -> Self {
+    pub(crate) fn new(
+        meta: &'w [EntityMeta],
+        archetypes: &'w [Archetype],
+        components: &'w ComponentRegistry,
+    ) -> Self {
         Self {
             meta,
             archetypes,
+            components,
+            filter: ArchetypeFilter::new::<Q>(components),
+            last_run: None,
             borrowed: false,
             _marker: PhantomData,
         }
     }
 
+    /// Compare change ticks against `tick` rather than
+    /// [`World::last_change_tick()`](crate::World::last_change_tick())
+    ///
+    /// Affects queries like [`ChangedSince`](crate::ChangedSince) and
+    /// [`Tracked`](crate::Tracked), letting each system pass the tick it stored when it last ran,
+    /// even while sharing the world with others.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123,));
+    /// let b = world.spawn((456,));
+    /// let first_run = world.increment_change_tick();
+    /// *world.get_mut::<i32>(a).unwrap() = 42;
+    /// let second_run = world.increment_change_tick();
+    /// *world.get_mut::<i32>(b).unwrap() = 42;
+    /// let changed = |tick| {
+    ///     world
+    ///         .query::<ChangedSince<i32>>()
+    ///         .changed_since(tick)
+    ///         .iter()
+    ///         .filter(|&(_, changed)| changed)
+    ///         .count()
+    /// };
+    /// assert_eq!(changed(first_run), 2);
+    /// assert_eq!(changed(second_run), 1);
+    /// ```
+    pub fn changed_since(mut self, tick: u32) -> Self {
+        self.last_run = Some(tick);
+        self
+    }
+
     /// Execute the query
     ///
     /// Must be called only once per query.
     // The lifetime narrowing here is required for soundness.
     pub fn iter(&mut self) -> QueryIter<'_, Q> {
         self.borrow();
-        unsafe { QueryIter::new(self.meta, self.archetypes.iter()) }
+        unsafe {
+            QueryIter::new(
+                self.meta,
+                self.archetypes.iter(),
+                self.filter.clone(),
+                self.last_run,
+            )
+        }
     }
 
-    /// Like `iter`, but returns child iterators of at most `batch_size` elements
+    /// Count the entities that `Q` would yield, without borrowing or fetching any components
     ///
-    /// Useful for distributing work over a threadpool.
-    // The lifetime narrowing here is required for soundness.
-    pub fn iter_batched(&mut self, batch_size: u32) -> BatchedIter<'_, Q> {
-        self.borrow();
-        unsafe { BatchedIter::new(self.meta, self.archetypes.iter(), batch_size) }
-    }
-
-    fn borrow(&mut self) {
-        if self.borrowed {
-            return;
-        }
-        for x in self.archetypes {
+    /// For queries whose matching is decided per archetype, this sums the lengths of the
+    /// archetypes that satisfy `Q`. Queries that select individual entities, like
+    /// [`ChangedFilter`](crate::ChangedFilter), fall back to checking every entity in each
+    /// matching archetype.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.spawn((123, true));
+    /// world.spawn((456,));
+    /// world.spawn((true,));
+    /// assert_eq!(world.query::<&i32>().count(), 2);
+    /// assert_eq!(world.query::<(&i32, &bool)>().count(), 1);
+    /// ```
+    pub fn count(&mut self) -> usize {
+        self.archetypes
+            .iter()
+            .map(|x| archetype_matches::<Q>(&self.filter, x))
+            .sum()
+    }
+
+    /// Like `iter`, but yields an [`EntityRef`] in place of each [`Entity`]
+    ///
+    /// Allows components outside of `Q` to be accessed on demand without widening the query, with
+    /// the usual dynamic borrow checks. Borrowing a component in a way that conflicts with `Q`, or
+    /// with an outstanding borrow from another entity in the same archetype, panics.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Rare(i32);
+    /// let mut world = World::new();
+    /// world.spawn((1,));
+    /// world.spawn((2, Rare(10)));
+    /// let mut total = 0;
+    /// for (entity, &x) in world.query::<&i32>().iter_entity_refs() {
+    ///     total += x;
+    ///     if let Some(rare) = entity.get::<Rare>() {
+    ///         total += rare.0;
+    ///     }
+    /// }
+    /// assert_eq!(total, 13);
+    /// ```
+    // The lifetime narrowing here is required for soundness.
+    pub fn iter_entity_refs(&mut self) -> QueryEntityRefIter<'_, Q> {
+        QueryEntityRefIter(self.iter())
+    }
+
+    /// Like `iter`, but skips entire archetypes for which `pred` returns `false`
+    ///
+    /// `pred` is called once per archetype that matches `Q`, before any of its entities are
+    /// visited, making this much cheaper than rejecting entities one at a time when the condition
+    /// depends only on which components are present. For conditions on component values, use
+    /// [`Iterator::filter`], which costs no more than checking the condition in the loop body.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Health(i32);
+    /// struct Invulnerable;
+    /// let mut world = World::new();
+    /// let a = world.spawn((Health(5),));
+    /// world.spawn((Health(50),));
+    /// world.spawn((Health(5), Invulnerable));
+    /// let dying = world
+    ///     .query::<&Health>()
+    ///     .iter_filter(|archetype| !archetype.has::<Invulnerable>())
+    ///     .filter(|(_, health)| health.0 < 10)
+    ///     .map(|(e, _)| e)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(dying, [a]);
+    /// ```
+    // The lifetime narrowing here is required for soundness.
+    pub fn iter_filter<F>(&mut self, pred: F) -> QueryFilterIter<'_, Q, F>
+    where
+        F: FnMut(&Archetype) -> bool,
+    {
+        QueryFilterIter {
+            iter: self.iter(),
+            pred,
+        }
+    }
+
+    /// Like `iter`, but yields entities in ascending order of [`Entity::id`], regardless of how
+    /// they're laid out in memory
+    ///
+    /// `iter` visits archetypes in order of creation, and each archetype's entities in storage
+    /// order, which despawning and component removal scramble. This makes iteration order depend
+    /// on the full history of the world, which is a hazard for lockstep simulations that must not
+    /// diverge across machines. Because entity IDs are allocated deterministically, sorting by ID
+    /// gives an order that depends only on which entities exist. Costs an allocation and a sort on
+    /// every call, so prefer `iter` where order doesn't matter.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let entities = (0..4).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    /// world.despawn(entities[0]).unwrap(); // Moves the last entity into the vacated slot
+    /// world.insert_one(entities[2], true).unwrap(); // Moves to another archetype
+    /// let order = world
+    ///     .query::<&i32>()
+    ///     .iter_sorted()
+    ///     .map(|(_, &x)| x)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(order, [1, 2, 3]);
+    /// ```
+    // The lifetime narrowing here is required for soundness.
+    pub fn iter_sorted(&mut self) -> crate::alloc::vec::IntoIter<(Entity, QueryItem<'_, Q>)> {
+        let mut items = self.iter().collect::<Vec<_>>();
+        items.sort_unstable_by_key(|&(entity, _)| entity.id());
+        items.into_iter()
+    }
+
+    /// Like `iter`, but returns child iterators of at most `batch_size` elements
+    ///
+    /// Useful for distributing work over a threadpool.
+    // The lifetime narrowing here is required for soundness.
+    pub fn iter_batched(&mut self, batch_size: u32) -> BatchedIter<'_, Q> {
+        self.borrow();
+        unsafe {
+            BatchedIter::new(
+                self.meta,
+                self.archetypes.iter(),
+                batch_size,
+                self.filter.clone(),
+                self.last_run,
+            )
+        }
+    }
+
+    /// Like `iter_batched`, but yields exactly one [`Batch`] per non-empty matching archetype
+    ///
+    /// Entities in an archetype share a component layout, so each batch's
+    /// [`column`](Batch::column)s cover every matching entity in the archetype as contiguous
+    /// slices, suitable for e.g. issuing one instanced draw call per archetype. Entities that
+    /// differ only in components outside `Q` still fall in distinct archetypes, and hence distinct
+    /// batches.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Transform([f32; 2]);
+    /// struct Mesh(u32);
+    /// let mut world = World::new();
+    /// world.spawn_batch((0..10).map(|i| (Transform([i as f32, 0.0]), Mesh(0))));
+    /// world.spawn_batch((0..5).map(|i| (Transform([i as f32, 1.0]), Mesh(1), true)));
+    /// let mut draws = Vec::new();
+    /// for batch in world.query::<(&Transform, &Mesh)>().iter_archetypes() {
+    ///     let meshes = batch.column::<Mesh>().unwrap();
+    ///     let transforms = batch.column::<Transform>().unwrap();
+    ///     assert_eq!(batch.entities().len(), transforms.len());
+    ///     draws.push((meshes[0].0, transforms.len()));
+    /// }
+    /// assert_eq!(draws, [(0, 10), (1, 5)]);
+    /// ```
+    // The lifetime narrowing here is required for soundness.
+    pub fn iter_archetypes(&mut self) -> BatchedIter<'_, Q> {
+        self.iter_batched(u32::MAX)
+    }
+
+    /// Like `iter`, but returns a rayon [`ParallelIterator`](rayon::iter::ParallelIterator)
+    ///
+    /// Work is split per archetype, and within each archetype into batches of at most
+    /// 1024 entities, exactly as [`iter_batched`](Self::iter_batched) would.
+    ///
+    /// The closures driving the resulting iterator must not access the `World` this query borrows
+    /// from. Components borrowed by the query will panic if borrowed again, and any other access
+    /// races with the parallel traversal.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// use rayon::prelude::*;
+    /// let mut world = World::new();
+    /// for i in 0..10_000 {
+    ///     world.spawn((i,));
+    /// }
+    /// world.query::<&mut i32>().par_iter().for_each(|(_, mut x)| *x *= 2);
+    /// assert_eq!(world.query::<&i32>().iter().map(|(_, &x)| x).sum::<i32>(), 99_990_000);
+    /// ```
+    // The lifetime narrowing here is required for soundness.
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "parallel")))]
+    pub fn par_iter(
+        &mut self,
+    ) -> impl rayon::iter::ParallelIterator<Item = (Entity, QueryItem<'_, Q>)> + '_
+    where
+        for<'q> QueryItem<'q, Q>: Send,
+    {
+        use crate::alloc::vec::Vec;
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+        let batches = self.iter_batched(PAR_BATCH_SIZE).collect::<Vec<_>>();
+        batches.into_par_iter().flat_map_iter(|batch| batch)
+    }
+
+    fn borrow(&mut self) {
+        if self.borrowed {
+            return;
+        }
+        assert_borrow::<Q>();
+        for x in self.archetypes.iter().filter(|x| self.filter.admits(x)) {
             // TODO: Release prior borrows on failure?
             if let Some(state) = Q::Fetch::prepare(x) {
                 Q::Fetch::borrow(x, state);
@@ -637,6 +1412,9 @@ impl<'w, Q: Query> QueryBorrow<'w, Q> {
         let x = QueryBorrow {
             meta: self.meta,
             archetypes: self.archetypes,
+            components: self.components,
+            filter: ArchetypeFilter::new::<R>(self.components),
+            last_run: self.last_run,
             borrowed: self.borrowed,
             _marker: PhantomData,
         };
@@ -652,7 +1430,7 @@ unsafe impl<'w, Q: Query> Sync for QueryBorrow<'w, Q> {}
 impl<'w, Q: Query> Drop for QueryBorrow<'w, Q> {
     fn drop(&mut self) {
         if self.borrowed {
-            for x in self.archetypes {
+            for x in self.archetypes.iter().filter(|x| self.filter.admits(x)) {
                 if let Some(state) = Q::Fetch::prepare(x) {
                     Q::Fetch::release(x, state);
                 }
@@ -670,10 +1448,101 @@ impl<'q, 'w, Q: Query> IntoIterator for &'q mut QueryBorrow<'w, Q> {
     }
 }
 
+/// Iterate over a query whose items don't borrow from the world, like [`Copied`] or
+/// [`Satisfies`], keeping the components borrowed until the iterator is dropped
+///
+/// Allows such iterators to be returned from functions. Items that borrow from the world can't
+/// be supported this way, as they could outlive the iterator and hence the borrow that guards
+/// them; return the `QueryBorrow` itself instead.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// #[derive(Copy, Clone)]
+/// struct Position(f32);
+/// struct Velocity(f32);
+///
+/// fn moving(world: &World) -> impl Iterator<Item = (Entity, Position)> + '_ {
+///     world.query::<With<Velocity, Copied<Position>>>().into_iter()
+/// }
+///
+/// fn moving_refs(world: &World) -> QueryBorrow<'_, (&Position, &Velocity)> {
+///     world.query()
+/// }
+///
+/// let mut world = World::new();
+/// world.spawn((Position(0.0),));
+/// let e = world.spawn((Position(1.0), Velocity(2.0)));
+/// assert_eq!(moving(&world).map(|(e, p)| (e, p.0)).collect::<Vec<_>>(), [(e, 1.0)]);
+/// for (_, (p, v)) in &mut moving_refs(&world) {
+///     assert_eq!((p.0, v.0), (1.0, 2.0));
+/// }
+/// ```
+impl<'w, Q: Query> IntoIterator for QueryBorrow<'w, Q>
+where
+    for<'a> QueryItem<'a, Q>: 'static,
+{
+    type Item = (Entity, QueryItem<'w, Q>);
+    type IntoIter = QueryIntoIter<'w, Q>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        self.borrow();
+        QueryIntoIter {
+            // Safety: the items don't borrow from the world, and the borrow is released only once
+            // the iterator, and hence any in-progress fetch, is dropped
+            iter: unsafe {
+                QueryIter::new(
+                    self.meta,
+                    self.archetypes.iter(),
+                    self.filter.clone(),
+                    self.last_run,
+                )
+            },
+            _borrow: self,
+        }
+    }
+}
+
+/// Iterator over the set of entities with the components in `Q`, owning the [`QueryBorrow`]
+///
+/// Obtained by calling `into_iter` on a `QueryBorrow` whose items don't borrow from the world.
+pub struct QueryIntoIter<'w, Q: Query> {
+    // Declared first so that it's dropped before the borrow is released
+    iter: QueryIter<'w, Q>,
+    _borrow: QueryBorrow<'w, Q>,
+}
+
+impl<'w, Q: Query> Iterator for QueryIntoIter<'w, Q> {
+    type Item = (Entity, QueryItem<'w, Q>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.iter.nth(n)
+    }
+}
+
+impl<Q: Query> ExactSizeIterator for QueryIntoIter<'_, Q> {
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
 /// Iterator over the set of entities with the components in `Q`
 pub struct QueryIter<'q, Q: Query> {
     meta: &'q [EntityMeta],
     archetypes: SliceIter<'q, Archetype>,
+    filter: ArchetypeFilter,
+    last_run: Option<u32>,
+    /// The archetype `iter` draws from, if any
+    archetype: Option<&'q Archetype>,
     iter: ChunkIter<Q>,
 }
 
@@ -682,10 +1551,18 @@ impl<'q, Q: Query> QueryIter<'q, Q> {
     ///
     /// `'q` must be sufficient to guarantee that `Q` cannot violate borrow safety, either with
     /// dynamic borrow checks or by representing exclusive access to the `World`.
-    unsafe fn new(meta: &'q [EntityMeta], archetypes: SliceIter<'q, Archetype>) -> Self {
+    unsafe fn new(
+        meta: &'q [EntityMeta],
+        archetypes: SliceIter<'q, Archetype>,
+        filter: ArchetypeFilter,
+        last_run: Option<u32>,
+    ) -> Self {
         Self {
             meta,
             archetypes,
+            filter,
+            last_run,
+            archetype: None,
             iter: ChunkIter::empty(),
         }
     }
@@ -699,18 +1576,44 @@ impl<'q, Q: Query> Iterator for QueryIter<'q, Q> {
 
     #[inline(always)]
     fn next(&mut self) -> Option<Self::Item> {
+        self.next_in(|_| true)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.len();
+        (n, Some(n))
+    }
+
+    /// Skips whole archetypes at once where possible, rather than visiting every entity
+    fn nth(&mut self, mut n: usize) -> Option<Self::Item> {
+        n -= unsafe { self.iter.skip(n) };
+        while n > 0 {
+            let archetype = self.archetypes.as_slice().first()?;
+            let len = archetype_matches::<Q>(&self.filter, archetype);
+            self.archetypes.next();
+            if len <= n {
+                n -= len;
+                continue;
+            }
+            self.enter(archetype, |_| true);
+            n -= unsafe { self.iter.skip(n) };
+        }
+        self.next()
+    }
+}
+
+impl<'q, Q: Query> QueryIter<'q, Q> {
+    /// Advance to the next entity, skipping any archetypes rejected by `pred`
+    #[inline(always)]
+    fn next_in(
+        &mut self,
+        mut pred: impl FnMut(&Archetype) -> bool,
+    ) -> Option<(Entity, QueryItem<'q, Q>)> {
         loop {
             match unsafe { self.iter.next() } {
                 None => {
                     let archetype = self.archetypes.next()?;
-                    let state = Q::Fetch::prepare(archetype);
-                    let fetch = state.map(|state| Q::Fetch::execute(archetype, state));
-                    self.iter = fetch.map_or(ChunkIter::empty(), |fetch| ChunkIter {
-                        entities: archetype.entities(),
-                        fetch,
-                        position: 0,
-                        len: archetype.len() as usize,
-                    });
+                    self.enter(archetype, &mut pred);
                     continue;
                 }
                 Some((id, components)) => {
@@ -726,9 +1629,17 @@ impl<'q, Q: Query> Iterator for QueryIter<'q, Q> {
         }
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        let n = self.len();
-        (n, Some(n))
+    /// Begin iterating over `archetype`, unless it doesn't match or `pred` rejects it
+    #[inline(always)]
+    fn enter(&mut self, archetype: &'q Archetype, pred: impl FnOnce(&Archetype) -> bool) {
+        self.archetype = Some(archetype);
+        let state = if self.filter.admits(archetype) {
+            Q::Fetch::prepare(archetype).filter(|_| pred(archetype))
+        } else {
+            None
+        };
+        let fetch = state.map(|state| execute_since::<Q::Fetch>(archetype, state, self.last_run));
+        self.iter = fetch.map_or(ChunkIter::empty(), |fetch| ChunkIter::new(archetype, fetch));
     }
 }
 
@@ -736,27 +1647,118 @@ impl<'q, Q: Query> ExactSizeIterator for QueryIter<'q, Q> {
     fn len(&self) -> usize {
         self.archetypes
             .clone()
-            .filter(|&x| Q::Fetch::access(x).is_some())
-            .map(|x| x.len() as usize)
+            .map(|x| archetype_matches::<Q>(&self.filter, x))
             .sum::<usize>()
             + self.iter.remaining()
     }
 }
 
+/// Iterator over the set of entities with the components in `Q`, yielding an [`EntityRef`] for
+/// each
+///
+/// Obtained from [`QueryBorrow::iter_entity_refs`].
+pub struct QueryEntityRefIter<'q, Q: Query>(QueryIter<'q, Q>);
+
+impl<'q, Q: Query> Iterator for QueryEntityRefIter<'q, Q> {
+    type Item = (EntityRef<'q>, QueryItem<'q, Q>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (entity, item) = self.0.next()?;
+        Some((self.entity_ref(entity), item))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        let (entity, item) = self.0.nth(n)?;
+        Some((self.entity_ref(entity), item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'q, Q: Query> QueryEntityRefIter<'q, Q> {
+    /// Handle to `entity`, which must be the item most recently yielded by the inner iterator
+    fn entity_ref(&self, entity: Entity) -> EntityRef<'q> {
+        let archetype = self.0.archetype.unwrap();
+        let index = self.0.iter.position as u32 - 1;
+        unsafe { EntityRef::new(archetype, entity, index) }
+    }
+}
+
+impl<Q: Query> ExactSizeIterator for QueryEntityRefIter<'_, Q> {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Iterator over the set of entities with the components in `Q`, in archetypes accepted by a
+/// predicate
+///
+/// Obtained from [`QueryBorrow::iter_filter`].
+pub struct QueryFilterIter<'q, Q: Query, F> {
+    iter: QueryIter<'q, Q>,
+    pred: F,
+}
+
+impl<'q, Q: Query, F: FnMut(&Archetype) -> bool> Iterator for QueryFilterIter<'q, Q, F> {
+    type Item = (Entity, QueryItem<'q, Q>);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next_in(&mut self.pred)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.iter.iter.remaining(), Some(self.iter.len()))
+    }
+}
+
 /// A query builder that's convertible directly into an iterator
 pub struct QueryMut<'q, Q: Query> {
     iter: QueryIter<'q, Q>,
+    components: &'q ComponentRegistry,
 }
 
 impl<'q, Q: Query> QueryMut<'q, Q> {
-    pub(crate) fn new(meta: &'q [EntityMeta], archetypes: &'q mut [Archetype]) -> Self {
+    pub(crate) fn new(
+        meta: &'q [EntityMeta],
+        archetypes: &'q mut [Archetype],
+        components: &'q ComponentRegistry,
+    ) -> Self {
+        unsafe { Self::new_unchecked(meta, archetypes, components) }
+    }
+
+    /// Safety: nothing may access the components borrowed by `Q` during `'q` except through the
+    /// result
+    pub(crate) unsafe fn new_unchecked(
+        meta: &'q [EntityMeta],
+        archetypes: &'q [Archetype],
+        components: &'q ComponentRegistry,
+    ) -> Self {
         assert_borrow::<Q>();
 
         Self {
-            iter: unsafe { QueryIter::new(meta, archetypes.iter()) },
+            iter: QueryIter::new(
+                meta,
+                archetypes.iter(),
+                ArchetypeFilter::new::<Q>(components),
+                None,
+            ),
+            components,
         }
     }
 
+    /// Compare change ticks against `tick` rather than
+    /// [`World::last_change_tick()`](crate::World::last_change_tick())
+    ///
+    /// See `QueryBorrow::changed_since`
+    pub fn changed_since(mut self, tick: u32) -> Self {
+        self.iter.last_run = Some(tick);
+        self
+    }
+
     /// Transform the query into one that requires a certain component without borrowing it
     ///
     /// See `QueryBorrow::with`
@@ -774,7 +1776,15 @@ impl<'q, Q: Query> QueryMut<'q, Q> {
     /// Helper to change the type of the query
     fn transform<R: Query>(self) -> QueryMut<'q, R> {
         QueryMut {
-            iter: unsafe { QueryIter::new(self.iter.meta, self.iter.archetypes) },
+            iter: unsafe {
+                QueryIter::new(
+                    self.iter.meta,
+                    self.iter.archetypes,
+                    ArchetypeFilter::new::<R>(self.components),
+                    self.iter.last_run,
+                )
+            },
+            components: self.components,
         }
     }
 }
@@ -789,16 +1799,73 @@ impl<'q, Q: Query> IntoIterator for QueryMut<'q, Q> {
     }
 }
 
-fn assert_borrow<Q: Query>() {
+/// A tuple of queries that can be iterated simultaneously, obtained from
+/// [`World::query_many`](crate::World::query_many)
+///
+/// Implemented for tuples of up to 8 [`Query`] types.
+pub trait QueryMany<'q> {
+    /// A tuple of [`QueryMut`]s, one for each query
+    type Output;
+
+    /// Safety: nothing may access `world`'s components during `'q` except through the result
+    #[doc(hidden)]
+    unsafe fn new(world: &'q World) -> Self::Output;
+
+    /// Invoke `f` for every component borrowed by each query, along with the query's position
+    #[doc(hidden)]
+    fn for_each_borrow(f: impl FnMut(usize, TypeId, &'static str, bool));
+}
+
+macro_rules! query_many_impl {
+    ($($name: ident),*) => {
+        impl<'q, $($name: Query),*> QueryMany<'q> for ($($name,)*) {
+            type Output = ($(QueryMut<'q, $name>,)*);
+
+            #[allow(unused_variables, clippy::unused_unit)]
+            unsafe fn new(world: &'q World) -> Self::Output {
+                ($(QueryMut::<$name>::new_unchecked(world.entities_meta(), world.archetypes_inner(), world.component_registry()),)*)
+            }
+
+            #[allow(unused_variables, unused_mut, unused_assignments)]
+            fn for_each_borrow(mut f: impl FnMut(usize, TypeId, &'static str, bool)) {
+                let mut i = 0;
+                $(
+                    $name::Fetch::for_each_borrow(|id, name, unique| f(i, id, name, unique));
+                    i += 1;
+                )*
+            }
+        }
+    };
+}
+
+smaller_tuples_too!(query_many_impl, H, G, F, E, D, C, B, A);
+
+/// Panics if any component is borrowed uniquely by one query in `Q` and borrowed at all by another
+pub(crate) fn assert_disjoint<'q, Q: QueryMany<'q>>() {
+    Q::for_each_borrow(|i, a, name, unique| {
+        Q::for_each_borrow(|j, b, _, other_unique| {
+            core::assert!(
+                i == j || a != b || !(unique || other_unique),
+                "queries {} and {} conflict over {}",
+                i.min(j),
+                i.max(j),
+                name
+            );
+        })
+    });
+}
+
+/// Panics if `Q` borrows a component uniquely while also borrowing it elsewhere
+pub(crate) fn assert_borrow<Q: Query>() {
     // This looks like an ugly O(n^2) loop, but everything's constant after inlining, so in
     // practice LLVM optimizes it out entirely.
     let mut i = 0;
-    Q::Fetch::for_each_borrow(|a, unique| {
+    Q::Fetch::for_each_borrow(|a, name, unique| {
         if unique {
             let mut j = 0;
-            Q::Fetch::for_each_borrow(|b, _| {
+            Q::Fetch::for_each_borrow(|b, _, _| {
                 if i != j {
-                    core::assert!(a != b, "query violates a unique borrow");
+                    core::assert!(a != b, "query violates a unique borrow of {}", name);
                 }
                 j += 1;
             })
@@ -807,6 +1874,86 @@ fn assert_borrow<Q: Query>() {
     });
 }
 
+/// Number of entities in `range` that pass `fetch`'s filter
+///
+/// Compiles down to `range.len()` for queries that don't filter.
+unsafe fn count_matches<'a, F: Fetch<'a>>(fetch: &F, range: core::ops::Range<usize>) -> usize {
+    if F::is_dense() {
+        return range.len();
+    }
+    if !fetch.may_match() {
+        return 0;
+    }
+    range.filter(|&n| fetch.filter(n)).count()
+}
+
+/// Construct `F` for `archetype`, comparing change ticks against `last_run` if given
+fn execute_since<'a, F: Fetch<'a>>(
+    archetype: &'a Archetype,
+    state: F::State,
+    last_run: Option<u32>,
+) -> F {
+    match last_run {
+        Some(last_run) => F::execute_since(archetype, state, last_run),
+        None => F::execute(archetype, state),
+    }
+}
+
+/// Number of entities in `archetype` that `Q`, with archetypes prefiltered by `filter`, would yield
+fn archetype_matches<Q: Query>(filter: &ArchetypeFilter, archetype: &Archetype) -> usize {
+    if !filter.admits(archetype) {
+        return 0;
+    }
+    if Q::Fetch::is_dense() {
+        return match Q::Fetch::access(archetype) {
+            None => 0,
+            Some(_) => archetype.len() as usize,
+        };
+    }
+    match Q::Fetch::prepare(archetype) {
+        None => 0,
+        Some(state) => unsafe { count_prepared::<Q::Fetch>(archetype, state) },
+    }
+}
+
+/// Number of entities in `archetype` that pass `F`'s filter, without executing `F`
+///
+/// # Safety
+///
+/// `state` must have been obtained from `F::prepare` on `archetype`
+unsafe fn count_prepared<'a, F: Fetch<'a>>(archetype: &Archetype, state: F::State) -> usize {
+    let len = archetype.len() as usize;
+    if F::is_dense() {
+        return len;
+    }
+    (0..len)
+        .filter(|&n| F::matches(archetype, state, n))
+        .count()
+}
+
+/// How many items ahead of the current one query iterators issue prefetches for
+#[cfg(feature = "prefetch")]
+const PREFETCH_DISTANCE: usize = 16;
+
+/// Hint that the memory at `ptr` will be read soon
+///
+/// Does nothing unless the `prefetch` feature is enabled on a target with a stable prefetch
+/// instruction. Never faults, even if `ptr` is invalid.
+#[inline(always)]
+#[allow(unused_variables)]
+pub(crate) fn prefetch<T>(ptr: *const T) {
+    #[cfg(all(feature = "prefetch", target_arch = "x86_64"))]
+    unsafe {
+        use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>(ptr.cast::<i8>());
+    }
+    #[cfg(all(feature = "prefetch", target_arch = "x86", target_feature = "sse"))]
+    unsafe {
+        use core::arch::x86::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>(ptr.cast::<i8>());
+    }
+}
+
 struct ChunkIter<Q: Query> {
     entities: NonNull<u32>,
     fetch: Q::Fetch,
@@ -815,6 +1962,21 @@ struct ChunkIter<Q: Query> {
 }
 
 impl<Q: Query> ChunkIter<Q> {
+    /// Iterate over every entity in `archetype`, unless `fetch` can't match any of them
+    fn new(archetype: &Archetype, fetch: Q::Fetch) -> Self {
+        let len = if fetch.may_match() {
+            archetype.len() as usize
+        } else {
+            0
+        };
+        Self {
+            entities: archetype.entities(),
+            fetch,
+            position: 0,
+            len,
+        }
+    }
+
     fn empty() -> Self {
         Self {
             entities: NonNull::dangling(),
@@ -826,17 +1988,42 @@ impl<Q: Query> ChunkIter<Q> {
 
     #[inline]
     unsafe fn next<'a>(&mut self) -> Option<(u32, <Q::Fetch as Fetch<'a>>::Item)> {
-        if self.position == self.len {
-            return None;
+        loop {
+            if self.position == self.len {
+                return None;
+            }
+            let position = self.position;
+            self.position += 1;
+            #[cfg(feature = "prefetch")]
+            self.fetch.prefetch(position + PREFETCH_DISTANCE);
+            if !self.fetch.filter(position) {
+                continue;
+            }
+            let entity = self.entities.as_ptr().add(position);
+            let item = self.fetch.get(position);
+            return Some((*entity, item));
         }
-        let entity = self.entities.as_ptr().add(self.position);
-        let item = self.fetch.get(self.position);
-        self.position += 1;
-        Some((*entity, item))
     }
 
     fn remaining(&self) -> usize {
-        self.len - self.position
+        unsafe { count_matches(&self.fetch, self.position..self.len) }
+    }
+
+    /// Advance past up to `n` matching entities, returning how many were skipped
+    unsafe fn skip(&mut self, n: usize) -> usize {
+        if Q::Fetch::is_dense() {
+            let skipped = n.min(self.len - self.position);
+            self.position += skipped;
+            return skipped;
+        }
+        let mut skipped = 0;
+        while skipped < n && self.position < self.len {
+            if self.fetch.filter(self.position) {
+                skipped += 1;
+            }
+            self.position += 1;
+        }
+        skipped
     }
 }
 
@@ -845,6 +2032,8 @@ pub struct BatchedIter<'q, Q: Query> {
     _marker: PhantomData<&'q Q>,
     meta: &'q [EntityMeta],
     archetypes: SliceIter<'q, Archetype>,
+    filter: ArchetypeFilter,
+    last_run: Option<u32>,
     batch_size: u32,
     batch: u32,
 }
@@ -858,11 +2047,15 @@ impl<'q, Q: Query> BatchedIter<'q, Q> {
         meta: &'q [EntityMeta],
         archetypes: SliceIter<'q, Archetype>,
         batch_size: u32,
+        filter: ArchetypeFilter,
+        last_run: Option<u32>,
     ) -> Self {
         Self {
             _marker: PhantomData,
             meta,
             archetypes,
+            filter,
+            last_run,
             batch_size,
             batch: 0,
         }
@@ -885,12 +2078,19 @@ impl<'q, Q: Query> Iterator for BatchedIter<'q, Q> {
                 self.batch = 0;
                 continue;
             }
-            let state = Q::Fetch::prepare(archetype);
-            let fetch = state.map(|state| Q::Fetch::execute(archetype, state));
+            let state = if self.filter.admits(archetype) {
+                Q::Fetch::prepare(archetype)
+            } else {
+                None
+            };
+            let fetch = state
+                .map(|state| execute_since::<Q::Fetch>(archetype, state, self.last_run))
+                .filter(|fetch| fetch.may_match());
             if let Some(fetch) = fetch {
                 self.batch += 1;
                 return Some(Batch {
                     meta: self.meta,
+                    archetype,
                     state: ChunkIter {
                         entities: archetype.entities(),
                         fetch,
@@ -910,12 +2110,230 @@ impl<'q, Q: Query> Iterator for BatchedIter<'q, Q> {
     }
 }
 
+/// Number of entities per unit of work in `QueryBorrow::par_iter`
+#[cfg(feature = "parallel")]
+const PAR_BATCH_SIZE: u32 = 1024;
+
 /// A sequence of entities yielded by `BatchedIter`
+///
+/// Every entity in a batch belongs to the same archetype, so the components of the entities not
+/// yet yielded are contiguous in memory and can be accessed as slices via
+/// [`column`](Self::column) and [`column_mut`](Self::column_mut).
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// for i in 0..100 {
+///     world.spawn((i as f32, i));
+/// }
+/// for mut batch in world.query::<(&mut f32, &i32)>().iter_batched(64) {
+///     assert_eq!(batch.entities().len(), batch.column::<i32>().unwrap().len());
+///     for x in batch.column_mut::<f32>().unwrap() {
+///         *x *= 2.0;
+///     }
+/// }
+/// assert_eq!(world.query::<&f32>().iter().map(|(_, &x)| x).sum::<f32>(), 9900.0);
+/// ```
 pub struct Batch<'q, Q: Query> {
     meta: &'q [EntityMeta],
+    archetype: &'q Archetype,
     state: ChunkIter<Q>,
 }
 
+impl<'q, Q: Query> Batch<'q, Q> {
+    /// The archetype all of this batch's entities belong to
+    pub fn archetype(&self) -> &'q Archetype {
+        self.archetype
+    }
+
+    /// The entities not yet yielded by this batch, in iteration order
+    ///
+    /// Like [`column`](Self::column), this covers the remaining range of the archetype as a whole,
+    /// including entities that a filter such as [`ChangedFilter`](crate::ChangedFilter) would
+    /// skip.
+    pub fn entities(&self) -> impl ExactSizeIterator<Item = Entity> + 'q {
+        let meta = self.meta;
+        self.archetype.ids()[self.state.position..self.state.len]
+            .iter()
+            .map(move |&id| Entity {
+                id,
+                generation: meta[id as usize].generation,
+            })
+    }
+
+    /// The `T` components of the entities not yet yielded by this batch
+    ///
+    /// Returns `None` unless the query borrows `T` immutably.
+    pub fn column<T: Component>(&self) -> Option<&[T]> {
+        if borrows::<Q, T>()? {
+            return None;
+        }
+        let state = self.archetype.get_state::<T>()?;
+        let base = self.archetype.get_base::<T>(state);
+        unsafe {
+            Some(slice::from_raw_parts(
+                base.as_ptr().add(self.state.position),
+                self.state.len - self.state.position,
+            ))
+        }
+    }
+
+    /// Uniquely borrow the `T` components of the entities not yet yielded by this batch
+    ///
+    /// Returns `None` unless the query borrows `T` uniquely. Every component in the slice is marked
+    /// as mutated, and the batch yields no further items afterwards.
+    pub fn column_mut<T: Component>(&mut self) -> Option<&mut [T]> {
+        if !borrows::<Q, T>()? {
+            return None;
+        }
+        let state = self.archetype.get_state::<T>()?;
+        let base = self.archetype.get_base::<T>(state);
+        let range = self.state.position..self.state.len;
+        // Items yielded later could alias the slice
+        self.state.position = self.state.len;
+        unsafe {
+            let tick = next_tick(self.archetype.change_tick());
+            self.archetype.mark_changed(state);
+            let mutated = self.archetype.get_mutated(state).as_ptr();
+            let change_ticks = self.archetype.get_change_ticks(state).as_ptr();
+            for i in range.clone() {
+                *mutated.add(i) = true;
+                *change_ticks.add(i) = tick;
+            }
+            Some(slice::from_raw_parts_mut(
+                base.as_ptr().add(range.start),
+                range.len(),
+            ))
+        }
+    }
+}
+
+/// Random access to the results of a query by [`Entity`]
+///
+/// Obtained from [`World::view`](crate::World::view). Components of every archetype matching `Q`
+/// remain borrowed until the view is dropped. Each lookup borrows the view uniquely, so two items
+/// for the same entity can never coexist; use [`get_many_mut`](Self::get_many_mut) to access several
+/// distinct entities at once.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// struct Target(Entity);
+/// let mut world = World::new();
+/// let a = world.spawn((1,));
+/// let b = world.spawn((2, Target(a)));
+/// let mut view = world.view::<(&mut i32, Option<&Target>)>();
+/// let target = view.get_mut(b).unwrap().1.unwrap().0;
+/// let [x, y] = view.get_many_mut([target, b]);
+/// *y.unwrap().0 += *x.unwrap().0;
+/// drop(view);
+/// assert_eq!(*world.get::<i32>(b).unwrap(), 3);
+/// ```
+pub struct View<'w, Q: Query> {
+    meta: &'w [EntityMeta],
+    archetypes: &'w [Archetype],
+    _marker: PhantomData<Q>,
+}
+
+impl<'w, Q: Query> View<'w, Q> {
+    pub(crate) fn new(meta: &'w [EntityMeta], archetypes: &'w [Archetype]) -> Self {
+        assert_borrow::<Q>();
+        for x in archetypes {
+            if let Some(state) = Q::Fetch::prepare(x) {
+                Q::Fetch::borrow(x, state);
+            }
+        }
+        Self {
+            meta,
+            archetypes,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Whether `entity` exists and satisfies `Q`
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.locate(entity).is_some()
+    }
+
+    /// Retrieve the query results for `entity`
+    ///
+    /// Returns `None` if `entity` doesn't exist or doesn't satisfy `Q`.
+    pub fn get_mut(&mut self, entity: Entity) -> Option<QueryItem<'_, Q>> {
+        let fetch = self.locate(entity)?;
+        unsafe { Some(fetch.0.get(fetch.1)) }
+    }
+
+    /// Retrieve the query results for several distinct entities at once
+    ///
+    /// Each element is `None` if the corresponding entity doesn't exist or doesn't satisfy `Q`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any entity appears more than once in `entities`.
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        entities: [Entity; N],
+    ) -> [Option<QueryItem<'_, Q>>; N] {
+        for (i, a) in entities.iter().enumerate() {
+            for b in &entities[..i] {
+                assert!(a != b, "entity {:?} requested more than once", a);
+            }
+        }
+        core::array::from_fn(|i| {
+            let fetch = self.locate(entities[i])?;
+            // Entities are distinct, so the results never alias
+            unsafe { Some(fetch.0.get(fetch.1)) }
+        })
+    }
+
+    /// Find the fetch and index for `entity`, if it satisfies `Q`
+    fn locate(&self, entity: Entity) -> Option<(Q::Fetch, usize)> {
+        let meta = self.meta.get(entity.id as usize)?;
+        if meta.generation != entity.generation {
+            return None;
+        }
+        let archetype = &self.archetypes[meta.location.archetype as usize];
+        if meta.location.index >= archetype.len() {
+            // Reserved but not yet flushed
+            return None;
+        }
+        let state = Q::Fetch::prepare(archetype)?;
+        let index = meta.location.index as usize;
+        let fetch = Q::Fetch::execute(archetype, state);
+        unsafe {
+            if !fetch.filter(index) {
+                return None;
+            }
+        }
+        Some((fetch, index))
+    }
+}
+
+impl<'w, Q: Query> Drop for View<'w, Q> {
+    fn drop(&mut self) {
+        for x in self.archetypes {
+            if let Some(state) = Q::Fetch::prepare(x) {
+                Q::Fetch::release(x, state);
+            }
+        }
+    }
+}
+
+unsafe impl<'w, Q: Query> Send for View<'w, Q> {}
+unsafe impl<'w, Q: Query> Sync for View<'w, Q> {}
+
+/// Whether `Q` borrows `T` uniquely, or `None` if it doesn't borrow `T` at all
+fn borrows<Q: Query, T: Component>() -> Option<bool> {
+    let mut result = None;
+    <Q::Fetch as Fetch<'static>>::for_each_borrow(|id, _, unique| {
+        if id == TypeId::of::<T>() {
+            result = Some(unique);
+        }
+    });
+    result
+}
+
 impl<'q, Q: Query> Iterator for Batch<'q, Q> {
     type Item = (Entity, QueryItem<'q, Q>);
 
@@ -970,22 +2388,68 @@ macro_rules! tuple_impl {
                 ($($name::execute(archetype, $name),)*)
             }
             #[allow(unused_variables, non_snake_case, clippy::unused_unit)]
+            fn execute_since(archetype: &'a Archetype, state: Self::State, last_run: u32) -> Self {
+                let ($($name,)*) = state;
+                ($($name::execute_since(archetype, $name, last_run),)*)
+            }
+            #[allow(unused_variables, non_snake_case, clippy::unused_unit)]
             fn release(archetype: &Archetype, state: Self::State) {
                 let ($($name,)*) = state;
                 $($name::release(archetype, $name);)*
             }
 
             #[allow(unused_variables, unused_mut, clippy::unused_unit)]
-            fn for_each_borrow(mut f: impl FnMut(TypeId, bool)) {
+            fn for_each_borrow(mut f: impl FnMut(TypeId, &'static str, bool)) {
                 $($name::for_each_borrow(&mut f);)*
             }
 
+            #[allow(unused_variables, unused_mut, clippy::unused_unit)]
+            fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+                $($name::for_each_requirement(&mut f);)*
+            }
+
             #[allow(unused_variables, clippy::unused_unit)]
             unsafe fn get(&self, n: usize) -> Self::Item {
                 #[allow(non_snake_case)]
                 let ($($name,)*) = self;
                 ($($name.get(n),)*)
             }
+
+            #[allow(unused_variables)]
+            #[inline]
+            unsafe fn filter(&self, n: usize) -> bool {
+                #[allow(non_snake_case)]
+                let ($($name,)*) = self;
+                true $(&& $name.filter(n))*
+            }
+
+            #[allow(unused_variables, non_snake_case)]
+            #[inline]
+            unsafe fn matches(archetype: &Archetype, state: Self::State, n: usize) -> bool {
+                let ($($name,)*) = state;
+                true $(&& $name::matches(archetype, $name, n))*
+            }
+
+            #[inline]
+            fn is_dense() -> bool {
+                true $(&& $name::is_dense())*
+            }
+
+            #[allow(unused_variables)]
+            #[inline]
+            fn may_match(&self) -> bool {
+                #[allow(non_snake_case)]
+                let ($($name,)*) = self;
+                true $(&& $name.may_match())*
+            }
+
+            #[inline(always)]
+            #[allow(unused_variables)]
+            unsafe fn prefetch(&self, n: usize) {
+                #[allow(non_snake_case)]
+                let ($($name,)*) = self;
+                $($name.prefetch(n);)*
+            }
         }
 
         impl<$($name: Query),*> Query for ($($name,)*) {
@@ -995,9 +2459,31 @@ macro_rules! tuple_impl {
 }
 
 //smaller_tuples_too!(tuple_impl, B, A);
-smaller_tuples_too!(tuple_impl, O, N, M, L, K, J, I, H, G, F, E, D, C, B, A);
+smaller_tuples_too!(tuple_impl, P, O, N, M, L, K, J, I, H, G, F, E, D, C, B, A);
 
 /// A prepared query can be stored independently of the [`World`] to amortize query set-up costs.
+///
+/// The archetypes matching the query are cached and only recomputed when the world's
+/// [`archetypes_generation`](World::archetypes_generation) changes or a different world is
+/// queried. Entities moving between already-known archetypes do not invalidate the cache.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// struct Position(f32);
+/// struct Velocity(f32);
+///
+/// let mut world = World::new();
+/// world.spawn((Position(0.0), Velocity(1.0)));
+/// let mut query = PreparedQuery::<(&mut Position, &Velocity)>::default();
+/// for _ in 0..3 {
+///     for (_, (mut pos, vel)) in query.query(&world).iter() {
+///         pos.0 += vel.0;
+///     }
+/// }
+/// let (_, (pos, _)) = query.query_mut(&mut world).next().unwrap();
+/// assert_eq!(pos.0, 3.0);
+/// ```
 pub struct PreparedQuery<Q: Query> {
     memo: (u64, u64),
     state: Box<[(usize, <Q::Fetch as Fetch<'static>>::State)]>,
@@ -1022,10 +2508,12 @@ impl<Q: Query> PreparedQuery<Q> {
     #[cold]
     fn prepare(world: &World) -> Self {
         let memo = world.memo();
+        let filter = ArchetypeFilter::new::<Q>(world.component_registry());
 
         let state = world
             .archetypes()
             .enumerate()
+            .filter(|(_, x)| filter.admits(x))
             .filter_map(|(idx, x)| Q::Fetch::prepare(x).map(|state| (idx, state)))
             .collect();
 
@@ -1044,7 +2532,7 @@ impl<Q: Query> PreparedQuery<Q> {
         let meta = world.entities_meta();
         let archetypes = world.archetypes_inner();
 
-        PreparedQueryBorrow::new(meta, archetypes, &*self.state)
+        PreparedQueryBorrow::new(meta, archetypes, &self.state)
     }
 
     /// Query a uniquely borrowed world
@@ -1080,6 +2568,7 @@ impl<'q, Q: Query> PreparedQueryBorrow<'q, Q> {
         archetypes: &'q [Archetype],
         state: &'q [(usize, <Q::Fetch as Fetch<'static>>::State)],
     ) -> Self {
+        assert_borrow::<Q>();
         for (idx, state) in state {
             Q::Fetch::borrow(&archetypes[*idx], *state);
         }
@@ -1149,12 +2638,7 @@ impl<'q, Q: Query> Iterator for PreparedQueryIter<'q, Q> {
                 None => {
                     let (idx, state) = self.state.next()?;
                     let archetype = &self.archetypes[*idx];
-                    self.iter = ChunkIter {
-                        entities: archetype.entities(),
-                        fetch: Q::Fetch::execute(archetype, *state),
-                        position: 0,
-                        len: archetype.len() as usize,
-                    };
+                    self.iter = ChunkIter::new(archetype, Q::Fetch::execute(archetype, *state));
                     continue;
                 }
                 Some((id, components)) => {
@@ -1180,7 +2664,9 @@ impl<Q: Query> ExactSizeIterator for PreparedQueryIter<'_, Q> {
     fn len(&self) -> usize {
         self.state
             .clone()
-            .map(|(idx, _)| self.archetypes[*idx].len() as usize)
+            .map(|&(idx, state)| unsafe {
+                count_prepared::<Q::Fetch>(&self.archetypes[idx], state)
+            })
             .sum::<usize>()
             + self.iter.remaining()
     }
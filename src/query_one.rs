@@ -1,6 +1,6 @@
 use core::marker::PhantomData;
 
-use crate::query::{Fetch, With, Without};
+use crate::query::{assert_borrow, Fetch, With, Without};
 use crate::{Archetype, Component, Query, QueryItem};
 
 /// A borrow of a [`World`](crate::World) sufficient to execute the query `Q` on a single entity
@@ -35,13 +35,17 @@ impl<'a, Q: Query> QueryOne<'a, Q> {
     // Note that this uses self's lifetime, not 'a, for soundness.
     pub fn get(&mut self) -> Option<QueryItem<'_, Q>> {
         if self.borrowed {
-            panic!("called QueryOnce::get twice; construct a new query instead");
+            panic!("called QueryOne::get twice; construct a new query instead");
         }
+        assert_borrow::<Q>();
         unsafe {
             let state = Q::Fetch::prepare(self.archetype)?;
             Q::Fetch::borrow(self.archetype, state);
             let fetch = Q::Fetch::execute(self.archetype, state);
             self.borrowed = true;
+            if !fetch.filter(self.index as usize) {
+                return None;
+            }
             Some(fetch.get(self.index as usize))
         }
     }
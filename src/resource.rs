@@ -0,0 +1,130 @@
+use crate::alloc::boxed::Box;
+use core::any::{type_name, Any, TypeId};
+use core::cell::UnsafeCell;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use crate::archetype::TypeIdMap;
+use crate::borrow::AtomicBorrow;
+use crate::Component;
+
+/// Singleton values stored alongside a [`World`](crate::World)'s entities, keyed by type
+#[derive(Default)]
+pub(crate) struct Resources {
+    map: TypeIdMap<Box<Resource>>,
+}
+
+impl Resources {
+    pub fn insert<T: Component>(&mut self, value: T) -> Option<T> {
+        let old = self.map.insert(
+            TypeId::of::<T>(),
+            Box::new(Resource {
+                state: AtomicBorrow::new(),
+                value: UnsafeCell::new(Box::new(value)),
+            }),
+        )?;
+        Some(*old.value.into_inner().downcast::<T>().unwrap())
+    }
+
+    pub fn remove<T: Component>(&mut self) -> Option<T> {
+        let old = self.map.remove(&TypeId::of::<T>())?;
+        Some(*old.value.into_inner().downcast::<T>().unwrap())
+    }
+
+    pub fn contains<T: Component>(&self) -> bool {
+        self.map.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn get<T: Component>(&self) -> Option<ResourceRef<'_, T>> {
+        let resource = self.map.get(&TypeId::of::<T>())?;
+        if !resource.state.borrow() {
+            panic!("{} already borrowed uniquely", type_name::<T>());
+        }
+        Some(ResourceRef {
+            resource,
+            target: unsafe { resource.get() },
+        })
+    }
+
+    pub fn get_mut<T: Component>(&self) -> Option<ResourceRefMut<'_, T>> {
+        let resource = self.map.get(&TypeId::of::<T>())?;
+        if !resource.state.borrow_mut() {
+            panic!("{} already borrowed", type_name::<T>());
+        }
+        Some(ResourceRefMut {
+            resource,
+            target: unsafe { resource.get_mut() },
+        })
+    }
+}
+
+struct Resource {
+    state: AtomicBorrow,
+    value: UnsafeCell<Box<dyn Any + Send + Sync>>,
+}
+
+// Access to `value` is guarded by `state`
+unsafe impl Sync for Resource {}
+
+impl Resource {
+    /// Safety: caller must hold a shared borrow of `state`
+    unsafe fn get<T: Component>(&self) -> NonNull<T> {
+        NonNull::from((*self.value.get()).downcast_ref::<T>().unwrap())
+    }
+
+    /// Safety: caller must hold a unique borrow of `state`
+    unsafe fn get_mut<T: Component>(&self) -> NonNull<T> {
+        NonNull::from((*self.value.get()).downcast_mut::<T>().unwrap())
+    }
+}
+
+/// Shared borrow of a resource, obtained from [`World::get_resource`](crate::World::get_resource)
+pub struct ResourceRef<'a, T: Component> {
+    resource: &'a Resource,
+    target: NonNull<T>,
+}
+
+unsafe impl<T: Component> Send for ResourceRef<'_, T> {}
+unsafe impl<T: Component> Sync for ResourceRef<'_, T> {}
+
+impl<T: Component> Drop for ResourceRef<'_, T> {
+    fn drop(&mut self) {
+        self.resource.state.release();
+    }
+}
+
+impl<T: Component> Deref for ResourceRef<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { self.target.as_ref() }
+    }
+}
+
+/// Unique borrow of a resource, obtained from
+/// [`World::get_resource_mut`](crate::World::get_resource_mut)
+pub struct ResourceRefMut<'a, T: Component> {
+    resource: &'a Resource,
+    target: NonNull<T>,
+}
+
+unsafe impl<T: Component> Send for ResourceRefMut<'_, T> {}
+unsafe impl<T: Component> Sync for ResourceRefMut<'_, T> {}
+
+impl<T: Component> Drop for ResourceRefMut<'_, T> {
+    fn drop(&mut self) {
+        self.resource.state.release_mut();
+    }
+}
+
+impl<T: Component> Deref for ResourceRefMut<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { self.target.as_ref() }
+    }
+}
+
+impl<T: Component> DerefMut for ResourceRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { self.target.as_mut() }
+    }
+}
@@ -23,7 +23,8 @@ use serde::{
 };
 
 use crate::{
-    Archetype, ColumnBatch, ColumnBatchBuilder, ColumnBatchType, Component, Entity, World,
+    Archetype, ColumnBatch, ColumnBatchBuilder, ColumnBatchType, Component, Entity, EntityMap,
+    World,
 };
 
 /// Implements serialization of archetypes
@@ -168,6 +169,8 @@ where
 }
 
 /// Serialize a [`World`] through a [`SerializeContext`] to a [`Serializer`]
+///
+/// Components omitted by the context, e.g. because their types are unknown to it, are skipped.
 pub fn serialize<C, S>(world: &World, context: &mut C, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -190,8 +193,8 @@ where
             let ctx = &mut *self.ctx.borrow_mut();
             let mut tuple = serializer.serialize_tuple(4)?;
             tuple.serialize_element(&self.archetype.len())?;
-            tuple.serialize_element(&(self.archetype.types().len() as u32))?;
             let components = ctx.component_count(self.archetype);
+            tuple.serialize_element(&(components as u32))?;
             let helper = SerializeComponentIds::<'_, C> {
                 archetype: self.archetype,
                 ctx: RefCell::new(ctx),
@@ -486,6 +489,9 @@ where
 }
 
 /// Deserialize a [`World`] with a [`DeserializeContext`] and a [`Deserializer`]
+///
+/// Entities are restored with exactly the IDs and generations they were serialized with, so
+/// `Entity` values stored inside components remain valid.
 pub fn deserialize<'de, C, D>(context: &mut C, deserializer: D) -> Result<World, D::Error>
 where
     C: DeserializeContext,
@@ -494,6 +500,76 @@ where
     deserializer.deserialize_seq(WorldVisitor(context))
 }
 
+/// Deserialize entities with a [`DeserializeContext`] and a [`Deserializer`] into an existing
+/// [`World`]
+///
+/// Unlike [`deserialize`], entities are allocated fresh handles so they can't collide with those
+/// already in `world`. The returned [`EntityMap`] records the handle each serialized entity was
+/// loaded as, allowing `Entity` values stored inside components to be updated to match.
+///
+/// # Example
+/// ```
+/// # use serde::Deserializer;
+/// use hecs::{*, serialize::column::*};
+///
+/// struct Target(Entity);
+///
+/// fn load<'de, C, D>(world: &mut World, context: &mut C, deserializer: D) -> Result<(), D::Error>
+/// where
+///     C: DeserializeContext,
+///     D: Deserializer<'de>,
+/// {
+///     let map = deserialize_into(world, context, deserializer)?;
+///     for (_, &entity) in map.iter() {
+///         if let Ok(mut target) = world.get_mut::<Target>(entity) {
+///             if let Some(&new) = map.get(target.0) {
+///                 target.0 = new;
+///             }
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn deserialize_into<'de, C, D>(
+    world: &mut World,
+    context: &mut C,
+    deserializer: D,
+) -> Result<EntityMap<Entity>, D::Error>
+where
+    C: DeserializeContext,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_seq(MergeVisitor(world, context))
+}
+
+struct MergeVisitor<'a, C>(&'a mut World, &'a mut C);
+
+impl<'de, 'a, C> Visitor<'de> for MergeVisitor<'a, C>
+where
+    C: DeserializeContext,
+{
+    type Value = EntityMap<Entity>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of archetypes")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<EntityMap<Entity>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut map = EntityMap::new();
+        let mut entities = Vec::new();
+        while let Some(bundle) =
+            seq.next_element_seed(DeserializeArchetype(self.1, &mut entities))?
+        {
+            let spawned = self.0.spawn_column_batch(bundle);
+            map.extend(entities.drain(..).zip(spawned));
+        }
+        Ok(map)
+    }
+}
+
 struct WorldVisitor<'a, C>(&'a mut C);
 
 impl<'de, 'a, C> Visitor<'de> for WorldVisitor<'a, C>
@@ -731,7 +807,7 @@ impl<'de, 'a> Visitor<'de> for EntitiesVisitor<'a> {
 #[cfg(test)]
 mod tests {
     use crate::alloc::vec::Vec;
-    use core::fmt;
+    use core::{any::TypeId, fmt};
 
     use serde::{Deserialize, Serialize};
 
@@ -760,7 +836,7 @@ mod tests {
     impl PartialEq for SerWorld {
         fn eq(&self, other: &Self) -> bool {
             fn same_components<T: Component + PartialEq>(x: &EntityRef, y: &EntityRef) -> bool {
-                x.get::<T>().as_ref().map(|x| &**x) == y.get::<T>().as_ref().map(|x| &**x)
+                x.get::<T>().as_deref() == y.get::<T>().as_deref()
             }
 
             for (x, y) in self.0.iter().zip(other.0.iter()) {
@@ -861,7 +937,10 @@ mod tests {
 
     impl SerializeContext for Context {
         fn component_count(&self, archetype: &Archetype) -> usize {
-            archetype.component_types().len()
+            archetype
+                .component_types()
+                .filter(|&t| t == TypeId::of::<Position>() || t == TypeId::of::<Velocity>())
+                .count()
         }
 
         fn serialize_component_ids<S: SerializeTuple>(
@@ -968,4 +1047,126 @@ mod tests {
             Token::SeqEnd,
         ])
     }
+
+    /// The result of deserializing a world into one with two existing entities
+    #[derive(Debug, PartialEq)]
+    struct Merged(SerWorld, EntityMap<Entity>);
+
+    impl Merged {
+        fn base() -> World {
+            let mut world = World::new();
+            world.spawn((Position([9.0, 9.0, 9.0]),));
+            world.spawn(());
+            world
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Merged {
+        fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+            let mut world = Self::base();
+            let map = deserialize_into(&mut world, &mut Context::default(), d)?;
+            Ok(Merged(SerWorld(world), map))
+        }
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn deserialize_into_existing() {
+        use serde_test::{Token, assert_de_tokens};
+
+        let mut source = World::new();
+        let old = source.spawn((Position([1.0, 1.0, 1.0]),));
+        source.despawn(old).unwrap();
+        let e0 = source.spawn((Position([0.0, 0.0, 0.0]),));
+        let e1 = source.spawn((Position([2.0, 2.0, 2.0]),));
+
+        let mut expected = Merged::base();
+        let loaded = expected
+            .spawn_column_batch({
+                let mut ty = ColumnBatchType::new();
+                ty.add::<Position>();
+                let mut batch = ty.into_batch(2);
+                let mut writer = batch.writer::<Position>().unwrap();
+                writer.push(Position([0.0, 0.0, 0.0])).unwrap();
+                writer.push(Position([2.0, 2.0, 2.0])).unwrap();
+                batch.build().unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert!(loaded.iter().all(|x| ![e0, e1].contains(x)));
+        let mut map = EntityMap::new();
+        map.insert(e0, loaded[0]);
+        map.insert(e1, loaded[1]);
+
+        assert_de_tokens(&Merged(SerWorld(expected), map), &[
+            Token::Seq { len: Some(1) },
+
+            Token::Tuple { len: 4 },
+            Token::U32(2),
+            Token::U32(1),
+            Token::Tuple { len: 1 },
+            Token::UnitVariant { name: "ComponentId", variant: "Position" },
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::Tuple { len: 2 },
+            Token::U64(e0.to_bits().into()),
+            Token::U64(e1.to_bits().into()),
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::NewtypeStruct { name: "Position" },
+            Token::Tuple { len: 3 },
+            Token::F32(0.0),
+            Token::F32(0.0),
+            Token::F32(0.0),
+            Token::TupleEnd,
+            Token::NewtypeStruct { name: "Position" },
+            Token::Tuple { len: 3 },
+            Token::F32(2.0),
+            Token::F32(2.0),
+            Token::F32(2.0),
+            Token::TupleEnd,
+            Token::TupleEnd,
+            Token::TupleEnd,
+            Token::TupleEnd,
+
+            Token::SeqEnd,
+        ])
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn skip_unknown_components() {
+        use serde_test::{Token, assert_tokens};
+
+        let mut world = World::new();
+        let p0 = Position([0.0, 0.0, 0.0]);
+        let e0 = world.spawn((p0, true));
+
+        assert_tokens(&SerWorld(world), &[
+            Token::NewtypeStruct { name: "SerWorld" },
+            Token::Seq { len: Some(1) },
+
+            Token::Tuple { len: 4 },
+            Token::U32(1),
+            Token::U32(1),
+            Token::Tuple { len: 1 },
+            Token::UnitVariant { name: "ComponentId", variant: "Position" },
+            Token::TupleEnd,
+            Token::Tuple { len: 2 },
+            Token::Tuple { len: 1 },
+            Token::U64(e0.to_bits().into()),
+            Token::TupleEnd,
+            Token::Tuple { len: 1 },
+            Token::NewtypeStruct { name: "Position" },
+            Token::Tuple { len: 3 },
+            Token::F32(0.0),
+            Token::F32(0.0),
+            Token::F32(0.0),
+            Token::TupleEnd,
+            Token::TupleEnd,
+            Token::TupleEnd,
+            Token::TupleEnd,
+
+            Token::SeqEnd,
+        ])
+    }
 }
@@ -271,7 +271,7 @@ mod tests {
     impl PartialEq for SerWorld {
         fn eq(&self, other: &Self) -> bool {
             fn same_components<T: Component + PartialEq>(x: &EntityRef, y: &EntityRef) -> bool {
-                x.get::<T>().as_ref().map(|x| &**x) == y.get::<T>().as_ref().map(|x| &**x)
+                x.get::<T>().as_deref() == y.get::<T>().as_deref()
             }
 
             for (x, y) in self.0.iter().zip(other.0.iter()) {
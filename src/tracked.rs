@@ -1,6 +1,14 @@
-use core::{any::TypeId, marker::PhantomData, ptr::NonNull};
+use core::{
+    any::{type_name, TypeId},
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+    sync::atomic::Ordering,
+};
 
-use crate::{Access, Archetype, Component, Fetch, Query};
+use crate::archetype::is_tick_newer;
+use crate::query::{FetchRead, FetchWrite};
+use crate::{Access, Archetype, Component, Fetch, Mut, Query};
 
 /// Query that retrieves mutation state of type `T` component.
 /// Added components do not count as mutated.
@@ -64,8 +72,12 @@ unsafe impl<'a, T: Component> Fetch<'a> for FetchMutated<T> {
     }
     fn release(_archetype: &Archetype, _state: Self::State) {}
 
-    fn for_each_borrow(mut f: impl FnMut(TypeId, bool)) {
-        f(TypeId::of::<T>(), false);
+    fn for_each_borrow(mut f: impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<T>(), type_name::<T>(), false);
+    }
+
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), true);
     }
 
     unsafe fn get(&self, n: usize) -> Self::Item {
@@ -127,8 +139,12 @@ unsafe impl<'a, T: Component> Fetch<'a> for FetchAdded<T> {
     }
     fn release(_archetype: &Archetype, _state: Self::State) {}
 
-    fn for_each_borrow(mut f: impl FnMut(TypeId, bool)) {
-        f(TypeId::of::<T>(), false);
+    fn for_each_borrow(mut f: impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<T>(), type_name::<T>(), false);
+    }
+
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), true);
     }
 
     unsafe fn get(&self, n: usize) -> Self::Item {
@@ -206,11 +222,823 @@ unsafe impl<'a, T: Component> Fetch<'a> for FetchChanged<T> {
     }
     fn release(_archetype: &Archetype, _state: Self::State) {}
 
-    fn for_each_borrow(mut f: impl FnMut(TypeId, bool)) {
-        f(TypeId::of::<T>(), false);
+    fn for_each_borrow(mut f: impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<T>(), type_name::<T>(), false);
+    }
+
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), true);
     }
 
     unsafe fn get(&self, n: usize) -> Self::Item {
         *self.0.as_ptr().add(n) || *self.1.as_ptr().add(n)
     }
 }
+
+/// Query that retrieves whether any of the components in the tuple `T` have changed
+///
+/// Yields `true` if at least one of them has been added or mutated since the last
+/// [`World::clear_trackers()`](crate::World::clear_trackers()), like OR-ing a [`Changed`] for each.
+/// Only entities having every component in `T` match. `Changed<(A, B)>` can't be used for this, as
+/// it refers to a component of type `(A, B)`.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// struct Transform(f32);
+/// struct Material(u32);
+///
+/// let mut world = World::new();
+/// let a = world.spawn((Transform(0.0), Material(0)));
+/// let b = world.spawn((Transform(1.0), Material(1)));
+/// world.spawn((Transform(2.0),));
+/// world.clear_trackers();
+/// world.get_mut::<Material>(b).unwrap().0 = 2;
+/// let mut changed = world
+///     .query::<AnyChanged<(Transform, Material)>>()
+///     .iter()
+///     .collect::<Vec<_>>();
+/// changed.sort_by_key(|&(e, _)| e);
+/// assert_eq!(changed, [(a, false), (b, true)]);
+/// ```
+pub struct AnyChanged<T>(PhantomData<fn(T)>);
+
+#[doc(hidden)]
+pub struct FetchAnyChanged<F>(F);
+
+macro_rules! any_changed_impl {
+    ($($name: ident),*) => {
+        impl<$($name: Component),*> Query for AnyChanged<($($name,)*)> {
+            type Fetch = FetchAnyChanged<($(FetchChanged<$name>,)*)>;
+        }
+
+        unsafe impl<'a, $($name: Component),*> Fetch<'a>
+            for FetchAnyChanged<($(FetchChanged<$name>,)*)>
+        {
+            type Item = bool;
+
+            type State = <($(FetchChanged<$name>,)*) as Fetch<'a>>::State;
+
+            fn dangling() -> Self {
+                Self(<($(FetchChanged<$name>,)*) as Fetch<'a>>::dangling())
+            }
+
+            fn access(archetype: &Archetype) -> Option<Access> {
+                <($(FetchChanged<$name>,)*) as Fetch<'a>>::access(archetype)
+            }
+
+            fn borrow(_archetype: &Archetype, _state: Self::State) {}
+            fn prepare(archetype: &Archetype) -> Option<Self::State> {
+                <($(FetchChanged<$name>,)*) as Fetch<'a>>::prepare(archetype)
+            }
+            fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
+                Self(<($(FetchChanged<$name>,)*) as Fetch<'a>>::execute(archetype, state))
+            }
+            fn release(_archetype: &Archetype, _state: Self::State) {}
+
+            fn for_each_borrow(f: impl FnMut(TypeId, &'static str, bool)) {
+                <($(FetchChanged<$name>,)*) as Fetch<'a>>::for_each_borrow(f);
+            }
+
+            fn for_each_requirement(f: impl FnMut(TypeId, bool)) {
+                <($(FetchChanged<$name>,)*) as Fetch<'a>>::for_each_requirement(f);
+            }
+
+            #[allow(unused_variables, non_snake_case)]
+            unsafe fn get(&self, n: usize) -> Self::Item {
+                let ($($name,)*) = self.0.get(n);
+                false $(|| $name)*
+            }
+        }
+    };
+}
+
+smaller_tuples_too!(
+    any_changed_impl,
+    P,
+    O,
+    N,
+    M,
+    L,
+    K,
+    J,
+    I,
+    H,
+    G,
+    F,
+    E,
+    D,
+    C,
+    B,
+    A
+);
+
+/// Query that retrieves the tick at which a `T` component was last added or mutated.
+///
+/// Unlike [`Changed`], this does not depend on [`World::clear_trackers()`](crate::World::clear_trackers()),
+/// so systems running at different rates can independently detect changes by comparing against
+/// the [`World::change_tick()`](crate::World::change_tick()) they last observed.
+///
+/// Borrows `T` immutably, so it cannot be combined with `&mut T` in the same query.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let e = world.spawn((123,));
+/// let before = world.change_tick();
+/// for (_id, tick) in world.query::<ChangeTick<i32>>().iter() {
+///   assert!(tick <= before);
+/// }
+/// for (_id, mut value) in world.query::<&mut i32>().iter() {
+///   *value = 42;
+/// }
+/// for (_id, tick) in world.query::<ChangeTick<i32>>().iter() {
+///   assert!(tick > before);
+/// }
+/// ```
+pub struct ChangeTick<T>(PhantomData<fn(T)>);
+
+impl<T: Component> Query for ChangeTick<T> {
+    type Fetch = FetchChangeTick<T>;
+}
+
+#[doc(hidden)]
+pub struct FetchChangeTick<T>(NonNull<u32>, PhantomData<fn(T)>);
+
+unsafe impl<'a, T: Component> Fetch<'a> for FetchChangeTick<T> {
+    type Item = u32;
+
+    type State = usize;
+
+    fn dangling() -> Self {
+        Self(NonNull::dangling(), PhantomData)
+    }
+
+    fn access(archetype: &Archetype) -> Option<Access> {
+        if archetype.has::<T>() {
+            Some(Access::Read)
+        } else {
+            None
+        }
+    }
+
+    fn borrow(archetype: &Archetype, state: Self::State) {
+        archetype.borrow::<T>(state);
+    }
+    fn prepare(archetype: &Archetype) -> Option<Self::State> {
+        archetype.get_state::<T>()
+    }
+    fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
+        Self(archetype.get_change_ticks(state), PhantomData)
+    }
+    fn release(archetype: &Archetype, state: Self::State) {
+        archetype.release::<T>(state);
+    }
+
+    fn for_each_borrow(mut f: impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<T>(), type_name::<T>(), false);
+    }
+
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), true);
+    }
+
+    unsafe fn get(&self, n: usize) -> Self::Item {
+        *self.0.as_ptr().add(n)
+    }
+}
+
+/// Query that retrieves whether the `T` component was added or mutated after a given tick
+///
+/// Lets each system detect changes since it last ran by storing the result of
+/// [`World::increment_change_tick()`](crate::World::increment_change_tick()) and passing it to
+/// [`QueryBorrow::changed_since()`](crate::QueryBorrow::changed_since()) when it next runs.
+/// Queries that aren't given a tick compare against
+/// [`World::last_change_tick()`](crate::World::last_change_tick()) instead. Comparisons account
+/// for the tick counter wrapping around; see
+/// [`World::check_change_ticks()`](crate::World::check_change_ticks()).
+///
+/// Borrows `T` immutably, so it cannot be combined with `&mut T` in the same query.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let e = world.spawn((123,));
+/// let last_run = world.increment_change_tick();
+/// let changed = |world: &World| {
+///     let mut query = world.query::<ChangedSince<i32>>().changed_since(last_run);
+///     let changed = query.iter().next().unwrap().1;
+///     changed
+/// };
+/// assert!(!changed(&world));
+/// *world.get_mut::<i32>(e).unwrap() = 42;
+/// assert!(changed(&world));
+/// ```
+pub struct ChangedSince<T>(PhantomData<fn(T)>);
+
+impl<T: Component> Query for ChangedSince<T> {
+    type Fetch = FetchChangedSince<T>;
+}
+
+#[doc(hidden)]
+pub struct FetchChangedSince<T>(NonNull<u32>, u32, u32, PhantomData<fn(T)>);
+
+unsafe impl<'a, T: Component> Fetch<'a> for FetchChangedSince<T> {
+    type Item = bool;
+
+    type State = usize;
+
+    fn dangling() -> Self {
+        Self(NonNull::dangling(), 0, 0, PhantomData)
+    }
+
+    fn access(archetype: &Archetype) -> Option<Access> {
+        if archetype.has::<T>() {
+            Some(Access::Read)
+        } else {
+            None
+        }
+    }
+
+    fn borrow(archetype: &Archetype, state: Self::State) {
+        archetype.borrow::<T>(state);
+    }
+    fn prepare(archetype: &Archetype) -> Option<Self::State> {
+        archetype.get_state::<T>()
+    }
+    fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
+        Self::execute_since(archetype, state, archetype.last_change_tick())
+    }
+    fn execute_since(archetype: &'a Archetype, state: Self::State, last_run: u32) -> Self {
+        Self(
+            archetype.get_change_ticks(state),
+            last_run,
+            archetype.change_tick().load(Ordering::Relaxed),
+            PhantomData,
+        )
+    }
+    fn release(archetype: &Archetype, state: Self::State) {
+        archetype.release::<T>(state);
+    }
+
+    fn for_each_borrow(mut f: impl FnMut(TypeId, &'static str, bool)) {
+        f(TypeId::of::<T>(), type_name::<T>(), false);
+    }
+
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), true);
+    }
+
+    unsafe fn get(&self, n: usize) -> Self::Item {
+        is_tick_newer(*self.0.as_ptr().add(n), self.1, self.2)
+    }
+}
+
+/// Query that yields a shared reference to a `T` component along with the ticks at which it was
+/// added and last changed
+///
+/// Dereferences to `&T`. Like [`ChangedSince`], [`is_added`](Self::is_added) and
+/// [`is_changed`](Self::is_changed) compare against the tick passed to
+/// [`QueryBorrow::changed_since()`](crate::QueryBorrow::changed_since()), or else
+/// [`World::last_change_tick()`](crate::World::last_change_tick()), rather than depending on
+/// [`World::clear_trackers()`](crate::World::clear_trackers()), and the raw ticks can be compared
+/// with [`World::change_tick()`](crate::World::change_tick()) to tell how long ago a change
+/// happened. See [`TrackedMut`] for unique access.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let a = world.spawn((1,));
+/// let b = world.spawn((2,));
+/// let last_run = world.change_tick();
+/// *world.get_mut::<i32>(b).unwrap() = 3;
+/// for (e, value) in world.query::<Tracked<i32>>().changed_since(last_run).iter() {
+///     assert!(!value.is_added());
+///     assert_eq!(value.is_changed(), e == b);
+///     assert_eq!(*value, if e == a { 1 } else { 3 });
+///     assert!(value.added_tick() <= value.last_changed_tick());
+/// }
+/// ```
+pub struct Tracked<'a, T: Component> {
+    value: &'a T,
+    added_tick: u32,
+    change_tick: u32,
+    last_run: u32,
+    current: u32,
+}
+
+impl<'a, T: Component> Tracked<'a, T> {
+    /// The component, with the lifetime of the query borrow
+    pub fn into_inner(self) -> &'a T {
+        self.value
+    }
+
+    /// Tick at which the component was added to the entity
+    ///
+    /// Replacing a component with [`World::insert`](crate::World::insert) counts as a change,
+    /// not an addition.
+    pub fn added_tick(&self) -> u32 {
+        self.added_tick
+    }
+
+    /// Tick at which the component was last added or mutated
+    pub fn last_changed_tick(&self) -> u32 {
+        self.change_tick
+    }
+
+    /// Whether the component was added after the query's tick
+    pub fn is_added(&self) -> bool {
+        is_tick_newer(self.added_tick, self.last_run, self.current)
+    }
+
+    /// Whether the component was added or mutated after the query's tick
+    pub fn is_changed(&self) -> bool {
+        is_tick_newer(self.change_tick, self.last_run, self.current)
+    }
+}
+
+impl<T: Component> Clone for Tracked<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Component> Copy for Tracked<'_, T> {}
+
+impl<T: Component> Deref for Tracked<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T: Component + core::fmt::Debug> core::fmt::Debug for Tracked<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T: Component> Query for Tracked<'_, T> {
+    type Fetch = FetchTracked<T>;
+}
+
+#[doc(hidden)]
+pub struct FetchTracked<T>(FetchRead<T>, NonNull<u32>, NonNull<u32>, u32, u32);
+
+unsafe impl<'a, T: Component> Fetch<'a> for FetchTracked<T> {
+    type Item = Tracked<'a, T>;
+
+    type State = usize;
+
+    fn dangling() -> Self {
+        Self(
+            FetchRead::dangling(),
+            NonNull::dangling(),
+            NonNull::dangling(),
+            0,
+            0,
+        )
+    }
+
+    fn access(archetype: &Archetype) -> Option<Access> {
+        FetchRead::<T>::access(archetype)
+    }
+
+    fn borrow(archetype: &Archetype, state: Self::State) {
+        FetchRead::<T>::borrow(archetype, state);
+    }
+    fn prepare(archetype: &Archetype) -> Option<Self::State> {
+        FetchRead::<T>::prepare(archetype)
+    }
+    fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
+        Self::execute_since(archetype, state, archetype.last_change_tick())
+    }
+    fn execute_since(archetype: &'a Archetype, state: Self::State, last_run: u32) -> Self {
+        Self(
+            FetchRead::execute(archetype, state),
+            archetype.get_added_ticks(state),
+            archetype.get_change_ticks(state),
+            last_run,
+            archetype.change_tick().load(Ordering::Relaxed),
+        )
+    }
+    fn release(archetype: &Archetype, state: Self::State) {
+        FetchRead::<T>::release(archetype, state);
+    }
+
+    fn for_each_borrow(f: impl FnMut(TypeId, &'static str, bool)) {
+        FetchRead::<T>::for_each_borrow(f);
+    }
+
+    fn for_each_requirement(f: impl FnMut(TypeId, bool)) {
+        FetchRead::<T>::for_each_requirement(f);
+    }
+
+    unsafe fn get(&self, n: usize) -> Self::Item {
+        Tracked {
+            value: self.0.get(n),
+            added_tick: *self.1.as_ptr().add(n),
+            change_tick: *self.2.as_ptr().add(n),
+            last_run: self.3,
+            current: self.4,
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn prefetch(&self, n: usize) {
+        self.0.prefetch(n);
+    }
+}
+
+/// Query that yields a [`Mut`] borrow of a `T` component along with the ticks at which it was
+/// added and last changed
+///
+/// The unique counterpart of [`Tracked`]. As with [`Mut`], the component is only recorded as
+/// changed once it's accessed through `DerefMut`, after which
+/// [`last_changed_tick`](Self::last_changed_tick) reports the new tick.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let e = world.spawn((1,));
+/// let last_run = world.change_tick();
+/// let mut query = world.query_mut::<TrackedMut<i32>>().changed_since(last_run);
+/// let (_, mut value) = query.into_iter().next().unwrap();
+/// assert!(!value.is_changed());
+/// *value += 1;
+/// assert!(value.is_changed());
+/// assert!(value.last_changed_tick() > value.added_tick());
+/// ```
+pub struct TrackedMut<'a, T: Component> {
+    value: Mut<'a, T>,
+    added_tick: u32,
+    last_run: u32,
+}
+
+impl<'a, T: Component> TrackedMut<'a, T> {
+    /// The [`Mut`] borrow of the component, discarding the added tick
+    pub fn into_inner(self) -> Mut<'a, T> {
+        self.value
+    }
+
+    /// Tick at which the component was added to the entity
+    ///
+    /// Replacing a component with [`World::insert`](crate::World::insert) counts as a change,
+    /// not an addition.
+    pub fn added_tick(&self) -> u32 {
+        self.added_tick
+    }
+
+    /// Tick at which the component was last added or mutated
+    pub fn last_changed_tick(&self) -> u32 {
+        *self.value.change_tick
+    }
+
+    /// Whether the component was added after the query's tick
+    pub fn is_added(&self) -> bool {
+        is_tick_newer(self.added_tick, self.last_run, self.current())
+    }
+
+    /// Whether the component was added or mutated after the query's tick
+    pub fn is_changed(&self) -> bool {
+        is_tick_newer(*self.value.change_tick, self.last_run, self.current())
+    }
+
+    /// The tick this borrow records upon mutation, drawn when the query reached its archetype
+    fn current(&self) -> u32 {
+        self.value.tick
+    }
+}
+
+impl<T: Component> Deref for TrackedMut<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Component> DerefMut for TrackedMut<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Component + core::fmt::Debug> core::fmt::Debug for TrackedMut<'_, T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T: Component> Query for TrackedMut<'_, T> {
+    type Fetch = FetchTrackedMut<T>;
+}
+
+#[doc(hidden)]
+pub struct FetchTrackedMut<T>(FetchWrite<T>, NonNull<u32>, u32);
+
+unsafe impl<'a, T: Component> Fetch<'a> for FetchTrackedMut<T> {
+    type Item = TrackedMut<'a, T>;
+
+    type State = usize;
+
+    fn dangling() -> Self {
+        Self(FetchWrite::dangling(), NonNull::dangling(), 0)
+    }
+
+    fn access(archetype: &Archetype) -> Option<Access> {
+        FetchWrite::<T>::access(archetype)
+    }
+
+    fn borrow(archetype: &Archetype, state: Self::State) {
+        FetchWrite::<T>::borrow(archetype, state);
+    }
+    fn prepare(archetype: &Archetype) -> Option<Self::State> {
+        FetchWrite::<T>::prepare(archetype)
+    }
+    fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
+        Self::execute_since(archetype, state, archetype.last_change_tick())
+    }
+    fn execute_since(archetype: &'a Archetype, state: Self::State, last_run: u32) -> Self {
+        Self(
+            FetchWrite::execute(archetype, state),
+            archetype.get_added_ticks(state),
+            last_run,
+        )
+    }
+    fn release(archetype: &Archetype, state: Self::State) {
+        FetchWrite::<T>::release(archetype, state);
+    }
+
+    fn for_each_borrow(f: impl FnMut(TypeId, &'static str, bool)) {
+        FetchWrite::<T>::for_each_borrow(f);
+    }
+
+    fn for_each_requirement(f: impl FnMut(TypeId, bool)) {
+        FetchWrite::<T>::for_each_requirement(f);
+    }
+
+    unsafe fn get(&self, n: usize) -> Self::Item {
+        TrackedMut {
+            value: self.0.get(n),
+            added_tick: *self.1.as_ptr().add(n),
+            last_run: self.2,
+        }
+    }
+
+    #[inline(always)]
+    unsafe fn prefetch(&self, n: usize) {
+        self.0.prefetch(n);
+    }
+}
+
+/// Query transformer that yields only entities whose `T` component has been added or mutated
+/// since the last [`World::clear_trackers()`](crate::World::clear_trackers())
+///
+/// Unlike [`Changed`], which yields every entity along with a flag, unchanged entities are skipped
+/// during iteration, so `Q` is only fetched for entities of interest, and archetypes in which no
+/// `T` has changed are skipped entirely. Like [`Changed`], `T` is borrowed immutably unless `Q`
+/// already borrows it, so `Q` may freely access it.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let a = world.spawn((1, true));
+/// let b = world.spawn((2, false));
+/// world.clear_trackers();
+/// *world.get_mut::<i32>(b).unwrap() = 3;
+/// let changed = world.query::<ChangedFilter<i32, (&i32, &bool)>>()
+///     .iter()
+///     .map(|(e, (&i, &b))| (e, i, b))
+///     .collect::<Vec<_>>();
+/// assert_eq!(changed, &[(b, 3, false)]);
+/// ```
+pub struct ChangedFilter<T, Q>(PhantomData<(Q, fn(T))>);
+
+impl<T: Component, Q: Query> Query for ChangedFilter<T, Q> {
+    type Fetch = FetchChangedFilter<T, Q::Fetch>;
+}
+
+#[doc(hidden)]
+pub struct FetchChangedFilter<T, F>(NonNull<bool>, NonNull<bool>, F, bool, PhantomData<fn(T)>);
+
+unsafe impl<'a, T: Component, F: Fetch<'a>> Fetch<'a> for FetchChangedFilter<T, F> {
+    type Item = F::Item;
+
+    type State = (usize, F::State);
+
+    fn dangling() -> Self {
+        Self(
+            NonNull::dangling(),
+            NonNull::dangling(),
+            F::dangling(),
+            false,
+            PhantomData,
+        )
+    }
+
+    fn access(archetype: &Archetype) -> Option<Access> {
+        if archetype.has::<T>() {
+            F::access(archetype)
+        } else {
+            None
+        }
+    }
+
+    fn borrow(archetype: &Archetype, state: Self::State) {
+        F::borrow(archetype, state.1)
+    }
+    fn prepare(archetype: &Archetype) -> Option<Self::State> {
+        Some((archetype.get_state::<T>()?, F::prepare(archetype)?))
+    }
+    fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
+        Self(
+            archetype.get_mutated(state.0),
+            archetype.get_added(state.0),
+            F::execute(archetype, state.1),
+            archetype.may_have_changed(state.0),
+            PhantomData,
+        )
+    }
+    fn execute_since(archetype: &'a Archetype, state: Self::State, last_run: u32) -> Self {
+        Self(
+            archetype.get_mutated(state.0),
+            archetype.get_added(state.0),
+            F::execute_since(archetype, state.1, last_run),
+            archetype.may_have_changed(state.0),
+            PhantomData,
+        )
+    }
+    fn release(archetype: &Archetype, state: Self::State) {
+        F::release(archetype, state.1)
+    }
+
+    fn for_each_borrow(f: impl FnMut(TypeId, &'static str, bool)) {
+        borrow_filtered::<T, F>(f);
+    }
+
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), true);
+        F::for_each_requirement(f);
+    }
+
+    unsafe fn get(&self, n: usize) -> Self::Item {
+        self.2.get(n)
+    }
+
+    #[inline(always)]
+    unsafe fn prefetch(&self, n: usize) {
+        self.2.prefetch(n);
+    }
+
+    #[inline]
+    unsafe fn filter(&self, n: usize) -> bool {
+        (*self.0.as_ptr().add(n) || *self.1.as_ptr().add(n)) && self.2.filter(n)
+    }
+
+    #[inline]
+    unsafe fn matches(archetype: &Archetype, state: Self::State, n: usize) -> bool {
+        (*archetype.get_mutated(state.0).as_ptr().add(n)
+            || *archetype.get_added(state.0).as_ptr().add(n))
+            && F::matches(archetype, state.1, n)
+    }
+
+    fn is_dense() -> bool {
+        false
+    }
+
+    #[inline]
+    fn may_match(&self) -> bool {
+        self.3 && self.2.may_match()
+    }
+}
+
+/// Report the borrows of `F`, along with an immutable borrow of `T` if `F` doesn't borrow it
+///
+/// Filters read `T`'s trackers, which conflicts with writes to `T` elsewhere, but their own query
+/// may access `T` however it likes.
+fn borrow_filtered<'a, T: Component, F: Fetch<'a>>(mut f: impl FnMut(TypeId, &'static str, bool)) {
+    let mut borrowed = false;
+    F::for_each_borrow(|id, _, _| borrowed |= id == TypeId::of::<T>());
+    if !borrowed {
+        f(TypeId::of::<T>(), type_name::<T>(), false);
+    }
+    F::for_each_borrow(f);
+}
+
+/// Query transformer that yields only entities whose `T` component has been added since the last
+/// [`World::clear_trackers()`](crate::World::clear_trackers())
+///
+/// See [`ChangedFilter`] for details.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// let mut world = World::new();
+/// let a = world.spawn((1,));
+/// world.clear_trackers();
+/// let b = world.spawn((2,));
+/// let added = world.query::<AddedFilter<i32, &i32>>()
+///     .iter()
+///     .map(|(e, &i)| (e, i))
+///     .collect::<Vec<_>>();
+/// assert_eq!(added, &[(b, 2)]);
+/// ```
+pub struct AddedFilter<T, Q>(PhantomData<(Q, fn(T))>);
+
+impl<T: Component, Q: Query> Query for AddedFilter<T, Q> {
+    type Fetch = FetchAddedFilter<T, Q::Fetch>;
+}
+
+#[doc(hidden)]
+pub struct FetchAddedFilter<T, F>(NonNull<bool>, F, bool, PhantomData<fn(T)>);
+
+unsafe impl<'a, T: Component, F: Fetch<'a>> Fetch<'a> for FetchAddedFilter<T, F> {
+    type Item = F::Item;
+
+    type State = (usize, F::State);
+
+    fn dangling() -> Self {
+        Self(NonNull::dangling(), F::dangling(), false, PhantomData)
+    }
+
+    fn access(archetype: &Archetype) -> Option<Access> {
+        if archetype.has::<T>() {
+            F::access(archetype)
+        } else {
+            None
+        }
+    }
+
+    fn borrow(archetype: &Archetype, state: Self::State) {
+        F::borrow(archetype, state.1)
+    }
+    fn prepare(archetype: &Archetype) -> Option<Self::State> {
+        Some((archetype.get_state::<T>()?, F::prepare(archetype)?))
+    }
+    fn execute(archetype: &'a Archetype, state: Self::State) -> Self {
+        Self(
+            archetype.get_added(state.0),
+            F::execute(archetype, state.1),
+            archetype.may_have_changed(state.0),
+            PhantomData,
+        )
+    }
+    fn execute_since(archetype: &'a Archetype, state: Self::State, last_run: u32) -> Self {
+        Self(
+            archetype.get_added(state.0),
+            F::execute_since(archetype, state.1, last_run),
+            archetype.may_have_changed(state.0),
+            PhantomData,
+        )
+    }
+    fn release(archetype: &Archetype, state: Self::State) {
+        F::release(archetype, state.1)
+    }
+
+    fn for_each_borrow(f: impl FnMut(TypeId, &'static str, bool)) {
+        borrow_filtered::<T, F>(f);
+    }
+
+    fn for_each_requirement(mut f: impl FnMut(TypeId, bool)) {
+        f(TypeId::of::<T>(), true);
+        F::for_each_requirement(f);
+    }
+
+    unsafe fn get(&self, n: usize) -> Self::Item {
+        self.1.get(n)
+    }
+
+    #[inline(always)]
+    unsafe fn prefetch(&self, n: usize) {
+        self.1.prefetch(n);
+    }
+
+    #[inline]
+    unsafe fn filter(&self, n: usize) -> bool {
+        *self.0.as_ptr().add(n) && self.1.filter(n)
+    }
+
+    #[inline]
+    unsafe fn matches(archetype: &Archetype, state: Self::State, n: usize) -> bool {
+        *archetype.get_added(state.0).as_ptr().add(n) && F::matches(archetype, state.1, n)
+    }
+
+    fn is_dense() -> bool {
+        false
+    }
+
+    #[inline]
+    fn may_match(&self) -> bool {
+        self.2 && self.1.may_match()
+    }
+}
@@ -8,8 +8,14 @@
 use crate::alloc::{vec, vec::Vec};
 use core::any::TypeId;
 use core::borrow::Borrow;
+use core::cell::UnsafeCell;
 use core::convert::TryFrom;
-use core::sync::atomic::{AtomicU64, Ordering};
+use core::hash::Hash;
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::AtomicU64;
+#[cfg(not(target_has_atomic = "64"))]
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
 use core::{fmt, mem, ptr};
 
 #[cfg(feature = "std")]
@@ -18,11 +24,21 @@ use std::error::Error;
 use hashbrown::{HashMap, HashSet};
 
 use crate::alloc::boxed::Box;
-use crate::archetype::{Archetype, TypeIdMap, TypeInfo};
+use crate::alloc::sync::Arc;
+use crate::archetype::{
+    hash_map_memory, next_tick, Archetype, ChangeTicks, ComponentRegistry, TypeIdMap, TypeInfo,
+};
+use crate::borrow::AtomicBorrow;
 use crate::entities::{Entities, EntityMeta, Location, ReserveEntitiesIterator};
+use crate::event::Events;
+use crate::observer::Observers;
+use crate::query::{assert_borrow, assert_disjoint};
+use crate::resource::Resources;
 use crate::{
-    Bundle, ColumnBatch, DynamicBundle, Entity, EntityRef, Fetch, MissingComponent, NoSuchEntity,
-    Query, QueryBorrow, QueryItem, QueryMut, QueryOne, Ref, RefMut,
+    Access, Bundle, Children, CloneRegistry, ColumnBatch, DynamicBundle, Entity, EntityBuilder,
+    EntityMap, EntityMut, EntityOccupied, EntityRef, Fetch, HierarchyError, MissingCloner,
+    MissingComponent, Mut, NoSuchEntity, Parent, Query, QueryBorrow, QueryItem, QueryMany,
+    QueryMut, QueryOne, Ref, RefMut, ResourceRef, ResourceRefMut, TakenEntity, View,
 };
 
 /// An unordered collection of entities, each having any number of distinctly typed components
@@ -48,23 +64,43 @@ pub struct World {
     archetypes: ArchetypeSet,
     /// Maps statically-typed bundle types to archetypes
     bundle_to_archetype: TypeIdMap<u32>,
+    /// The archetype most recently spawned into, and the static bundle type used, if any
+    ///
+    /// Lets runs of identical spawns skip looking up their archetype. Archetype IDs only change in
+    /// `shrink_to_fit`, which resets this.
+    last_spawn: (Option<TypeId>, u32),
     id: u64,
     removed_components: HashMap<TypeId, Vec<Entity>>,
+    observers: Observers,
+    resources: Resources,
+    events: Events,
+    despawn_queue: DespawnQueue,
 }
 
 impl World {
     /// Create an empty world
     pub fn new() -> Self {
+        // 64-bit atomics are unavailable on some 32-bit targets
+        #[cfg(target_has_atomic = "64")]
         static ID: AtomicU64 = AtomicU64::new(1);
+        #[cfg(not(target_has_atomic = "64"))]
+        static ID: AtomicUsize = AtomicUsize::new(1);
+        #[allow(clippy::unnecessary_cast)]
+        let id = ID
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| id.checked_add(1))
+            .unwrap() as u64;
 
         Self {
             entities: Entities::default(),
             archetypes: ArchetypeSet::new(),
             bundle_to_archetype: HashMap::default(),
-            id: ID
-                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| id.checked_add(1))
-                .unwrap(),
+            last_spawn: (None, 0),
+            id,
             removed_components: HashMap::default(),
+            observers: Observers::default(),
+            resources: Resources::default(),
+            events: Events::default(),
+            despawn_queue: DespawnQueue::default(),
         }
     }
 
@@ -99,11 +135,45 @@ impl World {
         entity
     }
 
+    /// Create an entity with components built from its own handle
+    ///
+    /// `f` receives the handle the new entity will keep, which is useful for components that
+    /// refer back to the entity they're attached to. See [`spawn`](Self::spawn). If `f` panics,
+    /// no entity is spawned and the handle it received is never live.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Node {
+    ///     this: Entity,
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// let e = world.spawn_with(|this| (Node { this }, 123));
+    /// assert_eq!(world.get::<Node>(e).unwrap().this, e);
+    /// ```
+    pub fn spawn_with<B: DynamicBundle>(&mut self, f: impl FnOnce(Entity) -> B) -> Entity {
+        self.flush();
+
+        let entity = self.entities.alloc();
+        let guard = FreeOnUnwind {
+            entities: &mut self.entities,
+            entity,
+        };
+        let components = f(entity);
+        mem::forget(guard);
+
+        self.spawn_inner(entity, components);
+
+        entity
+    }
+
     /// Create an entity with certain components and a specific [`Entity`] handle.
     ///
     /// See [`spawn`](Self::spawn).
     ///
-    /// Despawns any existing entity with the same [`Entity::id`].
+    /// Despawns any existing entity with the same [`Entity::id`]. See
+    /// [`try_spawn_at`](Self::try_spawn_at) to fail instead.
     ///
     /// Useful for easy handle-preserving deserialization. Be cautious resurrecting old `Entity`
     /// handles in already-populated worlds as it vastly increases the likelihood of collisions.
@@ -125,46 +195,106 @@ impl World {
         // necessary
         self.flush();
 
-        let loc = self.entities.alloc_at(handle);
-        if let Some(loc) = loc {
-            if let Some(moved) = unsafe {
-                self.archetypes.archetypes[loc.archetype as usize].remove(loc.index, true)
-            } {
+        self.alloc_replacing(handle);
+        self.spawn_inner(handle, components);
+    }
+
+    /// Like [`spawn_at`](Self::spawn_at), but fails if a live entity already has the same
+    /// [`Entity::id`], regardless of its generation
+    ///
+    /// Entities spawned later never collide with `handle`, since the allocator skips IDs in use.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123,));
+    /// world.despawn(a).unwrap();
+    /// world.try_spawn_at(a, (456,)).unwrap();
+    /// assert_eq!(world.try_spawn_at(a, (789,)), Err(EntityOccupied));
+    /// assert_eq!(*world.get::<i32>(a).unwrap(), 456);
+    /// ```
+    pub fn try_spawn_at(
+        &mut self,
+        handle: Entity,
+        components: impl DynamicBundle,
+    ) -> Result<(), EntityOccupied> {
+        self.flush();
+        if self.entities.is_live(handle.id) {
+            return Err(EntityOccupied);
+        }
+        self.alloc_replacing(handle);
+        self.spawn_inner(handle, components);
+        Ok(())
+    }
+
+    /// Allocate `handle`, despawning any live entity that previously occupied its ID
+    fn alloc_replacing(&mut self, handle: Entity) {
+        let previous = self.entities.resolve(handle.id);
+        if let Some(previous) = previous {
+            // Notify observers before anything changes, in case a callback panics
+            let loc = self.entities.get(previous).unwrap();
+            let archetype = &self.archetypes.archetypes[loc.archetype as usize];
+            self.observers.removed(archetype, loc.index, previous);
+        }
+        if let Some(loc) = self.entities.alloc_at(handle) {
+            // `alloc_at` only reports a location when the ID was live
+            let previous = previous.unwrap();
+            let archetype = &mut self.archetypes.archetypes[loc.archetype as usize];
+            if let Some(moved) = unsafe { archetype.remove(loc.index, true) } {
                 self.entities.meta[moved as usize].location.index = loc.index;
             }
+            for ty in archetype.types() {
+                self.removed_components
+                    .entry(ty.id())
+                    .or_insert_with(Vec::new)
+                    .push(previous);
+            }
         }
-
-        self.spawn_inner(handle, components);
     }
 
     fn spawn_inner(&mut self, entity: Entity, components: impl DynamicBundle) {
-        let archetype_id = match components.key() {
+        let key = components.key();
+        let (last_key, last) = self.last_spawn;
+        let archetype_id = match key {
+            Some(_) if key == last_key => last,
             Some(k) => {
                 let archetypes = &mut self.archetypes;
                 *self.bundle_to_archetype.entry(k).or_insert_with(|| {
-                    components.with_ids(|ids| archetypes.get(ids, &|| components.type_info()))
+                    components.with_ids(|ids| archetypes.get(ids, || components.type_info()))
                 })
             }
-            None => components.with_ids(|ids| self.archetypes.get(ids, &|| components.type_info())),
+            None => components.with_ids(|ids| {
+                let types = self.archetypes.archetypes[last as usize].types();
+                if types.iter().map(|x| x.id()).eq(ids.iter().copied()) {
+                    last
+                } else {
+                    self.archetypes.get(ids, || components.type_info())
+                }
+            }),
         };
+        self.last_spawn = (key, archetype_id);
 
         let archetype = &mut self.archetypes.archetypes[archetype_id as usize];
+        let tick = next_tick(archetype.change_tick());
         unsafe {
             let index = archetype.allocate(entity.id);
             components.put(|ptr, ty| {
-                archetype.put_dynamic(ptr, ty.id(), ty.layout().size(), index, true, false);
+                archetype.put_dynamic(ptr, ty.id(), index, true, false, tick);
             });
             self.entities.meta[entity.id as usize].location = Location {
                 archetype: archetype_id,
                 index,
             };
+            self.observers.added(archetype, index, entity);
         }
     }
 
     /// Efficiently spawn a large number of entities with the same statically-typed components
     ///
     /// Faster than calling [`spawn`](Self::spawn) repeatedly with the same components, but requires
-    /// that component types are known at compile time.
+    /// that component types are known at compile time. Like `spawn`, reuses the IDs of the most
+    /// recently despawned entities before allocating new ones.
     ///
     /// # Example
     /// ```
@@ -188,6 +318,7 @@ impl World {
         let (lower, upper) = iter.size_hint();
         let archetype_id = self.reserve_inner::<I::Item>(
             u32::try_from(upper.unwrap_or(lower)).expect("iterator too large"),
+            false,
         );
 
         SpawnBatchIter {
@@ -195,9 +326,110 @@ impl World {
             entities: &mut self.entities,
             archetype_id,
             archetype: &mut self.archetypes.archetypes[archetype_id as usize],
+            observers: &mut self.observers,
+        }
+    }
+
+    /// Like [`spawn_batch`](Self::spawn_batch), but allocates storage for exactly as many entities
+    /// as `iter` yields
+    ///
+    /// `spawn_batch` grows storage geometrically, which may leave spare capacity for future
+    /// spawns. This instead grows the target archetype by precisely `iter.len()`, in a single
+    /// allocation per component type, minimizing memory use when the entity count is known to be
+    /// final.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let batch = (0..100).map(|i| (i, "abc")).collect::<Vec<_>>();
+    /// let entities = world.spawn_batch_exact(batch).collect::<Vec<_>>();
+    /// assert_eq!(entities.len(), 100);
+    /// let archetype = world.archetypes().find(|x| x.has::<i32>()).unwrap();
+    /// assert_eq!(archetype.capacity(), 100);
+    /// ```
+    pub fn spawn_batch_exact<I>(&mut self, iter: I) -> SpawnBatchIter<'_, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        I::Item: Bundle + 'static,
+    {
+        self.flush();
+
+        let iter = iter.into_iter();
+        let archetype_id = self
+            .reserve_inner::<I::Item>(u32::try_from(iter.len()).expect("iterator too large"), true);
+
+        SpawnBatchIter {
+            inner: iter,
+            entities: &mut self.entities,
+            archetype_id,
+            archetype: &mut self.archetypes.archetypes[archetype_id as usize],
+            observers: &mut self.observers,
         }
     }
 
+    /// Spawn `count` entities, each with a clone of `template`
+    ///
+    /// Like [`spawn_batch_exact`](Self::spawn_batch_exact), allocates storage for exactly `count`
+    /// more entities up front. As with other batch spawns, entities are spawned as the returned
+    /// iterator is advanced, and any remaining ones when it's dropped.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// #[derive(Clone)]
+    /// struct Enemy { health: i32 }
+    ///
+    /// let mut world = World::new();
+    /// let wave = world.spawn_n(500, (Enemy { health: 10 }, "grunt")).collect::<Vec<_>>();
+    /// assert_eq!(wave.len(), 500);
+    /// assert_eq!(world.get::<Enemy>(wave[499]).unwrap().health, 10);
+    /// assert_eq!(world.query::<Added<Enemy>>().iter().filter(|&(_, added)| added).count(), 500);
+    /// ```
+    pub fn spawn_n<B>(
+        &mut self,
+        count: u32,
+        template: B,
+    ) -> SpawnBatchIter<'_, impl ExactSizeIterator<Item = B>>
+    where
+        B: Bundle + Clone + 'static,
+    {
+        self.spawn_batch_exact((0..count).map(move |_| template.clone()))
+    }
+
+    /// Spawn an entity for each `Ok` bundle in `iter`, passing errors through
+    ///
+    /// Returns the outcome of each item in order. Errors are produced by `iter` itself, e.g. while
+    /// validating user-authored data, before anything is spawned, so a rejected item never leaves
+    /// behind a partially constructed entity or a leaked ID, and doesn't prevent later items from
+    /// being spawned. Unlike [`spawn_batch`](Self::spawn_batch), every item is spawned
+    /// individually, so bundles may have differing or dynamic component types, such as those
+    /// produced by [`EntityBuilder`]. All items have been spawned when this returns.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let definitions = ["1", "2", "three"];
+    /// let results = world
+    ///     .try_spawn_batch(definitions.iter().map(|x| x.parse::<i32>().map(|x| (x,))))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(*world.get::<i32>(*results[1].as_ref().unwrap()).unwrap(), 2);
+    /// assert!(results[2].is_err());
+    /// assert_eq!(world.len(), 2);
+    /// ```
+    pub fn try_spawn_batch<I, B, E>(&mut self, iter: I) -> impl Iterator<Item = Result<Entity, E>>
+    where
+        I: IntoIterator<Item = Result<B, E>>,
+        B: DynamicBundle,
+    {
+        iter.into_iter()
+            .map(|x| x.map(|bundle| self.spawn(bundle)))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
     /// Super-efficiently spawn the contents of a [`ColumnBatch`]
     ///
     /// The fastest, but most specialized, way to spawn large numbers of entities. Useful for high
@@ -222,11 +454,101 @@ impl World {
         }
 
         // Return iterator over new IDs
-        SpawnColumnBatchIter {
+        let iter = SpawnColumnBatchIter {
             pending_end: id_alloc.pending_end,
             id_alloc,
             entities: &mut self.entities,
+        };
+
+        // Notify observers once every entity is in place. The iterator is responsible for consuming
+        // the freelist entries that were used, so a panicking callback can't leave them reusable.
+        if self.observers.observes_addition() {
+            for index in base..base + entity_count {
+                let entity = unsafe {
+                    iter.entities
+                        .resolve_unknown_gen(archetype.entity_id(index))
+                };
+                self.observers.added(archetype, index, entity);
+            }
+        }
+
+        iter
+    }
+
+    /// Move every entity from `other` into this world under fresh handles
+    ///
+    /// Returns a map from each entity's handle in `other` to its new handle in this world, which
+    /// can be used to fix up components that refer to other entities. [`Parent`] and [`Children`]
+    /// are fixed up automatically, preserving hierarchies without reporting the links as
+    /// [`Mutated`](crate::Mutated); links to entities that no longer exist in `other` are dropped.
+    /// Components are moved in bulk, an archetype at a time, without being cloned or dropped.
+    /// Every moved component is reported as [`Added`](crate::Added), and to observers registered
+    /// with [`on_add`](Self::on_add). Observers, resources, and events of `other` are discarded.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Target(Entity);
+    ///
+    /// let mut world = World::new();
+    /// world.spawn((1,));
+    /// let mut scene = World::new();
+    /// let a = scene.spawn((2,));
+    /// let b = scene.spawn((3, Target(a)));
+    ///
+    /// let map = world.merge(scene);
+    /// let (a, b) = (map.get(a).copied().unwrap(), map.get(b).copied().unwrap());
+    /// let mut target = world.get_mut::<Target>(b).unwrap();
+    /// target.0 = *map.get(target.0).unwrap();
+    /// drop(target);
+    /// assert_eq!(world.len(), 3);
+    /// assert_eq!(*world.get::<i32>(a).unwrap(), 2);
+    /// assert_eq!(world.get::<Target>(b).unwrap().0, a);
+    /// ```
+    pub fn merge(&mut self, mut other: World) -> EntityMap<Entity> {
+        other.flush();
+        let mut map = EntityMap::new();
+        for mut archetype in mem::take(&mut other.archetypes.archetypes) {
+            if archetype.is_empty() {
+                continue;
+            }
+            let old = archetype
+                .ids()
+                .iter()
+                .map(|&id| Entity {
+                    id,
+                    generation: other.entities.meta[id as usize].generation,
+                })
+                .collect::<Vec<_>>();
+            // Edges refer to archetypes of `other`
+            archetype.remove_edges.clear();
+            archetype.mark_added();
+            map.extend(
+                old.into_iter()
+                    .zip(self.spawn_column_batch(ColumnBatch(archetype))),
+            );
+        }
+        // Keep hierarchies from `other` intact. Links to entities that weren't moved are stale,
+        // and could alias unrelated entities of this world, so they're dropped.
+        let mut orphans = Vec::new();
+        for (_, &entity) in map.iter() {
+            if let Ok(parent) = self.get_mut_untracked::<Parent>(entity) {
+                match map.get(parent.0) {
+                    Some(&new) => parent.0 = new,
+                    None => orphans.push(entity),
+                }
+            }
+            if let Ok(children) = self.get_mut_untracked::<Children>(entity) {
+                children.0.retain(|&child| map.contains(child));
+                for child in &mut children.0 {
+                    *child = map.get(*child).copied().unwrap();
+                }
+            }
         }
+        for entity in orphans {
+            self.remove_one::<Parent>(entity).unwrap();
+        }
+        map
     }
 
     /// Hybrid of [`spawn_column_batch`](Self::spawn_column_batch) and [`spawn_at`](Self::spawn_at)
@@ -242,14 +564,7 @@ impl World {
 
         // Drop components of entities that will be replaced
         for &handle in handles {
-            let loc = self.entities.alloc_at(handle);
-            if let Some(loc) = loc {
-                if let Some(moved) = unsafe {
-                    self.archetypes.archetypes[loc.archetype as usize].remove(loc.index, true)
-                } {
-                    self.entities.meta[moved as usize].location.index = loc.index;
-                }
-            }
+            self.alloc_replacing(handle);
         }
 
         // Store components
@@ -264,6 +579,9 @@ impl World {
                 index: index as u32,
             };
         }
+        for (&handle, index) in handles.iter().zip(base..) {
+            self.observers.added(archetype, index, handle);
+        }
     }
 
     /// Allocate many entities ID concurrently
@@ -276,7 +594,7 @@ impl World {
     /// can also be done explicitly by calling [`flush`](Self::flush).
     ///
     /// Useful for reserving an ID that will later have components attached to it with `insert`.
-    pub fn reserve_entities(&self, count: u32) -> ReserveEntitiesIterator {
+    pub fn reserve_entities(&self, count: u32) -> ReserveEntitiesIterator<'_> {
         self.entities.reserve_entities(count)
     }
 
@@ -288,8 +606,17 @@ impl World {
     }
 
     /// Destroy an entity and all its components
+    ///
+    /// If `entity` is part of a hierarchy, it's removed from its parent's [`Children`] and its
+    /// children lose their [`Parent`]. See [`despawn_recursive`](Self::despawn_recursive) to destroy
+    /// the children as well.
     pub fn despawn(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
         self.flush();
+        self.unlink(entity)?;
+        // Notify observers while the entity is intact, in case a callback panics
+        let loc = self.entities.get(entity)?;
+        let archetype = &self.archetypes.archetypes[loc.archetype as usize];
+        self.observers.removed(archetype, loc.index, entity);
         let loc = self.entities.free(entity)?;
         let archetype = &mut self.archetypes.archetypes[loc.archetype as usize];
         if let Some(moved) = unsafe { archetype.remove(loc.index, true) } {
@@ -305,109 +632,805 @@ impl World {
         Ok(())
     }
 
-    /// Ensure at least `additional` entities with exact components `T` can be spawned without reallocating
-    pub fn reserve<T: Bundle + 'static>(&mut self, additional: u32) {
-        self.reserve_inner::<T>(additional);
-    }
-
-    fn reserve_inner<T: Bundle + 'static>(&mut self, additional: u32) -> u32 {
+    /// Remove an entity from the world, returning its components
+    ///
+    /// The entity is removed from any hierarchy, as with [`despawn`](Self::despawn), and its
+    /// [`Parent`] and [`Children`] components are dropped. The result can be passed to [`spawn`](Self::spawn),
+    /// typically on another world, which allocates a new handle, or individual components can be
+    /// extracted with [`TakenEntity::take`]. Either way, `entity` is invalidated immediately.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut staging = World::new();
+    /// let mut world = World::new();
+    /// let a = staging.spawn((123, "abc"));
+    /// let taken = staging.take(a).unwrap();
+    /// assert!(!staging.contains(a));
+    /// assert_eq!(taken.entity(), a);
+    /// let b = world.spawn(taken);
+    /// assert_eq!(*world.get::<i32>(b).unwrap(), 123);
+    /// assert_eq!(*world.get::<&str>(b).unwrap(), "abc");
+    /// ```
+    pub fn take(&mut self, entity: Entity) -> Result<TakenEntity, NoSuchEntity> {
         self.flush();
-        self.entities.reserve(additional);
-
-        let archetypes = &mut self.archetypes;
-        let archetype_id = *self
-            .bundle_to_archetype
-            .entry(TypeId::of::<T>())
-            .or_insert_with(|| {
-                T::with_static_ids(|ids| archetypes.get(ids, &|| T::static_type_info()))
+        self.unlink(entity)?;
+        let loc = self.entities.free(entity)?;
+        let archetype = &mut self.archetypes.archetypes[loc.archetype as usize];
+        for ty in archetype.types() {
+            self.removed_components
+                .entry(ty.id())
+                .or_default()
+                .push(entity);
+        }
+        let types = archetype.types().to_vec();
+        let mut builder = EntityBuilder::new();
+        unsafe {
+            let mut types = types.into_iter();
+            let moved = archetype.move_to(loc.index, |ptr, ty, _, _, _, _| {
+                let info = types.next().unwrap();
+                debug_assert_eq!(info.id(), ty);
+                builder.add_dynamic(ptr, info);
             });
+            if let Some(moved) = moved {
+                self.entities.meta[moved as usize].location.index = loc.index;
+            }
+        }
+        let mut taken = TakenEntity::new(entity, builder);
+        // Notify observers once the entity is fully taken, so a panicking callback can't leave it
+        // half-removed
+        if self.observers.observes_removal() {
+            for (ty, ptr) in taken.components() {
+                unsafe {
+                    self.observers.removed_detached(entity, ty, ptr);
+                }
+            }
+        }
+        taken.take::<Parent>();
+        taken.take::<Children>();
+        Ok(taken)
+    }
 
-        self.archetypes.archetypes[archetype_id as usize].reserve(additional);
-        archetype_id
+    /// Destroy an entity, its children, their children, and so on
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let root = world.spawn(());
+    /// let child = world.spawn(());
+    /// let grandchild = world.spawn(());
+    /// world.set_parent(child, root).unwrap();
+    /// world.set_parent(grandchild, child).unwrap();
+    /// world.despawn_recursive(child).unwrap();
+    /// assert!(!world.contains(grandchild));
+    /// assert!(world.get::<Children>(root).unwrap().is_empty());
+    /// ```
+    pub fn despawn_recursive(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        self.flush();
+        if !self.contains(entity) {
+            return Err(NoSuchEntity);
+        }
+        let mut pending = vec![entity];
+        while let Some(entity) = pending.pop() {
+            if let Ok(children) = self.remove_one::<Children>(entity) {
+                pending.extend(children.0);
+            }
+            // Children may have been despawned directly, leaving stale handles behind
+            let _ = self.despawn(entity);
+        }
+        Ok(())
     }
 
-    /// Despawn all entities
+    /// Destroy many entities at once, returning how many existed
     ///
-    /// Preserves allocated storage for reuse.
-    pub fn clear(&mut self) {
-        for archetype in &self.archetypes.archetypes {
+    /// Equivalent to calling [`despawn`](Self::despawn) on each entity and ignoring failures, but
+    /// entities are removed from the back of each archetype first to minimize how many components
+    /// need to be moved to fill the gaps.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// for i in 0..100 {
+    ///     world.spawn((i,));
+    /// }
+    /// let odd = world
+    ///     .query::<&i32>()
+    ///     .iter()
+    ///     .filter(|(_, &x)| x % 2 == 1)
+    ///     .map(|(e, _)| e)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(world.despawn_batch(odd), 50);
+    /// assert!(world.query::<&i32>().iter().all(|(_, &x)| x % 2 == 0));
+    /// ```
+    pub fn despawn_batch(&mut self, entities: impl IntoIterator<Item = Entity>) -> u32 {
+        self.flush();
+        let entities = entities.into_iter().collect::<Vec<_>>();
+        // Hierarchy maintenance may move entities between archetypes, so it goes first
+        for &entity in &entities {
+            let _ = self.unlink(entity);
+        }
+        let mut doomed = entities
+            .into_iter()
+            .filter_map(|entity| {
+                let loc = self.entities.get(entity).ok()?;
+                if loc.index == u32::MAX {
+                    return None;
+                }
+                Some((loc, entity))
+            })
+            .collect::<Vec<_>>();
+        // Removing in descending index order guarantees that the entity moved into each gap isn't
+        // itself awaiting removal
+        doomed.sort_unstable_by(|(x, _), (y, _)| {
+            x.archetype
+                .cmp(&y.archetype)
+                .then(x.index.cmp(&y.index).reverse())
+        });
+        doomed.dedup_by_key(|&mut (_, entity)| entity);
+        // Notify observers before anything is removed, in case a callback panics
+        for &(loc, entity) in &doomed {
+            let archetype = &self.archetypes.archetypes[loc.archetype as usize];
+            self.observers.removed(archetype, loc.index, entity);
+        }
+        for &(loc, entity) in &doomed {
+            self.entities.free(entity).unwrap();
+            let archetype = &mut self.archetypes.archetypes[loc.archetype as usize];
+            if let Some(moved) = unsafe { archetype.remove(loc.index, true) } {
+                self.entities.meta[moved as usize].location.index = loc.index;
+            }
             for ty in archetype.types() {
-                let archetype_entities: Vec<Entity> = archetype
-                    .ids()
-                    .iter()
-                    .map(|id| unsafe { self.find_entity_from_id(*id) })
-                    .collect();
-                let removed_entities = self
-                    .removed_components
+                self.removed_components
                     .entry(ty.id())
-                    .or_insert_with(Vec::new);
-                removed_entities.extend(archetype_entities);
+                    .or_default()
+                    .push(entity);
             }
         }
-        for archetype in &mut self.archetypes.archetypes {
-            archetype.clear();
-        }
-        self.entities.clear();
-    }
-
-    /// Whether `entity` still exists
-    pub fn contains(&self, entity: Entity) -> bool {
-        self.entities.contains(entity)
+        doomed.len() as u32
     }
 
-    /// Efficiently iterate over all entities that have certain components, using dynamic borrow
-    /// checking
-    ///
-    /// Prefer [`query_mut`](Self::query_mut) when concurrent access to the [`World`] is not required.
-    ///
-    /// Calling `iter` on the returned value yields `(Entity, Q)` tuples, where `Q` is some query
-    /// type. A query type is any type for which an implementation of [`Query`] exists, e.g. `&T`,
-    /// `&mut T`, a tuple of query types, or an `Option` wrapping a query type, where `T` is any
-    /// component type. Components queried with `&mut` must only appear once. Entities which do not
-    /// have a component type referenced outside of an `Option` will be skipped.
-    ///
-    /// Entities are yielded in arbitrary order.
+    /// Despawn every entity matching `Q` for which `f` returns `false`, returning how many were
+    /// despawned
     ///
-    /// The returned [`QueryBorrow`] can be further transformed with combinator methods; see its
-    /// documentation for details.
+    /// Entities that don't match `Q` are left untouched. Despawned entities are removed as if by
+    /// [`despawn_batch`](Self::despawn_batch), so [`on_remove`](Self::on_remove) callbacks run and
+    /// [`removed`](Self::removed) reports their components.
     ///
-    /// Iterating a query will panic if it would violate an existing unique reference or construct
-    /// an invalid unique reference. This occurs when two simultaneously-active queries could expose
-    /// the same entity. Simultaneous queries can access the same component type if and only if the
-    /// world contains no entities that have all components required by both queries, assuming no
-    /// other component borrows are outstanding.
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Lifetime(u32);
+    /// let mut world = World::new();
+    /// let a = world.spawn((Lifetime(0),));
+    /// let b = world.spawn((Lifetime(3),));
+    /// let c = world.spawn(("not a projectile",));
+    /// let despawned = world.retain::<&mut Lifetime>(|_, mut lifetime| {
+    ///     lifetime.0 = lifetime.0.saturating_sub(1);
+    ///     lifetime.0 > 0
+    /// });
+    /// assert_eq!(despawned, 1);
+    /// assert!(!world.contains(a));
+    /// assert_eq!(world.get::<Lifetime>(b).unwrap().0, 2);
+    /// assert!(world.contains(c));
+    /// ```
+    pub fn retain<Q: Query>(
+        &mut self,
+        mut f: impl for<'a> FnMut(Entity, QueryItem<'a, Q>) -> bool,
+    ) -> u32 {
+        let doomed = self
+            .query_mut::<Q>()
+            .into_iter()
+            .filter_map(|(entity, item)| if f(entity, item) { None } else { Some(entity) })
+            .collect::<Vec<_>>();
+        self.despawn_batch(doomed)
+    }
+
+    /// Queue `entity` to be despawned by the next call to [`apply_despawns`](Self::apply_despawns)
     ///
-    /// Iterating a query yields references with lifetimes bound to the [`QueryBorrow`] returned
-    /// here. To ensure those are invalidated, the return value of this method must be dropped for
-    /// its dynamic borrows from the world to be released. Similarly, lifetime rules ensure that
-    /// references obtained from a query cannot outlive the [`QueryBorrow`].
+    /// Unlike [`despawn`](Self::despawn), this only needs a shared reference, so it can be called
+    /// while iterating over a query, including from multiple threads. Queueing an entity more than
+    /// once, or one that's despawned by other means before the queue is applied, is harmless.
     ///
     /// # Example
     /// ```
     /// # use hecs::*;
+    /// struct Health(i32);
     /// let mut world = World::new();
-    /// let a = world.spawn((123, true, "abc"));
-    /// let b = world.spawn((456, false));
-    /// let c = world.spawn((42, "def"));
-    /// let entities = world.query::<(&i32, &bool)>()
-    ///     .iter()
-    ///     .map(|(e, (&i, &b))| (e, i, b)) // Copy out of the world
-    ///     .collect::<Vec<_>>();
-    /// assert_eq!(entities.len(), 2);
-    /// assert!(entities.contains(&(a, 123, true)));
-    /// assert!(entities.contains(&(b, 456, false)));
+    /// let a = world.spawn((Health(0),));
+    /// let b = world.spawn((Health(10),));
+    /// for (entity, health) in world.query::<&Health>().iter() {
+    ///     if health.0 <= 0 {
+    ///         world.despawn_later(entity);
+    ///     }
+    /// }
+    /// assert_eq!(world.apply_despawns(), 1);
+    /// assert!(!world.contains(a));
+    /// assert!(world.contains(b));
     /// ```
-    pub fn query<Q: Query>(&self) -> QueryBorrow<'_, Q> {
-        QueryBorrow::new(&self.entities.meta, &self.archetypes.archetypes)
+    pub fn despawn_later(&self, entity: Entity) {
+        self.despawn_queue.push(entity);
     }
 
-    /// Query a uniquely borrowed world
+    /// Despawn every entity queued by [`despawn_later`](Self::despawn_later), returning how many
+    /// existed
     ///
-    /// Like [`query`](Self::query), but faster because dynamic borrow checks can be skipped. Note
-    /// that, unlike [`query`](Self::query), this returns an `IntoIterator` which can be passed
+    /// Entities are removed as if by [`despawn_batch`](Self::despawn_batch).
+    pub fn apply_despawns(&mut self) -> u32 {
+        let entities = self.despawn_queue.take();
+        self.despawn_batch(entities)
+    }
+
+    /// Make `child` a child of `parent`, removing it from its previous parent, if any
+    ///
+    /// Fails with [`HierarchyError::Cycle`] if `parent` is `child` or one of its descendants.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn(());
+    /// let b = world.spawn(());
+    /// let c = world.spawn(());
+    /// world.set_parent(c, a).unwrap();
+    /// world.set_parent(c, b).unwrap();
+    /// assert_eq!(*world.get::<Parent>(c).unwrap(), Parent(b));
+    /// assert!(world.get::<Children>(a).unwrap().is_empty());
+    /// assert_eq!(&**world.get::<Children>(b).unwrap(), &[c]);
+    /// assert_eq!(world.set_parent(b, c), Err(HierarchyError::Cycle));
+    /// ```
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> Result<(), HierarchyError> {
+        self.flush();
+        if !self.contains(child) || !self.contains(parent) {
+            return Err(HierarchyError::NoSuchEntity);
+        }
+        if parent == child || self.ancestors(parent).any(|x| x == child) {
+            return Err(HierarchyError::Cycle);
+        }
+
+        self.detach(child)?;
+        self.insert_one(child, Parent(parent))?;
+        if let Ok(mut children) = self.get_mut::<Children>(parent) {
+            children.0.push(child);
+            return Ok(());
+        }
+        self.insert_one(parent, Children(vec![child]))?;
+        Ok(())
+    }
+
+    /// Remove `child` from its parent's [`Children`] and remove its [`Parent`]
+    ///
+    /// Returns the former parent, if any.
+    pub fn remove_parent(&mut self, child: Entity) -> Result<Option<Entity>, NoSuchEntity> {
+        self.flush();
+        let parent = self.detach(child)?;
+        if parent.is_some() {
+            self.remove_one::<Parent>(child).unwrap();
+        }
+        Ok(parent)
+    }
+
+    /// Iterate depth-first over the children of `root`, their children, and so on
+    ///
+    /// Each entity is yielded before its own children, and siblings are visited in the order of
+    /// their parent's [`Children`], so parents always come before their descendants, e.g. for
+    /// propagating transforms. Yields nothing if `root` doesn't exist or has no children.
+    ///
+    /// [`Children`] and [`Parent`] are borrowed only briefly as each entity is visited, so other
+    /// components may be modified during iteration. Panics if either is uniquely borrowed when
+    /// it's needed.
+    ///
+    /// A child is only followed if its [`Parent`] refers back to the entity listing it, and
+    /// iteration never returns to `root`, so a hierarchy made inconsistent by inserting
+    /// [`Parent`] or [`Children`] directly can't cause an infinite loop.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let root = world.spawn(());
+    /// let a = world.spawn(());
+    /// let b = world.spawn(());
+    /// let c = world.spawn(());
+    /// world.set_parent(a, root).unwrap();
+    /// world.set_parent(b, a).unwrap();
+    /// world.set_parent(c, root).unwrap();
+    /// assert_eq!(world.descendants(root).collect::<Vec<_>>(), [a, b, c]);
+    /// ```
+    pub fn descendants(&self, root: Entity) -> impl Iterator<Item = Entity> + '_ {
+        let mut pending = Vec::new();
+        self.push_children(root, &mut pending);
+        core::iter::from_fn(move || loop {
+            let (parent, entity) = pending.pop()?;
+            if entity == root || self.hierarchy_link::<Parent>(entity).map(|x| x.0) != Some(parent)
+            {
+                continue;
+            }
+            self.push_children(entity, &mut pending);
+            return Some(entity);
+        })
+    }
+
+    /// Iterate over the parent of `entity`, its parent, and so on, up to the root of its hierarchy
+    ///
+    /// Yields nothing if `entity` doesn't exist or has no [`Parent`]. Panics if [`Parent`] is
+    /// uniquely borrowed when it's needed.
+    ///
+    /// [`set_parent`](Self::set_parent) never creates cycles, but one may arise if [`Parent`] is
+    /// inserted directly. In that case, iteration stops after yielding as many entities as the
+    /// world contains, rather than looping forever.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let root = world.spawn(());
+    /// let a = world.spawn(());
+    /// let b = world.spawn(());
+    /// world.set_parent(a, root).unwrap();
+    /// world.set_parent(b, a).unwrap();
+    /// assert_eq!(world.ancestors(b).collect::<Vec<_>>(), [a, root]);
+    /// assert_eq!(world.ancestors(root).count(), 0);
+    /// ```
+    pub fn ancestors(&self, entity: Entity) -> impl Iterator<Item = Entity> + '_ {
+        let mut current = entity;
+        core::iter::from_fn(move || {
+            current = self.hierarchy_link::<Parent>(current)?.0;
+            Some(current)
+        })
+        .take(self.len() as usize)
+    }
+
+    /// Queue `(entity, child)` for each child of `entity`, such that the first child is popped first
+    fn push_children(&self, entity: Entity, pending: &mut Vec<(Entity, Entity)>) {
+        if let Some(children) = self.hierarchy_link::<Children>(entity) {
+            pending.extend(children.iter().rev().map(|&child| (entity, child)));
+        }
+    }
+
+    /// Borrow `entity`'s `T` component, if any, panicking if it's uniquely borrowed
+    fn hierarchy_link<T: Component>(&self, entity: Entity) -> Option<Ref<'_, T>> {
+        match self.get::<T>(entity) {
+            Ok(x) => Some(x),
+            Err(ComponentError::AlreadyBorrowed(name)) => panic!("{} already borrowed", name),
+            Err(_) => None,
+        }
+    }
+
+    /// Remove `entity` from its parent's [`Children`], leaving its [`Parent`] in place
+    fn detach(&mut self, entity: Entity) -> Result<Option<Entity>, NoSuchEntity> {
+        let parent = match self.get::<Parent>(entity) {
+            Ok(parent) => parent.0,
+            Err(ComponentError::NoSuchEntity) => return Err(NoSuchEntity),
+            Err(ComponentError::MissingComponent(_)) => return Ok(None),
+            Err(ComponentError::AlreadyBorrowed(_)) => unreachable!("world is borrowed uniquely"),
+        };
+        if let Ok(mut children) = self.get_mut::<Children>(parent) {
+            children.remove(entity);
+        }
+        Ok(Some(parent))
+    }
+
+    /// Remove every hierarchy link referring to `entity`, prior to despawning it
+    fn unlink(&mut self, entity: Entity) -> Result<(), NoSuchEntity> {
+        let loc = self.entities.get(entity)?;
+        if !self.archetypes.archetypes[loc.archetype as usize].has_hierarchy() {
+            return Ok(());
+        }
+        self.detach(entity)?;
+        let children = match self.get_mut::<Children>(entity) {
+            Ok(mut children) => mem::take(&mut children.0),
+            Err(_) => return Ok(()),
+        };
+        for child in children {
+            let _ = self.remove_one::<Parent>(child);
+        }
+        Ok(())
+    }
+
+    /// Ensure at least `additional` entities with exact components `T` can be spawned without reallocating
+    ///
+    /// Like [`Vec::reserve`], this does nothing if enough capacity is already available. The
+    /// archetype for `T` is created if it doesn't exist yet, but no entities are spawned, so
+    /// [`len`](Self::len) is unaffected except that outstanding reserved entities are
+    /// [flushed](Self::flush).
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.reserve::<(i32, bool)>(1000);
+    /// assert_eq!(world.len(), 0);
+    /// let before = world.archetypes().map(|a| a.capacity()).sum::<u32>();
+    /// world.spawn_batch((0..1000).map(|i| (i, true)));
+    /// assert_eq!(world.archetypes().map(|a| a.capacity()).sum::<u32>(), before);
+    /// ```
+    pub fn reserve<T: Bundle + 'static>(&mut self, additional: u32) {
+        self.reserve_inner::<T>(additional, false);
+    }
+
+    fn reserve_inner<T: Bundle + 'static>(&mut self, additional: u32, exact: bool) -> u32 {
+        self.flush();
+        self.entities.reserve(additional);
+
+        let archetypes = &mut self.archetypes;
+        let archetype_id = *self
+            .bundle_to_archetype
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                T::with_static_ids(|ids| archetypes.get(ids, || T::static_type_info()))
+            });
+
+        let archetype = &mut self.archetypes.archetypes[archetype_id as usize];
+        if exact {
+            archetype.reserve_exact(additional);
+        } else {
+            archetype.reserve(additional);
+        }
+        archetype_id
+    }
+
+    /// Despawn all entities
+    ///
+    /// Every component is dropped, but archetypes and their allocated storage are preserved for
+    /// reuse, unlike [`shrink_to_fit`](Self::shrink_to_fit). Every entity ID's generation is
+    /// advanced, so no handle from before the clear, including those from
+    /// [`reserve_entity`](Self::reserve_entity), can refer to an entity spawned afterwards.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123, true));
+    /// let (archetype, _) = world.location(a).unwrap();
+    /// let capacity = world.archetype(archetype).unwrap().capacity();
+    /// world.clear();
+    /// assert!(world.is_empty());
+    /// assert!(!world.contains(a));
+    /// assert_eq!(world.archetype(archetype).unwrap().capacity(), capacity);
+    /// ```
+    pub fn clear(&mut self) {
+        self.flush();
+        if self.observers.observes_removal() {
+            for archetype in &self.archetypes.archetypes {
+                for (index, &id) in archetype.ids().iter().enumerate() {
+                    let entity = unsafe { self.entities.resolve_unknown_gen(id) };
+                    self.observers.removed(archetype, index as u32, entity);
+                }
+            }
+        }
+        for archetype in &self.archetypes.archetypes {
+            for ty in archetype.types() {
+                let archetype_entities: Vec<Entity> = archetype
+                    .ids()
+                    .iter()
+                    .map(|id| unsafe { self.find_entity_from_id(*id) })
+                    .collect();
+                let removed_entities = self
+                    .removed_components
+                    .entry(ty.id())
+                    .or_insert_with(Vec::new);
+                removed_entities.extend(archetype_entities);
+            }
+        }
+        for archetype in &mut self.archetypes.archetypes {
+            archetype.clear();
+        }
+        self.entities.clear();
+    }
+
+    /// Release memory that isn't needed to store the current entities
+    ///
+    /// Shrinks each archetype's storage to fit the entities it holds, discarding any capacity
+    /// [`reserve`](Self::reserve)d in advance, and removes archetypes that have no entities left.
+    /// Removing archetypes moves those that remain, so [`archetypes_generation`] changes and
+    /// previously obtained archetype positions, e.g. from [`location`](Self::location), must be
+    /// looked up again. Useful after despawning large numbers of entities.
+    ///
+    /// [`archetypes_generation`]: Self::archetypes_generation
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let entities = world.spawn_batch((0..1000).map(|i| (i, true))).collect::<Vec<_>>();
+    /// let kept = world.spawn(("abc",));
+    /// world.despawn_batch(entities);
+    /// let generation = world.archetypes_generation();
+    /// world.shrink_to_fit();
+    /// assert_ne!(world.archetypes_generation(), generation);
+    /// assert!(world.archetypes().all(|a| a.capacity() == a.len()));
+    /// assert_eq!(*world.get::<&str>(kept).unwrap(), "abc");
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.flush();
+        if let Some(remap) = self.archetypes.remove_empty() {
+            self.last_spawn = (None, 0);
+            self.bundle_to_archetype
+                .retain(|_, index| remap_archetype(&remap, index));
+            for (old, new) in remap.iter().enumerate() {
+                let new = match *new {
+                    Some(new) if new != old as u32 => new,
+                    _ => continue,
+                };
+                for &id in self.archetypes.archetypes[new as usize].ids() {
+                    self.entities.meta[id as usize].location.archetype = new;
+                }
+            }
+        }
+        for archetype in &mut self.archetypes.archetypes {
+            archetype.shrink_to_fit();
+        }
+        self.archetypes.shrink_to_fit();
+        self.bundle_to_archetype.shrink_to_fit();
+    }
+
+    /// Renumber entities so that live entities occupy the lowest IDs, returning the new handle of
+    /// each entity that moved, keyed by its old handle
+    ///
+    /// Despawned IDs are already reused before new ones are allocated, so this is only useful after
+    /// heavy churn has left live entities scattered across a large range of IDs, e.g. to keep an
+    /// [`EntityMap`] dense. The old handles of moved entities become invalid. [`Parent`] and
+    /// [`Children`] links, entities passed to [`despawn_later`](Self::despawn_later), and keys
+    /// indexed by [`upsert`](Self::upsert) are updated automatically; any other stored handles
+    /// must be updated using the returned map.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let entities = (0..4).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    /// world.despawn(entities[0]).unwrap();
+    /// world.despawn(entities[1]).unwrap();
+    /// let moved = world.compact_entities();
+    /// assert_eq!(moved.len(), 2);
+    /// assert!(!world.contains(entities[3]));
+    /// let e = moved.get(entities[3]).copied().unwrap();
+    /// assert!(e.id() < 2);
+    /// assert_eq!(*world.get::<i32>(e).unwrap(), 3);
+    /// ```
+    pub fn compact_entities(&mut self) -> EntityMap<Entity> {
+        self.flush();
+        let mut map = EntityMap::new();
+        for (old, new) in self.entities.compact() {
+            let loc = self.entities.meta[new.id as usize].location;
+            self.archetypes.archetypes[loc.archetype as usize]
+                .set_entity_id(loc.index as usize, new.id);
+            map.insert(old, new);
+        }
+        if map.is_empty() {
+            return map;
+        }
+        for (_, mut parent) in self.query_mut::<&mut Parent>() {
+            if let Some(&new) = map.get(parent.0) {
+                parent.0 = new;
+            }
+        }
+        for (_, mut children) in self.query_mut::<&mut Children>() {
+            if children.iter().any(|&x| map.contains(x)) {
+                for child in &mut children.0 {
+                    *child = map.get(*child).copied().unwrap_or(*child);
+                }
+            }
+        }
+        for entity in self.despawn_queue.take() {
+            self.despawn_queue
+                .push(map.get(entity).copied().unwrap_or(entity));
+        }
+        self.observers.remap(&map);
+        map
+    }
+
+    /// Deep-copy every entity and component into a new world, using `registry` to clone components
+    ///
+    /// Entity handles, including those that have been despawned or reserved, are equally valid in
+    /// the copy, and component tracking state such as [`Added`](crate::Added) and
+    /// [`ChangeTick`](crate::ChangeTick) is preserved. Useful for snapshotting a world, e.g. for
+    /// rollback. Observers, resources, and events are not copied.
+    ///
+    /// Fails if a component type isn't registered, unless the registry is configured to
+    /// [skip](CloneRegistry::skip_unregistered) such components. Panics if a component is uniquely
+    /// borrowed.
+    pub fn clone_with(&self, registry: &CloneRegistry) -> Result<World, MissingCloner> {
+        let mut types = Vec::new();
+        for archetype in &self.archetypes.archetypes {
+            for ty in archetype.types() {
+                if registry.get(ty.id()).is_none() && !registry.skips_unregistered() {
+                    return Err(MissingCloner(ty.id()));
+                }
+            }
+        }
+
+        let mut world = World::new();
+        world.entities = self.entities.clone();
+        let ticks = &world.archetypes.change_tick;
+        ticks.current.store(self.change_tick(), Ordering::Relaxed);
+        ticks
+            .last_run
+            .store(self.last_change_tick(), Ordering::Relaxed);
+        world.removed_components = self.removed_components.clone();
+
+        for src in &self.archetypes.archetypes {
+            if src.is_empty() {
+                continue;
+            }
+            types.clear();
+            types.extend(
+                src.types()
+                    .iter()
+                    .filter_map(|&ty| Some((ty, registry.get(ty.id())?))),
+            );
+            let mut borrow = SharedColumns {
+                archetype: src,
+                states: Vec::with_capacity(types.len()),
+            };
+            for (ty, _) in &types {
+                let state = src.get_state_by_id(&ty.id()).unwrap();
+                if !src.borrow_dynamic(state, false) {
+                    panic!("component {:?} already borrowed uniquely", ty.id());
+                }
+                borrow.states.push(state);
+            }
+            let ids = types.iter().map(|(ty, _)| ty.id()).collect::<Box<[_]>>();
+            let dst_id = world
+                .archetypes
+                .get(ids, || types.iter().map(|&(ty, _)| ty).collect());
+            let dst = &mut world.archetypes.archetypes[dst_id as usize];
+            dst.reserve(src.len());
+            for (src_index, &id) in src.ids().iter().enumerate() {
+                unsafe {
+                    // Fill the vacant slot first so a panicking clone can't leave it half-written
+                    let index = dst.len();
+                    for &(ty, clone) in &types {
+                        dst.clone_component(src, src_index as u32, index, &ty, clone);
+                    }
+                    dst.allocate(id);
+                }
+                world.entities.meta[id as usize].location = Location {
+                    archetype: dst_id,
+                    index: dst.len() - 1,
+                };
+            }
+            drop(borrow);
+        }
+        Ok(world)
+    }
+
+    /// Whether `entity` still exists
+    ///
+    /// Entities obtained from [`reserve_entity`](Self::reserve_entity) exist immediately, even
+    /// before they're [`flush`](Self::flush)ed.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(entity)
+    }
+
+    /// Efficiently iterate over all entities that have certain components, using dynamic borrow
+    /// checking
+    ///
+    /// Prefer [`query_mut`](Self::query_mut) when concurrent access to the [`World`] is not required.
+    ///
+    /// Calling `iter` on the returned value yields `(Entity, Q)` tuples, where `Q` is some query
+    /// type. A query type is any type for which an implementation of [`Query`] exists, e.g. `&T`,
+    /// `&mut T`, a tuple of query types, or an `Option` wrapping a query type, where `T` is any
+    /// component type. Components queried with `&mut` must only appear once. Entities which do not
+    /// have a component type referenced outside of an `Option` will be skipped.
+    ///
+    /// Entities are yielded in arbitrary order.
+    ///
+    /// The returned [`QueryBorrow`] can be further transformed with combinator methods; see its
+    /// documentation for details.
+    ///
+    /// Iterating a query will panic if it would violate an existing unique reference or construct
+    /// an invalid unique reference. This occurs when two simultaneously-active queries could expose
+    /// the same entity. Simultaneous queries can access the same component type if and only if the
+    /// world contains no entities that have all components required by both queries, assuming no
+    /// other component borrows are outstanding. Iterating a query that borrows the same component
+    /// uniquely twice, or both uniquely and shared, such as `(&mut T, &T)`, always panics.
+    ///
+    /// Iterating a query yields references with lifetimes bound to the [`QueryBorrow`] returned
+    /// here. To ensure those are invalidated, the return value of this method must be dropped for
+    /// its dynamic borrows from the world to be released. Similarly, lifetime rules ensure that
+    /// references obtained from a query cannot outlive the [`QueryBorrow`].
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123, true, "abc"));
+    /// let b = world.spawn((456, false));
+    /// let c = world.spawn((42, "def"));
+    /// let entities = world.query::<(&i32, &bool)>()
+    ///     .iter()
+    ///     .map(|(e, (&i, &b))| (e, i, b)) // Copy out of the world
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(entities.len(), 2);
+    /// assert!(entities.contains(&(a, 123, true)));
+    /// assert!(entities.contains(&(b, 456, false)));
+    /// ```
+    pub fn query<Q: Query>(&self) -> QueryBorrow<'_, Q> {
+        QueryBorrow::new(
+            &self.entities.meta,
+            &self.archetypes.archetypes,
+            &self.archetypes.components,
+        )
+    }
+
+    /// Query a uniquely borrowed world
+    ///
+    /// Like [`query`](Self::query), but faster because dynamic borrow checks can be skipped. Note
+    /// that, unlike [`query`](Self::query), this returns an `IntoIterator` which can be passed
     /// directly to a `for` loop.
+    ///
+    /// Panics if `Q` borrows the same component both uniquely and otherwise, e.g. `(&mut T, &T)`.
+    ///
+    /// Structural changes such as [`insert`](Self::insert) can't happen while the iterator is
+    /// alive, but entities can be collected during iteration and modified afterwards.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Dead;
+    /// let mut world = World::new();
+    /// let a = world.spawn((5,));
+    /// let b = world.spawn((1,));
+    /// let mut dying = Vec::new();
+    /// for (e, mut health) in world.query_mut::<&mut i32>() {
+    ///     *health -= 2;
+    ///     if *health <= 0 {
+    ///         dying.push(e);
+    ///     }
+    /// }
+    /// for e in dying {
+    ///     world.insert_one(e, Dead).unwrap();
+    /// }
+    /// assert!(world.get::<Dead>(a).is_err());
+    /// assert!(world.get::<Dead>(b).is_ok());
+    /// ```
     pub fn query_mut<Q: Query>(&mut self) -> QueryMut<'_, Q> {
-        QueryMut::new(&self.entities.meta, &mut self.archetypes.archetypes)
+        QueryMut::new(
+            &self.entities.meta,
+            &mut self.archetypes.archetypes,
+            &self.archetypes.components,
+        )
+    }
+
+    /// Query for several disjoint sets of components at once, without runtime borrow checking
+    ///
+    /// `Q` is a tuple of queries, and the result is a tuple of [`QueryMut`]s that can be iterated
+    /// simultaneously, e.g. to compute interactions between two sets of entities with nested loops.
+    /// Panics, naming the component, if one query borrows a component uniquely that another query
+    /// also borrows. Each query is otherwise subject to the same rules as
+    /// [`query_mut`](Self::query_mut).
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Position(f32);
+    /// struct Attraction(f32);
+    /// let mut world = World::new();
+    /// let a = world.spawn((Position(0.0), Attraction(0.0)));
+    /// world.spawn((Position(1.0),));
+    /// world.spawn((Position(3.0),));
+    /// let (attractors, positions) = world.query_many::<(&mut Attraction, &Position)>();
+    /// let positions = positions.into_iter().map(|(_, p)| p.0).collect::<Vec<_>>();
+    /// for (_, mut attraction) in attractors {
+    ///     attraction.0 = positions.iter().sum();
+    /// }
+    /// assert_eq!(world.get::<Attraction>(a).unwrap().0, 4.0);
+    /// ```
+    pub fn query_many<'q, Q: QueryMany<'q>>(&'q mut self) -> Q::Output {
+        assert_disjoint::<Q>();
+        // Safety: `&mut self` rules out outside borrows, and the queries are disjoint
+        unsafe { Q::new(self) }
+    }
+
+    /// Borrow every archetype matching `Q` for random access by [`Entity`]
+    ///
+    /// Unlike [`query_one`](Self::query_one), borrows are taken once up front, making repeated
+    /// lookups cheap. Useful for following references between entities. Panics if `Q` conflicts with
+    /// an outstanding borrow. See [`View`].
+    pub fn view<Q: Query>(&self) -> View<'_, Q> {
+        View::new(&self.entities.meta, &self.archetypes.archetypes)
     }
 
     pub(crate) fn memo(&self) -> (u64, u64) {
@@ -422,132 +1445,609 @@ impl World {
         &self.archetypes.archetypes
     }
 
-    /// Prepare a query against a single entity, using dynamic borrow checking
+    pub(crate) fn component_registry(&self) -> &ComponentRegistry {
+        &self.archetypes.components
+    }
+
+    /// Prepare a query against a single entity, using dynamic borrow checking
+    ///
+    /// Prefer [`query_one_mut`](Self::query_one_mut) when concurrent access to the [`World`] is not
+    /// required.
+    ///
+    /// Call [`get`](QueryOne::get) on the resulting [`QueryOne`] to actually execute the query. The
+    /// [`QueryOne`] value is responsible for releasing the dynamically-checked borrow made by
+    /// `get`, so it can't be dropped while references returned by `get` are live.
+    ///
+    /// Handy for accessing multiple components simultaneously.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123, true, "abc"));
+    /// // The returned query must outlive the borrow made by `get`
+    /// let mut query = world.query_one::<(&mut i32, &bool)>(a).unwrap();
+    /// let (mut number, flag) = query.get().unwrap();
+    /// if *flag { *number *= 2; }
+    /// assert_eq!(*number, 246);
+    /// ```
+    pub fn query_one<Q: Query>(&self, entity: Entity) -> Result<QueryOne<'_, Q>, NoSuchEntity> {
+        let loc = self.entities.get(entity)?;
+        Ok(unsafe {
+            QueryOne::new(
+                &self.archetypes.archetypes[loc.archetype as usize],
+                loc.index,
+            )
+        })
+    }
+
+    /// Query a single entity in a uniquely borrow world
+    ///
+    /// Like [`query_one`](Self::query_one), but faster because dynamic borrow checks can be
+    /// skipped. Note that, unlike [`query_one`](Self::query_one), on success this returns the
+    /// query's results directly.
+    ///
+    /// Fails with [`QueryOneError::NoSuchEntity`] if `entity` was despawned, or
+    /// [`QueryOneError::Unsatisfied`] if it exists but doesn't match `Q`. As with any other query,
+    /// `&mut T` items only report the component as [`Mutated`](crate::Mutated) once they're
+    /// actually written through.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123, true));
+    /// let b = world.spawn((456,));
+    /// let (mut number, flag) = world.query_one_mut::<(&mut i32, &bool)>(a).unwrap();
+    /// if *flag { *number *= 2; }
+    /// assert_eq!(*world.get::<i32>(a).unwrap(), 246);
+    /// assert_eq!(
+    ///     world.query_one_mut::<(&mut i32, &bool)>(b).err(),
+    ///     Some(QueryOneError::Unsatisfied)
+    /// );
+    /// world.despawn(b).unwrap();
+    /// assert_eq!(world.query_one_mut::<&i32>(b).err(), Some(QueryOneError::NoSuchEntity));
+    /// ```
+    pub fn query_one_mut<Q: Query>(
+        &mut self,
+        entity: Entity,
+    ) -> Result<QueryItem<'_, Q>, QueryOneError> {
+        assert_borrow::<Q>();
+        let loc = self.entities.get(entity)?;
+        unsafe {
+            let archetype = &self.archetypes.archetypes[loc.archetype as usize];
+            let state = Q::Fetch::prepare(archetype).ok_or(QueryOneError::Unsatisfied)?;
+            let fetch = Q::Fetch::execute(archetype, state);
+            if !fetch.filter(loc.index as usize) {
+                return Err(QueryOneError::Unsatisfied);
+            }
+            Ok(fetch.get(loc.index as usize))
+        }
+    }
+
+    /// Borrow the `T` component of `entity`
+    ///
+    /// Fails with [`ComponentError::AlreadyBorrowed`] rather than panicking if the component is
+    /// already uniquely borrowed, whether from `entity` or another entity with the same components.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123,));
+    /// let b = world.spawn(("abc",));
+    /// assert_eq!(*world.get::<i32>(a).unwrap(), 123);
+    /// match world.get::<i32>(b) {
+    ///     Err(ComponentError::MissingComponent(_)) => {}
+    ///     _ => unreachable!(),
+    /// }
+    /// let _unique = world.get_mut::<i32>(a).unwrap();
+    /// assert_eq!(
+    ///     world.get::<i32>(a).err(),
+    ///     Some(ComponentError::AlreadyBorrowed(std::any::type_name::<i32>()))
+    /// );
+    /// ```
+    pub fn get<T: Component>(&self, entity: Entity) -> Result<Ref<'_, T>, ComponentError> {
+        let loc = self.entities.get(entity)?;
+        unsafe {
+            Ref::new(
+                &self.archetypes.archetypes[loc.archetype as usize],
+                loc.index,
+            )
+        }
+    }
+
+    /// Uniquely borrow the `T` component of `entity`
+    ///
+    /// Fails with [`ComponentError::AlreadyBorrowed`] rather than panicking if the component is
+    /// already borrowed, whether from `entity` or another entity with the same components.
+    pub fn get_mut<T: Component>(&self, entity: Entity) -> Result<RefMut<'_, T>, ComponentError> {
+        let loc = self.entities.get(entity)?;
+        unsafe {
+            RefMut::new(
+                &self.archetypes.archetypes[loc.archetype as usize],
+                loc.index,
+            )
+        }
+    }
+
+    /// Uniquely borrow the `T` component of `entity` without recording a change
+    ///
+    /// Unlike [`get_mut`](Self::get_mut), writes through the result are deliberately invisible to
+    /// [`Mutated`](crate::Mutated), [`Changed`](crate::Changed), [`ChangeTick`](crate::ChangeTick),
+    /// and similar queries, breaking the usual guarantee that every unique access is reported.
+    /// Useful for writes that aren't meaningful changes, such as initialization or interpolation
+    /// for rendering, that would otherwise trigger systems watching for changes. Changes recorded
+    /// by other accesses, before or after, are unaffected.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((1.0f32,));
+    /// world.clear_trackers();
+    /// *world.get_mut_untracked::<f32>(e).unwrap() = 1.5;
+    /// assert_eq!(*world.get::<f32>(e).unwrap(), 1.5);
+    /// assert!(!world.query_one::<Mutated<f32>>(e).unwrap().get().unwrap());
+    /// ```
+    pub fn get_mut_untracked<T: Component>(
+        &mut self,
+        entity: Entity,
+    ) -> Result<&mut T, ComponentError> {
+        let loc = self.entities.get(entity)?;
+        let archetype = &self.archetypes.archetypes[loc.archetype as usize];
+        let state = archetype
+            .get_state::<T>()
+            .ok_or_else(MissingComponent::new::<T>)?;
+        // `&mut self` guarantees no outstanding borrows
+        unsafe {
+            Ok(&mut *archetype
+                .get_base::<T>(state)
+                .as_ptr()
+                .add(loc.index as usize))
+        }
+    }
+
+    /// Uniquely borrow the `T` components of several distinct entities at once
+    ///
+    /// Fails if any entity doesn't exist or lacks a `T` component. Every component is reported
+    /// as [mutated](crate::Mutated) on success, whether or not it's actually written. See
+    /// [`View::get_many_mut`] for arbitrary queries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any entity appears more than once in `entities`.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct Velocity(f32);
+    /// let mut world = World::new();
+    /// let a = world.spawn((Velocity(1.0),));
+    /// let b = world.spawn((Velocity(-3.0),));
+    /// let [x, y] = world.get_many_mut::<Velocity, 2>([a, b]).unwrap();
+    /// core::mem::swap(x, y);
+    /// assert_eq!(world.get::<Velocity>(a).unwrap().0, -3.0);
+    /// ```
+    pub fn get_many_mut<T: Component, const N: usize>(
+        &mut self,
+        entities: [Entity; N],
+    ) -> Result<[&mut T; N], ComponentError> {
+        for (i, a) in entities.iter().enumerate() {
+            for b in &entities[..i] {
+                assert!(a != b, "entity {:?} requested more than once", a);
+            }
+        }
+        let mut locations = [(0, 0, 0); N];
+        for (location, &entity) in locations.iter_mut().zip(&entities) {
+            *location = self.locate_component::<T>(entity)?;
+        }
+        let archetypes = &self.archetypes.archetypes;
+        // `&mut self` guarantees no outstanding borrows, and entities are distinct, so the results
+        // never alias
+        Ok(locations.map(|(archetype, state, index)| unsafe {
+            let archetype = &archetypes[archetype as usize];
+            archetype.mark_changed(state);
+            *archetype.get_mutated(state).as_ptr().add(index) = true;
+            *archetype.get_change_ticks(state).as_ptr().add(index) =
+                next_tick(archetype.change_tick());
+            &mut *archetype.get_base::<T>(state).as_ptr().add(index)
+        }))
+    }
+
+    /// Access an entity regardless of its component types
+    ///
+    /// Does not immediately borrow any component. Fails if `entity` has been despawned, even if its
+    /// ID has since been reused by a newer entity.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123,));
+    /// world.despawn(a).unwrap();
+    /// let b = world.spawn((456,));
+    /// assert_eq!(a.id(), b.id());
+    /// assert_ne!(a.generation(), b.generation());
+    /// assert!(world.entity(a).is_err());
+    /// assert_eq!(*world.entity(b).unwrap().get::<i32>().unwrap(), 456);
+    /// ```
+    pub fn entity(&self, entity: Entity) -> Result<EntityRef<'_>, NoSuchEntity> {
+        let loc = self.entities.get(entity)?;
+        unsafe {
+            Ok(EntityRef::new(
+                &self.archetypes.archetypes[loc.archetype as usize],
+                entity,
+                loc.index,
+            ))
+        }
+    }
+
+    /// Given an id obtained from [`Entity::id`], reconstruct the still-live [`Entity`].
+    ///
+    /// # Safety
+    ///
+    /// `id` must correspond to a currently live [`Entity`]. A despawned or never-allocated `id`
+    /// will produce undefined behavior.
+    pub unsafe fn find_entity_from_id(&self, id: u32) -> Entity {
+        self.entities.resolve_unknown_gen(id)
+    }
+
+    /// Given an id obtained from [`Entity::id`], find the live [`Entity`] currently using it
+    ///
+    /// Returns `None` if no entity is using `id`, e.g. because the entity it was taken from has been
+    /// despawned. If `id` has been reused since it was obtained, the new entity is returned. Reserved
+    /// entities are not found until the world is [`flush`](Self::flush)ed.
+    ///
+    /// Useful for recovering full handles from data structures that store only ids.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123,));
+    /// assert_eq!(world.resolve_index(a.id()), Some(a));
+    /// world.despawn(a).unwrap();
+    /// assert_eq!(world.resolve_index(a.id()), None);
+    /// ```
+    pub fn resolve_index(&self, id: u32) -> Option<Entity> {
+        self.entities.resolve(id)
+    }
+
+    /// Iterate over the handles of all live entities, in order of [`Entity::id`]
+    ///
+    /// Unlike [`iter`](Self::iter), the order doesn't depend on which components entities have or
+    /// how they've moved between archetypes, so two worlds containing the same entities yield them
+    /// identically. Useful for building deterministic snapshots. Reserved entities are not included
+    /// until the world is [`flush`](Self::flush)ed.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123,));
+    /// let b = world.spawn((true,));
+    /// let c = world.spawn((456,));
+    /// world.despawn(b).unwrap();
+    /// let d = world.reserve_entity();
+    /// assert_eq!(world.entities().collect::<Vec<_>>(), [a, c]);
+    /// world.flush();
+    /// assert_eq!(world.entities().collect::<Vec<_>>(), [a, d, c]);
+    /// ```
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        (0..self.entities.meta.len() as u32).filter_map(move |id| self.entities.resolve(id))
+    }
+
+    /// Iterate over all entities in the world
+    ///
+    /// Entities are yielded archetype by archetype, in the order they're stored within each, so the
+    /// order is consistent until the world is next modified. Reserved entities are not included
+    /// until the world is [`flush`](Self::flush)ed. Also available by iterating over `&World`.
+    /// Prefer [`query`](Self::query) for better performance when components will be accessed in
+    /// predictable patterns.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn(());
+    /// let b = world.spawn(());
+    /// let ids = world.iter().map(|entity_ref| entity_ref.entity()).collect::<Vec<_>>();
+    /// assert_eq!(ids.len(), 2);
+    /// assert!(ids.contains(&a));
+    /// assert!(ids.contains(&b));
+    /// ```
+    ///
+    /// Components can be probed without knowing an entity's exact type, e.g. for serialization:
+    /// ```
+    /// # use hecs::*;
+    /// # use std::any::TypeId;
+    /// let mut world = World::new();
+    /// world.spawn((123, "abc"));
+    /// world.spawn((true,));
+    /// for entity in world.iter() {
+    ///     if let Some(x) = entity.get::<i32>() {
+    ///         assert_eq!(*x, 123);
+    ///         assert!(entity.component_types().any(|ty| ty == TypeId::of::<&str>()));
+    ///     } else {
+    ///         assert!(entity.has::<bool>());
+    ///     }
+    /// }
+    /// ```
+    pub fn iter(&self) -> Iter<'_> {
+        Iter::new(&self.archetypes.archetypes, &self.entities)
+    }
+
+    /// Iterate over all entities in the world with exclusive access to their components
+    ///
+    /// Entities are yielded in the same order as [`iter`](Self::iter). Because the world is
+    /// borrowed uniquely, components are accessed without runtime borrow checking. Also available
+    /// by iterating over `&mut World`.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1, true));
+    /// let b = world.spawn((2,));
+    /// for mut entity in &mut world {
+    ///     if entity.has::<bool>() {
+    ///         *entity.get_mut::<i32>().unwrap() *= 10;
+    ///     }
+    /// }
+    /// assert_eq!(*world.get::<i32>(a).unwrap(), 10);
+    /// assert_eq!(*world.get::<i32>(b).unwrap(), 2);
+    /// ```
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut { inner: self.iter() }
+    }
+
+    /// Entities which have had a `C` component removed since the last call to
+    /// [`clear_trackers`](Self::clear_trackers)
+    ///
+    /// A component counts as removed when it is taken off with [`remove`](Self::remove) or
+    /// [`remove_one`](Self::remove_one), or when its entity is despawned, cleared, or replaced by
+    /// [`spawn_at`](Self::spawn_at). Moving an entity between archetypes to add other components
+    /// does not count. Entities may appear more than once, and may no longer be live.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123, true));
+    /// let b = world.spawn((456,));
+    /// world.remove_one::<bool>(a).unwrap();
+    /// world.despawn(b).unwrap();
+    /// assert_eq!(world.removed::<bool>(), &[a]);
+    /// assert_eq!(world.removed::<i32>(), &[b]);
+    /// world.clear_trackers();
+    /// assert!(world.removed::<i32>().is_empty());
+    /// ```
+    pub fn removed<C: Component>(&self) -> &[Entity] {
+        self.removed_components
+            .get(&TypeId::of::<C>())
+            .map_or(&[], |entities| entities.as_slice())
+    }
+
+    /// Call `f` whenever a `T` component is added to an entity
     ///
-    /// Prefer [`query_one_mut`](Self::query_one_mut) when concurrent access to the [`World`] is not
-    /// required.
+    /// Fires when an entity is spawned with a `T`, and when a `T` is inserted, including when it
+    /// replaces an existing `T`. `f` is called as soon as the entity is complete, while the world is
+    /// still mutably borrowed, so it can't access the world itself; record any follow-up work in
+    /// e.g. a [`CommandBuffer`](crate::CommandBuffer) instead.
     ///
-    /// Call [`get`](QueryOne::get) on the resulting [`QueryOne`] to actually execute the query. The
-    /// [`QueryOne`] value is responsible for releasing the dynamically-checked borrow made by
-    /// `get`, so it can't be dropped while references returned by `get` are live.
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// # use std::sync::{Arc, Mutex};
+    /// let mut world = World::new();
+    /// let added = Arc::new(Mutex::new(Vec::new()));
+    /// let log = added.clone();
+    /// world.on_add::<i32>(move |entity, &x| log.lock().unwrap().push((entity, x)));
+    /// let a = world.spawn((123, true));
+    /// let b = world.spawn((true,));
+    /// world.insert_one(b, 456).unwrap();
+    /// assert_eq!(*added.lock().unwrap(), [(a, 123), (b, 456)]);
+    /// ```
+    pub fn on_add<T: Component>(&mut self, f: impl FnMut(Entity, &T) + Send + Sync + 'static) {
+        self.observers.on_add(f);
+    }
+
+    /// Call `f` whenever a `T` component is about to be removed from an entity
     ///
-    /// Handy for accessing multiple components simultaneously.
+    /// Fires when a `T` is removed or replaced, and when an entity with a `T` is despawned or
+    /// cleared. `f` is called before the world is modified, so the component is still intact, and
+    /// if `f` panics the entity is left as it was. Like [`on_add`](Self::on_add), `f` can't access
+    /// the world.
     ///
     /// # Example
     /// ```
     /// # use hecs::*;
+    /// # use std::sync::{Arc, Mutex};
     /// let mut world = World::new();
-    /// let a = world.spawn((123, true, "abc"));
-    /// // The returned query must outlive the borrow made by `get`
-    /// let mut query = world.query_one::<(&mut i32, &bool)>(a).unwrap();
-    /// let (mut number, flag) = query.get().unwrap();
-    /// if *flag { *number *= 2; }
-    /// assert_eq!(*number, 246);
+    /// let removed = Arc::new(Mutex::new(Vec::new()));
+    /// let log = removed.clone();
+    /// world.on_remove::<i32>(move |entity, &x| log.lock().unwrap().push((entity, x)));
+    /// let a = world.spawn((123, true));
+    /// let b = world.spawn((456,));
+    /// world.remove_one::<i32>(a).unwrap();
+    /// world.despawn(b).unwrap();
+    /// assert_eq!(*removed.lock().unwrap(), [(a, 123), (b, 456)]);
     /// ```
-    pub fn query_one<Q: Query>(&self, entity: Entity) -> Result<QueryOne<'_, Q>, NoSuchEntity> {
-        let loc = self.entities.get(entity)?;
-        Ok(unsafe {
-            QueryOne::new(
-                &self.archetypes.archetypes[loc.archetype as usize],
-                loc.index,
-            )
-        })
+    pub fn on_remove<T: Component>(&mut self, f: impl FnMut(Entity, &T) + Send + Sync + 'static) {
+        self.observers.on_remove(f);
     }
 
-    /// Query a single entity in a uniquely borrow world
+    /// Add `components` to the entity whose `K` component equals `key`, spawning it if none exists
     ///
-    /// Like [`query_one`](Self::query_one), but faster because dynamic borrow checks can be
-    /// skipped. Note that, unlike [`query_one`](Self::query_one), on success this returns the
-    /// query's results directly.
-    pub fn query_one_mut<Q: Query>(
+    /// Entities created this way are given `key` as a component, and are tracked in an index that
+    /// is updated as their `K` components are removed, including by despawning and
+    /// [`clear`](Self::clear), so stale keys don't accumulate. Useful for reconciling with external
+    /// state keyed by unique ids, e.g. when loading scenes or applying network updates.
+    ///
+    /// Only entities created by `upsert` are found, and only while their `K` component still
+    /// equals the key they were created with. Returns the affected entity.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// #[derive(Clone, PartialEq, Eq, Hash)]
+    /// struct Id(u64);
+    /// let mut world = World::new();
+    /// let a = world.upsert(Id(7), (10, "abc"));
+    /// assert_eq!(world.upsert(Id(7), (20,)), a);
+    /// assert_eq!(*world.get::<i32>(a).unwrap(), 20);
+    /// assert_eq!(*world.get::<&str>(a).unwrap(), "abc");
+    /// world.despawn(a).unwrap();
+    /// assert_eq!(world.find_by_key(&Id(7)), None);
+    /// assert_ne!(world.upsert(Id(7), (30,)), a);
+    /// ```
+    pub fn upsert<K: Component + Clone + Eq + Hash>(
         &mut self,
-        entity: Entity,
-    ) -> Result<QueryItem<'_, Q>, QueryOneError> {
-        let loc = self.entities.get(entity)?;
-        unsafe {
-            let archetype = &self.archetypes.archetypes[loc.archetype as usize];
-            let state = Q::Fetch::prepare(archetype).ok_or(QueryOneError::Unsatisfied)?;
-            let fetch = Q::Fetch::execute(archetype, state);
-            Ok(fetch.get(loc.index as usize))
+        key: K,
+        components: impl DynamicBundle,
+    ) -> Entity {
+        if let Some(entity) = self.find_by_key(&key) {
+            if !components.with_ids(|ids| ids.contains(&TypeId::of::<K>())) {
+                self.insert(entity, components)
+                    .expect("indexed entities are live");
+                return entity;
+            }
+            let mut builder = EntityBuilder::new();
+            builder.add_bundle(components).add(key.clone());
+            self.insert(entity, builder.build())
+                .expect("indexed entities are live");
+            // Replacing the `K` component dropped the entity from the index
+            self.observers.index::<K>().insert(key, entity);
+            return entity;
         }
+        let mut builder = EntityBuilder::new();
+        builder.add_bundle(components).add(key.clone());
+        let entity = self.spawn(builder.build());
+        self.observers.index::<K>().insert(key, entity);
+        entity
     }
 
-    /// Borrow the `T` component of `entity`
+    /// Find the entity created by [`upsert`](Self::upsert) with `key`, if it still exists
     ///
-    /// Panics if the component is already uniquely borrowed from another entity with the same
-    /// components.
-    pub fn get<T: Component>(&self, entity: Entity) -> Result<Ref<'_, T>, ComponentError> {
-        Ok(self
-            .entity(entity)?
-            .get()
-            .ok_or_else(MissingComponent::new::<T>)?)
+    /// Panics if `K` components are uniquely borrowed.
+    pub fn find_by_key<K: Component + Eq + Hash>(&self, key: &K) -> Option<Entity> {
+        let entity = *self.observers.get_index::<K>()?.get(key)?;
+        // The component may have been modified since it was indexed
+        let current = self.entity(entity).ok()?.get::<K>()?;
+        (*current == *key).then_some(entity)
     }
 
-    /// Uniquely borrow the `T` component of `entity`
+    /// Store `value` as the world's `T` resource, returning the previous one, if any
     ///
-    /// Panics if the component is already borrowed from another entity with the same components.
-    pub fn get_mut<T: Component>(&self, entity: Entity) -> Result<RefMut<'_, T>, ComponentError> {
-        Ok(self
-            .entity(entity)?
-            .get_mut()
-            .ok_or_else(MissingComponent::new::<T>)?)
+    /// Resources are singletons keyed by type, such as a frame's delta time, that live alongside
+    /// the world's entities without being attached to any of them. They aren't visible to queries.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// struct DeltaTime(f32);
+    /// let mut world = World::new();
+    /// assert!(world.insert_resource(DeltaTime(0.5)).is_none());
+    /// world.get_resource_mut::<DeltaTime>().unwrap().0 = 0.25;
+    /// assert_eq!(world.get_resource::<DeltaTime>().unwrap().0, 0.25);
+    /// assert_eq!(world.remove_resource::<DeltaTime>().unwrap().0, 0.25);
+    /// assert!(world.get_resource::<DeltaTime>().is_none());
+    /// ```
+    pub fn insert_resource<T: Component>(&mut self, value: T) -> Option<T> {
+        self.resources.insert(value)
     }
 
-    /// Access an entity regardless of its component types
-    ///
-    /// Does not immediately borrow any component.
-    pub fn entity(&self, entity: Entity) -> Result<EntityRef<'_>, NoSuchEntity> {
-        let loc = self.entities.get(entity)?;
-        unsafe {
-            Ok(EntityRef::new(
-                &self.archetypes.archetypes[loc.archetype as usize],
-                entity,
-                loc.index,
-            ))
-        }
+    /// Remove and return the `T` resource, if any
+    pub fn remove_resource<T: Component>(&mut self) -> Option<T> {
+        self.resources.remove()
     }
 
-    /// Given an id obtained from [`Entity::id`], reconstruct the still-live [`Entity`].
+    /// Whether a `T` resource is present
+    pub fn contains_resource<T: Component>(&self) -> bool {
+        self.resources.contains::<T>()
+    }
+
+    /// Borrow the `T` resource, if any
     ///
-    /// # Safety
+    /// Panics if the resource is already uniquely borrowed.
+    pub fn get_resource<T: Component>(&self) -> Option<ResourceRef<'_, T>> {
+        self.resources.get()
+    }
+
+    /// Uniquely borrow the `T` resource, if any
     ///
-    /// `id` must correspond to a currently live [`Entity`]. A despawned or never-allocated `id`
-    /// will produce undefined behavior.
-    pub unsafe fn find_entity_from_id(&self, id: u32) -> Entity {
-        self.entities.resolve_unknown_gen(id)
+    /// Panics if the resource is already borrowed.
+    pub fn get_resource_mut<T: Component>(&self) -> Option<ResourceRefMut<'_, T>> {
+        self.resources.get_mut()
     }
 
-    /// Iterate over all entities in the world
+    /// Queue `event` to be received by [`drain_events`](Self::drain_events)
     ///
-    /// Entities are yielded in arbitrary order. Prefer [`query`](Self::query) for better
-    /// performance when components will be accessed in predictable patterns.
+    /// Events are values of any type passed between systems without spawning entities for them.
+    /// Each is received once, by the first `drain_events::<E>` call after it's sent. Events that
+    /// are never drained are dropped by the second call to
+    /// [`update_events`](Self::update_events) after they were sent, so a consumer that runs
+    /// before a producer in the same frame still sees them in the next frame.
     ///
     /// # Example
     /// ```
     /// # use hecs::*;
+    /// struct Damage { target: Entity, amount: i32 }
+    ///
     /// let mut world = World::new();
-    /// let a = world.spawn(());
-    /// let b = world.spawn(());
-    /// let ids = world.iter().map(|entity_ref| entity_ref.entity()).collect::<Vec<_>>();
-    /// assert_eq!(ids.len(), 2);
-    /// assert!(ids.contains(&a));
-    /// assert!(ids.contains(&b));
+    /// let target = world.spawn((100,));
+    /// world.send_event(Damage { target, amount: 10 });
+    /// world.send_event(Damage { target, amount: 5 });
+    /// let damage = world.drain_events::<Damage>().collect::<Vec<_>>();
+    /// for event in &damage {
+    ///     *world.get_mut::<i32>(event.target).unwrap() -= event.amount;
+    /// }
+    /// assert_eq!(*world.get::<i32>(target).unwrap(), 85);
+    /// assert_eq!(world.drain_events::<Damage>().count(), 0);
     /// ```
-    pub fn iter(&self) -> Iter<'_> {
-        Iter::new(&self.archetypes.archetypes, &self.entities)
+    pub fn send_event<E: Send + Sync + 'static>(&mut self, event: E) {
+        self.events.send(event);
     }
 
-    #[allow(missing_docs)]
-    pub fn removed<C: Component>(&self) -> &[Entity] {
-        self.removed_components
-            .get(&TypeId::of::<C>())
-            .map_or(&[], |entities| entities.as_slice())
+    /// Remove and return every queued `E` event, in the order they were sent
+    ///
+    /// Events not yet yielded when the iterator is dropped are discarded.
+    pub fn drain_events<E: Send + Sync + 'static>(&mut self) -> impl Iterator<Item = E> + '_ {
+        self.events.drain()
+    }
+
+    /// Advance event queues to a new frame, dropping events that weren't drained since the
+    /// previous call
+    ///
+    /// Typically called once per frame, e.g. at the end. Because events survive one call, every
+    /// event is seen by a consumer that drains once per frame, whether it runs before or after the
+    /// event's producer.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.send_event("spawned");
+    /// world.update_events();
+    /// assert_eq!(world.drain_events::<&str>().collect::<Vec<_>>(), ["spawned"]);
+    /// world.send_event("unseen");
+    /// world.update_events();
+    /// world.update_events();
+    /// assert_eq!(world.drain_events::<&str>().count(), 0);
+    /// ```
+    pub fn update_events(&mut self) {
+        self.events.update();
     }
 
     /// Add `components` to `entity`
     ///
-    /// Computational cost is proportional to the number of components `entity` has. If an entity
-    /// already has a component of a certain type, it is dropped and replaced.
+    /// If `entity` already has a component of a certain type, it is overwritten in place: the old
+    /// value is dropped, and the new one is reported as [`Mutated`](crate::Mutated) rather than
+    /// [`Added`](crate::Added). If every component is overwritten, `entity` stays in its
+    /// archetype and the cost is proportional only to the number of components inserted.
+    /// Otherwise, `entity` migrates to a new archetype, at a cost proportional to the number of
+    /// components it has. The returned [`Insertion`] reports which occurred.
     ///
     /// When inserting a single component, see [`insert_one`](Self::insert_one) for convenience.
     ///
@@ -556,15 +2056,18 @@ impl World {
     /// # use hecs::*;
     /// let mut world = World::new();
     /// let e = world.spawn((123, "abc"));
-    /// world.insert(e, (456, true));
+    /// let insertion = world.insert(e, (456, true)).unwrap();
+    /// assert_eq!((insertion.added(), insertion.overwritten()), (1, 1));
+    /// assert!(insertion.migrated());
     /// assert_eq!(*world.get::<i32>(e).unwrap(), 456);
     /// assert_eq!(*world.get::<bool>(e).unwrap(), true);
+    /// assert!(!world.insert(e, (789,)).unwrap().migrated());
     /// ```
     pub fn insert(
         &mut self,
         entity: Entity,
         components: impl DynamicBundle,
-    ) -> Result<(), NoSuchEntity> {
+    ) -> Result<Insertion, NoSuchEntity> {
         self.flush();
         let loc = self.entities.get_mut(entity)?;
 
@@ -589,6 +2092,16 @@ impl World {
             },
         };
 
+        // Notify observers before anything changes, in case a callback panics
+        let source_arch = &self.archetypes.archetypes[loc.archetype as usize];
+        for &ty in &target.replaced {
+            self.observers
+                .removed_one(source_arch, loc.index, entity, ty.id());
+        }
+        // Observers are notified of additions once the entity is complete
+        let mut added = Vec::new();
+        let observes_addition = self.observers.observes_addition();
+
         unsafe {
             // Drop the components we're overwriting
             let source_arch = &mut self.archetypes.archetypes[loc.archetype as usize];
@@ -602,10 +2115,19 @@ impl World {
             if target.index == loc.archetype {
                 // Update components in the current archetype
                 let arch = &mut self.archetypes.archetypes[loc.archetype as usize];
+                let tick = next_tick(arch.change_tick());
+                let mut insertion = Insertion::default();
                 components.put(|ptr, ty| {
-                    arch.put_dynamic(ptr, ty.id(), ty.layout().size(), loc.index, false, true);
+                    arch.put_dynamic(ptr, ty.id(), loc.index, false, true, tick);
+                    if observes_addition {
+                        added.push(ty.id());
+                    }
+                    insertion.overwritten += 1;
                 });
-                return Ok(());
+                for ty in added {
+                    self.observers.added_one(arch, loc.index, entity, ty);
+                }
+                return Ok(insertion);
             }
 
             let (source_arch, target_arch) = index2(
@@ -619,61 +2141,321 @@ impl World {
             loc.archetype = target.index;
             let old_index = mem::replace(&mut loc.index, target_index);
 
+            // Move the existing components, including the bytes of any that were just dropped,
+            // which are overwritten below
+            if let Some(moved) =
+                source_arch.migrate(old_index, target_arch, target_index, |_, _| {
+                    unreachable!("insertion removed a component")
+                })
+            {
+                self.entities.meta[moved as usize].location.index = old_index;
+            }
+
             // Move the new components
+            let tick = next_tick(target_arch.change_tick());
+            let mut insertion = Insertion::default();
             components.put(|ptr, ty| {
                 let had_component = source_arch.has_dynamic(ty.id());
+                if had_component {
+                    insertion.overwritten += 1;
+                } else {
+                    insertion.added += 1;
+                }
                 target_arch.put_dynamic(
                     ptr,
                     ty.id(),
-                    ty.layout().size(),
                     target_index,
                     !had_component,
                     had_component,
+                    tick,
                 );
+                if observes_addition {
+                    added.push(ty.id());
+                }
             });
-
-            // Move the components we're keeping
-            for &ty in &target.retained {
-                let src = source_arch
-                    .get_dynamic(ty.id(), ty.layout().size(), old_index)
-                    .unwrap();
-                target_arch.put_dynamic(
-                    src.as_ptr(),
-                    ty.id(),
-                    ty.layout().size(),
-                    target_index,
-                    false,
-                    false,
-                )
+            for ty in added {
+                self.observers
+                    .added_one(target_arch, target_index, entity, ty);
             }
 
-            // Free storage in the old archetype
-            if let Some(moved) = source_arch.remove(old_index, false) {
-                self.entities.meta[moved as usize].location.index = old_index;
-            }
+            Ok(insertion)
         }
-        Ok(())
     }
 
     /// Add `component` to `entity`
     ///
-    /// See [`insert`](Self::insert).
+    /// If `entity` already had a component of the same type, it is overwritten in place and
+    /// reported as [`Mutated`](crate::Mutated); otherwise it's reported as
+    /// [`Added`](crate::Added). See [`insert`](Self::insert).
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((123,));
+    /// assert!(world.insert_one(e, "abc").unwrap().migrated());
+    /// assert_eq!(world.insert_one(e, "def").unwrap().overwritten(), 1);
+    /// assert_eq!(*world.get::<&str>(e).unwrap(), "def");
+    /// ```
     pub fn insert_one(
         &mut self,
         entity: Entity,
         component: impl Component,
-    ) -> Result<(), NoSuchEntity> {
+    ) -> Result<Insertion, NoSuchEntity> {
         self.insert(entity, (component,))
     }
 
+    /// Add a clone of `component` to each of `entities`
+    ///
+    /// Equivalent to calling [`insert_one`](Self::insert_one) for each entity, but entities are
+    /// grouped by archetype so that the destination archetype is looked up, and its storage
+    /// reserved, only once per group. Entities that already have a `T` have it overwritten in place
+    /// without migrating, reported as [`Mutated`](crate::Mutated). Entities that don't exist, and
+    /// repeats, are skipped. Returns the number of entities that received the component.
+    ///
+    /// Every clone is made before any entity is modified, so a panicking `clone` leaves the world
+    /// untouched. As with `insert`, observers are notified of removals before anything changes and
+    /// of additions once every entity is complete.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// #[derive(Clone)]
+    /// struct Selected;
+    ///
+    /// let mut world = World::new();
+    /// let entities = (0..100).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    /// assert_eq!(world.insert_batch(&entities[..10], Selected), 10);
+    /// assert_eq!(world.query::<&Selected>().iter().count(), 10);
+    /// world.despawn(entities[0]).unwrap();
+    /// assert_eq!(world.insert_batch(&entities[..20], Selected), 19);
+    /// assert_eq!(world.query::<&Selected>().iter().count(), 19);
+    /// ```
+    pub fn insert_batch<T: Component + Clone>(&mut self, entities: &[Entity], component: T) -> u32 {
+        self.flush();
+        let bundle = (component,);
+        let mut batch = entities
+            .iter()
+            .filter_map(|&entity| Some((self.entities.get(entity).ok()?, entity)))
+            .collect::<Vec<_>>();
+        // Migrating in descending index order guarantees that the entity moved into each gap isn't
+        // itself awaiting migration
+        batch.sort_unstable_by(|(x, _), (y, _)| {
+            x.archetype
+                .cmp(&y.archetype)
+                .then(x.index.cmp(&y.index).reverse())
+        });
+        batch.dedup_by_key(|&mut (_, entity)| entity);
+
+        // Clone every value before anything changes, in case `clone` panics
+        let mut values = batch
+            .iter()
+            .map(|_| bundle.0.clone())
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        // Notify observers before anything changes, in case a callback panics
+        let ty = TypeId::of::<T>();
+        if self.observers.observes_removal() {
+            for &(loc, entity) in &batch {
+                let arch = &self.archetypes.archetypes[loc.archetype as usize];
+                if arch.has_dynamic(ty) {
+                    self.observers.removed_one(arch, loc.index, entity, ty);
+                }
+            }
+        }
+
+        let mut rest = &batch[..];
+        while let Some(&(first, _)) = rest.first() {
+            let len = rest
+                .iter()
+                .position(|(loc, _)| loc.archetype != first.archetype)
+                .unwrap_or(rest.len());
+            let (group, next) = rest.split_at(len);
+            rest = next;
+
+            let key = TypeId::of::<(T,)>();
+            let target = match self.archetypes.insert_edges[first.archetype as usize].get(&key) {
+                Some(x) => x.index,
+                None => {
+                    let t = self.archetypes.get_insert_target(first.archetype, &bundle);
+                    self.archetypes.insert_edges[first.archetype as usize]
+                        .entry(key)
+                        .or_insert(t)
+                        .index
+                }
+            };
+
+            unsafe {
+                if target == first.archetype {
+                    // Overwrite in place
+                    let arch = &mut self.archetypes.archetypes[target as usize];
+                    let size = arch.types()[arch.get_state_by_id(&ty).unwrap()]
+                        .layout()
+                        .size();
+                    let tick = next_tick(arch.change_tick());
+                    for &(loc, _) in group {
+                        let ptr = arch.get_dynamic(ty, size, loc.index).unwrap();
+                        ptr::drop_in_place(ptr.as_ptr().cast::<T>());
+                        let mut value = mem::ManuallyDrop::new(values.next().unwrap());
+                        arch.put_dynamic(
+                            (&mut *value as *mut T).cast(),
+                            ty,
+                            loc.index,
+                            false,
+                            true,
+                            tick,
+                        );
+                    }
+                    continue;
+                }
+
+                let (source_arch, target_arch) = index2(
+                    &mut self.archetypes.archetypes,
+                    first.archetype as usize,
+                    target as usize,
+                );
+                target_arch.reserve(group.len() as u32);
+                let tick = next_tick(target_arch.change_tick());
+                for &(loc, entity) in group {
+                    let target_index = target_arch.allocate(entity.id);
+                    if let Some(moved) =
+                        source_arch.migrate(loc.index, target_arch, target_index, |_, _| {
+                            unreachable!("insertion removed a component")
+                        })
+                    {
+                        self.entities.meta[moved as usize].location.index = loc.index;
+                    }
+                    self.entities.meta[entity.id as usize].location = Location {
+                        archetype: target,
+                        index: target_index,
+                    };
+                    let mut value = mem::ManuallyDrop::new(values.next().unwrap());
+                    target_arch.put_dynamic(
+                        (&mut *value as *mut T).cast(),
+                        ty,
+                        target_index,
+                        true,
+                        false,
+                        tick,
+                    );
+                }
+            }
+        }
+
+        // Observers are notified of additions once every entity is complete
+        if self.observers.observes_addition() {
+            for &(_, entity) in &batch {
+                let loc = self.entities.meta[entity.id as usize].location;
+                let arch = &self.archetypes.archetypes[loc.archetype as usize];
+                self.observers.added_one(arch, loc.index, entity, ty);
+            }
+        }
+        batch.len() as u32
+    }
+
+    /// Uniquely borrow the `T` component of `entity`, first inserting the result of `f` if it has
+    /// none
+    ///
+    /// `f` is only called when `entity` lacks a `T`, in which case the new component is reported as
+    /// [`Added`](crate::Added) just as with [`insert_one`](Self::insert_one). As with `&mut T`
+    /// queries, the component is only reported as mutated once it's accessed through `DerefMut`.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((true,));
+    /// *world.get_or_insert_with(e, || 0).unwrap() += 1;
+    /// *world.get_or_insert_with(e, || 0).unwrap() += 1;
+    /// assert_eq!(*world.get::<i32>(e).unwrap(), 2);
+    /// ```
+    pub fn get_or_insert_with<T: Component>(
+        &mut self,
+        entity: Entity,
+        f: impl FnOnce() -> T,
+    ) -> Result<Mut<'_, T>, NoSuchEntity> {
+        self.flush();
+        if !self.entity(entity)?.has::<T>() {
+            self.insert_one(entity, f())?;
+        }
+        // Look the component up only after any archetype migration has completed
+        Ok(self
+            .query_one_mut::<&mut T>(entity)
+            .expect("component was just ensured present"))
+    }
+
+    /// Exchange the `T` components of `a` and `b` in place
+    ///
+    /// Unlike removing and reinserting the components, no archetype changes take place, making this
+    /// cheap enough to use frequently. Both components are reported as
+    /// [`Mutated`](crate::Mutated) and given a new [`ChangeTick`](crate::ChangeTick). If either
+    /// entity lacks a `T`, neither is modified and an error is returned.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn(("sword", 1));
+    /// let b = world.spawn(("shield", 2.0));
+    /// world.swap_component::<&str>(a, b).unwrap();
+    /// assert_eq!(*world.get::<&str>(a).unwrap(), "shield");
+    /// assert_eq!(*world.get::<&str>(b).unwrap(), "sword");
+    /// assert!(world.swap_component::<i32>(a, b).is_err());
+    /// ```
+    pub fn swap_component<T: Component>(
+        &mut self,
+        a: Entity,
+        b: Entity,
+    ) -> Result<(), ComponentError> {
+        self.flush();
+        let a = self.locate_component::<T>(a)?;
+        let b = self.locate_component::<T>(b)?;
+        let archetypes = &self.archetypes.archetypes;
+        let tick = self.increment_change_tick();
+        unsafe {
+            // `&mut self` rules out outstanding borrows, and `ptr::swap` tolerates `a == b`
+            ptr::swap(
+                archetypes[a.0 as usize]
+                    .get_base::<T>(a.1)
+                    .as_ptr()
+                    .add(a.2),
+                archetypes[b.0 as usize]
+                    .get_base::<T>(b.1)
+                    .as_ptr()
+                    .add(b.2),
+            );
+            for &(archetype, state, index) in &[a, b] {
+                let archetype = &archetypes[archetype as usize];
+                archetype.mark_changed(state);
+                *archetype.get_mutated(state).as_ptr().add(index) = true;
+                *archetype.get_change_ticks(state).as_ptr().add(index) = tick;
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the archetype, column, and row holding the `T` component of `entity`
+    fn locate_component<T: Component>(
+        &self,
+        entity: Entity,
+    ) -> Result<(u32, usize, usize), ComponentError> {
+        let loc = self.entities.get(entity)?;
+        let state = self.archetypes.archetypes[loc.archetype as usize]
+            .get_state::<T>()
+            .ok_or_else(MissingComponent::new::<T>)?;
+        Ok((loc.archetype, state, loc.index as usize))
+    }
+
     /// Remove components from `entity`
     ///
-    /// Computational cost is proportional to the number of components `entity` has. The entity
-    /// itself is not removed, even if no components remain; use `despawn` for that. If any
-    /// component in `T` is not present in `entity`, no components are removed and an error is
-    /// returned.
+    /// Computational cost is proportional to the number of components `entity` has, as removing
+    /// any components migrates `entity` to a new archetype. The entity itself is not removed, even
+    /// if no components remain; use `despawn` for that. If any component in `T` is not present in
+    /// `entity`, no components are removed and an error is returned.
     ///
-    /// When removing a single component, see [`remove_one`](Self::remove_one) for convenience.
+    /// When removing a single component, see [`remove_one`](Self::remove_one) for convenience. To
+    /// find out whether `entity` migrated, see [`remove_reporting`](Self::remove_reporting).
     ///
     /// # Example
     /// ```
@@ -686,36 +2468,55 @@ impl World {
     /// assert_eq!(*world.get::<bool>(e).unwrap(), true);
     /// ```
     pub fn remove<T: Bundle + 'static>(&mut self, entity: Entity) -> Result<T, ComponentError> {
+        self.remove_reporting(entity).map(|(bundle, _)| bundle)
+    }
+
+    /// Like [`remove`](Self::remove), but also returns a [`Removal`] reporting how many components
+    /// were removed and whether `entity` migrated to a new archetype
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let e = world.spawn((123, "abc", true));
+    /// let (x, removal) = world.remove_reporting::<(i32,)>(e).unwrap();
+    /// assert_eq!(x, (123,));
+    /// assert_eq!(removal.removed(), 1);
+    /// assert!(removal.migrated());
+    /// assert!(!world.remove_reporting::<()>(e).unwrap().1.migrated());
+    /// ```
+    pub fn remove_reporting<T: Bundle + 'static>(
+        &mut self,
+        entity: Entity,
+    ) -> Result<(T, Removal), ComponentError> {
         self.flush();
 
         // Gather current metadata
-        let loc = self.entities.get_mut(entity)?;
+        let loc = self.entities.get(entity)?;
         let old_index = loc.index;
         let source_arch = &self.archetypes.archetypes[loc.archetype as usize];
 
+        // Notify observers while the components are still in place
+        if self.observers.observes_removal()
+            && T::with_static_ids(|ids| ids.iter().all(|&id| source_arch.has_dynamic(id)))
+        {
+            let observers = &mut self.observers;
+            T::with_static_ids(|ids| {
+                for &id in ids {
+                    observers.removed_one(source_arch, old_index, entity, id);
+                }
+            });
+        }
+
         // Move out of the source archetype, or bail out if a component is missing
         let bundle = unsafe {
             T::get(|ty| source_arch.get_dynamic(ty.id(), ty.layout().size(), old_index))?
         };
 
-        // Find the target archetype ID
-        let target = match source_arch.remove_edges.get(&TypeId::of::<T>()) {
-            Some(&x) => x,
-            None => {
-                let removed = T::with_static_ids(|ids| ids.iter().copied().collect::<HashSet<_>>());
-                let info = source_arch
-                    .types()
-                    .iter()
-                    .cloned()
-                    .filter(|x| !removed.contains(&x.id()))
-                    .collect::<Vec<_>>();
-                let elements = info.iter().map(|x| x.id()).collect::<Box<_>>();
-                let index = self.archetypes.get(&*elements, move || info);
-                self.archetypes.archetypes[loc.archetype as usize]
-                    .remove_edges
-                    .insert(TypeId::of::<T>(), index);
-                index
-            }
+        let target = self.remove_target::<T>(loc.archetype);
+        let loc = self.entities.get_mut(entity).unwrap();
+        let removal = Removal {
+            removed: T::with_static_ids(|ids| ids.len() as u32),
         };
 
         // Store components to the target archetype and update metadata
@@ -731,83 +2532,227 @@ impl World {
                 loc.archetype = target;
                 loc.index = target_index;
                 let removed_components = &mut self.removed_components;
+                // Only the non-removed components are present in the target archetype. The removed
+                // ones were already moved out.
                 if let Some(moved) =
-                    source_arch.move_to(old_index, |src, ty, size, is_added, is_mutated| {
-                        // Only move the components present in the target archetype, i.e. the non-removed ones.
-                        if let Some(dst) = target_arch.get_dynamic(ty, size, target_index) {
-                            ptr::copy_nonoverlapping(src, dst.as_ptr(), size);
-                            let state = target_arch.get_state_by_id(&ty).unwrap();
-                            *target_arch
-                                .get_added(state)
-                                .as_ptr()
-                                .add(target_index as usize) = is_added;
-                            *target_arch
-                                .get_mutated(state)
-                                .as_ptr()
-                                .add(target_index as usize) = is_mutated;
-                        } else {
-                            let removed_entities =
-                                removed_components.entry(ty).or_insert_with(Vec::new);
-                            removed_entities.push(entity);
-                        }
+                    source_arch.migrate(old_index, target_arch, target_index, |ty, _| {
+                        removed_components.entry(ty.id()).or_default().push(entity);
                     })
                 {
                     self.entities.meta[moved as usize].location.index = old_index;
                 }
             }
-        }
+        }
+
+        Ok((bundle, removal))
+    }
+
+    /// Find the archetype that entities from `source` move to when `T`'s components are removed
+    fn remove_target<T: Bundle + 'static>(&mut self, source: u32) -> u32 {
+        let source_arch = &self.archetypes.archetypes[source as usize];
+        if let Some(&x) = source_arch.remove_edges.get(&TypeId::of::<T>()) {
+            return x;
+        }
+        let removed = T::with_static_ids(|ids| ids.iter().copied().collect::<HashSet<_>>());
+        let info = source_arch
+            .types()
+            .iter()
+            .cloned()
+            .filter(|x| !removed.contains(&x.id()))
+            .collect::<Vec<_>>();
+        let elements = info.iter().map(|x| x.id()).collect::<Box<_>>();
+        let index = self.archetypes.get(&*elements, move || info);
+        self.archetypes.archetypes[source as usize]
+            .remove_edges
+            .insert(TypeId::of::<T>(), index);
+        index
+    }
+
+    /// Remove the `T` component from every entity that has one
+    ///
+    /// Entities are migrated a whole archetype at a time, making this much faster than calling
+    /// [`remove_one`](Self::remove_one) on each entity. Removals are reported to
+    /// [`on_remove`](Self::on_remove) callbacks and by [`removed`](Self::removed) as usual. The
+    /// entities themselves are never despawned, even if no components remain.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123, "abc"));
+    /// let b = world.spawn((456,));
+    /// let c = world.spawn(("def",));
+    /// let mut removed = world.remove_all::<i32>().collect::<Vec<_>>();
+    /// removed.sort();
+    /// assert_eq!(removed, [(a, 123), (b, 456)]);
+    /// assert!(world.get::<i32>(a).is_err());
+    /// assert_eq!(*world.get::<&str>(a).unwrap(), "abc");
+    /// assert!(world.contains(b));
+    /// ```
+    pub fn remove_all<T: Component>(&mut self) -> impl Iterator<Item = (Entity, T)> {
+        self.flush();
+        let sources = (0..self.archetypes.archetypes.len() as u32)
+            .filter(|&i| {
+                let archetype = &self.archetypes.archetypes[i as usize];
+                archetype.has::<T>() && !archetype.is_empty()
+            })
+            .collect::<Vec<_>>();
+        let mut entities = Vec::new();
+        let mut values = Vec::new();
+        for source in sources {
+            let first = entities.len();
+            let archetype = &self.archetypes.archetypes[source as usize];
+            entities.extend(archetype.ids().iter().map(|&id| Entity {
+                id,
+                generation: self.entities.meta[id as usize].generation,
+            }));
+            if self.observers.observes_removal() {
+                let ty = TypeId::of::<T>();
+                for (index, &entity) in entities[first..].iter().enumerate() {
+                    self.observers
+                        .removed_one(archetype, index as u32, entity, ty);
+                }
+            }
 
-        Ok(bundle)
+            let target = self.remove_target::<(T,)>(source);
+            let (source_arch, target_arch) = index2(
+                &mut self.archetypes.archetypes,
+                source as usize,
+                target as usize,
+            );
+            let len = source_arch.len() as usize;
+            let start = unsafe {
+                source_arch.move_all_to(target_arch, |_, base| {
+                    let base = base.cast::<T>();
+                    values.extend((0..len).map(|i| ptr::read(base.add(i))));
+                })
+            };
+            for index in start..target_arch.len() {
+                let id = target_arch.entity_id(index);
+                self.entities.meta[id as usize].location = Location {
+                    archetype: target,
+                    index,
+                };
+            }
+            self.removed_components
+                .entry(TypeId::of::<T>())
+                .or_default()
+                .extend_from_slice(&entities[first..]);
+        }
+        entities.into_iter().zip(values)
     }
 
     /// Remove the `T` component from `entity`
     ///
-    /// See [`remove`](Self::remove).
+    /// The removal is reported by [`removed`](Self::removed). See [`remove`](Self::remove).
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123, "abc"));
+    /// let b = world.spawn((456,));
+    /// // Move a component from one entity to another
+    /// let name = world.remove_one::<&str>(a).unwrap();
+    /// world.insert_one(b, name).unwrap();
+    /// assert!(world.get::<&str>(a).is_err());
+    /// assert_eq!(*world.get::<&str>(b).unwrap(), "abc");
+    /// assert_eq!(world.removed::<&str>(), &[a]);
+    /// ```
     pub fn remove_one<T: Component>(&mut self, entity: Entity) -> Result<T, ComponentError> {
         self.remove::<(T,)>(entity).map(|(x,)| x)
     }
 
-    /// Borrow the `T` component of `entity` without safety checks
+    /// Borrow the `T` component of `entity` without validating `entity` or checking borrows
     ///
-    /// Should only be used as a building block for safe abstractions.
+    /// Like [`get`](Self::get), but skips the generation check on `entity`, and neither consults
+    /// nor updates the borrow state of `T`'s column, so the only remaining cost is finding `T`'s
+    /// column in the entity's archetype. Useful in hot loops over entities already known to be
+    /// live and to have a `T`. The analogue of [`slice::get_unchecked`].
     ///
     /// # Safety
     ///
-    /// `entity` must have been previously obtained from this [`World`], and no unique borrow of the
-    /// same component of `entity` may be live simultaneous to the returned reference.
-    pub unsafe fn get_unchecked<T: Component>(&self, entity: Entity) -> Result<&T, ComponentError> {
-        let loc = self.entities.get(entity)?;
-        let archetype = &self.archetypes.archetypes[loc.archetype as usize];
-        let state = archetype
-            .get_state::<T>()
-            .ok_or_else(MissingComponent::new::<T>)?;
-        Ok(&*archetype
-            .get_base::<T>(state)
-            .as_ptr()
-            .add(loc.index as usize))
+    /// - `entity` must be live in this [`World`], and must have a `T` component
+    /// - For as long as the returned reference is live, nothing may uniquely borrow `T` on
+    ///   `entity`, whether through a query, [`get_mut`](Self::get_mut), or
+    ///   [`get_unchecked_mut`](Self::get_unchecked_mut)
+    ///
+    /// The first is checked with debug assertions.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let entities = (0..10).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    /// // Safe: every entity in `entities` is live and has an `i32`, and no `i32` is uniquely
+    /// // borrowed while the sum is computed.
+    /// let sum: i32 = entities
+    ///     .iter()
+    ///     .map(|&e| unsafe { *world.get_unchecked::<i32>(e) })
+    ///     .sum();
+    /// assert_eq!(sum, 45);
+    /// ```
+    pub unsafe fn get_unchecked<T: Component>(&self, entity: Entity) -> &T {
+        &*self.locate_unchecked::<T>(entity)
     }
 
-    /// Uniquely borrow the `T` component of `entity` without safety checks
+    /// Uniquely borrow the `T` component of `entity` without validating `entity` or checking
+    /// borrows
     ///
-    /// Should only be used as a building block for safe abstractions.
+    /// Like [`get_mut`](Self::get_mut), but with the checks skipped by
+    /// [`get_unchecked`](Self::get_unchecked), and writes through the result aren't recorded as
+    /// changes, as with [`get_mut_untracked`](Self::get_mut_untracked).
     ///
     /// # Safety
     ///
-    /// `entity` must have been previously obtained from this [`World`], and no borrow of the same
-    /// component of `entity` may be live simultaneous to the returned reference.
-    pub unsafe fn get_unchecked_mut<T: Component>(
-        &self,
-        entity: Entity,
-    ) -> Result<&mut T, ComponentError> {
-        let loc = self.entities.get(entity)?;
-        let archetype = &self.archetypes.archetypes[loc.archetype as usize];
-        let state = archetype
-            .get_state::<T>()
-            .ok_or_else(MissingComponent::new::<T>)?;
-        Ok(&mut *archetype
+    /// - `entity` must be live in this [`World`], and must have a `T` component
+    /// - For as long as the returned reference is live, nothing else may borrow `T` on `entity` at
+    ///   all, including other calls to this method or [`get_unchecked`](Self::get_unchecked) for
+    ///   the same entity. Distinct entities may be borrowed simultaneously.
+    ///
+    /// The first is checked with debug assertions.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let entities = (0..10).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    /// // Safe: every entity in `entities` is live and has an `i32`, and each entity's `i32` is
+    /// // borrowed only once at a time.
+    /// for &e in &entities {
+    ///     unsafe {
+    ///         *world.get_unchecked_mut::<i32>(e) *= 2;
+    ///     }
+    /// }
+    /// assert_eq!(*world.get::<i32>(entities[9]).unwrap(), 18);
+    /// ```
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn get_unchecked_mut<T: Component>(&self, entity: Entity) -> &mut T {
+        &mut *self.locate_unchecked::<T>(entity)
+    }
+
+    /// Address of the `T` component of `entity`, which must be live and have a `T`
+    unsafe fn locate_unchecked<T: Component>(&self, entity: Entity) -> *mut T {
+        debug_assert!(self.contains(entity), "entity is not live");
+        let loc = self
+            .entities
+            .meta
+            .get_unchecked(entity.id as usize)
+            .location;
+        let archetype = self
+            .archetypes
+            .archetypes
+            .get_unchecked(loc.archetype as usize);
+        debug_assert!(
+            archetype.has::<T>(),
+            "entity has no {}",
+            core::any::type_name::<T>()
+        );
+        let state = archetype.get_state::<T>().unwrap_unchecked();
+        archetype
             .get_base::<T>(state)
             .as_ptr()
-            .add(loc.index as usize))
+            .add(loc.index as usize)
     }
 
     /// Convert all reserved entities into empty entities that can be iterated and accessed
@@ -824,10 +2769,148 @@ impl World {
     ///
     /// Useful for dynamically scheduling concurrent queries by checking borrows in advance, and for
     /// efficient serialization.
+    ///
+    /// Previously observed archetypes keep their position until
+    /// [`archetypes_generation`](Self::archetypes_generation) changes. Archetypes may be empty,
+    /// e.g. after all their entities have been despawned, until
+    /// [`shrink_to_fit`](Self::shrink_to_fit) removes them.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// # use std::any::TypeId;
+    /// let mut world = World::new();
+    /// world.spawn((123, true));
+    /// world.spawn((456, true));
+    /// let e = world.spawn(("abc",));
+    /// world.despawn(e).unwrap();
+    /// let table = world
+    ///     .archetypes()
+    ///     .filter(|archetype| !archetype.is_empty())
+    ///     .map(|archetype| (archetype.component_types().collect::<Vec<_>>(), archetype.len()))
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(table.len(), 1);
+    /// assert!(table[0].0.contains(&TypeId::of::<bool>()));
+    /// assert_eq!(table[0].1, 2);
+    /// ```
     pub fn archetypes(&self) -> impl ExactSizeIterator<Item = &'_ Archetype> + '_ {
         self.archetypes_inner().iter()
     }
 
+    /// Number of archetypes, including empty ones, equal to the length of
+    /// [`archetypes`](Self::archetypes)
+    ///
+    /// A steadily growing count suggests an archetype explosion, e.g. from inserting and removing
+    /// many different combinations of marker components.
+    pub fn archetype_count(&self) -> usize {
+        self.archetypes.archetypes.len()
+    }
+
+    /// Estimate of the heap memory in bytes owned by this world's entities and archetypes
+    ///
+    /// Sums [`Archetype::memory_usage`] for every archetype along with entity metadata and the
+    /// indices used to locate archetypes. Heap memory owned by the components themselves, and by
+    /// resources, observers, and events, is not counted.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let before = world.memory_usage();
+    /// world.spawn_batch((0..1000).map(|i| (i, true)));
+    /// let full = world.memory_usage();
+    /// assert!(full >= before + 1000 * 5);
+    /// world.clear();
+    /// world.shrink_to_fit();
+    /// assert!(world.memory_usage() < full);
+    /// ```
+    pub fn memory_usage(&self) -> usize {
+        self.entities.memory_usage()
+            + self.archetypes.memory_usage()
+            + hash_map_memory(&self.bundle_to_archetype)
+    }
+
+    /// The archetype at position `index` in [`archetypes`](Self::archetypes), if any
+    ///
+    /// Positions are stable until [`shrink_to_fit`](Self::shrink_to_fit) removes empty archetypes,
+    /// which changes [`archetypes_generation`](Self::archetypes_generation).
+    pub fn archetype(&self, index: u32) -> Option<&Archetype> {
+        self.archetypes.archetypes.get(index as usize)
+    }
+
+    /// The components `Q` would access in each archetype it matches, and how
+    ///
+    /// Yields the position of each matching archetype, as accepted by
+    /// [`archetype`](Self::archetype), alongside each component from [`Query::component_access`]
+    /// that's present in it. Useful for finding queries that can run in parallel without
+    /// conflicting, with finer granularity than comparing `component_access` alone. The result is
+    /// only valid until [`archetypes_generation`](Self::archetypes_generation) changes.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// # use std::any::TypeId;
+    /// let mut world = World::new();
+    /// let a = world.spawn((1, true));
+    /// world.spawn((2, "abc"));
+    /// let (archetype, _) = world.location(a).unwrap();
+    /// assert_eq!(
+    ///     world.archetype_component_access::<(&i32, Option<&mut bool>)>(),
+    ///     [
+    ///         (archetype, TypeId::of::<i32>(), Access::Read),
+    ///         (archetype, TypeId::of::<bool>(), Access::Write),
+    ///         (archetype + 1, TypeId::of::<i32>(), Access::Read),
+    ///     ]
+    /// );
+    /// ```
+    pub fn archetype_component_access<Q: Query>(&self) -> Vec<(u32, TypeId, Access)> {
+        let access = Q::component_access();
+        let mut result = Vec::new();
+        for (index, archetype) in self.archetypes.archetypes.iter().enumerate() {
+            if Q::Fetch::access(archetype).is_none() {
+                continue;
+            }
+            result.extend(
+                access
+                    .iter()
+                    .filter(|&&(id, _)| archetype.has_dynamic(id))
+                    .map(|&(id, access)| (index as u32, id, access)),
+            );
+        }
+        result
+    }
+
+    /// Find where `entity`'s components are stored
+    ///
+    /// Returns the position of its archetype, as accepted by [`archetype`](Self::archetype), and
+    /// its row within that archetype, which is index-aligned with [`Archetype::ids`] and columns
+    /// obtained from [`Archetype::get`]. Returns `None` if `entity` has been despawned, or is
+    /// reserved and the world hasn't been [`flush`](Self::flush)ed since.
+    ///
+    /// The archetype position only changes if the entity is moved or
+    /// [`shrink_to_fit`](Self::shrink_to_fit) removes other archetypes, but the row is invalidated
+    /// by any operation that spawns, despawns, or moves entities, including
+    /// [`insert`](Self::insert) and [`remove`](Self::remove).
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// world.spawn((1, true));
+    /// let e = world.spawn((2, false));
+    /// let (archetype, row) = world.location(e).unwrap();
+    /// let archetype = world.archetype(archetype).unwrap();
+    /// assert_eq!(archetype.ids()[row as usize], e.id());
+    /// assert_eq!(archetype.get::<i32>().unwrap()[row as usize], 2);
+    /// ```
+    pub fn location(&self, entity: Entity) -> Option<(u32, u32)> {
+        let loc = self.entities.get(entity).ok()?;
+        if loc.index == u32::MAX {
+            return None;
+        }
+        Some((loc.archetype, loc.index))
+    }
+
     /// Returns a distinct value after `archetypes` is changed
     ///
     /// Store the current value after deriving information from [`archetypes`](Self::archetypes),
@@ -835,22 +2918,33 @@ impl World {
     /// operation that relies on its correctness. Useful for determining whether e.g. a concurrent
     /// query execution plan is still correct.
     ///
-    /// The generation may be, but is not necessarily, changed as a result of adding or removing any
-    /// entity or component.
+    /// The generation increases exactly when a new archetype is created, i.e. when an entity first
+    /// takes on a previously unseen combination of component types, or when
+    /// [`reserve`](Self::reserve) prepares one, and when [`shrink_to_fit`](Self::shrink_to_fit)
+    /// removes empty archetypes. Spawning into, moving between, or emptying existing archetypes
+    /// leaves it unchanged. The archetype for entities with no components always exists, so it
+    /// never causes a change.
     ///
     /// # Example
     /// ```
     /// # use hecs::*;
     /// let mut world = World::new();
     /// let initial_gen = world.archetypes_generation();
+    /// world.spawn(());
+    /// assert_eq!(initial_gen, world.archetypes_generation());
     /// world.spawn((123, "abc"));
-    /// assert_ne!(initial_gen, world.archetypes_generation());
+    /// let next_gen = world.archetypes_generation();
+    /// assert!(next_gen > initial_gen);
+    /// world.spawn((456, "def"));
+    /// assert_eq!(next_gen, world.archetypes_generation());
     /// ```
     pub fn archetypes_generation(&self) -> ArchetypesGeneration {
         ArchetypesGeneration(self.archetypes.generation)
     }
 
     /// Clears each entity's tracker state. For example, each entity's component "mutated" state will be reset to `false`.
+    ///
+    /// Also forgets all records of removed components reported by [`removed`](Self::removed).
     pub fn clear_trackers(&mut self) {
         for archetype in &mut self.archetypes.archetypes {
             archetype.clear_trackers();
@@ -859,7 +2953,151 @@ impl World {
         self.removed_components.clear();
     }
 
+    /// Like [`clear_trackers`](Self::clear_trackers), but only affects `T` components
+    ///
+    /// Lets systems consuming [`Added`](crate::Added) or [`Mutated`](crate::Mutated) for
+    /// different components clear them independently. Also forgets records of removed `T`
+    /// components reported by [`removed`](Self::removed). Does nothing if no entity has ever had a
+    /// `T`.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123, true));
+    /// world.clear_trackers_for::<i32>();
+    /// assert!(!world.query_one::<Added<i32>>(a).unwrap().get().unwrap());
+    /// assert!(world.query_one::<Added<bool>>(a).unwrap().get().unwrap());
+    /// ```
+    pub fn clear_trackers_for<T: Component>(&mut self) {
+        for archetype in &mut self.archetypes.archetypes {
+            if let Some(state) = archetype.get_state::<T>() {
+                archetype.clear_trackers_for(state);
+            }
+        }
+
+        self.removed_components.remove(&TypeId::of::<T>());
+    }
+
+    /// The most recent change tick
+    ///
+    /// Every addition or mutation of a component draws a new, larger tick from the world, which
+    /// is recorded alongside the component and can be read back with
+    /// [`ChangeTick`](crate::ChangeTick). Unlike [`clear_trackers`](Self::clear_trackers), this
+    /// lets any number of systems running at different rates each detect changes since they last
+    /// ran, by remembering the value of this tick.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123,));
+    /// let b = world.spawn((456,));
+    /// let last_run = world.change_tick();
+    /// *world.get_mut::<i32>(b).unwrap() = 42;
+    /// let changed = world
+    ///     .query::<ChangeTick<i32>>()
+    ///     .iter()
+    ///     .filter(|&(_, tick)| tick > last_run)
+    ///     .map(|(e, _)| e)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(changed, [b]);
+    /// ```
+    pub fn change_tick(&self) -> u32 {
+        self.archetypes.change_tick.current.load(Ordering::Relaxed)
+    }
+
+    /// Draw a fresh change tick, larger than every tick recorded so far
+    ///
+    /// Useful for a system to mark the moment it ran: changes made afterwards will carry larger
+    /// ticks. See [`set_last_change_tick`](Self::set_last_change_tick).
+    pub fn increment_change_tick(&self) -> u32 {
+        next_tick(&self.archetypes.change_tick.current)
+    }
+
+    /// The tick that [`ChangedSince`](crate::ChangedSince) queries compare against by default
+    ///
+    /// Overridden for a single query by
+    /// [`QueryBorrow::changed_since`](crate::QueryBorrow::changed_since).
+    pub fn last_change_tick(&self) -> u32 {
+        self.archetypes.change_tick.last_run.load(Ordering::Relaxed)
+    }
+
+    /// Set the tick that [`ChangedSince`](crate::ChangedSince) queries compare against by default
+    ///
+    /// Typically set to the tick a system stored when it last ran, before running it again, so
+    /// that it sees only changes made in the meantime. Systems that share the world, e.g. by
+    /// running in parallel, should instead pass their tick to each query with
+    /// [`QueryBorrow::changed_since`](crate::QueryBorrow::changed_since). Access through
+    /// [`query_one`](Self::query_one) and the like always uses this tick.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123,));
+    /// let b = world.spawn((456,));
+    /// let mut last_run = world.increment_change_tick();
+    ///
+    /// *world.get_mut::<i32>(b).unwrap() = 42;
+    ///
+    /// world.set_last_change_tick(last_run);
+    /// let changed = world
+    ///     .query::<ChangedSince<i32>>()
+    ///     .iter()
+    ///     .filter(|&(_, changed)| changed)
+    ///     .map(|(e, _)| e)
+    ///     .collect::<Vec<_>>();
+    /// last_run = world.increment_change_tick();
+    /// assert_eq!(changed, [b]);
+    /// ```
+    pub fn set_last_change_tick(&mut self, tick: u32) {
+        self.archetypes
+            .change_tick
+            .last_run
+            .store(tick, Ordering::Relaxed);
+    }
+
+    /// Prevent old change ticks from appearing new after the tick counter wraps around
+    ///
+    /// Ticks older than [`MAX_CHANGE_AGE`](Self::MAX_CHANGE_AGE), including the
+    /// [`last_change_tick`](Self::last_change_tick), are clamped to that age. Comparisons made by
+    /// [`ChangedSince`](crate::ChangedSince) are exact so long as this is called at least once
+    /// every `MAX_CHANGE_AGE` ticks, e.g. once per frame, and no system goes longer than that
+    /// without running. Ticks stored outside the world, like a system's last run, should be
+    /// treated as `MAX_CHANGE_AGE` old once they exceed it.
+    pub fn check_change_ticks(&mut self) {
+        let current = self.change_tick();
+        for archetype in &mut self.archetypes.archetypes {
+            archetype.check_change_ticks(current, Self::MAX_CHANGE_AGE);
+        }
+        let last_run = self.last_change_tick();
+        if current.wrapping_sub(last_run) > Self::MAX_CHANGE_AGE {
+            self.set_last_change_tick(current.wrapping_sub(Self::MAX_CHANGE_AGE));
+        }
+    }
+
+    /// Age beyond which [`check_change_ticks`](Self::check_change_ticks) clamps change ticks
+    pub const MAX_CHANGE_AGE: u32 = u32::MAX / 2;
+
     /// Number of currently live entities
+    ///
+    /// Maintained incrementally, so this is cheap. Entities reserved with
+    /// [`reserve_entity`](Self::reserve_entity) or [`reserve_entities`](Self::reserve_entities) are
+    /// only counted once the world is [`flush`](Self::flush)ed.
+    ///
+    /// # Example
+    /// ```
+    /// # use hecs::*;
+    /// let mut world = World::new();
+    /// let a = world.spawn((123,));
+    /// world.reserve_entity();
+    /// assert_eq!(world.len(), 1);
+    /// world.flush();
+    /// assert_eq!(world.len(), 2);
+    /// world.despawn(a).unwrap();
+    /// assert_eq!(world.len(), 1);
+    /// ```
     #[inline]
     pub fn len(&self) -> u32 {
         self.entities.len()
@@ -889,6 +3127,14 @@ impl<'a> IntoIterator for &'a World {
     }
 }
 
+impl<'a> IntoIterator for &'a mut World {
+    type IntoIter = IterMut<'a>;
+    type Item = EntityMut<'a>;
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
+
 fn index2<T>(x: &mut [T], i: usize, j: usize) -> (&mut T, &mut T) {
     assert!(i != j);
     assert!(i < x.len());
@@ -897,6 +3143,50 @@ fn index2<T>(x: &mut [T], i: usize, j: usize) -> (&mut T, &mut T) {
     unsafe { (&mut *ptr.add(i), &mut *ptr.add(j)) }
 }
 
+/// How [`World::insert`] affected an entity
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Insertion {
+    added: u32,
+    overwritten: u32,
+}
+
+impl Insertion {
+    /// Number of components the entity didn't previously have
+    pub fn added(&self) -> u32 {
+        self.added
+    }
+
+    /// Number of components that replaced existing components of the same type in place
+    pub fn overwritten(&self) -> u32 {
+        self.overwritten
+    }
+
+    /// Whether the entity migrated to a different archetype, which happens exactly when any
+    /// components were [`added`](Self::added)
+    pub fn migrated(&self) -> bool {
+        self.added != 0
+    }
+}
+
+/// How [`World::remove_reporting`] affected an entity
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Removal {
+    removed: u32,
+}
+
+impl Removal {
+    /// Number of components removed from the entity
+    pub fn removed(&self) -> u32 {
+        self.removed
+    }
+
+    /// Whether the entity migrated to a different archetype, which happens exactly when any
+    /// components were [`removed`](Self::removed)
+    pub fn migrated(&self) -> bool {
+        self.removed != 0
+    }
+}
+
 /// Errors that arise when accessing components
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum ComponentError {
@@ -904,6 +3194,8 @@ pub enum ComponentError {
     NoSuchEntity,
     /// The entity did not have a requested component
     MissingComponent(MissingComponent),
+    /// The requested component, whose type is named, was already borrowed incompatibly
+    AlreadyBorrowed(&'static str),
 }
 
 #[cfg(feature = "std")]
@@ -915,6 +3207,7 @@ impl fmt::Display for ComponentError {
         match *self {
             NoSuchEntity => f.write_str("no such entity"),
             MissingComponent(ref x) => x.fmt(f),
+            AlreadyBorrowed(name) => write!(f, "{} already borrowed", name),
         }
     }
 }
@@ -963,8 +3256,45 @@ impl From<NoSuchEntity> for QueryOneError {
 ///
 /// This is just a convenient shorthand for `Send + Sync + 'static`, and never needs to be
 /// implemented manually.
+///
+/// If hecs is compiled with `--cfg hecs_explicit_components`, e.g. by setting `RUSTFLAGS`, only
+/// types implementing [`ComponentMeta`] are components, so that a misspelled or otherwise
+/// unintended type in a query or bundle is a compile error rather than a query that never matches.
+/// Because this takes away components from code that may not expect it, it's a configuration flag
+/// for the final application to opt into, rather than a Cargo feature that any dependency could
+/// enable.
 pub trait Component: Send + Sync + 'static {}
+#[cfg(not(hecs_explicit_components))]
 impl<T: Send + Sync + 'static> Component for T {}
+#[cfg(hecs_explicit_components)]
+impl<T: ComponentMeta> Component for T {}
+
+/// Metadata describing a component type, usually implemented with
+/// [`#[derive(Component)]`](macro@crate::Component)
+///
+/// Implementing this trait is what makes a type a [`Component`] when hecs is compiled with
+/// `--cfg hecs_explicit_components`, and is harmless otherwise.
+///
+/// # Example
+/// ```
+/// # use hecs::*;
+/// struct Position([f32; 2]);
+///
+/// impl ComponentMeta for Position {
+///     const NAME: &'static str = "position";
+///     const KEY: Option<&'static str> = Some("pos");
+/// }
+///
+/// let mut world = World::new();
+/// let e = world.spawn((Position([1.0, 2.0]),));
+/// assert_eq!(<Position as ComponentMeta>::KEY, Some("pos"));
+/// ```
+pub trait ComponentMeta: Send + Sync + 'static {
+    /// Human-readable name of the type, e.g. for debugging tools
+    const NAME: &'static str;
+    /// Identifies the type in serialized data, independently of its name and module path
+    const KEY: Option<&'static str> = None;
+}
 
 /// Iterator over all of a world's entities
 pub struct Iter<'a> {
@@ -998,7 +3328,7 @@ impl<'a> Iterator for Iter<'a> {
                     self.index = 0;
                 }
                 Some(current) => {
-                    if self.index == current.len() as u32 {
+                    if self.index == current.len() {
                         self.current = None;
                         continue;
                     }
@@ -1028,7 +3358,36 @@ impl<'a> Iterator for Iter<'a> {
 impl ExactSizeIterator for Iter<'_> {
     #[inline]
     fn len(&self) -> usize {
-        self.entities.len() as usize
+        let current = self.current.map_or(0, |x| x.len() - self.index);
+        self.archetypes
+            .clone()
+            .map(|x| x.len() as usize)
+            .sum::<usize>()
+            + current as usize
+    }
+}
+
+/// Iterator over all of a world's entities, with exclusive access to their components
+pub struct IterMut<'a> {
+    inner: Iter<'a>,
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = EntityMut<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        // Each entity is yielded at most once, and the world is borrowed uniquely
+        Some(unsafe { EntityMut::from_ref(self.inner.next()?) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.inner.len()
     }
 }
 
@@ -1051,8 +3410,68 @@ impl<A: DynamicBundle> core::iter::FromIterator<A> for World {
     }
 }
 
+/// Shared borrows of an archetype's columns, released on drop
+struct SharedColumns<'a> {
+    archetype: &'a Archetype,
+    states: Vec<usize>,
+}
+
+impl Drop for SharedColumns<'_> {
+    fn drop(&mut self) {
+        for &state in &self.states {
+            self.archetype.release_dynamic(state, false);
+        }
+    }
+}
+
+/// Frees an entity that was allocated but never given a location, if dropped
+struct FreeOnUnwind<'a> {
+    entities: &'a mut Entities,
+    entity: Entity,
+}
+
+impl Drop for FreeOnUnwind<'_> {
+    fn drop(&mut self) {
+        self.entities.free(self.entity).unwrap();
+    }
+}
+
+/// Entities passed to [`World::despawn_later`], guarded by a spin lock so that they can be
+/// recorded through a shared reference, even without `std`
+struct DespawnQueue {
+    lock: AtomicBorrow,
+    entities: UnsafeCell<Vec<Entity>>,
+}
+
+impl DespawnQueue {
+    fn push(&self, entity: Entity) {
+        while !self.lock.borrow_mut() {
+            core::hint::spin_loop();
+        }
+        unsafe {
+            (*self.entities.get()).push(entity);
+        }
+        self.lock.release_mut();
+    }
+
+    fn take(&mut self) -> Vec<Entity> {
+        mem::take(self.entities.get_mut())
+    }
+}
+
+impl Default for DespawnQueue {
+    fn default() -> Self {
+        Self {
+            lock: AtomicBorrow::new(),
+            entities: UnsafeCell::new(Vec::new()),
+        }
+    }
+}
+
 /// Determines freshness of information derived from [`World::archetypes`]
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+///
+/// Later generations of the same [`World`] compare greater than earlier ones.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct ArchetypesGeneration(u64);
 
 /// Entity IDs created by [`World::spawn_batch`]
@@ -1065,6 +3484,7 @@ where
     entities: &'a mut Entities,
     archetype_id: u32,
     archetype: &'a mut Archetype,
+    observers: &'a mut Observers,
 }
 
 impl<I> Drop for SpawnBatchIter<'_, I>
@@ -1087,16 +3507,18 @@ where
     fn next(&mut self) -> Option<Entity> {
         let components = self.inner.next()?;
         let entity = self.entities.alloc();
+        let tick = next_tick(self.archetype.change_tick());
         unsafe {
             let index = self.archetype.allocate(entity.id);
             components.put(|ptr, ty| {
                 self.archetype
-                    .put_dynamic(ptr, ty.id(), ty.layout().size(), index, true, false);
+                    .put_dynamic(ptr, ty.id(), index, true, false, tick);
             });
             self.entities.meta[entity.id as usize].location = Location {
                 archetype: self.archetype_id,
                 index,
             };
+            self.observers.added(self.archetype, index, entity);
         }
         Some(entity)
     }
@@ -1154,20 +3576,57 @@ struct ArchetypeSet {
     index: HashMap<Box<[TypeId]>, u32>,
     archetypes: Vec<Archetype>,
     generation: u64,
+    /// Source of change ticks, shared with every archetype
+    change_tick: Arc<ChangeTicks>,
     /// Maps static bundle types to the archetype that an entity from this archetype is moved to
     /// after inserting the components from that bundle. Stored separately from archetypes to avoid
     /// borrowck difficulties in `World::insert`.
     insert_edges: Vec<TypeIdMap<InsertTarget>>,
+    /// Dense indices for every component type in `archetypes`, used for fast query matching
+    components: ComponentRegistry,
 }
 
 impl ArchetypeSet {
+    fn memory_usage(&self) -> usize {
+        let index = hash_map_memory(&self.index)
+            + self
+                .index
+                .keys()
+                .map(|x| x.len() * mem::size_of::<TypeId>())
+                .sum::<usize>();
+        let insert_edges = self.insert_edges.capacity() * mem::size_of::<TypeIdMap<InsertTarget>>()
+            + self
+                .insert_edges
+                .iter()
+                .map(|edges| {
+                    hash_map_memory(edges)
+                        + edges
+                            .values()
+                            .map(|x| x.replaced.capacity() * mem::size_of::<TypeInfo>())
+                            .sum::<usize>()
+                })
+                .sum::<usize>();
+        index
+            + self.archetypes.capacity() * mem::size_of::<Archetype>()
+            + self
+                .archetypes
+                .iter()
+                .map(|x| x.memory_usage())
+                .sum::<usize>()
+            + insert_edges
+            + self.components.memory_usage()
+    }
+
     fn new() -> Self {
         // `flush` assumes archetype 0 always exists, representing entities with no components.
+        let change_tick = Arc::new(ChangeTicks::default());
         Self {
             index: Some((Box::default(), 0)).into_iter().collect(),
-            archetypes: vec![Archetype::new(Vec::new())],
+            archetypes: vec![Archetype::new(Vec::new(), change_tick.clone())],
             generation: 0,
+            change_tick,
             insert_edges: vec![HashMap::default()],
+            components: ComponentRegistry::default(),
         }
     }
 
@@ -1185,7 +3644,9 @@ impl ArchetypeSet {
 
     fn insert(&mut self, components: Box<[TypeId]>, info: Vec<TypeInfo>) -> u32 {
         let x = self.archetypes.len() as u32;
-        self.archetypes.push(Archetype::new(info));
+        let mut archetype = Archetype::new(info, self.change_tick.clone());
+        archetype.component_bits = self.components.register(archetype.types());
+        self.archetypes.push(archetype);
         let old = self.index.insert(components, x);
         debug_assert!(old.is_none(), "inserted duplicate archetype");
         self.post_insert();
@@ -1193,7 +3654,7 @@ impl ArchetypeSet {
     }
 
     /// Returns archetype ID and starting location index
-    fn insert_batch(&mut self, archetype: Archetype) -> (u32, u32) {
+    fn insert_batch(&mut self, mut archetype: Archetype) -> (u32, u32) {
         use hashbrown::hash_map::Entry;
 
         let ids = archetype
@@ -1215,6 +3676,8 @@ impl ArchetypeSet {
             Entry::Vacant(x) => {
                 // Brand new archetype
                 let id = self.archetypes.len() as u32;
+                archetype.set_change_tick(self.change_tick.clone());
+                archetype.component_bits = self.components.register(archetype.types());
                 self.archetypes.push(archetype);
                 x.insert(id);
                 self.post_insert();
@@ -1228,24 +3691,60 @@ impl ArchetypeSet {
         self.generation += 1;
     }
 
+    /// Remove every empty archetype other than the first, returning the new position of each
+    /// archetype if any were removed
+    fn remove_empty(&mut self) -> Option<Vec<Option<u32>>> {
+        // Archetype 0 must be kept for `flush`
+        if self.archetypes[1..].iter().all(|x| !x.is_empty()) {
+            return None;
+        }
+        let mut next = 0;
+        let remap = self
+            .archetypes
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                if i != 0 && x.is_empty() {
+                    return None;
+                }
+                next += 1;
+                Some(next - 1)
+            })
+            .collect::<Vec<_>>();
+
+        let mut kept = remap.iter().map(Option::is_some);
+        self.archetypes.retain(|_| kept.next().unwrap());
+        let mut kept = remap.iter().map(Option::is_some);
+        self.insert_edges.retain(|_| kept.next().unwrap());
+
+        self.index.retain(|_, index| remap_archetype(&remap, index));
+        for edges in &mut self.insert_edges {
+            edges.retain(|_, target| remap_archetype(&remap, &mut target.index));
+        }
+        for archetype in &mut self.archetypes {
+            archetype
+                .remove_edges
+                .retain(|_, index| remap_archetype(&remap, index));
+        }
+        self.generation += 1;
+        Some(remap)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.index.shrink_to_fit();
+        self.archetypes.shrink_to_fit();
+        self.insert_edges.shrink_to_fit();
+        for edges in &mut self.insert_edges {
+            edges.shrink_to_fit();
+        }
+    }
+
     fn get_insert_target(&mut self, src: u32, components: &impl DynamicBundle) -> InsertTarget {
         // Assemble Vec<TypeInfo> for the final entity
         let arch = &mut self.archetypes[src as usize];
         let mut info = arch.types().to_vec();
         let mut replaced = Vec::new(); // Elements in both archetype.types() and components.type_info()
-        let mut retained = Vec::new(); // Elements in archetype.types() but not components.type_info()
-
-        // Because both `components.type_info()` and `arch.types()` are
-        // ordered, we can identify elements in one but not the other efficiently with parallel
-        // iteration.
-        let mut src_ty = 0;
         for ty in components.type_info() {
-            while src_ty < arch.types().len() && arch.types()[src_ty] <= ty {
-                if arch.types()[src_ty] != ty {
-                    retained.push(arch.types()[src_ty]);
-                }
-                src_ty += 1;
-            }
             if arch.has_dynamic(ty.id()) {
                 replaced.push(ty);
             } else {
@@ -1253,16 +3752,22 @@ impl ArchetypeSet {
             }
         }
         info.sort_unstable();
-        retained.extend_from_slice(&arch.types()[src_ty..]);
 
         // Find the archetype it'll live in
         let elements = info.iter().map(|x| x.id()).collect::<Box<_>>();
         let index = self.get(elements, move || info);
-        InsertTarget {
-            replaced,
-            retained,
-            index,
+        InsertTarget { replaced, index }
+    }
+}
+
+/// Update an archetype position according to `remap`, returning `false` if it was removed
+fn remap_archetype(remap: &[Option<u32>], index: &mut u32) -> bool {
+    match remap[*index as usize] {
+        Some(new) => {
+            *index = new;
+            true
         }
+        None => false,
     }
 }
 
@@ -1270,8 +3775,6 @@ impl ArchetypeSet {
 struct InsertTarget {
     /// Components from the current archetype that are replaced by the insert
     replaced: Vec<TypeInfo>,
-    /// Components from the current archetype that are moved by the insert
-    retained: Vec<TypeInfo>,
     /// ID of the target archetype
     index: u32,
 }
@@ -1279,6 +3782,7 @@ struct InsertTarget {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{ChangeTick, ChangedSince};
 
     #[test]
     fn reuse_empty() {
@@ -1324,4 +3828,51 @@ mod tests {
         let a = world.spawn(("abc", 123));
         world.remove::<()>(a).unwrap();
     }
+
+    #[test]
+    fn changed_since_wraparound() {
+        let mut world = World::new();
+        let current = &world.archetypes.change_tick.current;
+        current.store(u32::MAX - 3, Ordering::Relaxed);
+        let a = world.spawn((1,));
+        let b = world.spawn((2,));
+        let last_run = world.increment_change_tick();
+        assert_eq!(last_run, u32::MAX);
+        world.set_last_change_tick(last_run);
+
+        *world.get_mut::<i32>(b).unwrap() = 3;
+        assert_eq!(world.change_tick(), 0);
+        assert!(!world.query_one_mut::<ChangedSince<i32>>(a).unwrap());
+        assert!(world.query_one_mut::<ChangedSince<i32>>(b).unwrap());
+    }
+
+    #[test]
+    fn check_change_ticks() {
+        let mut world = World::new();
+        let a = world.spawn((1,));
+        let last_run = world.increment_change_tick();
+
+        // Advance a full cycle of the counter, which would make `a` look new without clamping
+        let ticks = world.archetypes.change_tick.clone();
+        ticks
+            .current
+            .fetch_add(World::MAX_CHANGE_AGE, Ordering::Relaxed);
+        world.check_change_ticks();
+        ticks
+            .current
+            .fetch_add(World::MAX_CHANGE_AGE, Ordering::Relaxed);
+        world.check_change_ticks();
+
+        let recent = world.increment_change_tick();
+        world.set_last_change_tick(recent);
+        assert!(!world.query_one_mut::<ChangedSince<i32>>(a).unwrap());
+        // A system that hasn't run for longer than the maximum age conservatively sees changes
+        world.set_last_change_tick(last_run);
+        assert!(world.query_one_mut::<ChangedSince<i32>>(a).unwrap());
+        let tick = world.query_one_mut::<ChangeTick<i32>>(a).unwrap();
+        assert_eq!(
+            world.change_tick().wrapping_sub(tick),
+            World::MAX_CHANGE_AGE + 1
+        );
+    }
 }
@@ -1,12 +1,18 @@
-//!
-
 #[cfg(feature = "macros")]
 #[cfg_attr(miri, ignore)]
 #[test]
 fn derive() {
     const TEST_DIR: &str = "tests/derive";
     let t = trybuild::TestCases::new();
-    let failures = &["enum.rs", "union.rs", "wrong_lifetime.rs"];
+    let failures = &[
+        "enum.rs",
+        "union.rs",
+        "wrong_lifetime.rs",
+        "bad_bundle_attribute.rs",
+        "mut_reference.rs",
+        "bad_component_attribute.rs",
+        "bad_from_entity_attribute.rs",
+    ];
     let successes = &[
         "unit_structs.rs",
         "tuple_structs.rs",
@@ -15,11 +21,14 @@ fn derive() {
         "generics.rs",
         "nested_query.rs",
         "export.rs",
+        "bundle_attributes.rs",
+        "component.rs",
+        "from_entity.rs",
     ];
     for &passing_test in successes {
-        t.pass(&format!("{}/{}", TEST_DIR, passing_test));
+        t.pass(format!("{}/{}", TEST_DIR, passing_test));
     }
     for &failing_test in failures {
-        t.compile_fail(&format!("{}/{}", TEST_DIR, failing_test));
+        t.compile_fail(format!("{}/{}", TEST_DIR, failing_test));
     }
 }
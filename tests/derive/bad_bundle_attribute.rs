@@ -0,0 +1,16 @@
+use hecs::Bundle;
+
+#[derive(Bundle)]
+struct Foo {
+    #[bundle(flatten)]
+    foo: i32,
+}
+
+#[derive(Bundle)]
+struct Bar {
+    #[bundle]
+    #[bundle(skip)]
+    bar: i32,
+}
+
+fn main() {}
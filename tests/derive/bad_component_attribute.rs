@@ -0,0 +1,15 @@
+use hecs::Component;
+
+#[derive(Component)]
+#[component(name = 42)]
+struct Foo;
+
+#[derive(Component)]
+#[component(storage = "sparse")]
+struct Bar;
+
+#[derive(Component)]
+#[component(key = "a", key = "b")]
+struct Baz;
+
+fn main() {}
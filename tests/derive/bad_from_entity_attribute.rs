@@ -0,0 +1,21 @@
+use hecs::FromEntity;
+
+#[derive(FromEntity)]
+struct Foo {
+    #[from_entity(optional)]
+    foo: i32,
+}
+
+#[derive(FromEntity)]
+struct Bar {
+    #[from_entity(skip)]
+    #[from_entity(skip)]
+    bar: i32,
+}
+
+#[derive(FromEntity)]
+enum Baz {
+    A(i32),
+}
+
+fn main() {}
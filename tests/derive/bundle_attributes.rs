@@ -0,0 +1,26 @@
+use hecs::Bundle;
+
+#[derive(Bundle)]
+struct Inner<T> {
+    foo: T,
+}
+
+#[derive(Bundle)]
+struct Outer<T> {
+    #[bundle]
+    inner: Inner<T>,
+    bar: i32,
+    #[bundle(skip)]
+    baz: String,
+}
+
+#[derive(Bundle)]
+struct Tuple(#[bundle] Inner<bool>, #[bundle(skip)] u8);
+
+#[derive(Bundle)]
+struct Skipped {
+    #[bundle(skip)]
+    foo: i32,
+}
+
+fn main() {}
@@ -0,0 +1,36 @@
+use hecs::{Component, ComponentMeta, World};
+
+#[derive(Component)]
+struct Position([f32; 2]);
+
+#[derive(Component)]
+#[component(name = "velocity", key = "vel")]
+struct Velocity([f32; 2]);
+
+#[derive(Component)]
+#[component(key = "wrapper")]
+struct Wrapper<T>(T);
+
+#[derive(Component)]
+enum State {
+    Idle,
+}
+
+fn main() {
+    assert_eq!(<Position as ComponentMeta>::NAME, "Position");
+    assert_eq!(<Position as ComponentMeta>::KEY, None);
+    assert_eq!(<Velocity as ComponentMeta>::NAME, "velocity");
+    assert_eq!(<Velocity as ComponentMeta>::KEY, Some("vel"));
+    assert_eq!(<Wrapper<u8> as ComponentMeta>::NAME, "Wrapper");
+    assert_eq!(<Wrapper<u8> as ComponentMeta>::KEY, Some("wrapper"));
+    assert_eq!(<State as ComponentMeta>::NAME, "State");
+
+    let mut world = World::new();
+    let e = world.spawn((
+        Position([0.0; 2]),
+        Velocity([1.0; 2]),
+        Wrapper(State::Idle),
+        State::Idle,
+    ));
+    assert!(world.get::<Velocity>(e).is_ok());
+}
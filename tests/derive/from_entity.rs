@@ -0,0 +1,17 @@
+use hecs::FromEntity;
+
+#[derive(FromEntity)]
+struct Named<T: Clone> {
+    foo: T,
+    bar: Option<std::string::String>,
+    #[from_entity(skip)]
+    baz: Vec<u8>,
+}
+
+#[derive(FromEntity)]
+struct Tuple(i32, core::option::Option<bool>);
+
+#[derive(FromEntity)]
+struct Unit;
+
+fn main() {}
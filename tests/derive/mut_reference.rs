@@ -0,0 +1,9 @@
+use hecs::Query;
+
+#[derive(Query)]
+struct Foo<'a> {
+    foo: &'a i32,
+    bar: &'a mut bool,
+}
+
+fn main() {}
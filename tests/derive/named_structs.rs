@@ -1,4 +1,4 @@
-use hecs::{Bundle, Query};
+use hecs::{Bundle, Mut, Query};
 
 #[derive(Bundle)]
 struct Foo {
@@ -21,7 +21,7 @@ struct Baz {
 #[derive(Query)]
 struct Quux<'a> {
     foo: &'a i32,
-    bar: &'a mut bool,
+    bar: Mut<'a, bool>,
 }
 
 fn main() {}
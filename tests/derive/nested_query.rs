@@ -1,4 +1,4 @@
-use hecs::Query;
+use hecs::{Mut, Query};
 
 #[derive(Query)]
 struct Foo<'a> {
@@ -8,7 +8,7 @@ struct Foo<'a> {
 
 #[derive(Query)]
 struct Bar<'a> {
-    baz: &'a mut bool,
+    baz: Mut<'a, bool>,
 }
 
 fn main() {}
@@ -3,7 +3,7 @@ use hecs::Query;
 #[derive(Query)]
 struct Foo<'a> {
     foo: &'a i32,
-    bar: &'static mut bool,
+    bar: &'static bool,
 }
 
 fn main() {}
@@ -5,6 +5,8 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::any::TypeId;
+
 use hecs::*;
 
 #[test]
@@ -58,20 +60,53 @@ fn query_all() {
 #[test]
 #[cfg(feature = "macros")]
 fn derived_query() {
-    #[derive(Query, Debug, PartialEq)]
+    #[derive(Query, Debug)]
     struct Foo<'a> {
         x: &'a i32,
-        y: &'a mut bool,
+        y: Mut<'a, bool>,
     }
 
     let mut world = World::new();
     let e = world.spawn((42, false));
+    let foo = world.query_one_mut::<Foo>(e).unwrap();
+    assert_eq!(*foo.x, 42);
+    assert!(!*foo.y);
+}
+
+#[test]
+#[cfg(feature = "macros")]
+fn derived_query_iter() {
+    #[derive(Query)]
+    struct Physics<'a> {
+        position: Mut<'a, f32>,
+        velocity: &'a f64,
+        mass: Option<&'a u32>,
+    }
+
+    let mut world = World::new();
+    let a = world.spawn((1.0f32, 2.0f64));
+    let b = world.spawn((1.0f32, 3.0f64, 2u32));
+    world.spawn((1.0f32,));
+    for (_, mut item) in world.query::<Physics>().iter() {
+        *item.position += (*item.velocity / f64::from(*item.mass.unwrap_or(&1))) as f32;
+    }
+    assert_eq!(*world.get::<f32>(a).unwrap(), 3.0);
+    assert_eq!(*world.get::<f32>(b).unwrap(), 2.5);
     assert_eq!(
-        world.query_one_mut::<Foo>(e).unwrap(),
-        Foo {
-            x: &42,
-            y: &mut false
-        }
+        world.query::<Mutated<f32>>().iter().filter(|x| x.1).count(),
+        2
+    );
+    let mut borrows = Vec::new();
+    <<Physics as Query>::Fetch as Fetch>::for_each_borrow(|id, _, unique| {
+        borrows.push((id, unique))
+    });
+    assert_eq!(
+        borrows,
+        [
+            (TypeId::of::<f32>(), true),
+            (TypeId::of::<f64>(), false),
+            (TypeId::of::<u32>(), false),
+        ]
     );
 }
 
@@ -182,6 +217,33 @@ fn invalidate_prepared_query() {
     assert!(ents.contains(&(g, 789, "ghi")));
 }
 
+#[test]
+fn prepared_query_tracks_moved_entities() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.spawn((2, true));
+    let mut query = PreparedQuery::<&i32>::default();
+    assert_eq!(query.query(&world).iter().count(), 2);
+
+    // Moving entities between existing archetypes must not leave the cache stale
+    let generation = world.archetypes_generation();
+    world.insert_one(a, false).unwrap();
+    world.remove_one::<bool>(b).unwrap();
+    world.spawn((3,));
+    assert_eq!(generation, world.archetypes_generation());
+    let mut ents = query
+        .query_mut(&mut world)
+        .map(|(e, &x)| (e, x))
+        .collect::<Vec<_>>();
+    ents.sort_unstable_by_key(|&(_, x)| x);
+    assert_eq!(ents[..2], [(a, 1), (b, 2)]);
+    assert_eq!(ents.len(), 3);
+
+    // A query prepared for one world is recomputed for another
+    let other = World::new();
+    assert_eq!(query.query(&other).iter().count(), 0);
+}
+
 #[test]
 fn build_entity() {
     let mut world = World::new();
@@ -225,6 +287,59 @@ fn access_builder_components() {
     assert_eq!(*world.get::<i32>(g).unwrap(), 456);
 }
 
+#[test]
+fn builder_overwrite_and_clear() {
+    use std::sync::Arc;
+
+    #[repr(align(64))]
+    struct Aligned(u8);
+
+    let counter = Arc::new(());
+    let mut entity = EntityBuilder::new();
+    entity.add(1u8).add(counter.clone()).add(Aligned(7));
+    // Overwriting drops the previous value
+    entity.add(counter.clone());
+    assert_eq!(Arc::strong_count(&counter), 2);
+    assert_eq!(
+        entity.get::<Aligned>().unwrap() as *const _ as usize % 64,
+        0
+    );
+    assert_eq!(entity.get::<Aligned>().unwrap().0, 7);
+    // Clearing drops everything
+    entity.clear();
+    assert_eq!(Arc::strong_count(&counter), 1);
+    assert!(!entity.has::<u8>());
+
+    let mut world = World::new();
+    entity.add(Aligned(42)).add(counter.clone());
+    let e = world.spawn(entity.build());
+    assert_eq!(Arc::strong_count(&counter), 2);
+    assert_eq!(world.get::<Aligned>(e).unwrap().0, 42);
+    world.despawn(e).unwrap();
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+#[test]
+fn reusable_builder_roundtrip() {
+    let mut world = World::new();
+    let mut entity = EntityBuilder::new_cloneable();
+    entity.add("abc").add(123).add(true);
+    let bundle = entity.build();
+    let e = world.spawn(&bundle.clone());
+    assert_eq!(*world.get::<i32>(e).unwrap(), 123);
+    assert_eq!(*world.get::<&str>(e).unwrap(), "abc");
+    // Lookups remain correct after the builder has been sorted for spawning
+    let entity = EntityBuilder::from(bundle);
+    assert_eq!(*entity.get::<&str>().unwrap(), "abc");
+    assert_eq!(*entity.get::<i32>().unwrap(), 123);
+    assert!(*entity.get::<bool>().unwrap());
+
+    // Cloning an empty builder doesn't allocate
+    let empty = EntityBuilder::new_cloneable().build();
+    let f = world.spawn(&empty.clone());
+    assert_eq!(world.entity(f).unwrap().component_types().count(), 0);
+}
+
 #[test]
 fn build_entity_bundle() {
     let mut world = World::new();
@@ -273,7 +388,7 @@ fn dynamic_components() {
 }
 
 #[test]
-#[should_panic(expected = "already borrowed")]
+#[should_panic(expected = "query violates a unique borrow of i32")]
 fn illegal_borrow() {
     let mut world = World::new();
     world.spawn(("abc", 123));
@@ -283,7 +398,7 @@ fn illegal_borrow() {
 }
 
 #[test]
-#[should_panic(expected = "already borrowed")]
+#[should_panic(expected = "query violates a unique borrow of i32")]
 fn illegal_borrow_2() {
     let mut world = World::new();
     world.spawn(("abc", 123));
@@ -302,6 +417,51 @@ fn illegal_query_mut_borrow() {
     world.query_mut::<(&i32, &mut i32)>();
 }
 
+#[test]
+#[should_panic(expected = "query violates a unique borrow")]
+fn illegal_or_borrow() {
+    let mut world = World::new();
+    world.spawn(("abc", 123));
+
+    world.query_mut::<(Or<&mut i32, &bool>, &i32)>();
+}
+
+#[test]
+#[should_panic(expected = "already borrowed")]
+fn illegal_or_borrow_2() {
+    let mut world = World::new();
+    let e = world.spawn((123, true));
+
+    let _borrow = world.get_mut::<bool>(e).unwrap();
+    world.query::<Or<&i32, &bool>>().iter();
+}
+
+#[test]
+#[should_panic(expected = "query violates a unique borrow of i32")]
+fn illegal_query_borrow() {
+    let mut world = World::new();
+    // Detected even when no entity matches
+    world.spawn(("abc",));
+    world.query::<(&mut i32, &i32)>().iter();
+}
+
+#[test]
+#[should_panic(expected = "query violates a unique borrow of bool")]
+fn illegal_query_one_mut_borrow() {
+    let mut world = World::new();
+    let e = world.spawn((true,));
+    let _ = world.query_one_mut::<(&mut bool, Option<&bool>)>(e);
+}
+
+#[test]
+#[should_panic(expected = "query violates a unique borrow of i32")]
+fn illegal_prepared_query_borrow() {
+    let mut world = World::new();
+    world.spawn((123,));
+    let mut query = PreparedQuery::<(&i32, &mut i32)>::new();
+    query.query(&world).iter();
+}
+
 #[test]
 fn disjoint_queries() {
     let mut world = World::new();
@@ -322,12 +482,29 @@ fn shared_borrow() {
 }
 
 #[test]
-#[should_panic(expected = "already borrowed")]
 fn illegal_random_access() {
+    let mut world = World::new();
+    let e = world.spawn(("abc", 123));
+    let f = world.spawn(("def", 456));
+    let borrow = world.get_mut::<i32>(e).unwrap();
+    let conflict = ComponentError::AlreadyBorrowed(std::any::type_name::<i32>());
+    assert_eq!(world.get::<i32>(e).err(), Some(conflict.clone()));
+    assert_eq!(world.get_mut::<i32>(f).err(), Some(conflict.clone()));
+    assert_eq!(conflict.to_string(), "i32 already borrowed");
+    assert!(world.get::<&str>(e).is_ok());
+    drop(borrow);
+    let _shared = world.get::<i32>(e).unwrap();
+    assert!(world.get::<i32>(f).is_ok());
+    assert_eq!(world.get_mut::<i32>(f).err(), Some(conflict));
+}
+
+#[test]
+#[should_panic(expected = "already borrowed")]
+fn illegal_entity_ref_access() {
     let mut world = World::new();
     let e = world.spawn(("abc", 123));
     let _borrow = world.get_mut::<i32>(e).unwrap();
-    world.get::<i32>(e).unwrap();
+    world.entity(e).unwrap().get::<i32>();
 }
 
 #[test]
@@ -345,6 +522,96 @@ fn derived_bundle() {
     assert_eq!(*world.get::<char>(e).unwrap(), 'a');
 }
 
+#[test]
+#[cfg(feature = "macros")]
+fn derived_bundle_attributes() {
+    #[derive(Bundle, Debug, PartialEq)]
+    struct Body {
+        position: [f32; 2],
+        velocity: [f64; 2],
+    }
+
+    #[derive(Bundle, Debug, PartialEq)]
+    struct Player<T> {
+        #[bundle]
+        body: Body,
+        health: T,
+        #[bundle(skip)]
+        name: Option<String>,
+    }
+
+    let mut world = World::new();
+    let player = Player {
+        body: Body {
+            position: [1.0, 2.0],
+            velocity: [0.0, 1.0],
+        },
+        health: 100u32,
+        name: Some("Alice".into()),
+    };
+    let info = player.type_info();
+    assert_eq!(info.len(), 3);
+    assert!(info.windows(2).all(|x| x[0] <= x[1]));
+    let e = world.spawn(player);
+    let entity = world.entity(e).unwrap();
+    assert_eq!(entity.len(), 3);
+    assert!(!entity.has::<Option<String>>());
+    assert_eq!(*world.get::<[f32; 2]>(e).unwrap(), [1.0, 2.0]);
+    assert_eq!(*world.get::<u32>(e).unwrap(), 100);
+    assert!(world.remove::<Player<i32>>(e).is_err());
+    assert_eq!(world.entity(e).unwrap().len(), 3);
+    assert_eq!(
+        world.remove::<Player<u32>>(e).unwrap(),
+        Player {
+            body: Body {
+                position: [1.0, 2.0],
+                velocity: [0.0, 1.0],
+            },
+            health: 100,
+            name: None,
+        }
+    );
+    assert!(world.entity(e).unwrap().is_empty());
+}
+
+#[test]
+#[cfg(feature = "macros")]
+fn derived_from_entity() {
+    #[derive(FromEntity, Debug, PartialEq)]
+    struct Snapshot {
+        position: [f32; 2],
+        health: u32,
+        name: Option<String>,
+        #[from_entity(skip)]
+        cached: Vec<u8>,
+    }
+
+    let mut world = World::new();
+    let a = world.spawn(([1.0f32, 2.0], 100u32, String::from("a")));
+    let b = world.spawn(([3.0f32, 4.0], 50u32));
+    let c = world.spawn(([5.0f32, 6.0],));
+
+    // Clones leave the entity's components in place
+    let snapshot = Snapshot::from_entity(world.entity(a).unwrap()).unwrap();
+    assert_eq!(
+        snapshot,
+        Snapshot {
+            position: [1.0, 2.0],
+            health: 100,
+            name: Some("a".into()),
+            cached: Vec::new(),
+        }
+    );
+    assert_eq!(*world.get::<String>(a).unwrap(), "a");
+    assert_eq!(
+        Snapshot::from_entity(world.entity(b).unwrap())
+            .unwrap()
+            .name,
+        None
+    );
+    assert_eq!(Snapshot::from_entity(world.entity(c).unwrap()), None);
+}
+
 #[test]
 #[cfg(feature = "macros")]
 #[cfg_attr(
@@ -390,6 +657,112 @@ fn clear() {
     assert_eq!(world.iter().count(), 0);
 }
 
+#[test]
+fn clear_preserves_storage() {
+    use std::sync::Arc;
+
+    let mut world = World::new();
+    let drops = Arc::new(());
+    let a = world.spawn((1, drops.clone()));
+    world.spawn((2, drops.clone(), true));
+    let reserved = world.reserve_entity();
+    let archetypes = world.archetypes().len();
+    let capacities = world.archetypes().map(|x| x.capacity()).collect::<Vec<_>>();
+
+    world.clear();
+    assert_eq!(Arc::strong_count(&drops), 1);
+    assert!(world.is_empty());
+    assert_eq!(world.archetypes().len(), archetypes);
+    for (archetype, &capacity) in world.archetypes().zip(&capacities) {
+        assert!(archetype.capacity() >= capacity);
+    }
+
+    // Neither spawned nor reserved handles alias entities spawned afterwards
+    let spawned = (0..4).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    assert!(!spawned.contains(&a));
+    assert!(!spawned.contains(&reserved));
+    assert!(!world.contains(a));
+    assert!(!world.contains(reserved));
+}
+
+#[test]
+fn memory_usage() {
+    let mut world = World::new();
+    assert_eq!(world.archetype_count(), 1);
+    let empty = world.memory_usage();
+
+    world.spawn_batch((0..100).map(|i| (i as u64, true)));
+    world.spawn((1u64,));
+    assert_eq!(world.archetype_count(), 3);
+    let per_archetype = world.archetypes().map(|x| x.memory_usage()).sum::<usize>();
+    let columns = world
+        .archetypes()
+        .map(|x| {
+            // Component storage, plus added and mutated flags and two ticks per component
+            let per_entity = x.component_types().count() * 10 + 4;
+            x.capacity() as usize * per_entity
+        })
+        .sum::<usize>();
+    assert!(per_archetype >= columns + 100 * 9);
+    assert!(world.memory_usage() > empty + per_archetype);
+}
+
+#[test]
+fn compact_entities() {
+    let mut world = World::new();
+    let entities = (0..8).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    for &e in &entities[..6] {
+        world.despawn(e).unwrap();
+    }
+    // Freed IDs are reused most recently freed first
+    let reused = world.spawn((8,));
+    assert_eq!(reused.id(), entities[5].id());
+    world.set_parent(entities[7], entities[6]).unwrap();
+    world.despawn_later(entities[7]);
+
+    let moved = world.compact_entities();
+    assert_eq!(moved.len(), 3);
+    assert!(moved.contains(reused));
+    let parent = moved.get(entities[6]).copied().unwrap();
+    let child = moved.get(entities[7]).copied().unwrap();
+    assert!(!world.contains(entities[6]));
+    assert!(!world.contains(entities[7]));
+    let mut ids = world.iter().map(|e| e.entity().id()).collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, [0, 1, 2]);
+    assert_eq!(*world.get::<i32>(parent).unwrap(), 6);
+    assert_eq!(*world.get::<i32>(child).unwrap(), 7);
+    assert_eq!(*world.get::<Parent>(child).unwrap(), Parent(parent));
+    assert_eq!(**world.get::<Children>(parent).unwrap(), [child]);
+
+    // Remaining IDs are handed out lowest first
+    let new = (0..3).map(|_| world.spawn(()).id()).collect::<Vec<_>>();
+    assert_eq!(new, [3, 4, 5]);
+
+    assert_eq!(world.apply_despawns(), 1);
+    assert!(!world.contains(child));
+    assert_eq!(world.compact_entities().len(), 1);
+    assert!(world.compact_entities().is_empty());
+}
+
+#[test]
+fn compact_entities_upsert() {
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    struct Id(u64);
+
+    let mut world = World::new();
+    let filler = (0..4).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    let a = world.upsert(Id(1), (10,));
+    for e in filler {
+        world.despawn(e).unwrap();
+    }
+    let moved = world.compact_entities();
+    let new = moved.get(a).copied().unwrap();
+    assert_eq!(world.find_by_key(&Id(1)), Some(new));
+    assert_eq!(world.upsert(Id(1), (20,)), new);
+    assert_eq!(*world.get::<i32>(new).unwrap(), 20);
+}
+
 #[test]
 fn remove_missing() {
     let mut world = World::new();
@@ -397,6 +770,59 @@ fn remove_missing() {
     assert!(world.remove_one::<bool>(e).is_err());
 }
 
+#[test]
+fn remove_all() {
+    use std::sync::{Arc, Mutex};
+
+    let mut world = World::new();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let remove_log = log.clone();
+    world.on_remove::<i32>(move |e, &x| remove_log.lock().unwrap().push((e, x)));
+
+    // The target archetype for `(i32, bool)` already holds an entity
+    let a = world.spawn((true,));
+    let b = world.spawn((1, true));
+    let c = world.spawn((2, "abc"));
+    let d = world.spawn((3, true));
+    let e = world.spawn(("def",));
+    world.clear_trackers();
+    *world.get_mut::<bool>(d).unwrap() = false;
+
+    let mut removed = world.remove_all::<i32>().collect::<Vec<_>>();
+    removed.sort();
+    assert_eq!(removed, [(b, 1), (c, 2), (d, 3)]);
+    let mut observed = log.lock().unwrap().clone();
+    observed.sort();
+    assert_eq!(observed, removed);
+    let mut reported = world.removed::<i32>().to_vec();
+    reported.sort();
+    assert_eq!(reported, [b, c, d]);
+
+    // Entities keep their other components, along with their tracking state
+    assert_eq!(world.len(), 5);
+    assert!(world.query_mut::<&i32>().into_iter().next().is_none());
+    assert!(*world.get::<bool>(a).unwrap());
+    assert!(*world.get::<bool>(b).unwrap());
+    assert_eq!(*world.get::<&str>(c).unwrap(), "abc");
+    assert!(!*world.get::<bool>(d).unwrap());
+    assert_eq!(*world.get::<&str>(e).unwrap(), "def");
+    let mut mutated = world
+        .query_mut::<Mutated<bool>>()
+        .into_iter()
+        .filter(|&(_, x)| x)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    mutated.sort();
+    assert_eq!(mutated, [d]);
+
+    // Moved entities can still be modified and despawned
+    world.despawn(a).unwrap();
+    world.insert_one(b, 4).unwrap();
+    assert!(!*world.get::<bool>(d).unwrap());
+    assert_eq!(world.remove_all::<i32>().collect::<Vec<_>>(), [(b, 4)]);
+    assert_eq!(world.remove_all::<i32>().count(), 0);
+}
+
 #[test]
 fn reserve() {
     let mut world = World::new();
@@ -404,6 +830,8 @@ fn reserve() {
     let b = world.reserve_entity();
 
     assert_eq!(world.query::<()>().iter().count(), 0);
+    assert!(world.contains(a));
+    assert!(world.contains(b));
 
     world.flush();
 
@@ -418,6 +846,57 @@ fn reserve() {
     assert!(entities.contains(&b));
 }
 
+#[test]
+fn reserve_bundle() {
+    let mut world = World::new();
+    world.spawn((1,));
+    let archetypes = world.archetypes().len();
+    world.reserve::<(i32, bool)>(100);
+    assert_eq!(world.len(), 1);
+    assert_eq!(world.archetypes().len(), archetypes + 1);
+    let archetype = world
+        .archetypes()
+        .find(|a| a.has::<i32>() && a.has::<bool>())
+        .unwrap();
+    assert!(archetype.is_empty());
+    let capacity = archetype.capacity();
+    assert!(capacity >= 100);
+
+    // Reserving no more than the spare capacity is a no-op
+    world.reserve::<(bool, i32)>(capacity);
+    world.reserve::<(i32, bool)>(10);
+    assert_eq!(world.archetypes().len(), archetypes + 1);
+    let archetype = world
+        .archetypes()
+        .find(|a| a.has::<i32>() && a.has::<bool>())
+        .unwrap();
+    assert_eq!(archetype.capacity(), capacity);
+    assert_eq!(world.len(), 1);
+}
+
+#[test]
+fn reserve_recycled() {
+    let mut world = World::new();
+    let old = world.spawn((42,));
+    world.despawn(old).unwrap();
+
+    // Reserving reuses the freed ID under a new generation
+    let reserved = world.reserve_entities(2).collect::<Vec<_>>();
+    assert!(reserved.iter().any(|e| e.id() == old.id()));
+    assert!(!world.contains(old));
+    for &e in &reserved {
+        assert!(world.contains(e));
+        assert!(world.get::<i32>(e).is_err());
+    }
+
+    world.flush();
+    assert_eq!(world.len(), 2);
+    for &e in &reserved {
+        assert!(world.contains(e));
+        assert_eq!(world.entity(e).unwrap().len(), 0);
+    }
+}
+
 #[test]
 fn query_batched() {
     let mut world = World::new();
@@ -444,6 +923,29 @@ fn query_batched() {
     assert!(entities.contains(&c));
 }
 
+#[test]
+#[cfg(feature = "parallel")]
+fn query_par_iter() {
+    use rayon::prelude::*;
+
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    world.spawn_batch((0..5000).map(|x| (x, "abc")));
+    world
+        .query::<&mut i32>()
+        .par_iter()
+        .for_each(|(_, mut x)| *x += 1);
+    assert_eq!(*world.get::<i32>(a).unwrap(), 2);
+    assert_eq!(world.query::<&i32>().par_iter().count(), 5001);
+    let sum = world
+        .query::<&i32>()
+        .without::<bool>()
+        .par_iter()
+        .map(|(_, &x)| x)
+        .sum::<i32>();
+    assert_eq!(sum, (1..=5000).sum::<i32>());
+}
+
 #[test]
 fn spawn_batch() {
     let mut world = World::new();
@@ -456,6 +958,32 @@ fn spawn_batch() {
     assert_eq!(entities.len(), 100);
 }
 
+#[test]
+fn spawn_batch_tracking() {
+    let mut world = World::new();
+    let old = world
+        .spawn_batch((0..10).map(|x| (x, "abc")))
+        .collect::<Vec<_>>();
+    for (_, mut x) in world.query::<&mut i32>().iter() {
+        *x += 1;
+    }
+    for e in old {
+        world.despawn(e).unwrap();
+    }
+    world.clear_trackers();
+
+    // New entities reuse the storage vacated above
+    let new = world
+        .spawn_batch((0..100).map(|x| (x, "abc")))
+        .collect::<Vec<_>>();
+    assert_eq!(new.len(), 100);
+    for (e, (added, mutated)) in world.query::<(Added<i32>, Mutated<i32>)>().iter() {
+        assert!(new.contains(&e));
+        assert!(added);
+        assert!(!mutated);
+    }
+}
+
 #[test]
 fn query_one() {
     let mut world = World::new();
@@ -473,6 +1001,67 @@ fn query_one() {
     assert!(world.query_one::<&i32>(a).is_err());
 }
 
+#[test]
+fn query_one_borrow() {
+    let mut world = World::new();
+    let e = world.spawn((123, true, "abc"));
+    {
+        let mut query = world.query_one::<(&mut i32, &bool)>(e).unwrap();
+        let (mut number, &flag) = query.get().unwrap();
+        if flag {
+            *number = 42;
+        }
+        // Components the query doesn't name remain available
+        assert_eq!(*world.get::<&str>(e).unwrap(), "abc");
+    }
+    // Dropping the query releases its borrows
+    *world.get_mut::<i32>(e).unwrap() += 1;
+    *world.get_mut::<bool>(e).unwrap() = false;
+    assert_eq!(*world.get::<i32>(e).unwrap(), 43);
+}
+
+#[test]
+fn query_one_borrow_conflict() {
+    let mut world = World::new();
+    let e = world.spawn((123,));
+    let mut query = world.query_one::<&mut i32>(e).unwrap();
+    let _number = query.get().unwrap();
+    assert!(matches!(
+        world.get::<i32>(e),
+        Err(ComponentError::AlreadyBorrowed(_))
+    ));
+}
+
+#[test]
+fn query_one_mut() {
+    let mut world = World::new();
+    let a = world.spawn((123, true));
+    let b = world.spawn((456,));
+    world.clear_trackers();
+    {
+        let (mut number, flag) = world.query_one_mut::<(&mut i32, &bool)>(a).unwrap();
+        assert!(*flag);
+        *number += 1;
+    }
+    assert_eq!(*world.get::<i32>(a).unwrap(), 124);
+    assert!(world.query_one_mut::<Mutated<i32>>(a).unwrap());
+
+    // Reading through a unique borrow doesn't count as a change
+    let number = world.query_one_mut::<&mut i32>(b).unwrap();
+    assert_eq!(*number, 456);
+    assert!(!world.query_one_mut::<Mutated<i32>>(b).unwrap());
+
+    assert_eq!(
+        world.query_one_mut::<(&mut i32, &bool)>(b).err(),
+        Some(QueryOneError::Unsatisfied)
+    );
+    world.despawn(b).unwrap();
+    assert_eq!(
+        world.query_one_mut::<&mut i32>(b).err(),
+        Some(QueryOneError::NoSuchEntity)
+    );
+}
+
 #[test]
 fn remove_tracking() {
     let mut world = World::new();
@@ -543,113 +1132,3207 @@ fn remove_tracking() {
 }
 
 #[test]
-#[cfg_attr(
-    debug_assertions,
-    should_panic(
-        expected = "attempted to allocate entity with duplicate f32 components; each type must occur at most once!"
-    )
-)]
-#[cfg_attr(
-    not(debug_assertions),
-    should_panic(
-        expected = "attempted to allocate entity with duplicate components; each type must occur at most once!"
-    )
-)]
-fn duplicate_components_panic() {
+fn insert_tracking() {
     let mut world = World::new();
-    world.reserve::<(f32, i64, f32)>(1);
+    let a = world.spawn((1, "abc"));
+    let b = world.spawn((2, "def"));
+    world.clear_trackers();
+    *world.get_mut::<i32>(a).unwrap() = 3;
+
+    // Migrating preserves the tracking state of components that aren't replaced
+    world.insert_one(a, true).unwrap();
+    world.insert(b, (false, "ghi")).unwrap();
+    assert_eq!(
+        world.query_one_mut::<(Added<i32>, Mutated<i32>, Mutated<&str>)>(a),
+        Ok((false, true, false))
+    );
+    assert_eq!(
+        world.query_one_mut::<(Added<i32>, Mutated<i32>, Mutated<&str>)>(b),
+        Ok((false, false, true))
+    );
+    assert_eq!(world.query_one_mut::<Added<bool>>(b), Ok(true));
 }
 
 #[test]
-fn spawn_column_batch() {
+fn remove_tracking_spawn_at() {
     let mut world = World::new();
-    let mut batch_ty = ColumnBatchType::new();
-    batch_ty.add::<i32>().add::<bool>();
+    let a = world.spawn(("abc", 123));
+    world.clear_trackers();
 
-    // Unique archetype
-    let b;
-    {
-        let mut batch = batch_ty.clone().into_batch(2);
-        let mut bs = batch.writer::<bool>().unwrap();
-        bs.push(true).unwrap();
-        bs.push(false).unwrap();
-        let mut is = batch.writer::<i32>().unwrap();
-        is.push(42).unwrap();
-        is.push(43).unwrap();
-        let entities = world
-            .spawn_column_batch(batch.build().unwrap())
-            .collect::<Vec<_>>();
-        assert_eq!(entities.len(), 2);
-        assert_eq!(
-            world.query_one_mut::<(&i32, &bool)>(entities[0]).unwrap(),
-            (&42, &true)
-        );
-        assert_eq!(
-            world.query_one_mut::<(&i32, &bool)>(entities[1]).unwrap(),
-            (&43, &false)
-        );
-        world.despawn(entities[0]).unwrap();
-        b = entities[1];
-    }
+    // Reusing the ID of a live entity replaces it, removing its components
+    world.spawn_at(a, (true,));
+    assert_eq!(world.removed::<i32>(), &[a]);
+    assert_eq!(world.removed::<&'static str>(), &[a]);
+    assert_eq!(world.removed::<bool>(), &[]);
 
-    // Duplicate archetype
-    {
-        let mut batch = batch_ty.clone().into_batch(2);
-        let mut bs = batch.writer::<bool>().unwrap();
-        bs.push(true).unwrap();
-        bs.push(false).unwrap();
-        let mut is = batch.writer::<i32>().unwrap();
-        is.push(44).unwrap();
-        is.push(45).unwrap();
-        let entities = world
-            .spawn_column_batch(batch.build().unwrap())
+    // Claiming a free ID removes nothing
+    let c = world.spawn((456,));
+    world.despawn(c).unwrap();
+    world.clear_trackers();
+    world.spawn_at(c, (789,));
+    assert_eq!(world.removed::<i32>(), &[]);
+}
+
+#[test]
+fn insert_remove_one_tracking() {
+    let mut world = World::new();
+    let a = world.spawn((123, true));
+    let b = world.spawn((456,));
+    world.clear_trackers();
+
+    let flag = world.remove_one::<bool>(a).unwrap();
+    assert!(flag);
+    assert_eq!(world.removed::<bool>(), &[a]);
+    assert_eq!(
+        world.remove_one::<bool>(a),
+        Err(ComponentError::MissingComponent(MissingComponent::new::<
+            bool,
+        >()))
+    );
+
+    world.insert_one(b, flag).unwrap();
+    world.insert_one(b, 789).unwrap();
+    let mut query = world
+        .query_one::<(Added<bool>, Added<i32>, Mutated<i32>)>(b)
+        .unwrap();
+    assert_eq!(query.get(), Some((true, false, true)));
+    drop(query);
+    // The untouched component keeps its tracker state across the move
+    let mut query = world.query_one::<(Added<i32>, Mutated<i32>)>(a).unwrap();
+    assert_eq!(query.get(), Some((false, false)));
+    drop(query);
+
+    world.despawn(a).unwrap();
+    assert_eq!(world.insert_one(a, 0), Err(NoSuchEntity));
+}
+
+#[test]
+fn clear_trackers_for() {
+    let mut world = World::new();
+    let a = world.spawn((123, true));
+    let b = world.spawn((456,));
+    *world.get_mut::<i32>(b).unwrap() = 42;
+    world.remove_one::<bool>(a).unwrap();
+    world.clear_trackers_for::<i32>();
+    for (_, (added, mutated)) in world.query::<(Added<i32>, Mutated<i32>)>().iter() {
+        assert!(!added);
+        assert!(!mutated);
+    }
+    assert!(world.removed::<i32>().is_empty());
+    assert_eq!(world.removed::<bool>(), &[a]);
+
+    // Components no entity has ever had are ignored
+    world.clear_trackers_for::<&str>();
+}
+
+#[test]
+fn changed_filter() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, false));
+    let c = world.spawn((3, "abc"));
+    let d = world.spawn((4u8,));
+    world.clear_trackers();
+
+    assert_eq!(world.query::<ChangedFilter<i32, &i32>>().iter().len(), 0);
+    assert_eq!(world.query::<ChangedFilter<i32, &i32>>().iter().count(), 0);
+
+    *world.get_mut::<i32>(b).unwrap() = 5;
+    {
+        let mut query = world.query::<ChangedFilter<i32, (&i32, &bool)>>();
+        let mut iter = query.iter();
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next(), Some((b, (&5, &false))));
+        assert_eq!(iter.len(), 0);
+        assert!(iter.next().is_none());
+    }
+
+    // Mutating through the filter is allowed, and leaves other entities untouched
+    for (_, mut x) in world.query::<ChangedFilter<i32, &mut i32>>().iter() {
+        *x *= 10;
+    }
+    assert_eq!(*world.get::<i32>(a).unwrap(), 1);
+    assert_eq!(*world.get::<i32>(b).unwrap(), 50);
+    assert_eq!(*world.get::<i32>(c).unwrap(), 3);
+
+    world.insert_one(c, 6u8).unwrap();
+    let mut added = world
+        .query::<AddedFilter<u8, &u8>>()
+        .iter()
+        .map(|(e, &x)| (e, x))
+        .collect::<Vec<_>>();
+    added.sort();
+    assert_eq!(added, &[(c, 6)]);
+    let mut optional = world
+        .query::<Option<AddedFilter<u8, &u8>>>()
+        .iter()
+        .map(|(e, x)| (e, x.copied()))
+        .collect::<Vec<_>>();
+    optional.sort();
+    assert_eq!(optional, &[(a, None), (b, None), (c, Some(6)), (d, None)]);
+    assert_eq!(world.query_one_mut::<AddedFilter<u8, &u8>>(c), Ok(&6));
+    assert!(world.query_one_mut::<AddedFilter<u8, &u8>>(d).is_err());
+    assert!(world
+        .query_one::<AddedFilter<u8, &u8>>(d)
+        .unwrap()
+        .get()
+        .is_none());
+
+    world.clear_trackers();
+    assert_eq!(world.query::<ChangedFilter<i32, ()>>().iter().len(), 0);
+}
+
+#[test]
+fn changed_filter_archetypes() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.spawn((2, true));
+    let c = world.spawn((3, "abc"));
+    let d = world.spawn((4, 5u8));
+    let e = world.spawn((5, false));
+    world.clear_trackers();
+    fn changed(world: &World) -> Vec<Entity> {
+        let mut changed = world
+            .query::<ChangedFilter<i32, ()>>()
+            .iter()
+            .map(|(e, ())| e)
             .collect::<Vec<_>>();
-        assert_eq!(entities.len(), 2);
-        assert_eq!(*world.get::<i32>(b).unwrap(), 43);
-        assert_eq!(*world.get::<i32>(entities[0]).unwrap(), 44);
-        assert_eq!(*world.get::<i32>(entities[1]).unwrap(), 45);
+        changed.sort();
+        changed
+    }
+    assert!(changed(&world).is_empty());
+
+    // Archetypes are only skipped if none of their entities changed, however they were changed
+    for (entity, mut x) in world.query_mut::<&mut i32>() {
+        if entity == a {
+            *x += 1;
+        }
+    }
+    *world.get_mut::<i32>(b).unwrap() += 1;
+    for archetype in world.archetypes().filter(|x| x.has::<u8>()) {
+        archetype.get_mut::<i32>().unwrap()[0] += 1;
     }
+    assert_eq!(changed(&world), [a, b, d]);
+    world.clear_trackers();
+    world.insert_one(c, 6).unwrap();
+    world.insert_one(a, 1.0f32).unwrap();
+    assert_eq!(changed(&world), [c]);
+    world.clear_trackers_for::<i32>();
+    assert!(changed(&world).is_empty());
+    world.swap_component::<i32>(a, d).unwrap();
+    assert_eq!(changed(&world), [a, d]);
+    world.clear_trackers();
+
+    // `Or` only yields the sides that matched
+    *world.get_mut::<i32>(b).unwrap() = 0;
+    let mut items = world
+        .query::<Or<ChangedFilter<i32, &i32>, &bool>>()
+        .iter()
+        .map(|(e, x)| (e, x.cloned()))
+        .collect::<Vec<_>>();
+    items.sort_by_key(|&(e, _)| e);
+    assert_eq!(items, [(b, Or::Both(0, true)), (e, Or::Right(false))]);
+
+    // Filters read `T`'s trackers, so they conflict with writes to `T`
+    assert_eq!(
+        <ChangedFilter<i32, &bool>>::component_access(),
+        [
+            (TypeId::of::<i32>(), Access::Read),
+            (TypeId::of::<bool>(), Access::Read)
+        ]
+    );
+    assert_eq!(
+        <AddedFilter<i32, &mut i32>>::component_access(),
+        [(TypeId::of::<i32>(), Access::Write)]
+    );
 }
 
 #[test]
-fn columnar_access() {
+fn count_has_no_side_effects() {
     let mut world = World::new();
-    let e = world.spawn(("abc", 123));
-    let f = world.spawn(("def", 456, true));
-    let g = world.spawn(("ghi", 789, false));
-    let mut archetypes = world.archetypes();
-    let _empty = archetypes.next().unwrap();
-    let a = archetypes.next().unwrap();
-    assert_eq!(a.ids(), &[e.id()]);
-    assert_eq!(*a.get::<i32>().unwrap(), [123]);
-    assert!(a.get::<bool>().is_none());
-    let b = archetypes.next().unwrap();
-    assert_eq!(b.ids(), &[f.id(), g.id()]);
-    assert_eq!(*b.get::<i32>().unwrap(), [456, 789]);
+    let a = world.spawn((1u32,));
+    let b = world.spawn((2u32, true));
+    world.clear_trackers();
+    *world.get_mut::<u32>(b).unwrap() += 1;
+    let tick = world.change_tick();
+
+    let mut query = world.query::<ChangedFilter<u32, &mut u32>>();
+    assert_eq!(query.count(), 1);
+    let iter = query.iter();
+    assert_eq!(iter.len(), 1);
+    assert_eq!(iter.size_hint(), (1, Some(1)));
+    drop(query);
+    assert_eq!(
+        world
+            .query::<(AddedFilter<u32, &mut u32>, Or<&bool, ()>)>()
+            .iter()
+            .len(),
+        0
+    );
+    let mut prepared = PreparedQuery::<ChangedFilter<u32, &mut u32>>::new();
+    assert_eq!(prepared.query_mut(&mut world).len(), 1);
+    assert_eq!(world.change_tick(), tick);
+
+    let mut mutated = world.query::<Mutated<u32>>().iter().collect::<Vec<_>>();
+    mutated.sort();
+    assert_eq!(mutated, [(a, false), (b, true)]);
 }
 
 #[test]
-fn empty_entity_ref() {
+fn any_changed() {
     let mut world = World::new();
-    let e = world.spawn(());
-    let r = world.entity(e).unwrap();
-    assert_eq!(r.entity(), e);
+    let a = world.spawn((1, true, "a"));
+    let b = world.spawn((2, false));
+    let c = world.spawn((3, true));
+    world.spawn((4,));
+    world.clear_trackers();
+    *world.get_mut::<bool>(b).unwrap() = true;
+    world.insert_one(c, "c").unwrap();
+
+    let mut changed = world
+        .query::<AnyChanged<(i32, bool)>>()
+        .iter()
+        .collect::<Vec<_>>();
+    changed.sort_by_key(|&(e, _)| e);
+    assert_eq!(changed, [(a, false), (b, true), (c, false)]);
+    assert!(world.query_one_mut::<AnyChanged<(i32, &str)>>(c).unwrap());
+    assert!(!world
+        .query_one_mut::<AnyChanged<(bool, &str, i32)>>(a)
+        .unwrap());
 }
 
 #[test]
-fn query_or() {
+fn get_or_insert_with() {
     let mut world = World::new();
-    let e = world.spawn(("abc", 123));
-    let _ = world.spawn(("def",));
-    let f = world.spawn(("ghi", true));
-    let g = world.spawn(("jkl", 456, false));
-    let results = world
-        .query::<(&&str, Or<&i32, &bool>)>()
+    let a = world.spawn((1, true));
+    let b = world.spawn((2,));
+    world.clear_trackers();
+
+    let value = world
+        .get_or_insert_with(a, || -> i32 { unreachable!() })
+        .unwrap();
+    assert_eq!(*value, 1);
+    assert!(!world.query_one_mut::<Changed<i32>>(a).unwrap());
+
+    *world.get_or_insert_with(b, || false).unwrap() = true;
+    assert!(*world.get::<bool>(b).unwrap());
+    assert_eq!(*world.get::<i32>(b).unwrap(), 2);
+    assert_eq!(
+        world.query_one_mut::<(Added<bool>, Mutated<bool>)>(b),
+        Ok((true, true))
+    );
+
+    let c = world.reserve_entity();
+    assert_eq!(*world.get_or_insert_with(c, || "abc").unwrap(), "abc");
+
+    world.despawn(a).unwrap();
+    assert!(world.get_or_insert_with(a, || 0).is_err());
+}
+
+#[test]
+fn swap_component() {
+    let mut world = World::new();
+    let a = world.spawn((1, "a"));
+    let b = world.spawn((2, true));
+    let c = world.spawn((3,));
+    let d = world.spawn(("d",));
+    world.clear_trackers();
+    let before = world.change_tick();
+
+    world.swap_component::<i32>(a, b).unwrap();
+    assert_eq!(*world.get::<i32>(a).unwrap(), 2);
+    assert_eq!(*world.get::<i32>(b).unwrap(), 1);
+    let mut mutated = world
+        .query::<(Mutated<i32>, ChangeTick<i32>)>()
         .iter()
-        .map(|(handle, (&s, value))| (handle, s, value.cloned()))
+        .filter(|&(_, (mutated, tick))| mutated && tick > before)
+        .map(|(e, _)| e)
         .collect::<Vec<_>>();
-    assert_eq!(results.len(), 3);
-    assert!(results.contains(&(e, "abc", Or::Left(123))));
-    assert!(results.contains(&(f, "ghi", Or::Right(true))));
-    assert!(results.contains(&(g, "jkl", Or::Both(456, false))));
+    mutated.sort();
+    assert_eq!(mutated, &[a, b]);
+
+    // Swapping with itself is a no-op beyond change tracking
+    world.swap_component::<i32>(c, c).unwrap();
+    assert_eq!(*world.get::<i32>(c).unwrap(), 3);
+
+    assert_eq!(
+        world.swap_component::<i32>(a, d),
+        Err(ComponentError::MissingComponent(
+            MissingComponent::new::<i32>()
+        ))
+    );
+    assert_eq!(*world.get::<i32>(a).unwrap(), 2);
+    world.despawn(c).unwrap();
+    assert_eq!(
+        world.swap_component::<i32>(c, a),
+        Err(ComponentError::NoSuchEntity)
+    );
+}
+
+#[test]
+fn view() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2,));
+    let c = world.spawn(("abc",));
+    let d = world.spawn((3,));
+    world.despawn(d).unwrap();
+    let e = world.reserve_entity();
+
+    let mut view = world.view::<(&mut i32, Option<&bool>)>();
+    assert!(view.contains(a) && view.contains(b));
+    assert!(!view.contains(c) && !view.contains(d) && !view.contains(e));
+    assert!(view.get_mut(c).is_none());
+    assert!(view.get_mut(d).is_none());
+    assert!(view.get_mut(e).is_none());
+    {
+        let (mut x, flag) = view.get_mut(a).unwrap();
+        assert_eq!(flag, Some(&true));
+        *x += 10;
+    }
+    {
+        let [x, y, z] = view.get_many_mut([a, b, c]);
+        let (x, y) = (x.unwrap().0, y.unwrap().0);
+        assert_eq!((*x, *y), (11, 2));
+        assert!(z.is_none());
+    }
+
+    // Shared borrows may coexist with the view
+    assert!(world.get::<&str>(c).is_ok());
+    assert_eq!(world.query::<&bool>().iter().count(), 1);
+    drop(view);
+    assert_eq!(*world.get::<i32>(a).unwrap(), 11);
+}
+
+#[test]
+fn view_borrow_conflict() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let _view = world.view::<&mut i32>();
+    assert!(matches!(
+        world.get::<i32>(a),
+        Err(ComponentError::AlreadyBorrowed(_))
+    ));
+}
+
+#[test]
+#[should_panic(expected = "requested more than once")]
+fn view_duplicate_entities() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let mut view = world.view::<&mut i32>();
+    let _ = view.get_many_mut([a, a]);
+}
+
+#[test]
+fn dynamic_query() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, "abc"));
+    let c = world.spawn((3, true, "def"));
+    world.spawn((false,));
+    world.clear_trackers();
+
+    let query = DynamicQuery::new(&[
+        (TypeId::of::<i32>(), Access::Write),
+        (TypeId::of::<bool>(), Access::Iterate),
+    ]);
+    let mut seen = Vec::new();
+    query.query(&world).for_each(|entity, components| {
+        assert_eq!(components.len(), 2);
+        assert!(components[1].is_null());
+        unsafe {
+            *components[0].cast::<i32>() *= 10;
+        }
+        seen.push(entity);
+    });
+    seen.sort();
+    assert_eq!(seen, [a, c]);
+    assert_eq!(*world.get::<i32>(a).unwrap(), 10);
+    assert_eq!(*world.get::<i32>(b).unwrap(), 2);
+    assert!(world.query_one_mut::<Mutated<i32>>(c).unwrap());
+    assert!(!world.query_one_mut::<Mutated<i32>>(b).unwrap());
+
+    // Shared access coexists with typed shared borrows, and borrows end with the query
+    let shared = DynamicQuery::new(&[(TypeId::of::<&str>(), Access::Read)]);
+    let mut borrow = shared.query(&world);
+    let _typed = world.query::<&&str>().iter().count();
+    let mut names = Vec::new();
+    borrow.for_each(|_, components| names.push(unsafe { *components[0].cast::<&str>() }));
+    names.sort_unstable();
+    assert_eq!(names, ["abc", "def"]);
+    drop(borrow);
+    world.query::<&mut &str>().iter().count();
+
+    // An empty query matches every entity
+    let mut count = 0;
+    DynamicQuery::new(&[])
+        .query(&world)
+        .for_each(|_, components| {
+            assert!(components.is_empty());
+            count += 1;
+        });
+    assert_eq!(count, 4);
+}
+
+#[test]
+fn dynamic_query_releases_on_conflict() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let query = DynamicQuery::new(&[
+        (TypeId::of::<i32>(), Access::Write),
+        (TypeId::of::<bool>(), Access::Write),
+    ]);
+    let guard = world.get::<bool>(a).unwrap();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        query.query(&world);
+    }));
+    assert!(result.is_err());
+    drop(guard);
+    // The `i32` borrow taken before the conflict was found has been released
+    *world.get_mut::<i32>(a).unwrap() = 2;
+    query.query(&world).for_each(|_, _| {});
+}
+
+#[test]
+#[should_panic(expected = "already borrowed")]
+fn dynamic_query_conflicts_with_typed_query() {
+    let mut world = World::new();
+    world.spawn((1,));
+    let mut typed = world.query::<&i32>();
+    let _iter = typed.iter();
+    DynamicQuery::new(&[(TypeId::of::<i32>(), Access::Write)]).query(&world);
+}
+
+#[test]
+#[should_panic(expected = "more than once")]
+fn dynamic_query_duplicate_component() {
+    DynamicQuery::new(&[
+        (TypeId::of::<i32>(), Access::Read),
+        (TypeId::of::<i32>(), Access::Write),
+    ]);
+}
+
+#[test]
+fn archetypes_generation() {
+    let mut world = World::new();
+    let gen = world.archetypes_generation();
+    let a = world.spawn(());
+    world.spawn_batch((0..10).map(|_| ()));
+    assert_eq!(world.archetypes_generation(), gen);
+
+    world.insert_one(a, 1).unwrap();
+    let gen2 = world.archetypes_generation();
+    assert!(gen2 > gen);
+    world.spawn((2,));
+    world.remove_one::<i32>(a).unwrap();
+    world.insert_one(a, 3).unwrap();
+    world.despawn(a).unwrap();
+    assert_eq!(world.archetypes_generation(), gen2);
+
+    world.reserve::<(i32, bool)>(10);
+    let gen3 = world.archetypes_generation();
+    assert!(gen3 > gen2);
+    world.spawn((true, 4));
+    world.clear();
+    assert_eq!(world.archetypes_generation(), gen3);
+}
+
+#[test]
+fn changed_since_per_system() {
+    fn run(world: &mut World, last_run: &mut u32) -> Vec<Entity> {
+        world.set_last_change_tick(*last_run);
+        let mut changed = world
+            .query::<ChangedSince<i32>>()
+            .iter()
+            .filter(|&(_, changed)| changed)
+            .map(|(e, _)| e)
+            .collect::<Vec<_>>();
+        changed.sort();
+        *last_run = world.increment_change_tick();
+        changed
+    }
+
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.spawn((2,));
+    let (mut fast, mut slow) = (0, 0);
+    assert_eq!(run(&mut world, &mut fast), [a, b]);
+    assert_eq!(run(&mut world, &mut slow), [a, b]);
+
+    *world.get_mut::<i32>(a).unwrap() = 10;
+    assert_eq!(run(&mut world, &mut fast), [a]);
+    *world.get_mut::<i32>(b).unwrap() = 20;
+    assert_eq!(run(&mut world, &mut fast), [b]);
+    assert_eq!(run(&mut world, &mut fast), []);
+    assert_eq!(run(&mut world, &mut slow), [a, b]);
+    assert_eq!(run(&mut world, &mut slow), []);
+
+    // Independent of the global trackers
+    *world.get_mut::<i32>(a).unwrap() = 30;
+    world.clear_trackers();
+    assert_eq!(run(&mut world, &mut fast), [a]);
+}
+
+#[test]
+fn changed_since_per_query() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2,));
+    let slow = world.increment_change_tick();
+    *world.get_mut::<i32>(a).unwrap() = 10;
+    let fast = world.increment_change_tick();
+    *world.get_mut::<i32>(b).unwrap() = 20;
+    world.set_last_change_tick(world.change_tick());
+
+    // Both ticks are honored by queries borrowing the world at the same time
+    let mut slow_query = world
+        .query::<(ChangedSince<i32>, Option<Tracked<i32>>)>()
+        .changed_since(slow);
+    let mut fast_query = world
+        .query::<(ChangedSince<i32>, Option<Tracked<i32>>)>()
+        .changed_since(fast);
+    let mut slow_changed = slow_query
+        .iter()
+        .filter(|&(_, (changed, tracked))| changed && tracked.unwrap().is_changed())
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    slow_changed.sort();
+    assert_eq!(slow_changed, [a, b]);
+    let fast_changed = fast_query
+        .iter()
+        .filter(|&(_, (changed, _))| changed)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(fast_changed, [b]);
+    drop((slow_query, fast_query));
+
+    // Carried through transformations and batched iteration
+    let mut query = world
+        .query::<ChangedSince<i32>>()
+        .changed_since(fast)
+        .with::<bool>();
+    assert_eq!(query.iter().map(|(_, x)| x).collect::<Vec<_>>(), [false]);
+    drop(query);
+    let changed = world
+        .query::<ChangedSince<i32>>()
+        .changed_since(fast)
+        .iter_batched(1)
+        .flatten()
+        .filter(|&(_, x)| x)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(changed, [b]);
+
+    // Without a tick, the world's is used
+    assert_eq!(
+        world
+            .query_mut::<ChangedSince<i32>>()
+            .into_iter()
+            .filter(|&(_, x)| x)
+            .count(),
+        0
+    );
+    assert_eq!(
+        world
+            .query_mut::<ChangedSince<i32>>()
+            .changed_since(slow)
+            .into_iter()
+            .filter(|&(_, x)| x)
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn tracked() {
+    let mut world = World::new();
+    let a = world.spawn((1, "a"));
+    let b = world.spawn((2, "b"));
+    let spawned = world.change_tick();
+    world.set_last_change_tick(spawned);
+
+    // Overwriting counts as a change, and migrating preserves ticks
+    world.insert(a, (3, true)).unwrap();
+    world.despawn(b).unwrap();
+    let c = world.spawn((4, "c"));
+    {
+        let value = world.query_one_mut::<Tracked<i32>>(a).unwrap();
+        assert_eq!(*value, 3);
+        assert!(value.added_tick() <= spawned);
+        assert!(value.last_changed_tick() > spawned);
+        assert!(!value.is_added());
+        assert!(value.is_changed());
+    }
+    {
+        let name = world.query_one_mut::<Tracked<&str>>(a).unwrap();
+        assert!(!name.is_changed());
+        assert_eq!(name.added_tick(), name.last_changed_tick());
+    }
+    assert!(world.query_one_mut::<Tracked<bool>>(a).unwrap().is_added());
+    assert!(world.query_one_mut::<Tracked<i32>>(c).unwrap().is_added());
+
+    world.set_last_change_tick(world.change_tick());
+    let mut query = world.query::<TrackedMut<i32>>();
+    for (_, mut value) in query.iter() {
+        assert!(!value.is_changed());
+        *value += 1;
+        assert!(value.is_changed());
+        assert!(!value.is_added());
+    }
+    drop(query);
+    assert_eq!(*world.get::<i32>(c).unwrap(), 5);
+}
+
+#[test]
+#[should_panic(expected = "query violates a unique borrow of i32")]
+fn tracked_conflict() {
+    let mut world = World::new();
+    world.spawn((1,));
+    world
+        .query::<(Tracked<i32>, TrackedMut<i32>)>()
+        .iter()
+        .for_each(|_| {});
+}
+
+#[test]
+fn clone_with() {
+    let mut registry = CloneRegistry::new();
+    registry.register::<i32>().register::<Vec<u8>>();
+    let mut world = World::new();
+    let a = world.spawn((1, vec![1u8]));
+    let b = world.spawn((2,));
+    let c = world.spawn((3,));
+    world.despawn(c).unwrap();
+    world.clear_trackers();
+    *world.get_mut::<i32>(b).unwrap() = 20;
+    let d = world.reserve_entity();
+
+    let mut snapshot = world.clone_with(&registry).unwrap();
+    assert_eq!(snapshot.len(), world.len());
+    assert!(!snapshot.contains(c));
+    assert!(snapshot.contains(d));
+    assert_eq!(*snapshot.get::<i32>(a).unwrap(), 1);
+    assert_eq!(*snapshot.get::<Vec<u8>>(a).unwrap(), [1]);
+    assert_eq!(
+        snapshot.query_one_mut::<(Added<i32>, Mutated<i32>)>(b),
+        Ok((false, true))
+    );
+    assert_eq!(snapshot.change_tick(), world.change_tick());
+
+    // Handles are allocated identically
+    assert_eq!(world.spawn((4,)), snapshot.spawn((4,)));
+    snapshot.flush();
+    assert!(snapshot.entity(d).unwrap().is_empty());
+
+    // The copy is independent of the original
+    world.get_mut::<Vec<u8>>(a).unwrap().push(2);
+    world.despawn(b).unwrap();
+    assert_eq!(*snapshot.get::<Vec<u8>>(a).unwrap(), [1]);
+    assert_eq!(*snapshot.get::<i32>(b).unwrap(), 20);
+}
+
+#[test]
+fn clone_with_unregistered() {
+    use std::sync::Arc;
+
+    let value = Arc::new(());
+    let mut world = World::new();
+    let a = world.spawn((1, value.clone()));
+    let b = world.spawn((2,));
+
+    let mut registry = CloneRegistry::new();
+    registry.register::<i32>();
+    assert_eq!(
+        world.clone_with(&registry).err(),
+        Some(MissingCloner(TypeId::of::<Arc<()>>()))
+    );
+    assert_eq!(Arc::strong_count(&value), 2);
+
+    registry.skip_unregistered(true);
+    let snapshot = world.clone_with(&registry).unwrap();
+    assert!(snapshot.get::<Arc<()>>(a).is_err());
+    assert_eq!(*snapshot.get::<i32>(a).unwrap(), 1);
+    assert_eq!(*snapshot.get::<i32>(b).unwrap(), 2);
+    assert_eq!(snapshot.archetypes().filter(|x| !x.is_empty()).count(), 1);
+
+    registry.register::<Arc<()>>();
+    let snapshot = world.clone_with(&registry).unwrap();
+    assert_eq!(Arc::strong_count(&value), 3);
+    drop(snapshot);
+    assert_eq!(Arc::strong_count(&value), 2);
+}
+
+#[test]
+#[should_panic(expected = "already borrowed uniquely")]
+fn clone_with_borrow_conflict() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let mut registry = CloneRegistry::new();
+    registry.register::<i32>();
+    let _guard = world.get_mut::<i32>(a).unwrap();
+    let _ = world.clone_with(&registry);
+}
+
+#[test]
+fn query_count() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    world.spawn((2,));
+    world.spawn((3, "abc"));
+    world.spawn((false,));
+    assert_eq!(world.query::<&i32>().count(), 3);
+    assert_eq!(world.query::<(&i32, &bool)>().count(), 1);
+    assert_eq!(world.query::<Without<bool, &i32>>().count(), 2);
+    assert_eq!(world.query::<Or<&bool, &&str>>().count(), 3);
+    assert_eq!(world.query::<()>().count(), 4);
+
+    // Counting doesn't borrow components
+    let guard = world.get_mut::<i32>(a).unwrap();
+    assert_eq!(world.query::<&mut i32>().count(), 3);
+    drop(guard);
+
+    world.clear_trackers();
+    assert_eq!(world.query::<ChangedFilter<i32, ()>>().count(), 0);
+    *world.get_mut::<i32>(a).unwrap() = 10;
+    assert_eq!(world.query::<ChangedFilter<i32, ()>>().count(), 1);
+    assert_eq!(
+        world.query::<Or<ChangedFilter<i32, ()>, &bool>>().count(),
+        2
+    );
+}
+
+#[test]
+#[cfg_attr(
+    debug_assertions,
+    should_panic(
+        expected = "attempted to allocate entity with duplicate f32 components; each type must occur at most once!"
+    )
+)]
+#[cfg_attr(
+    not(debug_assertions),
+    should_panic(
+        expected = "attempted to allocate entity with duplicate components; each type must occur at most once!"
+    )
+)]
+fn duplicate_components_panic() {
+    let mut world = World::new();
+    world.reserve::<(f32, i64, f32)>(1);
+}
+
+#[test]
+fn change_ticks() {
+    let mut world = World::new();
+    let a = world.spawn((123, true));
+    let b = world.spawn((456, true));
+    let spawned = world.change_tick();
+    let tick = |world: &World, e| {
+        world
+            .query_one::<ChangeTick<i32>>(e)
+            .unwrap()
+            .get()
+            .unwrap()
+    };
+    assert!(tick(&world, a) <= spawned);
+    assert!(tick(&world, b) <= spawned);
+
+    // Two observers that last looked at different times
+    let slow_observer = spawned;
+    *world.get_mut::<i32>(a).unwrap() = 42;
+    let fast_observer = world.change_tick();
+    for (_, mut x) in world.query::<&mut i32>().iter() {
+        if *x == 456 {
+            *x = 789;
+        }
+    }
+    assert!(tick(&world, a) > slow_observer);
+    assert!(tick(&world, a) <= fast_observer);
+    assert!(tick(&world, b) > fast_observer);
+
+    // Reading through a unique borrow isn't a change
+    let (a_tick, b_tick) = (tick(&world, a), tick(&world, b));
+    for (_, x) in world.query::<&mut i32>().iter() {
+        let _ = *x;
+    }
+    assert_eq!((tick(&world, a), tick(&world, b)), (a_tick, b_tick));
+
+    // Writes through the same query share a tick, and repeated writes through the same borrow are
+    // a single change
+    let before = world.change_tick();
+    for (_, mut x) in world.query::<&mut i32>().iter() {
+        *x += 1;
+        *x += 1;
+    }
+    assert_eq!(tick(&world, a), before + 1);
+    assert_eq!(tick(&world, b), before + 1);
+    {
+        let mut x = world.get_mut::<i32>(a).unwrap();
+        *x += 1;
+        *x += 1;
+    }
+    assert_eq!(world.change_tick(), before + 2);
+    assert_eq!(tick(&world, a), before + 2);
+
+    // Moving between archetypes preserves ticks of retained components
+    let b_tick = tick(&world, b);
+    world.insert_one(b, "abc").unwrap();
+    world.remove_one::<bool>(b).unwrap();
+    assert_eq!(tick(&world, b), b_tick);
+
+    // Overwriting counts as a change
+    world.insert_one(b, 0).unwrap();
+    assert!(tick(&world, b) > b_tick);
+}
+
+#[test]
+fn spawn_column_batch() {
+    let mut world = World::new();
+    let mut batch_ty = ColumnBatchType::new();
+    batch_ty.add::<i32>().add::<bool>();
+
+    // Unique archetype
+    let b;
+    {
+        let mut batch = batch_ty.clone().into_batch(2);
+        let mut bs = batch.writer::<bool>().unwrap();
+        bs.push(true).unwrap();
+        bs.push(false).unwrap();
+        let mut is = batch.writer::<i32>().unwrap();
+        is.push(42).unwrap();
+        is.push(43).unwrap();
+        let entities = world
+            .spawn_column_batch(batch.build().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(entities.len(), 2);
+        assert_eq!(
+            world.query_one_mut::<(&i32, &bool)>(entities[0]).unwrap(),
+            (&42, &true)
+        );
+        assert_eq!(
+            world.query_one_mut::<(&i32, &bool)>(entities[1]).unwrap(),
+            (&43, &false)
+        );
+        world.despawn(entities[0]).unwrap();
+        b = entities[1];
+    }
+
+    // Duplicate archetype
+    {
+        let mut batch = batch_ty.clone().into_batch(2);
+        let mut bs = batch.writer::<bool>().unwrap();
+        bs.push(true).unwrap();
+        bs.push(false).unwrap();
+        let mut is = batch.writer::<i32>().unwrap();
+        is.push(44).unwrap();
+        is.push(45).unwrap();
+        let entities = world
+            .spawn_column_batch(batch.build().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(entities.len(), 2);
+        assert_eq!(*world.get::<i32>(b).unwrap(), 43);
+        assert_eq!(*world.get::<i32>(entities[0]).unwrap(), 44);
+        assert_eq!(*world.get::<i32>(entities[1]).unwrap(), 45);
+    }
+}
+
+#[test]
+fn spawn_dynamic_column_batch() {
+    use std::alloc::Layout;
+    use std::any::TypeId;
+    use std::mem;
+    use std::sync::Arc;
+
+    unsafe fn drop_arc(x: *mut u8) {
+        x.cast::<Arc<()>>().drop_in_place();
+    }
+    let arc_ty = unsafe {
+        TypeInfo::from_parts(TypeId::of::<Arc<()>>(), Layout::new::<Arc<()>>(), drop_arc)
+    };
+
+    let counter = Arc::new(());
+    let mut world = World::new();
+    let mut batch_ty = ColumnBatchType::new();
+    batch_ty.add_dynamic(arc_ty).add::<i32>();
+
+    // Abandoned batches drop what was written to them
+    {
+        let mut batch = batch_ty.clone().into_batch(2);
+        let mut writer = batch.writer_dynamic(TypeId::of::<Arc<()>>()).unwrap();
+        let mut x = counter.clone();
+        assert!(unsafe { writer.push((&mut x as *mut Arc<()>).cast()) });
+        mem::forget(x);
+        assert_eq!(Arc::strong_count(&counter), 2);
+    }
+    assert_eq!(Arc::strong_count(&counter), 1);
+
+    let mut batch = batch_ty.into_batch(2);
+    assert!(batch.writer_dynamic(TypeId::of::<bool>()).is_none());
+    for _ in 0..2 {
+        // Writers resume where previous writers left off
+        let mut writer = batch.writer_dynamic(TypeId::of::<Arc<()>>()).unwrap();
+        let mut x = counter.clone();
+        assert!(unsafe { writer.push((&mut x as *mut Arc<()>).cast()) });
+        mem::forget(x);
+        batch.writer::<i32>().unwrap().push(42).unwrap();
+    }
+    let mut writer = batch.writer_dynamic(TypeId::of::<Arc<()>>()).unwrap();
+    assert!(!unsafe { writer.push((&mut counter.clone() as *mut Arc<()>).cast()) });
+    assert_eq!(writer.fill(), 2);
+    assert_eq!(batch.writer::<i32>().unwrap().push(43), Err(43));
+
+    let entities = world
+        .spawn_column_batch(batch.build().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(Arc::strong_count(&counter), 3);
+    assert_eq!(*world.get::<i32>(entities[1]).unwrap(), 42);
+    world.despawn(entities[0]).unwrap();
+    assert_eq!(Arc::strong_count(&counter), 2);
+}
+
+#[test]
+fn build_dynamic_entity() {
+    use std::any::TypeId;
+    use std::mem;
+
+    let mut world = World::new();
+    let mut entity = EntityBuilder::new();
+    let mut name = String::from("abc");
+    unsafe {
+        entity.add_dynamic((&mut name as *mut String).cast(), TypeInfo::of::<String>());
+    }
+    mem::forget(name);
+    entity.add(123);
+    let e = world.spawn(entity.build());
+    assert_eq!(*world.get::<String>(e).unwrap(), "abc");
+    assert!(world
+        .entity(e)
+        .unwrap()
+        .component_types()
+        .any(|ty| ty == TypeId::of::<i32>()));
+}
+
+#[test]
+fn columnar_access() {
+    let mut world = World::new();
+    let e = world.spawn(("abc", 123));
+    let f = world.spawn(("def", 456, true));
+    let g = world.spawn(("ghi", 789, false));
+    let mut archetypes = world.archetypes();
+    let _empty = archetypes.next().unwrap();
+    let a = archetypes.next().unwrap();
+    assert_eq!(a.ids(), &[e.id()]);
+    assert_eq!(*a.get::<i32>().unwrap(), [123]);
+    assert!(a.get::<bool>().is_none());
+    let b = archetypes.next().unwrap();
+    assert_eq!(b.ids(), &[f.id(), g.id()]);
+    assert_eq!(*b.get::<i32>().unwrap(), [456, 789]);
+}
+
+#[test]
+fn query_nth() {
+    let mut world = World::new();
+    for i in 0..10 {
+        world.spawn((i,));
+        world.spawn((i, true));
+        if i % 3 == 0 {
+            world.spawn((i, "abc"));
+        }
+        world.spawn((true,));
+    }
+    world.clear_trackers();
+    for (i, (_, mut x)) in world.query_mut::<&mut i32>().into_iter().enumerate() {
+        if i % 4 == 1 {
+            *x += 100;
+        }
+    }
+
+    fn check<Q: Query>(world: &World) {
+        let mut query = world.query::<Q>();
+        let naive = query.iter().map(|(e, _)| e).collect::<Vec<_>>();
+        for n in 0..naive.len() + 2 {
+            assert_eq!(query.iter().nth(n).map(|(e, _)| e), naive.get(n).copied());
+            let skipped = query.iter().skip(n).map(|(e, _)| e).collect::<Vec<_>>();
+            assert_eq!(skipped, naive.get(n..).unwrap_or(&[]));
+        }
+        // Repeated calls pick up where the last left off
+        let mut iter = query.iter();
+        let mut expected = naive.iter();
+        while let Some((e, _)) = iter.nth(2) {
+            assert_eq!(Some(&e), expected.nth(2));
+            assert_eq!(iter.len(), expected.len());
+        }
+        assert!(expected.nth(2).is_none());
+    }
+    check::<&i32>(&world);
+    check::<(&i32, &bool)>(&world);
+    check::<ChangedFilter<i32, &i32>>(&world);
+
+    let mut query = world.query::<&i32>();
+    let mut refs = query.iter_entity_refs();
+    let (entity, &x) = refs.nth(5).unwrap();
+    assert_eq!(*entity.get::<i32>().unwrap(), x);
+}
+
+#[test]
+fn insert_overwrites_in_place() {
+    let mut world = World::new();
+    let e = world.spawn((1, true));
+    let other = world.spawn((2, false));
+    world.clear_trackers();
+    let location = world.location(e);
+    let generation = world.archetypes_generation();
+
+    let insertion = world.insert(e, (10, false)).unwrap();
+    assert_eq!(insertion.added(), 0);
+    assert_eq!(insertion.overwritten(), 2);
+    assert!(!insertion.migrated());
+    assert_eq!(world.location(e), location);
+    assert_eq!(world.archetypes_generation(), generation);
+    assert_eq!(*world.get::<i32>(e).unwrap(), 10);
+    assert!(!*world.get::<bool>(e).unwrap());
+    let mut changes = world
+        .query::<(Added<i32>, Mutated<i32>)>()
+        .iter()
+        .collect::<Vec<_>>();
+    changes.sort_by_key(|x| x.0);
+    assert_eq!(changes, [(e, (false, true)), (other, (false, false))]);
+
+    let insertion = world.insert(e, (20, "abc")).unwrap();
+    assert_eq!(insertion.added(), 1);
+    assert_eq!(insertion.overwritten(), 1);
+    assert!(insertion.migrated());
+    assert_ne!(world.location(e).unwrap().0, location.unwrap().0);
+    assert!(world.query_one_mut::<Mutated<i32>>(e).unwrap());
+    assert!(world.query_one_mut::<Added<&str>>(e).unwrap());
+
+    assert_eq!(world.insert_one(e, 30).unwrap().overwritten(), 1);
+    assert_eq!(world.insert(e, ()).unwrap(), Insertion::default());
+    world.despawn(e).unwrap();
+    assert!(world.insert_one(e, 40).is_err());
+}
+
+#[test]
+fn remove_reporting() {
+    let mut world = World::new();
+    let e = world.spawn((1, true));
+    let location = world.location(e).unwrap();
+    let ((), removal) = world.remove_reporting::<()>(e).unwrap();
+    assert_eq!(removal, Removal::default());
+    assert!(!removal.migrated());
+    assert_eq!(world.location(e).unwrap(), location);
+
+    let ((x,), removal) = world.remove_reporting::<(bool,)>(e).unwrap();
+    assert!(x);
+    assert_eq!(removal.removed(), 1);
+    assert!(removal.migrated());
+    assert_ne!(world.location(e).unwrap().0, location.0);
+    assert!(world.remove_reporting::<(bool,)>(e).is_err());
+    assert_eq!(*world.get::<i32>(e).unwrap(), 1);
+}
+
+#[test]
+fn entity_map() {
+    let mut world = World::new();
+    let a = world.spawn(());
+    let b = world.spawn(());
+    let mut map = EntityMap::new();
+    assert!(map.is_empty());
+    assert_eq!(map.insert(b, 2), None);
+    assert_eq!(map.insert(a, 1), None);
+    assert_eq!(map.insert(a, 10), Some(1));
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(a), Some(&10));
+    *map.get_mut(b).unwrap() += 1;
+    assert_eq!(map.iter().collect::<Vec<_>>(), [(a, &10), (b, &3)]);
+
+    // Stale handles can't observe values associated with an ID's new occupant
+    world.despawn(a).unwrap();
+    let c = world.spawn(());
+    assert_eq!(c.id(), a.id());
+    assert_eq!(map.get(c), None);
+    assert_eq!(map.remove(c), None);
+    assert_eq!(map.insert(c, 100), None);
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(a), None);
+    assert!(!map.contains(a));
+    assert_eq!(map.remove(a), None);
+    assert_eq!(map.remove(c), Some(100));
+    assert_eq!(map.len(), 1);
+    assert_eq!(map, vec![(b, 3)].into_iter().collect());
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.get(b), None);
+}
+
+#[test]
+fn entity_order() {
+    let mut world = World::new();
+    let a = world.spawn(());
+    let b = world.spawn(());
+    world.despawn(a).unwrap();
+    let c = world.spawn(());
+    let mut entities = [c, b, a];
+    entities.sort();
+    assert_eq!(entities, [a, b, c]);
+    assert!(entities.windows(2).all(|x| x[0].to_bits() < x[1].to_bits()));
+}
+
+#[test]
+fn component_access() {
+    let i32_id = TypeId::of::<i32>();
+    let bool_id = TypeId::of::<bool>();
+    let str_id = TypeId::of::<&str>();
+    assert_eq!(<()>::component_access(), []);
+    assert_eq!(
+        <(&i32, (&mut i32, Option<&bool>))>::component_access(),
+        [(i32_id, Access::Write), (bool_id, Access::Read)]
+    );
+    assert_eq!(
+        <Or<&bool, Mut<&str>>>::component_access(),
+        [(bool_id, Access::Read), (str_id, Access::Write)]
+    );
+    assert_eq!(
+        <Without<bool, (Mutated<i32>, Satisfies<&mut &str>)>>::component_access(),
+        [(i32_id, Access::Read)]
+    );
+
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn(("abc",));
+    let c = world.spawn((2, "def"));
+    let [a, b, c] = [a, b, c].map(|x| world.location(x).unwrap().0);
+    assert_eq!(
+        world.archetype_component_access::<Or<&bool, &mut &str>>(),
+        [
+            (a, bool_id, Access::Read),
+            (b, str_id, Access::Write),
+            (c, str_id, Access::Write),
+        ]
+    );
+    assert_eq!(
+        world.archetype_component_access::<With<&str, &i32>>(),
+        [(c, i32_id, Access::Read)]
+    );
+}
+
+#[test]
+fn retain() {
+    let mut world = World::new();
+    let entities = (0..10)
+        .map(|i| world.spawn((i, i % 2 == 0)))
+        .collect::<Vec<_>>();
+    let other = world.spawn((10,));
+    let unmatched = world.spawn(("abc",));
+    world.on_remove::<i32>(|_, &x| assert!(x % 2 == 1 && x < 10));
+
+    // Cull the odd entities from the middle of a single archetype
+    let mut seen = Vec::new();
+    let despawned = world.retain::<(&i32, &bool)>(|entity, (&x, &even)| {
+        seen.push(entity);
+        assert_eq!(x % 2 == 0, even);
+        even
+    });
+    assert_eq!(despawned, 5);
+    seen.sort();
+    assert_eq!(seen, entities);
+    for (i, &entity) in entities.iter().enumerate() {
+        assert_eq!(world.contains(entity), i % 2 == 0);
+        if i % 2 == 0 {
+            assert_eq!(*world.get::<i32>(entity).unwrap(), i as i32);
+        }
+    }
+    assert!(world.contains(other));
+    assert!(world.contains(unmatched));
+    let mut removed = world.removed::<i32>().to_vec();
+    removed.sort();
+    assert_eq!(
+        removed,
+        entities
+            .iter()
+            .copied()
+            .skip(1)
+            .step_by(2)
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(world.retain::<&i32>(|_, _| true), 0);
+    assert_eq!(world.len(), 7);
+}
+
+#[test]
+fn shrink_to_fit() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let wave = world
+        .spawn_batch((0..100).map(|i| (i, "wave")))
+        .collect::<Vec<_>>();
+    let b = world.spawn((2, 3.0f32));
+    world.insert_one(b, false).unwrap();
+    world.reserve::<(u8,)>(50);
+    let mut query = PreparedQuery::<&i32>::default();
+    assert_eq!(query.query(&world).iter().count(), 102);
+
+    world.despawn_batch(wave);
+    let generation = world.archetypes_generation();
+    let archetypes = world.archetypes().len();
+    world.shrink_to_fit();
+    assert_ne!(world.archetypes_generation(), generation);
+    // The `(i32, &str)`, `(i32, f32)`, and `(u8,)` archetypes are gone
+    assert_eq!(world.archetypes().len(), archetypes - 3);
+    assert!(world.archetypes().skip(1).all(|x| !x.is_empty()));
+    assert!(world.archetypes().all(|x| x.capacity() == x.len()));
+    for &(entity, value) in &[(a, 1), (b, 2)] {
+        let (archetype, row) = world.location(entity).unwrap();
+        let archetype = world.archetype(archetype).unwrap();
+        assert_eq!(archetype.ids()[row as usize], entity.id());
+        assert_eq!(archetype.get::<i32>().unwrap()[row as usize], value);
+    }
+    assert_eq!(query.query(&world).iter().count(), 2);
+
+    // Cached moves between the removed archetypes are recreated on demand
+    world.remove_one::<bool>(b).unwrap();
+    assert_eq!(*world.get::<f32>(b).unwrap(), 3.0);
+    world.spawn((3, "wave"));
+    world.spawn((4u8,));
+    assert_eq!(query.query(&world).iter().count(), 3);
+
+    // Moving `b` back emptied the `(i32, f32, bool)` archetype
+    world.shrink_to_fit();
+    let generation = world.archetypes_generation();
+    world.shrink_to_fit();
+    assert_eq!(world.archetypes_generation(), generation);
+    assert_eq!(*world.get::<i32>(a).unwrap(), 1);
+}
+
+#[test]
+fn spawn_archetype_cache() {
+    let mut world = World::new();
+    let mut builder = EntityBuilder::new_cloneable();
+    builder.add(1).add(true);
+    let dynamic = builder.build();
+
+    // Alternate between static and dynamic bundles of the same and differing types
+    let x = world.spawn(("abc",));
+    let a = world.spawn((2, false));
+    let b = world.spawn(&dynamic);
+    let c = world.spawn(&dynamic);
+    let d = world.spawn((3,));
+    let e = world.spawn(&dynamic);
+    assert_eq!(world.location(a).unwrap().0, world.location(b).unwrap().0);
+    assert_eq!(world.location(c).unwrap().0, world.location(e).unwrap().0);
+    assert_ne!(world.location(d).unwrap().0, world.location(e).unwrap().0);
+    assert_eq!(world.archetype_count(), 4);
+
+    // Removing empty archetypes renumbers the rest
+    let f = world.spawn((4,));
+    world.despawn(x).unwrap();
+    world.shrink_to_fit();
+    let g = world.spawn((5,));
+    let h = world.spawn(&dynamic);
+    assert_eq!(world.archetype_count(), 3);
+    assert_eq!(world.location(g).unwrap().0, world.location(f).unwrap().0);
+    assert_eq!(world.location(h).unwrap().0, world.location(a).unwrap().0);
+    assert_eq!(*world.get::<i32>(g).unwrap(), 5);
+}
+
+#[test]
+fn spawn_with() {
+    struct Node {
+        this: Entity,
+    }
+
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    world.despawn(a).unwrap();
+    let b = world.spawn_with(|this| (Node { this }, 2));
+    assert_eq!(b.id(), a.id());
+    assert_ne!(b, a);
+    assert_eq!(world.get::<Node>(b).unwrap().this, b);
+    assert_eq!(*world.get::<i32>(b).unwrap(), 2);
+
+    // Panicking while building the bundle leaves nothing behind
+    let mut seen = None;
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        world.spawn_with(|this| -> (Node,) {
+            seen = Some(this);
+            panic!("oops");
+        })
+    }));
+    assert!(result.is_err());
+    let seen = seen.unwrap();
+    assert!(!world.contains(seen));
+    assert_eq!(world.len(), 1);
+    let c = world.spawn(());
+    assert_eq!(c.id(), seen.id());
+    assert_ne!(c, seen);
+}
+
+#[test]
+fn entities_in_id_order() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.spawn(("b",));
+    let c = world.spawn((3,));
+    assert_eq!(world.entities().collect::<Vec<_>>(), [a, b, c]);
+
+    // Archetype order differs from ID order
+    world.insert_one(a, true).unwrap();
+    assert_eq!(
+        world.iter().map(|e| e.entity()).collect::<Vec<_>>(),
+        [c, b, a]
+    );
+    assert_eq!(world.entities().collect::<Vec<_>>(), [a, b, c]);
+
+    world.despawn(b).unwrap();
+    let reserved = world.reserve_entities(2).collect::<Vec<_>>();
+    assert_eq!(world.entities().collect::<Vec<_>>(), [a, c]);
+    world.flush();
+    let mut expected = vec![a, c];
+    expected.extend_from_slice(&reserved);
+    expected.sort_by_key(|e| e.id());
+    assert_eq!(world.entities().collect::<Vec<_>>(), expected);
+
+    world.clear();
+    assert_eq!(world.entities().count(), 0);
+}
+
+#[test]
+fn atomic_ref() {
+    use std::sync::atomic::Ordering;
+
+    let mut world = World::new();
+    let entities = (0..10u64)
+        .map(|i| world.spawn((i, i as f32)))
+        .collect::<Vec<_>>();
+    let other = world.spawn((100u64, true));
+    world.clear_trackers();
+
+    // Every entity accumulates a contribution from every other entity
+    let mut query = world.query::<(AtomicRef<u64>, &f32)>();
+    let items = query.iter().map(|(_, x)| x).collect::<Vec<_>>();
+    std::thread::scope(|s| {
+        for chunk in items.chunks(3) {
+            let items = &items;
+            s.spawn(move || {
+                for &(_, &x) in chunk {
+                    for &(total, _) in items {
+                        total.fetch_add(x as u64, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+    drop(query);
+    for (i, &e) in entities.iter().enumerate() {
+        assert_eq!(*world.get::<u64>(e).unwrap(), i as u64 + 45);
+        assert!(world.query_one::<Mutated<u64>>(e).unwrap().get().unwrap());
+    }
+    assert_eq!(*world.get::<u64>(other).unwrap(), 100);
+    assert!(!world
+        .query_one::<Mutated<u64>>(other)
+        .unwrap()
+        .get()
+        .unwrap());
+}
+
+#[test]
+#[should_panic(expected = "borrowed")]
+fn atomic_ref_conflict() {
+    let mut world = World::new();
+    let e = world.spawn((1u32,));
+    let mut query = world.query::<AtomicRef<u32>>();
+    let _items = query.iter().collect::<Vec<_>>();
+    assert!(matches!(
+        world.get::<u32>(e),
+        Err(ComponentError::AlreadyBorrowed(_))
+    ));
+    world.query::<&u32>().iter();
+}
+
+#[test]
+fn merge() {
+    use std::sync::Arc;
+
+    let mut world = World::new();
+    let existing = world.spawn((1, "existing"));
+    let added = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    {
+        let added = added.clone();
+        world.on_add::<i32>(move |_, _| {
+            added.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        });
+    }
+
+    let marker = Arc::new(());
+    let mut scene = World::new();
+    let hole = scene.spawn((0,));
+    let root = scene.spawn((2, "root".to_string(), marker.clone()));
+    scene.despawn(hole).unwrap();
+    let child = scene.spawn((3, "child".to_string()));
+    scene.set_parent(child, root).unwrap();
+    let empty = scene.spawn(());
+    let reserved = scene.reserve_entity();
+    scene.clear_trackers();
+
+    let map = world.merge(scene);
+    assert_eq!(map.len(), 4);
+    assert!(!map.contains(hole));
+    assert_eq!(world.len(), 5);
+    assert_eq!(added.load(std::sync::atomic::Ordering::Relaxed), 2);
+    assert_eq!(Arc::strong_count(&marker), 2);
+
+    let root = *map.get(root).unwrap();
+    let child = *map.get(child).unwrap();
+    assert_eq!(*world.get::<String>(root).unwrap(), "root");
+    assert_eq!(*world.get::<String>(child).unwrap(), "child");
+    assert_eq!(world.get::<Parent>(child).unwrap().0, root);
+    assert_eq!(&**world.get::<Children>(root).unwrap(), [child]);
+    assert!(world.contains(*map.get(empty).unwrap()));
+    assert!(world.contains(*map.get(reserved).unwrap()));
+    assert_eq!(*world.get::<&str>(existing).unwrap(), "existing");
+    assert_eq!(
+        world
+            .query::<Added<String>>()
+            .iter()
+            .filter(|&(_, added)| added)
+            .count(),
+        2
+    );
+
+    // Merged components are dropped with the world they're moved into
+    world.despawn(root).unwrap();
+    assert_eq!(Arc::strong_count(&marker), 1);
+}
+
+#[test]
+fn merge_stale_hierarchy() {
+    let mut world = World::new();
+    world.spawn_batch((0..8).map(|i| (i,)));
+
+    let mut scene = World::new();
+    let dead = scene.spawn((0,));
+    let parent = scene.spawn((1,));
+    let a = scene.spawn((2,));
+    let b = scene.spawn((3,));
+    scene.set_parent(a, parent).unwrap();
+    scene.set_parent(b, parent).unwrap();
+    // Leave stale links behind
+    scene.remove_one::<Parent>(b).unwrap();
+    scene.despawn(b).unwrap();
+    let orphan = scene.spawn((4, Parent(dead)));
+    scene.despawn(dead).unwrap();
+    assert_eq!(scene.get::<Children>(parent).unwrap().len(), 2);
+
+    let map = world.merge(scene);
+    let parent = *map.get(parent).unwrap();
+    let a = *map.get(a).unwrap();
+    let orphan = *map.get(orphan).unwrap();
+    assert_eq!(&**world.get::<Children>(parent).unwrap(), [a]);
+    assert_eq!(world.get::<Parent>(a).unwrap().0, parent);
+    assert!(world.get::<Parent>(orphan).is_err());
+    assert_eq!(*world.get::<i32>(orphan).unwrap(), 4);
+
+    // Fixing up links isn't a mutation
+    assert!(!world.query_one_mut::<Mutated<Parent>>(a).unwrap());
+    assert!(!world.query_one_mut::<Mutated<Children>>(parent).unwrap());
+}
+
+#[test]
+fn optional_trackers() {
+    let mut world = World::new();
+    let still = world.spawn((1.0f32,));
+    let moving = world.spawn((2.0f32, 3u8));
+    let moved = world.spawn((4.0f32, 5u8));
+    world.clear_trackers();
+    *world.get_mut::<u8>(moved).unwrap() = 6;
+
+    let mut results = world
+        .query::<(&f32, Option<(&u8, Mutated<u8>)>)>()
+        .iter()
+        .map(|(e, (_, x))| (e, x.map(|(&x, mutated)| (x, mutated))))
+        .collect::<Vec<_>>();
+    results.sort_by_key(|&(e, _)| e);
+    assert_eq!(
+        results,
+        [
+            (still, None),
+            (moving, Some((3, false))),
+            (moved, Some((6, true)))
+        ]
+    );
+
+    let mut results = world
+        .query::<(
+            Option<Mutated<u8>>,
+            Option<Added<u8>>,
+            Option<Changed<u8>>,
+            Option<ChangedFilter<u8, &u8>>,
+        )>()
+        .iter()
+        .map(|(e, (mutated, added, changed, filtered))| {
+            (e, mutated, added, changed, filtered.copied())
+        })
+        .collect::<Vec<_>>();
+    results.sort_by_key(|&(e, ..)| e);
+    assert_eq!(
+        results,
+        [
+            (still, None, None, None, None),
+            (moving, Some(false), Some(false), Some(false), None),
+            (moved, Some(true), Some(false), Some(true), Some(6)),
+        ]
+    );
+
+    assert_eq!(
+        world.query_one::<Option<Mutated<u8>>>(still).unwrap().get(),
+        Some(None)
+    );
+    assert_eq!(
+        world.view::<Option<Mutated<u8>>>().get_mut(moved),
+        Some(Some(true))
+    );
+}
+
+#[test]
+fn get_mut_untracked() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, false));
+    world.clear_trackers();
+    let tick = world
+        .query_one::<ChangeTick<i32>>(a)
+        .unwrap()
+        .get()
+        .unwrap();
+
+    *world.get_mut_untracked::<i32>(a).unwrap() = 10;
+    assert_eq!(*world.get::<i32>(a).unwrap(), 10);
+    assert!(!world.query_one::<Changed<i32>>(a).unwrap().get().unwrap());
+    assert_eq!(
+        world
+            .query_one::<ChangeTick<i32>>(a)
+            .unwrap()
+            .get()
+            .unwrap(),
+        tick
+    );
+
+    // Tracked writes in the same frame are still reported
+    *world.get_mut::<i32>(b).unwrap() = 20;
+    *world.get_mut_untracked::<i32>(b).unwrap() = 21;
+    assert!(world.query_one::<Changed<i32>>(b).unwrap().get().unwrap());
+    *world.get_mut::<i32>(a).unwrap() = 11;
+    assert!(world.query_one::<Changed<i32>>(a).unwrap().get().unwrap());
+
+    assert!(matches!(
+        world.get_mut_untracked::<&str>(a),
+        Err(ComponentError::MissingComponent(_))
+    ));
+    world.despawn(a).unwrap();
+    assert!(matches!(
+        world.get_mut_untracked::<i32>(a),
+        Err(ComponentError::NoSuchEntity)
+    ));
+    let reserved = world.reserve_entity();
+    assert!(matches!(
+        world.get_mut_untracked::<i32>(reserved),
+        Err(ComponentError::MissingComponent(_))
+    ));
+}
+
+#[test]
+fn get_many_mut() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2,));
+    let c = world.spawn((3,));
+    let d = world.spawn(("abc",));
+    world.clear_trackers();
+
+    let [x, y] = world.get_many_mut::<i32, 2>([a, c]).unwrap();
+    std::mem::swap(x, y);
+    assert_eq!(*world.get::<i32>(a).unwrap(), 3);
+    assert_eq!(*world.get::<i32>(c).unwrap(), 1);
+    let mut mutated = world
+        .query_mut::<Mutated<i32>>()
+        .into_iter()
+        .filter(|&(_, x)| x)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    mutated.sort();
+    assert_eq!(mutated, [a, c]);
+
+    assert!(matches!(
+        world.get_many_mut::<i32, 2>([b, d]),
+        Err(ComponentError::MissingComponent(_))
+    ));
+    world.despawn(c).unwrap();
+    assert_eq!(
+        world.get_many_mut::<i32, 3>([a, b, c]).err(),
+        Some(ComponentError::NoSuchEntity)
+    );
+    assert_eq!(world.get_many_mut::<i32, 0>([]).unwrap().len(), 0);
+}
+
+#[test]
+#[should_panic(expected = "requested more than once")]
+fn get_many_mut_duplicate() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.spawn((2,));
+    let _ = world.get_many_mut::<i32, 3>([a, b, a]);
+}
+
+#[test]
+fn query_many_archetypes() {
+    let mut world = World::new();
+    macro_rules! spawn_tagged {
+        ($($n:literal)*) => {
+            $(
+                world.spawn(($n, [0u8; $n]));
+                world.spawn(($n, [0u8; $n], true));
+            )*
+        };
+    }
+    // Enough component types that later ones don't share a word of the archetype bitsets
+    spawn_tagged!(
+        0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19
+        20 21 22 23 24 25 26 27 28 29 30 31 32 33 34 35 36 37 38 39
+        40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59
+        60 61 62 63 64 65 66 67 68 69
+    );
+    world.spawn((-1, "late"));
+
+    assert_eq!(world.query::<&i32>().iter().count(), 141);
+    assert_eq!(world.query::<(&i32, &bool)>().iter().count(), 70);
+    assert_eq!(world.query::<Without<bool, &i32>>().iter().count(), 71);
+    assert_eq!(world.query::<&i32>().with::<&str>().iter().count(), 1);
+    assert_eq!(world.query::<(&&str, Option<&bool>)>().iter().count(), 1);
+    assert_eq!(world.query::<&[u8; 69]>().iter().count(), 2);
+    assert_eq!(world.query::<(&[u8; 0], &[u8; 69])>().iter().count(), 0);
+    assert_eq!(world.query::<Or<&bool, &&str>>().iter().count(), 71);
+    assert_eq!(world.query_mut::<&mut [u8; 68]>().into_iter().count(), 2);
+    assert_eq!(
+        world
+            .query::<Without<[u8; 3], &i32>>()
+            .iter_batched(10)
+            .flatten()
+            .count(),
+        139
+    );
+
+    // Component types no archetype has
+    assert_eq!(world.query::<&u64>().iter().count(), 0);
+    assert_eq!(world.query::<&u64>().count(), 0);
+    assert_eq!(world.query::<Option<&u64>>().iter().count(), 141);
+    assert_eq!(world.query::<Without<u64, &i32>>().iter().count(), 141);
+    let mut query = PreparedQuery::<(&i32, &u64)>::default();
+    assert_eq!(query.query(&world).iter().count(), 0);
+    let e = world.spawn((5, 6u64));
+    assert_eq!(
+        query.query(&world).iter().collect::<Vec<_>>(),
+        [(e, (&5, &6))]
+    );
+    assert_eq!(world.query::<Without<u64, &i32>>().iter().count(), 141);
+}
+
+#[test]
+fn upsert() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct Id(u64);
+
+    let mut world = World::new();
+    let a = world.upsert(Id(1), (1, true));
+    let b = world.upsert(Id(2), (2,));
+    assert_ne!(a, b);
+    assert_eq!(world.upsert(Id(1), (10, "abc")), a);
+    assert_eq!(*world.get::<i32>(a).unwrap(), 10);
+    assert!(*world.get::<bool>(a).unwrap());
+    assert_eq!(*world.get::<Id>(a).unwrap(), Id(1));
+    assert_eq!(world.find_by_key(&Id(2)), Some(b));
+    assert_eq!(world.find_by_key(&Id(3)), None);
+
+    // A bundle can't change an entity's key
+    assert_eq!(world.upsert(Id(2), (Id(5), 20)), b);
+    assert_eq!(*world.get::<Id>(b).unwrap(), Id(2));
+    assert_eq!(world.find_by_key(&Id(5)), None);
+    assert_eq!(world.find_by_key(&Id(2)), Some(b));
+    assert_eq!(world.upsert(Id(2), (30,)), b);
+    let e = world.upsert(Id(6), (Id(7),));
+    assert_eq!(*world.get::<Id>(e).unwrap(), Id(6));
+    assert_eq!(world.find_by_key(&Id(6)), Some(e));
+    world.despawn(e).unwrap();
+
+    // Removing the key by any means forgets the entity
+    world.remove_one::<Id>(b).unwrap();
+    assert_eq!(world.find_by_key(&Id(2)), None);
+    let c = world.upsert(Id(2), (3,));
+    assert_ne!(c, b);
+    world.despawn(a).unwrap();
+    assert_eq!(world.find_by_key(&Id(1)), None);
+    world.clear();
+    assert_eq!(world.find_by_key(&Id(2)), None);
+
+    // Keys modified in place no longer match
+    let added = Arc::new(AtomicUsize::new(0));
+    let counter = added.clone();
+    world.on_add::<Id>(move |_, _| {
+        counter.fetch_add(1, Ordering::Relaxed);
+    });
+    let d = world.upsert(Id(4), ());
+    assert_eq!(added.load(Ordering::Relaxed), 1);
+    world.get_mut::<Id>(d).unwrap().0 = 40;
+    assert_eq!(world.find_by_key(&Id(4)), None);
+    assert_ne!(world.upsert(Id(4), ()), d);
+}
+
+#[test]
+fn query_iter_filter() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.spawn((2, true));
+    let c = world.spawn((3, "abc"));
+    let _ = world.spawn(("def",));
+
+    let mut visited = Vec::new();
+    let mut query = world.query::<&i32>();
+    let results = query
+        .iter_filter(|archetype| {
+            visited.push(archetype.len());
+            !archetype.has::<bool>()
+        })
+        .map(|(e, &x)| (e, x))
+        .collect::<Vec<_>>();
+    assert_eq!(results, [(a, 1), (c, 3)]);
+    // Only archetypes matching the query are offered to the predicate
+    assert_eq!(visited.len(), 3);
+    drop(query);
+
+    let mut query = world.query::<&mut i32>();
+    for (_, mut x) in query.iter_filter(|archetype| archetype.has::<&str>()) {
+        *x *= 10;
+    }
+    drop(query);
+    assert_eq!(*world.get::<i32>(b).unwrap(), 2);
+    assert_eq!(*world.get::<i32>(c).unwrap(), 30);
+}
+
+#[test]
+fn spawn_batch_exact() {
+    let mut world = World::new();
+    let entities = world
+        .spawn_batch_exact((0..100).map(|i| (i, true)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(entities.len(), 100);
+    for (i, &e) in entities.iter().enumerate() {
+        assert_eq!(*world.get::<i32>(e).unwrap(), i as i32);
+    }
+    let archetype = world.archetypes().find(|x| x.has::<i32>()).unwrap();
+    assert_eq!(archetype.capacity(), 100);
+
+    // Growing an existing archetype is exact too
+    world.spawn_batch_exact((0..5).map(|i| (i, false)));
+    let archetype = world.archetypes().find(|x| x.has::<i32>()).unwrap();
+    assert_eq!(archetype.capacity(), 105);
+    assert_eq!(archetype.len(), 105);
+}
+
+#[test]
+fn try_spawn_batch() {
+    let mut world = World::new();
+    let mut x = EntityBuilder::new();
+    x.add(1).add(true);
+    let mut y = EntityBuilder::new();
+    y.add("abc");
+    let items = vec![
+        Ok(x.build()),
+        Err("invalid"),
+        Ok(y.build()),
+        Err("also invalid"),
+    ];
+    let results = world.try_spawn_batch(items).collect::<Vec<_>>();
+    assert_eq!(results.len(), 4);
+    let a = results[0].unwrap();
+    let b = results[2].unwrap();
+    assert_eq!(results[1], Err("invalid"));
+    assert_eq!(results[3], Err("also invalid"));
+    assert_eq!(*world.get::<i32>(a).unwrap(), 1);
+    assert_eq!(*world.get::<&str>(b).unwrap(), "abc");
+
+    // Rejected items don't consume IDs
+    assert_eq!(world.len(), 2);
+    assert_eq!(world.spawn(()).id(), 2);
+}
+
+#[test]
+fn spawn_n() {
+    use std::sync::Arc;
+
+    let mut world = World::new();
+    let shared = Arc::new(());
+    world.spawn_n(3, (shared.clone(), 1)).next();
+    assert_eq!(Arc::strong_count(&shared), 4);
+    assert_eq!(world.len(), 3);
+    let archetype = world.archetypes().find(|x| x.has::<i32>()).unwrap();
+    assert_eq!(archetype.capacity(), 3);
+    assert!(world
+        .query::<(Added<i32>, Added<Arc<()>>)>()
+        .iter()
+        .all(|(_, (a, b))| a && b));
+
+    assert_eq!(world.spawn_n(0, (shared.clone(), 1)).len(), 0);
+    assert_eq!(Arc::strong_count(&shared), 4);
+}
+
+#[test]
+fn query_many() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, false, "b"));
+    let c = world.spawn(("c",));
+    {
+        let (numbers, flags, names) = world.query_many::<(&mut i32, &bool, &&str)>();
+        let flags = flags.into_iter().collect::<Vec<_>>();
+        let names = names.into_iter().map(|(e, _)| e).collect::<Vec<_>>();
+        for (e, mut x) in numbers {
+            let flag = flags.iter().find(|&&(f, _)| f == e).unwrap().1;
+            if *flag {
+                *x += 10;
+            }
+            *x += names.len() as i32;
+        }
+    }
+    assert_eq!(*world.get::<i32>(a).unwrap(), 13);
+    assert_eq!(*world.get::<i32>(b).unwrap(), 4);
+    assert!(world.get::<i32>(c).is_err());
+
+    // Shared borrows of the same component don't conflict
+    let (x, y) = world.query_many::<(&i32, (&i32, &bool))>();
+    assert_eq!(x.into_iter().count(), 2);
+    assert_eq!(y.into_iter().count(), 2);
+}
+
+#[test]
+#[should_panic(expected = "queries 0 and 1 conflict over bool")]
+fn query_many_conflict() {
+    let mut world = World::new();
+    world.spawn((1, true));
+    world.query_many::<(&mut bool, (&i32, &bool))>();
+}
+
+#[test]
+fn resolve_index() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.spawn((2,));
+    assert_eq!(world.resolve_index(a.id()), Some(a));
+    assert_eq!(world.resolve_index(b.id()), Some(b));
+    assert_eq!(world.resolve_index(1000), None);
+
+    world.despawn(a).unwrap();
+    assert_eq!(world.resolve_index(a.id()), None);
+    let reserved = world.reserve_entity();
+    assert_eq!(reserved.id(), a.id());
+    assert_eq!(world.resolve_index(a.id()), None);
+    world.flush();
+    assert_eq!(world.resolve_index(a.id()), Some(reserved));
+    assert_ne!(reserved, a);
+}
+
+#[test]
+fn entity_only_query() {
+    struct Dead;
+    let mut world = World::new();
+    let a = world.spawn((1, Dead));
+    let _ = world.spawn((2,));
+    let c = world.spawn(("abc", Dead));
+    // No component data is borrowed, so this coexists with unique borrows of the filter type
+    let mut unique = world.query::<&mut Dead>();
+    let _unique = unique.iter();
+    let mut query = world.query::<With<Dead, ()>>();
+    let mut dead = query.iter().map(|(e, ())| e).collect::<Vec<_>>();
+    dead.sort();
+    assert_eq!(dead, [a, c]);
+}
+
+#[test]
+fn bundle_type_info() {
+    let mut builder = EntityBuilder::new();
+    builder.add(1u8).add("abc").add(2u64);
+    let built = builder.build();
+    let info = built.type_info();
+    let mut world = World::new();
+    let e = world.spawn(built);
+    assert_eq!(
+        info.iter().map(|x| x.id()).collect::<Vec<_>>(),
+        world
+            .entity(e)
+            .unwrap()
+            .component_types()
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(info, (2u64, "abc", 1u8).type_info());
+    #[cfg(debug_assertions)]
+    assert_eq!(
+        TypeInfo::of::<u8>().type_name(),
+        Some(std::any::type_name::<u8>())
+    );
+}
+
+#[test]
+fn entity_location() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, false));
+    let c = world.spawn((3,));
+    let locate = |world: &World, e| {
+        let (archetype, row) = world.location(e).unwrap();
+        let archetype = world.archetype(archetype).unwrap();
+        assert_eq!(archetype.ids()[row as usize], e.id());
+        archetype.get::<i32>().unwrap()[row as usize]
+    };
+    assert_eq!(locate(&world, a), 1);
+    assert_eq!(locate(&world, b), 2);
+    assert_eq!(locate(&world, c), 3);
+    assert_ne!(world.location(a).unwrap().0, world.location(c).unwrap().0);
+
+    // Rows change as entities move
+    world.despawn(a).unwrap();
+    assert!(world.location(a).is_none());
+    assert_eq!(world.location(b).unwrap().1, 0);
+    world.insert_one(b, "abc").unwrap();
+    assert_eq!(locate(&world, b), 2);
+
+    let reserved = world.reserve_entity();
+    assert!(world.location(reserved).is_none());
+    world.flush();
+    assert_eq!(world.location(reserved).unwrap().0, 0);
+    assert!(world.archetype(world.archetypes().len() as u32).is_none());
+}
+
+#[test]
+fn columnar_change_flags() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, false));
+    world.clear_trackers();
+    let c = world.spawn((3, true));
+    *world.get_mut::<i32>(a).unwrap() = 10;
+
+    let archetype = world.archetypes().find(|x| x.has::<bool>()).unwrap();
+    assert_eq!(archetype.ids(), &[a.id(), b.id(), c.id()]);
+    assert_eq!(
+        *archetype.added_flags::<i32>().unwrap(),
+        [false, false, true]
+    );
+    assert_eq!(
+        *archetype.mutated_flags::<i32>().unwrap(),
+        [true, false, false]
+    );
+    assert_eq!(*archetype.mutated_flags::<bool>().unwrap(), [false; 3]);
+    assert!(archetype.added_flags::<&str>().is_none());
+
+    // The flags hold a shared borrow of their column
+    let flags = archetype.mutated_flags::<i32>().unwrap();
+    assert!(std::panic::catch_unwind(|| archetype.get_mut::<i32>()).is_err());
+    drop(flags);
+    drop(archetype.get_mut::<i32>().unwrap());
+
+    world.clear_trackers();
+    let archetype = world.archetypes().find(|x| x.has::<bool>()).unwrap();
+    assert_eq!(*archetype.added_flags::<i32>().unwrap(), [false; 3]);
+    assert_eq!(*archetype.mutated_flags::<i32>().unwrap(), [false; 3]);
+}
+
+#[test]
+fn empty_entity_ref() {
+    let mut world = World::new();
+    let e = world.spawn(());
+    let r = world.entity(e).unwrap();
+    assert_eq!(r.entity(), e);
+}
+
+#[test]
+fn query_or() {
+    let mut world = World::new();
+    let e = world.spawn(("abc", 123));
+    let _ = world.spawn(("def",));
+    let f = world.spawn(("ghi", true));
+    let g = world.spawn(("jkl", 456, false));
+    let results = world
+        .query::<(&&str, Or<&i32, &bool>)>()
+        .iter()
+        .map(|(handle, (&s, value))| (handle, s, value.cloned()))
+        .collect::<Vec<_>>();
+    assert_eq!(results.len(), 3);
+    assert!(results.contains(&(e, "abc", Or::Left(123))));
+    assert!(results.contains(&(f, "ghi", Or::Right(true))));
+    assert!(results.contains(&(g, "jkl", Or::Both(456, false))));
+}
+
+#[test]
+fn filters_do_not_borrow() {
+    let mut world = World::new();
+    let a = world.spawn((123, true));
+    let b = world.spawn((456,));
+
+    // A unique borrow of the filtered component doesn't conflict with the filter
+    let mut flag = world.get_mut::<bool>(a).unwrap();
+    let with = world
+        .query::<With<bool, &i32>>()
+        .iter()
+        .map(|(e, &i)| (e, i))
+        .collect::<Vec<_>>();
+    let without = world
+        .query::<Without<bool, &i32>>()
+        .iter()
+        .map(|(e, &i)| (e, i))
+        .collect::<Vec<_>>();
+    *flag = false;
+    drop(flag);
+    assert_eq!(with, [(a, 123)]);
+    assert_eq!(without, [(b, 456)]);
+
+    // Nor does a unique borrow of the same component elsewhere in the query
+    for (_, (&i, mut flag)) in world.query_mut::<(With<bool, &i32>, &mut bool)>() {
+        *flag = i == 123;
+    }
+    assert!(*world.get::<bool>(a).unwrap());
+}
+
+#[test]
+fn world_iter_len() {
+    let mut world = World::new();
+    world.spawn((123,));
+    world.spawn((456,));
+    world.spawn((true,));
+    let _ = world.reserve_entity();
+    let mut iter = world.iter();
+    assert_eq!(iter.len(), 3);
+    iter.next().unwrap();
+    assert_eq!(iter.len(), 2);
+    iter.next().unwrap();
+    iter.next().unwrap();
+    assert_eq!(iter.len(), 0);
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn world_iter_mut() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2,));
+    let c = world.spawn((3, false));
+    let reserved = world.reserve_entity();
+    world.clear_trackers();
+
+    // Archetype order, then slot order, and the same for shared and unique iteration
+    let order = (&world).into_iter().map(|x| x.entity()).collect::<Vec<_>>();
+    assert_eq!(order, &[a, c, b]);
+    let mut iter = world.iter_mut();
+    assert_eq!(iter.len(), 3);
+    let mut first = iter.next().unwrap();
+    let mut second = iter.next().unwrap();
+    assert_eq!((first.entity(), second.entity()), (a, c));
+    // Handles to distinct entities may be used simultaneously
+    *first.get_mut::<i32>().unwrap() += 10;
+    *second.get_mut::<i32>().unwrap() += 10;
+    assert!(second.get::<bool>().is_some_and(|x| !*x));
+    assert_eq!(second.len(), 2);
+    assert!(iter.next().unwrap().get::<bool>().is_none());
+    assert!(iter.next().is_none());
+
+    assert!(!(&mut world).into_iter().any(|x| x.entity() == reserved));
+    assert_eq!(*world.get::<i32>(a).unwrap(), 11);
+    assert_eq!(*world.get::<i32>(c).unwrap(), 13);
+    let mut mutated = world
+        .query::<Mutated<i32>>()
+        .iter()
+        .filter(|&(_, x)| x)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    mutated.sort();
+    assert_eq!(mutated, &[a, c]);
+}
+
+#[test]
+fn observers() {
+    use std::sync::{Arc, Mutex};
+
+    let mut world = World::new();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let add_log = log.clone();
+    world.on_add::<i32>(move |e, &x| add_log.lock().unwrap().push(("add", e, x)));
+    let remove_log = log.clone();
+    world.on_remove::<i32>(move |e, &x| remove_log.lock().unwrap().push(("remove", e, x)));
+    let take = || std::mem::take(&mut *log.lock().unwrap());
+
+    let a = world.spawn((1, true));
+    let b = world.spawn(("abc",));
+    assert_eq!(take(), [("add", a, 1)]);
+
+    // Insertion, including replacement and migration between archetypes
+    world.insert(b, (2, false)).unwrap();
+    world.insert_one(b, 3).unwrap();
+    assert_eq!(take(), [("add", b, 2), ("remove", b, 2), ("add", b, 3)]);
+
+    // Failed removals don't notify
+    assert!(world.remove::<(i32, f32)>(b).is_err());
+    assert_eq!(world.remove_one::<i32>(b), Ok(3));
+    assert_eq!(take(), [("remove", b, 3)]);
+
+    world.despawn(a).unwrap();
+    assert_eq!(take(), [("remove", a, 1)]);
+
+    let batch = world.spawn_batch((4..6).map(|x| (x,))).collect::<Vec<_>>();
+    assert_eq!(take(), [("add", batch[0], 4), ("add", batch[1], 5)]);
+
+    let mut batch_ty = ColumnBatchType::new();
+    batch_ty.add::<i32>();
+    let mut column = batch_ty.into_batch(1);
+    column.writer::<i32>().unwrap().push(6).unwrap();
+    let c = world
+        .spawn_column_batch(column.build().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(take(), [("add", c[0], 6)]);
+
+    world.spawn_at(c[0], (7,));
+    assert_eq!(take(), [("remove", c[0], 6), ("add", c[0], 7)]);
+
+    world.clear();
+    let mut cleared = take();
+    cleared.sort();
+    let mut expected = vec![
+        ("remove", batch[0], 4),
+        ("remove", batch[1], 5),
+        ("remove", c[0], 7),
+    ];
+    expected.sort();
+    assert_eq!(cleared, expected);
+}
+
+#[test]
+fn observer_panic_safety() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::Arc;
+
+    let counter = Arc::new(());
+
+    // Panicking removal callbacks leave entities untouched
+    let mut world = World::new();
+    world.on_remove::<Arc<()>>(|_, _| panic!("on_remove"));
+    let a = world.spawn((counter.clone(), 1));
+    let b = world.spawn((counter.clone(), 2));
+    assert!(catch_unwind(AssertUnwindSafe(|| world.despawn(a))).is_err());
+    assert!(catch_unwind(AssertUnwindSafe(|| world.despawn_batch([a, b]))).is_err());
+    assert!(catch_unwind(AssertUnwindSafe(|| world.insert_one(a, counter.clone()))).is_err());
+    assert_eq!(Arc::strong_count(&counter), 3);
+    assert_eq!(*world.get::<i32>(a).unwrap(), 1);
+    assert_eq!(*world.get::<i32>(b).unwrap(), 2);
+    assert_eq!(world.len(), 2);
+    drop(world);
+    assert_eq!(Arc::strong_count(&counter), 1);
+
+    // Panicking addition callbacks see complete entities
+    let mut world = World::new();
+    world.on_add::<Arc<()>>(|_, _| panic!("on_add"));
+    let a = world.spawn((1,));
+    assert!(catch_unwind(AssertUnwindSafe(|| world.insert_one(a, counter.clone()))).is_err());
+    assert!(catch_unwind(AssertUnwindSafe(|| world.insert_one(a, counter.clone()))).is_err());
+    assert_eq!(Arc::strong_count(&counter), 2);
+    assert!(world.get::<Arc<()>>(a).is_ok());
+    let mut batch_ty = ColumnBatchType::new();
+    batch_ty.add::<Arc<()>>();
+    let mut batch = batch_ty.into_batch(2);
+    let mut writer = batch.writer::<Arc<()>>().unwrap();
+    writer.push(counter.clone()).unwrap();
+    writer.push(counter.clone()).unwrap();
+    let batch = batch.build().unwrap();
+    assert!(catch_unwind(AssertUnwindSafe(|| world.spawn_column_batch(batch).count())).is_err());
+    assert_eq!(world.len(), 3);
+    let c = world.spawn((3,));
+    assert_eq!(world.len(), 4);
+    assert_eq!(*world.get::<i32>(c).unwrap(), 3);
+    drop(world);
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+#[test]
+fn hierarchy() {
+    let mut world = World::new();
+    let root = world.spawn(());
+    let a = world.spawn((1,));
+    let b = world.spawn((2,));
+    let c = world.spawn((3,));
+    world.set_parent(a, root).unwrap();
+    world.set_parent(b, root).unwrap();
+    world.set_parent(c, a).unwrap();
+    assert_eq!(&**world.get::<Children>(root).unwrap(), &[a, b]);
+    assert_eq!(*world.get::<Parent>(c).unwrap(), Parent(a));
+
+    // Cycles are rejected without modifying anything
+    assert_eq!(world.set_parent(root, c), Err(HierarchyError::Cycle));
+    assert_eq!(world.set_parent(a, a), Err(HierarchyError::Cycle));
+    assert!(world.get::<Parent>(root).is_err());
+    assert_eq!(*world.get::<Parent>(a).unwrap(), Parent(root));
+
+    // Reparenting removes the stale link
+    world.set_parent(c, b).unwrap();
+    assert!(world.get::<Children>(a).unwrap().is_empty());
+    assert_eq!(&**world.get::<Children>(b).unwrap(), &[c]);
+
+    assert_eq!(world.remove_parent(a), Ok(Some(root)));
+    assert_eq!(world.remove_parent(a), Ok(None));
+    assert_eq!(&**world.get::<Children>(root).unwrap(), &[b]);
+
+    // Despawning a parent orphans its children
+    world.set_parent(a, c).unwrap();
+    world.despawn(c).unwrap();
+    assert!(world.get::<Parent>(a).is_err());
+    assert!(world.get::<Children>(b).unwrap().is_empty());
+
+    world.set_parent(a, b).unwrap();
+    world.despawn_recursive(root).unwrap();
+    assert_eq!(world.len(), 0);
+    assert_eq!(world.despawn_recursive(root), Err(NoSuchEntity));
+    assert_eq!(world.set_parent(a, b), Err(HierarchyError::NoSuchEntity));
+}
+
+#[test]
+fn hierarchy_traversal() {
+    let mut world = World::new();
+    let root = world.spawn(());
+    let a = world.spawn(());
+    let b = world.spawn(());
+    let c = world.spawn(());
+    let d = world.spawn(());
+    world.set_parent(a, root).unwrap();
+    world.set_parent(b, a).unwrap();
+    world.set_parent(c, root).unwrap();
+    world.set_parent(d, b).unwrap();
+    assert_eq!(world.descendants(root).collect::<Vec<_>>(), [a, b, d, c]);
+    assert_eq!(world.descendants(a).collect::<Vec<_>>(), [b, d]);
+    assert_eq!(world.descendants(c).count(), 0);
+    assert_eq!(world.ancestors(d).collect::<Vec<_>>(), [b, a, root]);
+    assert_eq!(world.ancestors(root).count(), 0);
+
+    // Hierarchy components are only borrowed while each entity is visited
+    for entity in world.descendants(root) {
+        assert!(world.get_mut::<Children>(root).is_ok());
+        assert!(world.get::<Parent>(entity).is_ok());
+    }
+
+    // Cycles introduced by inserting hierarchy components directly don't hang traversal
+    let x = world.spawn(());
+    world.set_parent(root, x).unwrap();
+    let children = (*world.get::<Children>(x).unwrap()).clone();
+    world.insert_one(d, children).unwrap();
+    world.insert_one(root, Parent(d)).unwrap();
+    assert_eq!(world.descendants(root).collect::<Vec<_>>(), [a, b, d, c]);
+    assert_eq!(world.descendants(a).collect::<Vec<_>>(), [b, d, root, c]);
+    assert_eq!(world.ancestors(d).count(), world.len() as usize);
+
+    world.despawn(x).unwrap();
+    world.despawn(c).unwrap();
+    assert_eq!(world.descendants(c).count(), 0);
+    assert_eq!(world.ancestors(c).count(), 0);
+}
+
+#[test]
+fn hierarchy_inconsistent_links() {
+    let mut world = World::new();
+    let root = world.spawn(());
+    let a = world.spawn(());
+    let b = world.spawn(());
+    world.set_parent(a, root).unwrap();
+    world.set_parent(b, root).unwrap();
+
+    // Stale and duplicate child handles don't abort recursive despawning
+    let children = (*world.get::<Children>(root).unwrap()).clone();
+    world.insert_one(b, children).unwrap();
+    world.despawn_recursive(root).unwrap();
+    assert_eq!(world.len(), 0);
+
+    // Cycles introduced by inserting `Parent` directly don't hang `set_parent`
+    let a = world.spawn((Parent(root),));
+    let b = world.spawn((Parent(a),));
+    world.insert_one(a, Parent(b)).unwrap();
+    let c = world.spawn(());
+    world.set_parent(c, a).unwrap();
+    assert_eq!(*world.get::<Parent>(c).unwrap(), Parent(a));
+}
+
+#[test]
+fn satisfies() {
+    let mut world = World::new();
+    let a = world.spawn((123, true));
+    let b = world.spawn((456,));
+    let _ = world.spawn((true,));
+
+    // A unique borrow of the tested component doesn't conflict with the query
+    let mut flag = world.get_mut::<bool>(a).unwrap();
+    let mut results = world
+        .query::<(&i32, Satisfies<bool>)>()
+        .iter()
+        .map(|(e, (&i, has_bool))| (e, i, has_bool))
+        .collect::<Vec<_>>();
+    *flag = false;
+    drop(flag);
+    results.sort();
+    assert_eq!(results, [(a, 123, true), (b, 456, false)]);
+
+    // Alone, it matches every entity
+    assert_eq!(world.query::<Satisfies<&str>>().iter().len(), 3);
+    assert!(world.query::<Satisfies<&str>>().iter().all(|(_, x)| !x));
+}
+
+#[test]
+fn copied() {
+    let mut world = World::new();
+    let a = world.spawn((1, 2.0f32));
+    let b = world.spawn((3,));
+    for (_, (x, mut y)) in world.query::<(Copied<i32>, &mut f32)>().iter() {
+        *y *= x as f32 + 1.0;
+    }
+    assert_eq!(*world.get::<f32>(a).unwrap(), 4.0);
+
+    // Reads don't conflict with other shared borrows
+    let shared = world.get::<i32>(b).unwrap();
+    let mut values = world
+        .query::<(Copied<i32>, Option<Copied<f32>>)>()
+        .iter()
+        .map(|(_, x)| x)
+        .collect::<Vec<_>>();
+    drop(shared);
+    values.sort_by_key(|&(x, _)| x);
+    assert_eq!(values, [(1, Some(4.0)), (3, None)]);
+    assert!(world.get_mut::<i32>(a).is_ok());
+    assert_eq!(world.query_one_mut::<Copied<i32>>(b), Ok(3));
+}
+
+#[test]
+#[should_panic(expected = "query violates a unique borrow of i32")]
+fn copied_conflict() {
+    let mut world = World::new();
+    world.spawn((1,));
+    world.query::<(Copied<i32>, &mut i32)>().iter().count();
+}
+
+#[test]
+fn query_into_iter() {
+    fn flags(world: &World) -> impl ExactSizeIterator<Item = (Entity, bool)> + '_ {
+        world.query::<With<i32, Copied<bool>>>().into_iter()
+    }
+
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    world.spawn((false,));
+    let iter = flags(&world);
+    assert_eq!(iter.len(), 1);
+    // The components stay borrowed until the iterator is dropped
+    assert!(world.get_mut::<bool>(a).is_err());
+    assert_eq!(iter.collect::<Vec<_>>(), [(a, true)]);
+    assert!(world.get_mut::<bool>(a).is_ok());
+}
+
+#[test]
+fn query_or_nested() {
+    let mut world = World::new();
+    let a = world.spawn((123,));
+    let b = world.spawn((true,));
+    let c = world.spawn(("abc",));
+    let _ = world.spawn((1.0f32,));
+    let mut results = world
+        .query::<Or<&i32, Or<&bool, &&str>>>()
+        .iter()
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    results.sort();
+    let mut expected = vec![a, b, c];
+    expected.sort();
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn chained_filters() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let _ = world.spawn((2, true, "frozen"));
+    let _ = world.spawn((3,));
+
+    let mut awake = world.get_mut::<bool>(a).unwrap();
+    let mut frozen = world.query::<&mut &str>();
+    frozen.iter();
+    for (e, mut x) in world
+        .query::<&mut i32>()
+        .with::<bool>()
+        .without::<&str>()
+        .iter()
+    {
+        assert_eq!(e, a);
+        *x = 42;
+    }
+    *awake = false;
+    drop(awake);
+    drop(frozen);
+    assert_eq!(*world.get::<i32>(a).unwrap(), 42);
+}
+
+#[test]
+fn resources() {
+    let mut world = World::new();
+    assert!(world.get_resource::<i32>().is_none());
+    assert!(world.insert_resource(1i32).is_none());
+    assert_eq!(world.insert_resource(2i32), Some(1));
+    assert!(world.contains_resource::<i32>());
+    {
+        let a = world.get_resource::<i32>().unwrap();
+        let b = world.get_resource::<i32>().unwrap();
+        assert_eq!(*a + *b, 4);
+    }
+    *world.get_resource_mut::<i32>().unwrap() += 1;
+    assert_eq!(*world.get_resource::<i32>().unwrap(), 3);
+    // Resources don't show up in queries
+    assert_eq!(world.query::<&i32>().iter().count(), 0);
+    assert_eq!(world.remove_resource::<i32>(), Some(3));
+    assert!(!world.contains_resource::<i32>());
+}
+
+#[test]
+#[should_panic(expected = "already borrowed")]
+fn resource_borrow_conflict() {
+    let mut world = World::new();
+    world.insert_resource(1i32);
+    let _a = world.get_resource::<i32>().unwrap();
+    let _b = world.get_resource_mut::<i32>().unwrap();
+}
+
+#[test]
+fn events() {
+    use std::sync::Arc;
+
+    let mut world = World::new();
+    assert_eq!(world.drain_events::<i32>().count(), 0);
+
+    // A consumer running before the producer sees events sent in the previous frame
+    world.send_event(1);
+    world.send_event(true);
+    world.update_events();
+    world.send_event(2);
+    assert_eq!(world.drain_events::<i32>().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(world.drain_events::<i32>().count(), 0);
+
+    // Undrained events last two updates
+    let dropped = Arc::new(());
+    world.send_event(dropped.clone());
+    world.update_events();
+    assert_eq!(Arc::strong_count(&dropped), 2);
+    world.update_events();
+    assert_eq!(Arc::strong_count(&dropped), 1);
+    assert_eq!(world.drain_events::<bool>().count(), 0);
+
+    // Partially consumed drains discard the rest
+    world.send_event(3);
+    world.send_event(4);
+    assert_eq!(world.drain_events::<i32>().next(), Some(3));
+    assert_eq!(world.drain_events::<i32>().count(), 0);
+}
+
+#[test]
+fn batch_columns() {
+    let mut world = World::new();
+    let ents = (0..10).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    world.spawn((10, true));
+    world.clear_trackers();
+
+    {
+        let mut query = world.query::<(&i32, Option<&bool>)>();
+        let mut batches = query.iter_batched(4);
+        let mut batch = batches.next().unwrap();
+        assert_eq!(batch.entities().collect::<Vec<_>>(), &ents[0..4]);
+        batch.next().unwrap();
+        assert_eq!(batch.entities().collect::<Vec<_>>(), &ents[1..4]);
+        assert_eq!(batch.column::<i32>().unwrap(), &[1, 2, 3]);
+        assert!(batch.column::<bool>().is_none());
+        assert!(batch.column_mut::<i32>().is_none());
+    }
+
+    let mut seen = 0;
+    for mut batch in world.query::<(&mut i32, Option<&bool>)>().iter_batched(4) {
+        let n = batch.entities().len();
+        assert!(n <= 4);
+        seen += n;
+        let column = batch.column_mut::<i32>().unwrap();
+        assert_eq!(column.len(), n);
+        for x in column {
+            *x += 100;
+        }
+        assert!(batch.next().is_none());
+    }
+    assert_eq!(seen, 11);
+    let mut mutated = world
+        .query::<(&i32, Mutated<i32>)>()
+        .iter()
+        .map(|(_, (&x, m))| {
+            assert!(m);
+            x
+        })
+        .collect::<Vec<_>>();
+    mutated.sort_unstable();
+    assert_eq!(mutated, (100..111).collect::<Vec<_>>());
+}
+
+#[test]
+fn batch_columns_filtered() {
+    let mut world = World::new();
+    let ents = (0..6).map(|i| world.spawn((i * 10,))).collect::<Vec<_>>();
+    world.clear_trackers();
+    *world.get_mut::<i32>(ents[3]).unwrap() += 1;
+    *world.get_mut::<i32>(ents[4]).unwrap() += 1;
+
+    let mut query = world.query::<ChangedFilter<i32, &i32>>();
+    let mut batch = query.iter_archetypes().next().unwrap();
+    let check = |column: &[i32], entities: Vec<Entity>| {
+        assert_eq!(column.len(), entities.len());
+        for (&x, e) in column.iter().zip(entities) {
+            assert_eq!(x, *world.get::<i32>(e).unwrap());
+        }
+    };
+    check(batch.column::<i32>().unwrap(), batch.entities().collect());
+    assert_eq!(batch.column::<i32>().unwrap(), &[0, 10, 20, 31, 41, 50]);
+    assert_eq!(*batch.next().unwrap().1, 31);
+    check(batch.column::<i32>().unwrap(), batch.entities().collect());
+    assert_eq!(batch.column::<i32>().unwrap(), &[41, 50]);
+}
+
+#[test]
+fn despawn_batch() {
+    use std::sync::{Arc, Mutex};
+
+    let mut world = World::new();
+    let removed = Arc::new(Mutex::new(Vec::new()));
+    let log = removed.clone();
+    world.on_remove::<i32>(move |_, &x| log.lock().unwrap().push(x));
+    let ents = (0..20)
+        .map(|i| {
+            if i % 3 == 0 {
+                world.spawn((i, true))
+            } else {
+                world.spawn((i,))
+            }
+        })
+        .collect::<Vec<_>>();
+    let doomed = ents
+        .iter()
+        .copied()
+        .filter(|&e| *world.get::<i32>(e).unwrap() % 2 == 0)
+        .collect::<Vec<_>>();
+    // Duplicates and stale handles are ignored
+    let mut batch = doomed.clone();
+    batch.push(doomed[0]);
+    assert_eq!(world.despawn_batch(batch), 10);
+    assert_eq!(world.len(), 10);
+    for &e in &ents {
+        assert_eq!(world.contains(e), !doomed.contains(&e));
+        if let Ok(x) = world.get::<i32>(e) {
+            assert_eq!(*x % 2, 1);
+        }
+    }
+    let mut removed = removed.lock().unwrap().clone();
+    removed.sort_unstable();
+    assert_eq!(removed, [0, 2, 4, 6, 8, 10, 12, 14, 16, 18]);
+    assert_eq!(world.removed::<bool>().len(), 4);
+
+    let fresh = (0..10).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    for e in &doomed {
+        assert!(!fresh.contains(e));
+    }
+}
+
+#[test]
+fn despawn_later() {
+    let mut world = World::new();
+    let entities = (0..100).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    let dead = world.spawn(());
+    world.despawn(dead).unwrap();
+
+    // Queued from several threads while a query is borrowed
+    let query = world.query::<&i32>();
+    std::thread::scope(|s| {
+        for t in 0..4 {
+            let (world, entities) = (&world, &entities);
+            s.spawn(move || {
+                for &e in entities {
+                    if e.id() % 4 == t || e.id() % 10 == 0 {
+                        world.despawn_later(e);
+                    }
+                }
+            });
+        }
+    });
+    drop(query);
+    world.despawn_later(dead);
+    assert_eq!(world.len(), 100);
+
+    assert_eq!(world.apply_despawns(), 100);
+    assert_eq!(world.len(), 0);
+    assert_eq!(world.apply_despawns(), 0);
+}
+
+#[test]
+fn clear_invalidates_handles() {
+    let mut world = World::new();
+    let a = world.spawn((1,));
+    let b = world.spawn((2,));
+    world.clear();
+    assert_eq!(world.len(), 0);
+    assert!(!world.contains(a));
+    let c = world.spawn((3,));
+    let d = world.spawn((4,));
+    assert_eq!(world.len(), 2);
+    assert!(c != a && c != b && d != a && d != b);
+    assert!(world.get::<i32>(a).is_err());
+    assert_eq!(*world.get::<i32>(c).unwrap(), 3);
+}
+
+#[test]
+fn stale_handle_rejected() {
+    let mut world = World::new();
+    let ents = (0..10).map(|i| world.spawn((i,))).collect::<Vec<_>>();
+    let old = ents[5];
+    world.despawn(old).unwrap();
+    let new = world.spawn((42,));
+    assert_eq!(old.id(), new.id());
+    assert_ne!(old.generation(), new.generation());
+    assert!(!world.contains(old));
+    assert!(world.entity(old).is_err());
+    assert!(world.get::<i32>(old).is_err());
+    assert!(world.query_one::<&i32>(old).is_err());
+    assert_eq!(world.despawn(old), Err(NoSuchEntity));
+    assert_eq!(*world.get::<i32>(new).unwrap(), 42);
+}
+
+#[test]
+fn try_spawn_at() {
+    let mut world = World::new();
+    let a = Entity::from_bits(3 << 32 | 5).unwrap();
+    world.try_spawn_at(a, (1,)).unwrap();
+    assert_eq!(*world.get::<i32>(a).unwrap(), 1);
+
+    // Same ID with a different generation is still occupied
+    let b = Entity::from_bits(7 << 32 | 5).unwrap();
+    assert_eq!(world.try_spawn_at(b, (2,)), Err(EntityOccupied));
+    assert!(!world.contains(b));
+
+    // Fresh allocations fill the gaps below the restored ID without colliding
+    let fresh = (0..10).map(|i| world.spawn((i + 10,))).collect::<Vec<_>>();
+    assert!(fresh.iter().all(|e| e.id() != a.id()));
+    assert_eq!(world.len(), 11);
+    assert_eq!(*world.get::<i32>(a).unwrap(), 1);
+
+    world.despawn(a).unwrap();
+    world.try_spawn_at(b, (2,)).unwrap();
+    assert_eq!(*world.get::<i32>(b).unwrap(), 2);
+}
+
+#[test]
+fn archetype_columns() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2, true));
+    world.spawn(("abc",));
+    world.clear_trackers();
+
+    let archetype = world.archetypes().find(|x| x.has::<i32>()).unwrap();
+    let ids = archetype.ids().to_vec();
+    assert_eq!(ids, [a.id(), b.id()]);
+    {
+        let column = archetype.get::<i32>().unwrap();
+        assert_eq!(*column, [1, 2]);
+        let mut column = archetype.get_mut::<bool>().unwrap();
+        column[1] = false;
+    }
+    assert!(archetype.get_mut::<f32>().is_none());
+    assert!(!*world.get::<bool>(b).unwrap());
+    assert_eq!(
+        world
+            .query::<Mutated<bool>>()
+            .iter()
+            .filter(|&(_, m)| m)
+            .count(),
+        2
+    );
+    assert_eq!(
+        world
+            .query::<Mutated<i32>>()
+            .iter()
+            .filter(|&(_, m)| m)
+            .count(),
+        0
+    );
+}
+
+#[test]
+#[should_panic(expected = "already borrowed")]
+fn archetype_column_borrow_conflict() {
+    let mut world = World::new();
+    world.spawn((1,));
+    let mut query = world.query::<&mut i32>();
+    let _items = query.iter();
+    let archetype = world.archetypes().find(|x| x.has::<i32>()).unwrap();
+    let _column = archetype.get_mut::<i32>();
+}
+
+#[test]
+fn take_entity() {
+    use std::sync::Arc;
+
+    let mut staging = World::new();
+    let mut world = World::new();
+    let rc = Arc::new(());
+    let parent = staging.spawn(());
+    let a = staging.spawn((rc.clone(), 7u8));
+    let b = staging.spawn((1, rc.clone()));
+    let child = staging.spawn(());
+    staging.set_parent(a, parent).unwrap();
+    staging.set_parent(child, a).unwrap();
+
+    let mut taken = staging.take(a).unwrap();
+    assert_eq!(taken.entity(), a);
+    assert!(!staging.contains(a));
+    assert!(staging.take(a).is_err());
+    assert!(staging.get::<Parent>(child).is_err());
+    assert!(staging.get::<Children>(parent).unwrap().is_empty());
+    assert_eq!(staging.removed::<u8>(), &[a]);
+    // Hierarchy links don't survive the move
+    assert!(!taken.has::<Parent>() && !taken.has::<Children>());
+    *taken.get_mut::<u8>().unwrap() += 1;
+    // The entity that filled the vacated slot is still reachable
+    assert_eq!(*staging.get::<i32>(b).unwrap(), 1);
+
+    let moved = world.spawn(taken);
+    assert_eq!(*world.get::<u8>(moved).unwrap(), 8);
+    assert_eq!(Arc::strong_count(&rc), 3);
+
+    // Components are dropped if the entity is never spawned
+    drop(staging.take(b).unwrap());
+    assert_eq!(Arc::strong_count(&rc), 2);
+}
+
+#[test]
+fn take_entity_components() {
+    use std::sync::Arc;
+
+    let mut world = World::new();
+    let rc = Arc::new(());
+    let a = world.spawn((rc.clone(), 1u8, "abc", 2.0f64));
+    let mut taken = world.take(a).unwrap();
+    // The handle is invalidated even while the components are still alive
+    assert!(!world.contains(a));
+    assert!(world.spawn(()).generation() != a.generation());
+
+    let extracted = taken.take::<Arc<()>>().unwrap();
+    assert!(taken.take::<Arc<()>>().is_none());
+    assert!(!taken.has::<Arc<()>>());
+    assert_eq!(taken.take::<&str>(), Some("abc"));
+    // Remaining components are still found after others are removed
+    assert_eq!(taken.get::<u8>(), Some(&1));
+    assert_eq!(taken.get::<f64>(), Some(&2.0));
+    assert_eq!(taken.component_types().count(), 2);
+    drop(taken);
+    assert_eq!(Arc::strong_count(&rc), 2);
+    drop(extracted);
+    assert_eq!(Arc::strong_count(&rc), 1);
+
+    // What's left can still be spawned
+    let a = world.spawn((1u8, "abc", rc.clone()));
+    let mut taken = world.take(a).unwrap();
+    taken.take::<&str>().unwrap();
+    let b = world.spawn(taken);
+    assert_eq!(*world.get::<u8>(b).unwrap(), 1);
+    assert!(world.get::<&str>(b).is_err());
+    assert_eq!(Arc::strong_count(&rc), 2);
+}
+
+#[test]
+fn take_entity_observers() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::{Arc, Mutex};
+
+    let mut world = World::new();
+    let removed = Arc::new(Mutex::new(Vec::new()));
+    let log = removed.clone();
+    world.on_remove::<u8>(move |entity, &x| log.lock().unwrap().push((entity, x)));
+    let parent = world.spawn(());
+    let a = world.spawn((1u8,));
+    world.set_parent(a, parent).unwrap();
+    let taken = world.take(a).unwrap();
+    assert_eq!(*removed.lock().unwrap(), [(a, 1)]);
+    assert_eq!(taken.get::<u8>(), Some(&1));
+
+    // A panicking callback can't leave the entity half-taken
+    let rc = Arc::new(());
+    world.on_remove::<Arc<()>>(|_, _| panic!("on_remove"));
+    let b = world.spawn((rc.clone(), 2u8));
+    let c = world.spawn((3u8,));
+    assert!(catch_unwind(AssertUnwindSafe(|| world.take(b))).is_err());
+    assert!(!world.contains(b));
+    assert_eq!(Arc::strong_count(&rc), 1);
+    assert_eq!(*world.get::<u8>(c).unwrap(), 3);
+}
+
+#[test]
+fn sixteen_element_tuples() {
+    struct C<const N: usize>(usize);
+
+    let mut world = World::new();
+    let e = world.spawn((
+        C::<0>(0),
+        C::<1>(1),
+        C::<2>(2),
+        C::<3>(3),
+        C::<4>(4),
+        C::<5>(5),
+        C::<6>(6),
+        C::<7>(7),
+        C::<8>(8),
+        C::<9>(9),
+        C::<10>(10),
+        C::<11>(11),
+        C::<12>(12),
+        C::<13>(13),
+        C::<14>(14),
+        C::<15>(15),
+    ));
+    world.spawn((C::<0>(0), C::<1>(1)));
+    type Q<'a> = (
+        &'a C<0>,
+        &'a C<1>,
+        &'a C<2>,
+        &'a C<3>,
+        &'a C<4>,
+        &'a C<5>,
+        &'a C<6>,
+        &'a C<7>,
+        &'a C<8>,
+        &'a C<9>,
+        &'a C<10>,
+        &'a C<11>,
+        &'a C<12>,
+        &'a C<13>,
+        &'a C<14>,
+        &'a mut C<15>,
+    );
+    let mut query = world.query::<Q>();
+    let items = query.iter().collect::<Vec<_>>();
+    assert_eq!(items.len(), 1);
+    let (entity, (c0, .., c14, c15)) = &items[0];
+    assert_eq!(*entity, e);
+    assert_eq!((c0.0, c14.0, c15.0), (0, 14, 15));
+}
+
+#[test]
+#[should_panic(expected = "query violates a unique borrow")]
+fn sixteen_element_tuple_duplicate_borrow() {
+    let mut world = World::new();
+    world.spawn((0u8, 0u16));
+    world.query_mut::<(
+        &u8,
+        &u16,
+        &u8,
+        &u16,
+        &u8,
+        &u16,
+        &u8,
+        &u16,
+        &u8,
+        &u16,
+        &u8,
+        &u16,
+        &u8,
+        &u16,
+        &u8,
+        &mut u16,
+    )>();
+}
+
+#[test]
+fn len_tracking() {
+    let mut world = World::new();
+    assert!(world.is_empty());
+    let a = world.spawn((1,));
+    let b = world.spawn((2, true));
+    world.spawn_batch((0..10).map(|i| (i,))).for_each(|_| {});
+    assert_eq!(world.len(), 12);
+
+    // Archetype migrations don't change the count
+    world.insert_one(a, "abc").unwrap();
+    world.remove_one::<bool>(b).unwrap();
+    assert_eq!(world.len(), 12);
+
+    world.reserve_entities(3).for_each(|_| {});
+    assert_eq!(world.len(), 12);
+    world.flush();
+    assert_eq!(world.len(), 15);
+
+    world.despawn(a).unwrap();
+    drop(world.take(b).unwrap());
+    assert_eq!(world.len(), 13);
+    assert_eq!(world.len() as usize, world.iter().count());
+    world.clear();
+    assert!(world.is_empty());
+}
+
+#[test]
+fn query_entity_refs() {
+    let mut world = World::new();
+    let a = world.spawn((1, "a"));
+    let b = world.spawn((2, true));
+    let c = world.spawn(("c",));
+    let mut query = world.query::<&mut i32>();
+    let iter = query.iter_entity_refs();
+    assert_eq!(iter.len(), 2);
+    let mut seen = Vec::new();
+    for (entity, mut x) in iter {
+        seen.push(entity.entity());
+        *x += 1;
+        if let Some(mut flag) = entity.get_mut::<bool>() {
+            *flag = false;
+        }
+        assert_eq!(entity.get::<&str>().is_some(), entity.entity() == a);
+    }
+    drop(query);
+    seen.sort();
+    assert_eq!(seen, [a, b]);
+    assert!(!*world.get::<bool>(b).unwrap());
+    assert_eq!(*world.get::<i32>(a).unwrap(), 2);
+    assert!(world.contains(c));
+}
+
+#[test]
+#[should_panic(expected = "already borrowed")]
+fn query_entity_refs_conflict() {
+    let mut world = World::new();
+    world.spawn((1,));
+    for (entity, _) in world.query::<&mut i32>().iter_entity_refs() {
+        entity.get::<i32>();
+    }
+}
+
+#[test]
+fn get_unchecked() {
+    let mut world = World::new();
+    let a = world.spawn((1, true));
+    let b = world.spawn((2,));
+    world.clear_trackers();
+    unsafe {
+        // Checked borrows coexist, since only the unchecked access is untracked
+        let _flag = world.get_mut::<bool>(a).unwrap();
+        *world.get_unchecked_mut::<i32>(a) += 10;
+        *world.get_unchecked_mut::<i32>(b) += *world.get_unchecked::<i32>(a);
+        assert!(*world.get_unchecked::<bool>(a));
+    }
+    assert_eq!(*world.get::<i32>(a).unwrap(), 11);
+    assert_eq!(*world.get::<i32>(b).unwrap(), 13);
+    assert_eq!(
+        world
+            .query::<Mutated<i32>>()
+            .iter()
+            .filter(|&(_, m)| m)
+            .count(),
+        0
+    );
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "not live")]
+fn get_unchecked_despawned() {
+    let mut world = World::new();
+    let e = world.spawn((1,));
+    world.despawn(e).unwrap();
+    world.spawn((2,));
+    unsafe {
+        world.get_unchecked::<i32>(e);
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "entity has no")]
+fn get_unchecked_missing() {
+    let mut world = World::new();
+    let e = world.spawn((1,));
+    unsafe {
+        world.get_unchecked::<bool>(e);
+    }
+}
+
+#[test]
+fn insert_batch() {
+    let mut world = World::new();
+    let a = world.spawn((1, "a"));
+    let b = world.spawn((2,));
+    let c = world.spawn((3, true));
+    let d = world.spawn((4,));
+    let e = world.spawn((5,));
+    let dead = world.spawn((6,));
+    world.despawn(dead).unwrap();
+    world.clear_trackers();
+
+    assert_eq!(world.insert_batch(&[d, a, dead, b, c, d], true), 4);
+    for (entity, x, flag) in [(a, 1, true), (b, 2, true), (c, 3, true), (d, 4, true)] {
+        assert_eq!(*world.get::<i32>(entity).unwrap(), x);
+        assert_eq!(*world.get::<bool>(entity).unwrap(), flag);
+    }
+    assert_eq!(*world.get::<&str>(a).unwrap(), "a");
+    assert_eq!(*world.get::<i32>(e).unwrap(), 5);
+    assert!(world.get::<bool>(e).is_err());
+
+    let mut added = world
+        .query::<Added<bool>>()
+        .iter()
+        .filter(|&(_, x)| x)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    added.sort();
+    let mut expected = vec![a, b, d];
+    expected.sort();
+    assert_eq!(added, expected);
+    let mutated = world
+        .query::<Mutated<bool>>()
+        .iter()
+        .filter(|&(_, x)| x)
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(mutated, [c]);
+}
+
+#[test]
+fn insert_batch_panic_safety() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // Panics on the third clone, counting live values
+    struct Fragile(Arc<AtomicUsize>, Arc<AtomicUsize>);
+    impl Clone for Fragile {
+        fn clone(&self) -> Self {
+            assert!(self.1.fetch_add(1, Ordering::Relaxed) != 2, "clone");
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Self(self.0.clone(), self.1.clone())
+        }
+    }
+    impl Drop for Fragile {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    let live = Arc::new(AtomicUsize::new(0));
+    let fragile = || {
+        live.fetch_add(1, Ordering::Relaxed);
+        Fragile(live.clone(), Arc::new(AtomicUsize::new(0)))
+    };
+    let mut world = World::new();
+    let a = world.spawn((1, fragile()));
+    let b = world.spawn((2, fragile()));
+    let c = world.spawn((3,));
+    let d = world.spawn((4,));
+    assert_eq!(live.load(Ordering::Relaxed), 2);
+
+    // Neither overwritten nor migrated entities are touched
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        world.insert_batch(&[a, b, c, d], fragile())
+    }));
+    assert!(result.is_err());
+    assert_eq!(live.load(Ordering::Relaxed), 2);
+    assert!(world.get::<Fragile>(a).is_ok());
+    assert!(world.get::<Fragile>(b).is_ok());
+    assert!(world.get::<Fragile>(c).is_err());
+    assert!(world.get::<Fragile>(d).is_err());
+    drop(world);
+    assert_eq!(live.load(Ordering::Relaxed), 0);
+
+    // Panicking callbacks leave the world consistent
+    let mut world = World::new();
+    let a = world.spawn((1, 1.0f32));
+    let b = world.spawn((2,));
+    world.on_remove::<f32>(|_, _| panic!("on_remove"));
+    assert!(catch_unwind(AssertUnwindSafe(|| world.insert_batch(&[a, b], 2.0f32))).is_err());
+    assert_eq!(*world.get::<f32>(a).unwrap(), 1.0);
+    assert!(world.get::<f32>(b).is_err());
+
+    let mut world = World::new();
+    let a = world.spawn((1, 1.0f32));
+    let b = world.spawn((2,));
+    world.on_add::<f32>(|_, _| panic!("on_add"));
+    assert!(catch_unwind(AssertUnwindSafe(|| world.insert_batch(&[a, b], 2.0f32))).is_err());
+    assert_eq!(*world.get::<f32>(a).unwrap(), 2.0);
+    assert_eq!(*world.get::<f32>(b).unwrap(), 2.0);
+    assert_eq!(*world.get::<i32>(b).unwrap(), 2);
+}
+
+#[test]
+fn query_iter_sorted() {
+    let mut world = World::new();
+    let entities = (0..100)
+        .map(|i| world.spawn((i, i as f32)))
+        .collect::<Vec<_>>();
+    for &e in entities.iter().step_by(3) {
+        world.despawn(e).unwrap();
+    }
+    for &e in entities.iter().skip(1).step_by(7) {
+        let _ = world.remove_one::<f32>(e);
+    }
+    let recycled = world.spawn((1000, 0.0f32));
+
+    let mut expected = entities
+        .iter()
+        .copied()
+        .filter(|&e| world.contains(e))
+        .collect::<Vec<_>>();
+    expected.push(recycled);
+    expected.sort_by_key(|e| e.id());
+    for (_, (mut x, _)) in world.query::<(&mut i32, Option<&f32>)>().iter_sorted() {
+        *x += 1;
+    }
+    let order = world
+        .query::<&i32>()
+        .iter_sorted()
+        .map(|(e, _)| e)
+        .collect::<Vec<_>>();
+    assert_eq!(order, expected);
+    assert_eq!(*world.get::<i32>(recycled).unwrap(), 1001);
+}
+
+#[test]
+fn query_iter_archetypes() {
+    let mut world = World::new();
+    let a = world
+        .spawn_batch((0..3000).map(|i| (i,)))
+        .collect::<Vec<_>>();
+    let b = world
+        .spawn_batch((0..5).map(|i| (i, true)))
+        .collect::<Vec<_>>();
+    world.spawn(("unrelated",));
+    let emptied = world.spawn((0, 'x'));
+    world.despawn(emptied).unwrap();
+
+    let mut query = world.query::<&mut i32>();
+    let mut batches = query.iter_archetypes().collect::<Vec<_>>();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].entities().collect::<Vec<_>>(), a);
+    assert_eq!(batches[1].entities().collect::<Vec<_>>(), b);
+    assert!(batches[1].archetype().has::<bool>());
+    for x in batches[1].column_mut::<i32>().unwrap() {
+        *x += 100;
+    }
+    drop(batches);
+    drop(query);
+    assert_eq!(*world.get::<i32>(b[4]).unwrap(), 104);
+    assert_eq!(*world.get::<i32>(a[4]).unwrap(), 4);
 }